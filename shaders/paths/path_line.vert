@@ -0,0 +1,38 @@
+#version 450
+
+layout (location = 0) in vec2 position;
+// offset from `position`, in units of the line width in pixels
+layout (location = 1) in vec2 offset;
+layout (location = 2) in vec4 color;
+
+layout (location = 0) out vec4 v_color;
+
+layout (push_constant) uniform NodePC {
+  mat4 view_transform;
+  float node_width;
+  float scale;
+  vec2 viewport_dims;
+  uint texture_period;
+} node_uniform;
+
+void main() {
+  mat4 transform = node_uniform.view_transform;
+
+  vec4 pos = transform * vec4(position, 0.0, 1.0);
+
+  // the view transform has the same scale on both axes, so the
+  // offset keeps its length in pixels regardless of direction
+  vec2 unit_px = (transform * vec4(1.0, 0.0, 0.0, 0.0)).xy
+                 * node_uniform.viewport_dims * 0.5;
+  float px_per_unit = length(unit_px);
+
+  vec2 offset_clip = (transform * vec4(offset, 0.0, 0.0)).xy;
+
+  if (px_per_unit > 0.0) {
+    pos.xy += offset_clip * (node_uniform.node_width / px_per_unit);
+  }
+
+  v_color = color;
+
+  gl_Position = pos;
+}
@@ -25,10 +25,16 @@ use crate::{geometry::*, universe::Node, view::*};
 use nalgebra_glm as glm;
 
 pub mod bed;
+pub mod gaf;
 pub mod gff;
+pub mod lift;
+pub mod signal;
 
 pub use bed::*;
+pub use gaf::*;
 pub use gff::*;
+pub use lift::*;
+pub use signal::*;
 
 #[derive(Debug, Default, Clone)]
 pub struct Label {
@@ -499,6 +505,9 @@ pub struct Annotations {
     gff3_annotations: HashMap<String, Arc<Gff3Records>>,
     bed_annotations: HashMap<String, Arc<BedRecords>>,
 
+    gaf_names: Vec<String>,
+    gaf_alignments: HashMap<String, Arc<GafRecords>>,
+
     label_sets: HashMap<String, Arc<AnnotationLabelSet>>,
 
     annotation_default_ref_path: HashMap<String, PathId>,
@@ -557,6 +566,29 @@ impl Annotations {
         self.bed_annotations.get(name)
     }
 
+    pub fn gaf_names(&self) -> &[String] {
+        &self.gaf_names
+    }
+
+    pub fn insert_gaf_arc(&mut self, name: &str, records: Arc<GafRecords>) {
+        if self
+            .gaf_alignments
+            .insert(name.to_string(), records)
+            .is_none()
+        {
+            self.gaf_names.push(name.to_string());
+        }
+    }
+
+    pub fn remove_gaf(&mut self, name: &str) {
+        self.gaf_alignments.remove(name);
+        self.gaf_names.retain(|n| n != name);
+    }
+
+    pub fn get_gaf(&self, name: &str) -> Option<&Arc<GafRecords>> {
+        self.gaf_alignments.get(name)
+    }
+
     pub fn insert_label_set(
         &mut self,
         name: &str,
@@ -639,6 +671,32 @@ pub trait AnnotationCollection {
     fn records(&self) -> &[Self::Record];
 
     fn wrap_column(column: Self::ColumnKey) -> AnnotationColumn;
+
+    /// Whether any records in the collection are linked into a
+    /// feature hierarchy, e.g. gene -> transcript -> exon
+    fn has_hierarchy(&self) -> bool {
+        false
+    }
+
+    /// Indices of the records directly below the given record in the
+    /// feature hierarchy
+    fn children(&self, _record_ix: usize) -> &[usize] {
+        &[]
+    }
+
+    fn has_parent(&self, _record_ix: usize) -> bool {
+        false
+    }
+
+    /// The exonic intervals of the given record, sorted and in the
+    /// same coordinates as the record. Records without any exon
+    /// structure are treated as a single exon
+    fn exon_intervals(&self, record_ix: usize) -> Vec<(usize, usize)> {
+        self.records()
+            .get(record_ix)
+            .map(|record| vec![record.range()])
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -712,6 +770,19 @@ pub fn path_name_range(path_name: &[u8]) -> Option<(&[u8], usize, usize)> {
     Some((name, start, end))
 }
 
+/// The name of the sequence a path covers, without any PanSN prefix
+/// or `:start-end` range, e.g. `chr1` for `HG38#0#chr1:100-200`
+pub fn path_name_sequence(path_name: &[u8]) -> &[u8] {
+    let name = path_name_range(path_name)
+        .map(|(name, _, _)| name)
+        .unwrap_or(path_name);
+
+    match name.rfind_byte(b'#') {
+        Some(ix) => &name[ix + 1..],
+        None => name,
+    }
+}
+
 pub fn path_name_offset(path_name: &[u8]) -> Option<usize> {
     path_name_range(path_name).map(|(_, s, _)| s)
     /*
@@ -760,6 +831,55 @@ pub fn path_step_range(
     Some(&steps[start..end])
 }
 
+/// Returns the handles of the path steps that overlap the exons of
+/// the given record, in path order
+pub fn record_exon_steps<C>(
+    records: &C,
+    record_ix: usize,
+    steps: &[(Handle, StepPtr, usize)],
+    offset: Option<usize>,
+) -> Option<Vec<Handle>>
+where
+    C: AnnotationCollection,
+{
+    let mut handles = Vec::new();
+
+    for (start, end) in records.exon_intervals(record_ix) {
+        if end < start {
+            continue;
+        }
+
+        if let Some(range) = path_step_range(steps, offset, start, end) {
+            handles.extend(range.iter().map(|(h, _, _)| *h));
+        }
+    }
+
+    if handles.is_empty() {
+        None
+    } else {
+        Some(handles)
+    }
+}
+
+/// Sorts the intervals and merges any that overlap
+pub fn merge_intervals(intervals: &mut Vec<(usize, usize)>) {
+    intervals.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(intervals.len());
+
+    for &(start, end) in intervals.iter() {
+        if let Some(last) = merged.last_mut() {
+            if start <= last.1 {
+                last.1 = last.1.max(end);
+                continue;
+            }
+        }
+        merged.push((start, end));
+    }
+
+    *intervals = merged;
+}
+
 pub fn path_step_radius(
     steps: &[(Handle, StepPtr, usize)],
     nodes: &[Node],
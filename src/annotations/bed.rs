@@ -27,6 +27,9 @@ pub struct BedRecord {
     // TODO add header support
     pub rest: Vec<Vec<u8>>,
     // headers: FxHashMap<Vec<u8>, usize>
+    /// The exons of a BED12 record, as absolute `(start, end)`
+    /// intervals; empty if the record has no block columns
+    pub blocks: Vec<(usize, usize)>,
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -191,6 +194,8 @@ impl BedRecord {
         let end: usize = parse_next(&mut fields)?;
 
         let rest: Vec<Vec<u8>> = fields.map(|field| field.to_owned()).collect();
+
+        let blocks = Self::parse_blocks(start, &rest).unwrap_or_default();
         // let mut rest: Vec<Vec<u8>> = Vec::new();
 
         // let mut count = 0;
@@ -205,8 +210,42 @@ impl BedRecord {
             start,
             end,
             rest,
+            blocks,
         })
     }
+
+    /// Parses the BED12 `blockSizes` and `blockStarts` columns, with
+    /// block starts relative to the record start
+    fn parse_blocks(
+        start: usize,
+        rest: &[Vec<u8>],
+    ) -> Option<Vec<(usize, usize)>> {
+        let count: usize = rest.get(6)?.to_str().ok()?.parse().ok()?;
+
+        let parse_list = |field: &[u8]| -> Option<Vec<usize>> {
+            field
+                .split_str(",")
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_str().ok()?.parse().ok())
+                .collect()
+        };
+
+        let sizes = parse_list(rest.get(7)?)?;
+        let starts = parse_list(rest.get(8)?)?;
+
+        if sizes.len() < count || starts.len() < count {
+            return None;
+        }
+
+        let blocks = starts
+            .into_iter()
+            .zip(sizes)
+            .take(count)
+            .map(|(s, len)| (start + s, start + s + len))
+            .collect();
+
+        Some(blocks)
+    }
 }
 
 impl std::fmt::Display for BedColumn {
@@ -259,6 +298,18 @@ impl AnnotationCollection for BedRecords {
     fn wrap_column(column: Self::ColumnKey) -> AnnotationColumn {
         AnnotationColumn::Bed(column)
     }
+
+    fn exon_intervals(&self, record_ix: usize) -> Vec<(usize, usize)> {
+        match self.records.get(record_ix) {
+            Some(record) if !record.blocks.is_empty() => {
+                let mut blocks = record.blocks.clone();
+                blocks.sort();
+                blocks
+            }
+            Some(record) => vec![record.range()],
+            None => Vec::new(),
+        }
+    }
 }
 
 impl AnnotationRecord for BedRecord {
@@ -0,0 +1,349 @@
+use bstr::ByteSlice;
+
+use anyhow::Result;
+
+use handlegraph::handle::{Handle, NodeId};
+
+use super::Strand;
+
+/// Read alignments to the graph, parsed from a GAF file, e.g. as
+/// produced by GraphAligner or minigraph
+#[derive(Debug, Clone, Default)]
+pub struct GafRecords {
+    file_name: String,
+
+    pub records: Vec<GafRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GafRecord {
+    pub query_name: Vec<u8>,
+    pub query_len: usize,
+    pub query_start: usize,
+    pub query_end: usize,
+
+    pub strand: Strand,
+
+    /// The oriented node walk of the alignment; empty if the path
+    /// column couldn't be mapped to node IDs in the graph (e.g. if
+    /// the alignment is to a stable coordinate system)
+    pub steps: Vec<Handle>,
+
+    pub path_len: usize,
+    pub path_start: usize,
+    pub path_end: usize,
+
+    pub matches: usize,
+    pub block_len: usize,
+    pub mapq: u8,
+
+    pub tags: Vec<Vec<u8>>,
+}
+
+impl GafRecords {
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn parse_gaf_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        use std::fs::File;
+
+        use std::io::{BufRead, BufReader};
+
+        let file_name = path.as_ref().file_name().unwrap();
+        let file_name = file_name.to_str().unwrap().to_string();
+
+        let file = File::open(path)?;
+
+        let mut reader = BufReader::new(file);
+
+        let mut buf: Vec<u8> = Vec::new();
+
+        let mut records = Vec::new();
+
+        loop {
+            buf.clear();
+
+            let read = reader.read_until(b'\n', &mut buf)?;
+
+            if read == 0 {
+                break;
+            }
+
+            let line = buf[0..read].trim_end();
+
+            if line.is_empty() || line[0] == b'#' {
+                continue;
+            }
+
+            let fields = line.split_str("\t");
+
+            if let Some(record) = GafRecord::parse_row(fields) {
+                records.push(record);
+            }
+        }
+
+        Ok(Self { file_name, records })
+    }
+}
+
+/// Returns the number of alignment steps on each node in the graph,
+/// indexed by node ID - 1
+pub fn alignment_node_depth<'a, I>(node_count: usize, records: I) -> Vec<u32>
+where
+    I: IntoIterator<Item = &'a GafRecord>,
+{
+    let mut depth = vec![0u32; node_count];
+
+    for record in records {
+        for handle in record.steps.iter() {
+            let ix = (handle.id().0 as usize).wrapping_sub(1);
+            if let Some(d) = depth.get_mut(ix) {
+                *d += 1;
+            }
+        }
+    }
+
+    depth
+}
+
+fn parse_next<'a, T, I>(fields: &mut I) -> Option<T>
+where
+    T: std::str::FromStr,
+    I: Iterator<Item = &'a [u8]> + 'a,
+{
+    let field = fields.next()?;
+    let field = field.as_bstr().to_str().ok()?;
+    field.parse().ok()
+}
+
+/// Parses an oriented walk such as `>12<13>14` into handles; a
+/// lone unoriented segment name is treated as a forward step
+fn parse_walk(path: &[u8]) -> Option<Vec<Handle>> {
+    let parse_id = |id: &[u8]| -> Option<NodeId> {
+        let id: u64 = id.to_str().ok()?.parse().ok()?;
+        Some(NodeId::from(id))
+    };
+
+    if !path.starts_with(b">") && !path.starts_with(b"<") {
+        let id = parse_id(path)?;
+        return Some(vec![Handle::pack(id, false)]);
+    }
+
+    let mut steps = Vec::new();
+
+    let mut rest = path;
+
+    while !rest.is_empty() {
+        let is_reverse = rest[0] == b'<';
+
+        let end = rest[1..]
+            .find_byteset(b"<>")
+            .map(|ix| ix + 1)
+            .unwrap_or(rest.len());
+
+        let id = parse_id(&rest[1..end])?;
+        steps.push(Handle::pack(id, is_reverse));
+
+        rest = &rest[end..];
+    }
+
+    Some(steps)
+}
+
+impl GafRecord {
+    fn parse_row<'a, I>(mut fields: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a [u8]> + 'a,
+    {
+        let query_name = fields.next()?.to_owned();
+
+        let query_len = parse_next(&mut fields)?;
+        let query_start = parse_next(&mut fields)?;
+        let query_end = parse_next(&mut fields)?;
+
+        let strand = parse_next(&mut fields)?;
+
+        let steps = parse_walk(fields.next()?).unwrap_or_default();
+
+        let path_len = parse_next(&mut fields)?;
+        let path_start = parse_next(&mut fields)?;
+        let path_end = parse_next(&mut fields)?;
+
+        let matches = parse_next(&mut fields)?;
+        let block_len = parse_next(&mut fields)?;
+        let mapq = parse_next(&mut fields)?;
+
+        let tags = fields.map(|field| field.to_owned()).collect();
+
+        Some(Self {
+            query_name,
+            query_len,
+            query_start,
+            query_end,
+
+            strand,
+
+            steps,
+
+            path_len,
+            path_start,
+            path_end,
+
+            matches,
+            block_len,
+            mapq,
+
+            tags,
+        })
+    }
+
+    /// Returns the value of the SAM-style optional field with the
+    /// given two-letter name, e.g. `tag(b"cg")` for the CIGAR string
+    pub fn tag(&self, name: &[u8]) -> Option<&[u8]> {
+        self.tags.iter().find_map(|tag| {
+            if tag.len() >= 5 && &tag[0..2] == name && tag[2] == b':' {
+                Some(&tag[5..])
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Alignment identity, using the `id:f` tag if present, otherwise
+    /// the number of matches divided by the alignment block length
+    pub fn identity(&self) -> f32 {
+        if let Some(id) = self
+            .tag(b"id")
+            .and_then(|id| id.to_str().ok())
+            .and_then(|id| id.parse().ok())
+        {
+            return id;
+        }
+
+        if self.block_len == 0 {
+            0.0
+        } else {
+            self.matches as f32 / self.block_len as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle(id: u64, is_reverse: bool) -> Handle {
+        Handle::pack(NodeId::from(id), is_reverse)
+    }
+
+    fn parse_line(line: &[u8]) -> Option<GafRecord> {
+        GafRecord::parse_row(line.split_str("\t"))
+    }
+
+    #[test]
+    fn parse_oriented_walk() {
+        let steps = parse_walk(b">12<13>14").unwrap();
+        assert_eq!(
+            steps,
+            vec![handle(12, false), handle(13, true), handle(14, false)]
+        );
+
+        assert_eq!(parse_walk(b"<7").unwrap(), vec![handle(7, true)]);
+    }
+
+    #[test]
+    fn parse_unoriented_segment() {
+        assert_eq!(parse_walk(b"42").unwrap(), vec![handle(42, false)]);
+    }
+
+    #[test]
+    fn parse_walk_rejects_bad_input() {
+        assert!(parse_walk(b">12<x>14").is_none());
+        assert!(parse_walk(b">").is_none());
+        assert!(parse_walk(b"chr1").is_none());
+        assert!(parse_walk(b"").is_none());
+    }
+
+    #[test]
+    fn parse_row_fields() {
+        let line = b"read1\t100\t0\t100\t+\t>1>2<3\t300\t10\t110\t95\t100\t60\tid:f:0.97\tcg:Z:100M";
+        let record = parse_line(line).unwrap();
+
+        assert_eq!(record.query_name, b"read1");
+        assert_eq!(record.query_len, 100);
+        assert_eq!(record.strand, Strand::Pos);
+        assert_eq!(
+            record.steps,
+            vec![handle(1, false), handle(2, false), handle(3, true)]
+        );
+        assert_eq!(record.path_len, 300);
+        assert_eq!(record.path_start, 10);
+        assert_eq!(record.path_end, 110);
+        assert_eq!(record.matches, 95);
+        assert_eq!(record.block_len, 100);
+        assert_eq!(record.mapq, 60);
+        assert_eq!(record.tags.len(), 2);
+    }
+
+    #[test]
+    fn parse_row_with_stable_coordinates() {
+        // the path isn't a walk over node IDs, so there are no steps
+        let line =
+            b"read1\t100\t0\t100\t-\tchr1:0-1000\t1000\t10\t110\t95\t100\t60";
+        let record = parse_line(line).unwrap();
+
+        assert_eq!(record.strand, Strand::Neg);
+        assert!(record.steps.is_empty());
+    }
+
+    #[test]
+    fn parse_row_rejects_truncated_lines() {
+        assert!(parse_line(b"read1\t100\t0\t100\t+\t>1>2").is_none());
+        assert!(
+            parse_line(b"read1\tx\t0\t100\t+\t>1\t1\t0\t1\t1\t1\t60").is_none()
+        );
+    }
+
+    #[test]
+    fn tags() {
+        let line = b"r\t1\t0\t1\t+\t>1\t1\t0\t1\t1\t1\t60\tNM:i:3\tcg:Z:1M";
+        let record = parse_line(line).unwrap();
+
+        assert_eq!(record.tag(b"NM"), Some(&b"3"[..]));
+        assert_eq!(record.tag(b"cg"), Some(&b"1M"[..]));
+        assert_eq!(record.tag(b"id"), None);
+    }
+
+    #[test]
+    fn identity_prefers_tag() {
+        let line = b"r\t1\t0\t1\t+\t>1\t1\t0\t1\t50\t100\t60\tid:f:0.9";
+        let record = parse_line(line).unwrap();
+        assert!((record.identity() - 0.9).abs() < 1e-6);
+
+        let line = b"r\t1\t0\t1\t+\t>1\t1\t0\t1\t50\t100\t60";
+        let record = parse_line(line).unwrap();
+        assert!((record.identity() - 0.5).abs() < 1e-6);
+
+        let line = b"r\t1\t0\t1\t+\t>1\t1\t0\t1\t0\t0\t60";
+        let record = parse_line(line).unwrap();
+        assert_eq!(record.identity(), 0.0);
+    }
+
+    #[test]
+    fn node_depth_ignores_unknown_nodes() {
+        let line = b"r\t1\t0\t1\t+\t>0>1>2>9\t1\t0\t1\t1\t1\t60";
+        let record = parse_line(line).unwrap();
+
+        let depth = alignment_node_depth(3, std::iter::once(&record));
+        assert_eq!(depth, vec![1, 1, 0]);
+    }
+}
@@ -4,7 +4,7 @@ use bstr::ByteSlice;
 
 use anyhow::Result;
 
-use log::error;
+use log::{error, warn};
 
 use super::{
     AnnotationCollection, AnnotationColumn, AnnotationRecord, ColumnKey, Strand,
@@ -17,6 +17,11 @@ pub struct Gff3Records {
     pub records: Vec<Gff3Record>,
 
     pub attribute_keys: HashSet<Vec<u8>>,
+
+    /// Records linked via the `Parent` attribute, indexed by the
+    /// parent record
+    children: HashMap<usize, Vec<usize>>,
+    has_parent: Vec<bool>,
 }
 
 impl AnnotationCollection for Gff3Records {
@@ -86,6 +91,62 @@ impl AnnotationCollection for Gff3Records {
     fn wrap_column(column: Gff3Column) -> AnnotationColumn {
         AnnotationColumn::Gff3(column)
     }
+
+    fn has_hierarchy(&self) -> bool {
+        !self.children.is_empty()
+    }
+
+    fn children(&self, record_ix: usize) -> &[usize] {
+        self.children
+            .get(&record_ix)
+            .map(|c| c.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn has_parent(&self, record_ix: usize) -> bool {
+        self.has_parent.get(record_ix).copied().unwrap_or(false)
+    }
+
+    fn exon_intervals(&self, record_ix: usize) -> Vec<(usize, usize)> {
+        let record = if let Some(record) = self.records.get(record_ix) {
+            record
+        } else {
+            return Vec::new();
+        };
+
+        if record.type_() == b"exon" {
+            return vec![record.range()];
+        }
+
+        let mut exons = Vec::new();
+
+        let mut stack = vec![record_ix];
+        let mut visited: HashSet<usize> = HashSet::default();
+
+        while let Some(ix) = stack.pop() {
+            if !visited.insert(ix) {
+                continue;
+            }
+
+            for &child in self.children(ix) {
+                let child_rec = &self.records[child];
+                if child_rec.type_() == b"exon" {
+                    exons.push(child_rec.range());
+                } else {
+                    stack.push(child);
+                }
+            }
+        }
+
+        if exons.is_empty() {
+            return vec![record.range()];
+        }
+
+        // a gene's exons are the union of its transcripts' exons
+        super::merge_intervals(&mut exons);
+
+        exons
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -299,12 +360,194 @@ impl Gff3Records {
             }
         }
 
-        Ok(Self {
+        let mut result = Self {
             file_name,
 
             records,
             attribute_keys,
-        })
+
+            children: HashMap::default(),
+            has_parent: Vec::new(),
+        };
+
+        result.link_parents();
+
+        Ok(result)
+    }
+
+    /// Builds the feature hierarchy from the `ID` and `Parent`
+    /// attributes; a record can have several parents, e.g. an exon
+    /// shared by multiple transcripts
+    fn link_parents(&mut self) {
+        let mut id_indices: HashMap<&[u8], usize> = HashMap::default();
+
+        for (ix, record) in self.records.iter().enumerate() {
+            if let Some(id) = record.get_tag(b"ID").and_then(|ids| ids.first())
+            {
+                id_indices.entry(id.as_slice()).or_insert(ix);
+            }
+        }
+
+        let mut links: Vec<(usize, usize)> = Vec::new();
+
+        for (ix, record) in self.records.iter().enumerate() {
+            let parents = record.get_tag(b"Parent").into_iter().flatten();
+
+            for parent in parents.flat_map(|p| p.split_str(",")) {
+                if let Some(&parent_ix) = id_indices.get(parent) {
+                    links.push((parent_ix, ix));
+                }
+            }
+        }
+
+        self.set_hierarchy(links);
+    }
+
+    /// GTF files have no `ID`/`Parent` attributes, instead features
+    /// are grouped by `gene_id` and `transcript_id`. Only files that
+    /// include `gene` and `transcript` lines get a hierarchy
+    fn link_gtf_parents(&mut self) {
+        let first_tag = |record: &Gff3Record, key: &[u8]| {
+            record.get_tag(key).and_then(|v| v.first()).cloned()
+        };
+
+        let mut genes: HashMap<Vec<u8>, usize> = HashMap::default();
+        let mut transcripts: HashMap<Vec<u8>, usize> = HashMap::default();
+
+        for (ix, record) in self.records.iter().enumerate() {
+            match record.type_() {
+                b"gene" => {
+                    if let Some(id) = first_tag(record, b"gene_id") {
+                        genes.entry(id).or_insert(ix);
+                    }
+                }
+                b"transcript" => {
+                    if let Some(id) = first_tag(record, b"transcript_id") {
+                        transcripts.entry(id).or_insert(ix);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut links: Vec<(usize, usize)> = Vec::new();
+
+        for (ix, record) in self.records.iter().enumerate() {
+            let gene = first_tag(record, b"gene_id")
+                .and_then(|id| genes.get(&id).copied());
+
+            let parent = match record.type_() {
+                b"gene" => None,
+                b"transcript" => gene,
+                _ => first_tag(record, b"transcript_id")
+                    .and_then(|id| transcripts.get(&id).copied())
+                    .or(gene),
+            };
+
+            if let Some(parent_ix) = parent {
+                links.push((parent_ix, ix));
+            }
+        }
+
+        self.set_hierarchy(links);
+    }
+
+    /// Sets the hierarchy from a list of `(parent, child)` record
+    /// indices
+    fn set_hierarchy(&mut self, links: Vec<(usize, usize)>) {
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::default();
+        let mut has_parent = vec![false; self.records.len()];
+
+        for (parent_ix, ix) in links {
+            if parent_ix != ix {
+                children.entry(parent_ix).or_default().push(ix);
+                has_parent[ix] = true;
+            }
+        }
+
+        self.children = children;
+        self.has_parent = has_parent;
+    }
+
+    /// Parses a GTF (GFF2) file, e.g. from Ensembl or GENCODE, into
+    /// the same representation as GFF3 files, with attributes such as
+    /// `gene_id` and `transcript_id` as columns
+    pub fn parse_gtf_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        use std::fs::File;
+        use std::io::{BufRead, BufReader};
+
+        let file_name = path.as_ref().file_name().unwrap();
+        let file_name = file_name.to_str().unwrap().to_string();
+
+        let file = File::open(path)?;
+
+        let mut reader = BufReader::new(file);
+
+        let mut buf: Vec<u8> = Vec::new();
+
+        let mut records = Vec::new();
+
+        let mut attribute_keys: HashSet<Vec<u8>> = HashSet::default();
+
+        let mut line_num = 0;
+        let mut skipped = 0;
+
+        loop {
+            buf.clear();
+
+            let read = reader.read_until(b'\n', &mut buf)?;
+
+            if read == 0 {
+                break;
+            }
+
+            line_num += 1;
+
+            let line = buf[0..read].trim_end();
+
+            if line.is_empty() || line[0] == b'#' {
+                continue;
+            }
+
+            let fields = line.split_str("\t");
+
+            if let Some(record) = Gff3Record::parse_gtf_row(fields) {
+                for key in record.attributes.keys() {
+                    if !attribute_keys.contains(key) {
+                        attribute_keys.insert(key.to_owned());
+                    }
+                }
+
+                records.push(record);
+            } else {
+                // skip malformed lines rather than rejecting the
+                // entire file
+                warn!(
+                    "Skipping GTF line {} that could not be parsed: \"{}\"",
+                    line_num,
+                    line.as_bstr()
+                );
+                skipped += 1;
+            }
+        }
+
+        if skipped > 0 {
+            warn!("Skipped {} malformed lines in {}", skipped, file_name);
+        }
+
+        let mut result = Self {
+            file_name,
+
+            records,
+            attribute_keys,
+
+            children: HashMap::default(),
+            has_parent: Vec::new(),
+        };
+
+        result.link_gtf_parents();
+
+        Ok(result)
     }
 }
 
@@ -360,8 +603,34 @@ where
     field.parse().ok()
 }
 
+/// Splits a GTF attribute column on the semicolons that aren't
+/// inside quoted values
+fn split_gtf_attributes(raw: &[u8]) -> Vec<&[u8]> {
+    let mut result = Vec::new();
+
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (ix, &b) in raw.iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b';' if !in_quotes => {
+                result.push(&raw[start..ix]);
+                start = ix + 1;
+            }
+            _ => (),
+        }
+    }
+
+    result.push(&raw[start..]);
+
+    result
+}
+
 impl Gff3Record {
-    pub fn parse_row<'a, I>(mut fields: I) -> Option<Self>
+    /// Parses the eight columns that are shared by GFF3 and GTF,
+    /// returning a record without attributes
+    fn parse_fixed_columns<'a, I>(fields: &mut I) -> Option<Self>
     where
         I: Iterator<Item = &'a [u8]> + 'a,
     {
@@ -369,8 +638,8 @@ impl Gff3Record {
         let source = fields.next()?;
         let type_ = fields.next()?;
 
-        let start: usize = parse_next(&mut fields)?;
-        let end: usize = parse_next(&mut fields)?;
+        let start: usize = parse_next(fields)?;
+        let end: usize = parse_next(fields)?;
 
         let score_field = fields.next()?;
 
@@ -382,11 +651,68 @@ impl Gff3Record {
             Some(score)
         };
 
-        let strand: Strand = parse_next(&mut fields)?;
+        let strand: Strand = parse_next(fields)?;
 
         let frame = fields.next()?;
 
-        let mut attributes: HashMap<Vec<u8>, Vec<Vec<u8>>> = HashMap::default();
+        Some(Self {
+            seq_id: seq_id.to_owned(),
+            source: source.to_owned(),
+            type_: type_.to_owned(),
+            start,
+            end,
+            score,
+            strand,
+            frame: frame.to_owned(),
+            attributes: HashMap::default(),
+        })
+    }
+
+    /// Parses a GTF row, with attributes in the `key "value";` form
+    pub fn parse_gtf_row<'a, I>(mut fields: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a [u8]> + 'a,
+    {
+        let mut record = Self::parse_fixed_columns(&mut fields)?;
+
+        let attributes_raw = fields.next().unwrap_or(b"");
+
+        for attribute in split_gtf_attributes(attributes_raw) {
+            let attribute = attribute.trim();
+
+            if attribute.is_empty() {
+                continue;
+            }
+
+            let (tag, val) = match attribute.find_byteset(b" \t") {
+                Some(ix) => (&attribute[..ix], attribute[ix..].trim()),
+                None => (attribute, &b""[..]),
+            };
+
+            let val = if val.len() >= 2
+                && val.starts_with(b"\"")
+                && val.ends_with(b"\"")
+            {
+                &val[1..val.len() - 1]
+            } else {
+                val
+            };
+
+            record
+                .attributes
+                .entry(tag.to_owned())
+                .or_default()
+                .push(val.to_owned());
+        }
+
+        Some(record)
+    }
+
+    pub fn parse_row<'a, I>(mut fields: I) -> Option<Self>
+    where
+        I: Iterator<Item = &'a [u8]> + 'a,
+    {
+        let mut record = Self::parse_fixed_columns(&mut fields)?;
 
         let attributes_raw = fields.next()?;
 
@@ -397,23 +723,14 @@ impl Gff3Record {
             let tag = attr_fields.next()?;
             let val = attr_fields.next()?;
 
-            attributes
+            record
+                .attributes
                 .entry(tag.to_owned())
                 .or_default()
                 .push(val.to_owned());
         }
 
-        Some(Self {
-            seq_id: seq_id.to_owned(),
-            source: source.to_owned(),
-            type_: type_.to_owned(),
-            start,
-            end,
-            score,
-            strand,
-            frame: frame.to_owned(),
-            attributes,
-        })
+        Some(record)
     }
 
     pub fn parse_gff3_file<P: AsRef<std::path::Path>>(
@@ -459,3 +776,71 @@ impl Gff3Record {
         Ok(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_gtf_line(line: &[u8]) -> Option<Gff3Record> {
+        Gff3Record::parse_gtf_row(line.split_str("\t"))
+    }
+
+    fn tag<'a>(record: &'a Gff3Record, key: &[u8]) -> Vec<&'a [u8]> {
+        record
+            .get_tag(key)
+            .map(|vals| vals.iter().map(|v| v.as_slice()).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn gtf_row_fixed_columns() {
+        let line =
+            b"chr1\tHAVANA\texon\t11869\t12227\t.\t+\t.\tgene_id \"ENSG1\";";
+        let record = parse_gtf_line(line).unwrap();
+
+        assert_eq!(record.seq_id(), b"chr1");
+        assert_eq!(record.type_(), b"exon");
+        assert_eq!(record.start(), 11869);
+        assert_eq!(record.end(), 12227);
+        assert_eq!(record.score(), None);
+        assert_eq!(record.strand(), Strand::Pos);
+    }
+
+    #[test]
+    fn gtf_row_attributes() {
+        let line = b"chr1\tsrc\tgene\t1\t10\t0.5\t-\t0\tgene_id \"G1\"; gene_name \"ABC\"; level 2; tag \"basic\"; tag \"CCDS\";";
+        let record = parse_gtf_line(line).unwrap();
+
+        assert_eq!(record.score(), Some(0.5));
+        assert_eq!(tag(&record, b"gene_id"), vec![&b"G1"[..]]);
+        assert_eq!(tag(&record, b"gene_name"), vec![&b"ABC"[..]]);
+        assert_eq!(tag(&record, b"level"), vec![&b"2"[..]]);
+        assert_eq!(tag(&record, b"tag"), vec![&b"basic"[..], &b"CCDS"[..]]);
+    }
+
+    #[test]
+    fn gtf_row_semicolon_in_quotes() {
+        let line = b"chr1\tsrc\tgene\t1\t10\t.\t+\t.\tgene_id \"G1\"; note \"a; b\"; level 1;";
+        let record = parse_gtf_line(line).unwrap();
+
+        assert_eq!(tag(&record, b"note"), vec![&b"a; b"[..]]);
+        assert_eq!(tag(&record, b"level"), vec![&b"1"[..]]);
+        assert_eq!(record.attributes().len(), 3);
+    }
+
+    #[test]
+    fn gtf_row_without_attributes() {
+        let line = b"chr1\tsrc\tgene\t1\t10\t.\t.\t.";
+        let record = parse_gtf_line(line).unwrap();
+
+        assert_eq!(record.strand(), Strand::None);
+        assert!(record.attributes().is_empty());
+    }
+
+    #[test]
+    fn gtf_row_rejects_bad_input() {
+        assert!(parse_gtf_line(b"chr1\tsrc\tgene\tx\t10\t.\t+\t.").is_none());
+        assert!(parse_gtf_line(b"chr1\tsrc\tgene\t1\t10\t.\t?\t.").is_none());
+        assert!(parse_gtf_line(b"chr1\tsrc\tgene\t1").is_none());
+    }
+}
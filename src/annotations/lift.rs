@@ -0,0 +1,294 @@
+use bstr::ByteSlice;
+
+use handlegraph::{
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    pathhandlegraph::*,
+};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::graph_query::GraphQuery;
+
+use super::{AnnotationCollection, AnnotationRecord};
+
+/// How much of a record's reference nodes are found on another path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LiftStatus {
+    Present,
+    Partial,
+    Absent,
+}
+
+impl std::fmt::Display for LiftStatus {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            LiftStatus::Present => write!(f, "present"),
+            LiftStatus::Partial => write!(f, "partial"),
+            LiftStatus::Absent => write!(f, "absent"),
+        }
+    }
+}
+
+/// A record projected onto one path, via the nodes it covers on the
+/// reference path
+#[derive(Debug, Clone, Copy)]
+pub struct LiftedRecord {
+    /// Index into `LiftedAnnotations::records`
+    pub record: usize,
+    /// Index into `LiftedAnnotations::paths`
+    pub path: usize,
+
+    /// The span of the record's nodes on the path, in the path's
+    /// coordinates (including any offset from the path name)
+    pub range: Option<(usize, usize)>,
+
+    /// Fraction of the record's reference sequence that's on the path
+    pub coverage: f32,
+}
+
+impl LiftedRecord {
+    pub fn status(&self) -> LiftStatus {
+        if self.range.is_none() || self.coverage <= 0.0 {
+            LiftStatus::Absent
+        } else if self.coverage >= 1.0 {
+            LiftStatus::Present
+        } else {
+            LiftStatus::Partial
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LiftedSource {
+    /// Index of the record in its annotation collection
+    pub record_ix: usize,
+    pub name: String,
+}
+
+/// Annotation records lifted from a reference path onto all paths in
+/// the graph; `lifted` holds one entry per record and path, grouped
+/// by record
+#[derive(Debug, Clone)]
+pub struct LiftedAnnotations {
+    pub file_name: String,
+    pub ref_path: PathId,
+
+    pub paths: Vec<(PathId, String)>,
+    pub records: Vec<LiftedSource>,
+
+    pub lifted: Vec<LiftedRecord>,
+}
+
+impl LiftedAnnotations {
+    /// Maps each record onto the reference path, then finds the nodes
+    /// of each record on every path. `name_column` is used to name
+    /// the records, otherwise the record's reference range is used
+    pub fn lift_records<C>(
+        graph: &GraphQuery,
+        records: &C,
+        record_indices: &[usize],
+        ref_path: PathId,
+        name_column: Option<&C::ColumnKey>,
+    ) -> Option<Self>
+    where
+        C: AnnotationCollection,
+    {
+        use rayon::prelude::*;
+
+        let ref_steps = graph.path_pos_steps(ref_path)?;
+        let ref_name = graph.graph().get_path_name_vec(ref_path)?;
+        let ref_offset = super::path_name_offset(&ref_name);
+
+        let mut sources: Vec<LiftedSource> =
+            Vec::with_capacity(record_indices.len());
+
+        // total reference sequence length of each record's nodes
+        let mut total_lens: Vec<usize> = Vec::new();
+
+        let mut node_records: FxHashMap<NodeId, Vec<usize>> =
+            FxHashMap::default();
+
+        for &record_ix in record_indices {
+            let record = records.records().get(record_ix)?;
+
+            if record.end() < record.start() {
+                continue;
+            }
+
+            let range = super::path_step_range(
+                &ref_steps,
+                ref_offset,
+                record.start(),
+                record.end(),
+            );
+
+            let nodes = range
+                .into_iter()
+                .flatten()
+                .map(|(h, _, _)| h.id())
+                .collect::<FxHashSet<_>>();
+
+            let index = sources.len();
+
+            let name = name_column
+                .and_then(|column| record.get_first(column))
+                .map(|name| format!("{}", name.as_bstr()))
+                .unwrap_or_else(|| {
+                    format!(
+                        "{}:{}-{}",
+                        record.seq_id().as_bstr(),
+                        record.start(),
+                        record.end()
+                    )
+                });
+
+            sources.push(LiftedSource { record_ix, name });
+
+            let total_len = nodes
+                .iter()
+                .map(|&id| graph.graph().node_len(Handle::pack(id, false)))
+                .sum();
+            total_lens.push(total_len);
+
+            for id in nodes {
+                node_records.entry(id).or_default().push(index);
+            }
+        }
+
+        let paths = graph
+            .graph()
+            .path_ids()
+            .filter_map(|path| {
+                let name = graph.graph().get_path_name_vec(path)?;
+                Some((path, name.to_str().ok()?.to_string()))
+            })
+            .collect::<Vec<_>>();
+
+        let per_path: Vec<Vec<LiftedRecord>> = paths
+            .par_iter()
+            .enumerate()
+            .map(|(path_ix, (path, path_name))| {
+                let steps = graph.path_pos_steps(*path).unwrap_or_default();
+                let offset =
+                    super::path_name_offset(path_name.as_bytes()).unwrap_or(0);
+
+                // (start, end, nodes on this path)
+                let mut ranges: FxHashMap<
+                    usize,
+                    (usize, usize, FxHashSet<NodeId>),
+                > = FxHashMap::default();
+
+                for (handle, _, pos) in steps {
+                    let id = handle.id();
+
+                    if let Some(indices) = node_records.get(&id) {
+                        let end = pos + graph.graph().node_len(handle);
+
+                        for &index in indices {
+                            let entry =
+                                ranges.entry(index).or_insert_with(|| {
+                                    (pos, end, FxHashSet::default())
+                                });
+                            entry.0 = entry.0.min(pos);
+                            entry.1 = entry.1.max(end);
+                            entry.2.insert(id);
+                        }
+                    }
+                }
+
+                (0..sources.len())
+                    .map(|index| {
+                        if let Some((start, end, nodes)) = ranges.get(&index) {
+                            let len: usize = nodes
+                                .iter()
+                                .map(|&id| {
+                                    graph
+                                        .graph()
+                                        .node_len(Handle::pack(id, false))
+                                })
+                                .sum();
+
+                            let total = total_lens[index];
+
+                            let coverage = if total == 0 {
+                                0.0
+                            } else {
+                                len as f32 / total as f32
+                            };
+
+                            LiftedRecord {
+                                record: index,
+                                path: path_ix,
+                                range: Some((start + offset, end + offset)),
+                                coverage,
+                            }
+                        } else {
+                            LiftedRecord {
+                                record: index,
+                                path: path_ix,
+                                range: None,
+                                coverage: 0.0,
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut lifted = Vec::with_capacity(sources.len() * paths.len());
+
+        for index in 0..sources.len() {
+            for path_lifted in per_path.iter() {
+                lifted.push(path_lifted[index]);
+            }
+        }
+
+        Some(Self {
+            file_name: records.file_name().to_string(),
+            ref_path,
+
+            paths,
+            records: sources,
+
+            lifted,
+        })
+    }
+
+    pub fn path_name(&self, lifted: &LiftedRecord) -> &str {
+        &self.paths[lifted.path].1
+    }
+
+    pub fn record_name(&self, lifted: &LiftedRecord) -> &str {
+        &self.records[lifted.record].name
+    }
+
+    /// Writes the lifted records that are at least partially present
+    /// as BED, using the path names as sequence names. The score
+    /// column holds the coverage (0-1000), and the status is added as
+    /// an extra column
+    pub fn write_bed<W: std::io::Write>(
+        &self,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        for lifted in self.lifted.iter() {
+            if let Some((start, end)) = lifted.range {
+                writeln!(
+                    out,
+                    "{}\t{}\t{}\t{}\t{}\t.\t{}",
+                    self.path_name(lifted),
+                    start,
+                    end,
+                    self.record_name(lifted),
+                    (lifted.coverage * 1000.0).round() as usize,
+                    lifted.status()
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,525 @@
+use bstr::ByteSlice;
+
+use anyhow::{bail, Result};
+
+use handlegraph::{handle::Handle, handlegraph::*, pathhandlegraph::*};
+
+use handlegraph::packedgraph::paths::StepPtr;
+
+use crate::graph_query::GraphQuery;
+
+/// A continuous signal (e.g. coverage or methylation) along one or
+/// more reference sequences, parsed from a bedGraph or WIG file
+#[derive(Debug, Clone, Default)]
+pub struct SignalTrack {
+    file_name: String,
+
+    /// Intervals use 0-based, half-open coordinates, regardless of
+    /// the source file format
+    pub intervals: Vec<SignalInterval>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SignalInterval {
+    pub chr: Vec<u8>,
+    pub start: usize,
+    pub end: usize,
+    pub value: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SignalAggregate {
+    /// Mean of the signal over the node, weighted by overlap length
+    Mean,
+    Max,
+    /// Sum of the signal over each base of the node
+    Sum,
+}
+
+impl std::fmt::Display for SignalAggregate {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            SignalAggregate::Mean => write!(f, "Mean"),
+            SignalAggregate::Max => write!(f, "Max"),
+            SignalAggregate::Sum => write!(f, "Sum"),
+        }
+    }
+}
+
+impl std::str::FromStr for SignalAggregate {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        match s.to_lowercase().as_str() {
+            "mean" => Ok(SignalAggregate::Mean),
+            "max" => Ok(SignalAggregate::Max),
+            "sum" => Ok(SignalAggregate::Sum),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum WigStep {
+    Variable {
+        span: usize,
+    },
+    Fixed {
+        next: usize,
+        step: usize,
+        span: usize,
+    },
+}
+
+fn parse_field<T: std::str::FromStr>(field: &[u8]) -> Option<T> {
+    field.to_str().ok()?.parse().ok()
+}
+
+fn wig_declaration_value<'a>(line: &'a [u8], key: &str) -> Option<&'a [u8]> {
+    line.fields().find_map(|field| {
+        let mut split = field.splitn_str(2, "=");
+        let k = split.next()?;
+        let v = split.next()?;
+        if k == key.as_bytes() {
+            Some(v)
+        } else {
+            None
+        }
+    })
+}
+
+impl SignalTrack {
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// The distinct sequence names in the track, in file order
+    pub fn chromosomes(&self) -> Vec<Vec<u8>> {
+        let mut chrs: Vec<Vec<u8>> = Vec::new();
+
+        for interval in self.intervals.iter() {
+            if !chrs.contains(&interval.chr) {
+                chrs.push(interval.chr.clone());
+            }
+        }
+
+        chrs
+    }
+
+    /// Parses either a bedGraph or a WIG file, based on the file
+    /// extension
+    pub fn parse_signal_file<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<Self> {
+        let ext = path
+            .as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        match ext.as_deref() {
+            Some("wig") => Self::parse_wig_file(path),
+            Some("bedgraph") | Some("bdg") => Self::parse_bedgraph_file(path),
+            _ => bail!("Unknown signal file extension: {:?}", path.as_ref()),
+        }
+    }
+
+    fn read_lines<P, F>(path: P, mut f: F) -> Result<String>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnMut(&[u8]) -> Result<()>,
+    {
+        use std::fs::File;
+
+        use std::io::{BufRead, BufReader};
+
+        let file_name = path.as_ref().file_name().unwrap();
+        let file_name = file_name.to_str().unwrap().to_string();
+
+        let file = File::open(path)?;
+
+        let mut reader = BufReader::new(file);
+
+        let mut buf: Vec<u8> = Vec::new();
+
+        loop {
+            buf.clear();
+
+            let read = reader.read_until(b'\n', &mut buf)?;
+
+            if read == 0 {
+                break;
+            }
+
+            let line = buf[0..read].trim();
+
+            if line.is_empty()
+                || line[0] == b'#'
+                || line.starts_with(b"track")
+                || line.starts_with(b"browser")
+            {
+                continue;
+            }
+
+            f(line)?;
+        }
+
+        Ok(file_name)
+    }
+
+    pub fn parse_bedgraph_file<P: AsRef<std::path::Path>>(
+        path: P,
+    ) -> Result<Self> {
+        let mut intervals = Vec::new();
+
+        let file_name = Self::read_lines(path, |line| {
+            let mut fields = line.fields();
+
+            let interval = (|| {
+                let chr = fields.next()?.to_owned();
+                let start = parse_field(fields.next()?)?;
+                let end = parse_field(fields.next()?)?;
+                let value = parse_field(fields.next()?)?;
+
+                Some(SignalInterval {
+                    chr,
+                    start,
+                    end,
+                    value,
+                })
+            })();
+
+            if let Some(interval) = interval {
+                intervals.push(interval);
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Self {
+            file_name,
+            intervals,
+        })
+    }
+
+    pub fn parse_wig_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let mut intervals = Vec::new();
+
+        let mut chr: Vec<u8> = Vec::new();
+        let mut wig_step: Option<WigStep> = None;
+
+        let file_name = Self::read_lines(path, |line| {
+            if line.starts_with(b"variableStep")
+                || line.starts_with(b"fixedStep")
+            {
+                let get = |key: &str| wig_declaration_value(line, key);
+
+                chr = get("chrom")
+                    .map(|c| c.to_owned())
+                    .ok_or_else(|| anyhow::anyhow!("WIG line without chrom"))?;
+
+                let span = get("span").and_then(parse_field).unwrap_or(1);
+
+                if line.starts_with(b"variableStep") {
+                    wig_step = Some(WigStep::Variable { span });
+                } else {
+                    let start: usize =
+                        get("start").and_then(parse_field).ok_or_else(
+                            || anyhow::anyhow!("fixedStep line without start"),
+                        )?;
+                    let step = get("step").and_then(parse_field).unwrap_or(1);
+
+                    wig_step = Some(WigStep::Fixed {
+                        // WIG positions are 1-based
+                        next: start.max(1) - 1,
+                        step,
+                        span,
+                    });
+                }
+
+                return Ok(());
+            }
+
+            match wig_step.as_mut() {
+                Some(WigStep::Variable { span }) => {
+                    let mut fields = line.fields();
+
+                    let pos: Option<usize> =
+                        fields.next().and_then(parse_field);
+                    let value: Option<f32> =
+                        fields.next().and_then(parse_field);
+
+                    if let (Some(pos), Some(value)) = (pos, value) {
+                        let start = pos.max(1) - 1;
+                        intervals.push(SignalInterval {
+                            chr: chr.clone(),
+                            start,
+                            end: start + *span,
+                            value,
+                        });
+                    }
+                }
+                Some(WigStep::Fixed { next, step, span }) => {
+                    if let Some(value) = parse_field::<f32>(line) {
+                        intervals.push(SignalInterval {
+                            chr: chr.clone(),
+                            start: *next,
+                            end: *next + *span,
+                            value,
+                        });
+                    }
+                    *next += *step;
+                }
+                None => {
+                    bail!("WIG data line before step declaration");
+                }
+            }
+
+            Ok(())
+        })?;
+
+        Ok(Self {
+            file_name,
+            intervals,
+        })
+    }
+
+    /// Aggregates the signal onto each node in the graph, via the
+    /// coordinates of the reference path. Only intervals on the `chr`
+    /// sequence are used, as the path only covers one sequence.
+    ///
+    /// Returns one entry per node, indexed by node ID - 1; nodes not
+    /// covered by the signal are `None`
+    pub fn node_values(
+        &self,
+        graph: &GraphQuery,
+        path: PathId,
+        chr: &[u8],
+        aggregate: SignalAggregate,
+    ) -> Option<Vec<Option<f32>>> {
+        let steps = graph.path_pos_steps(path)?;
+
+        let offset = graph
+            .graph()
+            .get_path_name_vec(path)
+            .and_then(|name| super::path_name_offset(&name))
+            .unwrap_or(0);
+
+        let node_count = graph.node_count();
+
+        // (sum of value * overlap, total overlap, max value)
+        let mut acc: Vec<(f64, usize, f32)> =
+            vec![(0.0, 0, std::f32::MIN); node_count];
+
+        for interval in self.intervals.iter() {
+            if interval.chr != chr {
+                continue;
+            }
+
+            if interval.end <= offset {
+                continue;
+            }
+
+            let start = interval.start.saturating_sub(offset);
+            let end = interval.end - offset;
+
+            for (handle, _, pos) in overlapping_steps(&steps, start, end) {
+                let node_len = graph.graph().node_len(*handle);
+
+                let overlap_start = start.max(*pos);
+                let overlap_end = end.min(*pos + node_len);
+
+                if overlap_end <= overlap_start {
+                    continue;
+                }
+
+                let overlap = overlap_end - overlap_start;
+
+                let ix = (handle.id().0 - 1) as usize;
+                if let Some((sum, len, max)) = acc.get_mut(ix) {
+                    *sum += (interval.value as f64) * (overlap as f64);
+                    *len += overlap;
+                    *max = (*max).max(interval.value);
+                }
+            }
+        }
+
+        let values = acc
+            .into_iter()
+            .map(|(sum, len, max)| {
+                if len == 0 {
+                    return None;
+                }
+
+                let v = match aggregate {
+                    SignalAggregate::Mean => (sum / len as f64) as f32,
+                    SignalAggregate::Max => max,
+                    SignalAggregate::Sum => sum as f32,
+                };
+
+                Some(v)
+            })
+            .collect();
+
+        Some(values)
+    }
+}
+
+/// Returns the steps whose node overlaps the (offset-adjusted) range
+/// `start..end`, including a node that begins before `start`
+fn overlapping_steps(
+    steps: &[(Handle, StepPtr, usize)],
+    start: usize,
+    end: usize,
+) -> &[(Handle, StepPtr, usize)] {
+    let first = steps
+        .partition_point(|(_, _, p)| *p <= start)
+        .saturating_sub(1);
+    let last = steps.partition_point(|(_, _, p)| *p < end);
+
+    if first >= last {
+        &[]
+    } else {
+        &steps[first..last]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_text(file_name: &str, text: &str) -> Result<SignalTrack> {
+        let dir = std::env::temp_dir()
+            .join(format!("gfaestus-signal-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join(file_name);
+        std::fs::write(&path, text).unwrap();
+
+        let track = SignalTrack::parse_signal_file(&path);
+
+        std::fs::remove_file(&path).unwrap();
+
+        track
+    }
+
+    fn intervals(track: &SignalTrack) -> Vec<(&[u8], usize, usize, f32)> {
+        track
+            .intervals
+            .iter()
+            .map(|i| (i.chr.as_slice(), i.start, i.end, i.value))
+            .collect()
+    }
+
+    #[test]
+    fn bedgraph_intervals_stay_zero_based() {
+        let text = "track type=bedGraph name=test\n\
+                    # comment\n\
+                    chr2\t0\t10\t1.5\n\
+                    chr1\t10\t25\t-2\n\
+                    chr1\tnot_a_number\t30\t1\n\
+                    \n\
+                    chr2 40 45 0.25\n";
+
+        let track = parse_text("test.bedGraph", text).unwrap();
+
+        assert_eq!(track.file_name(), "test.bedGraph");
+        assert_eq!(
+            intervals(&track),
+            vec![
+                (&b"chr2"[..], 0, 10, 1.5),
+                (&b"chr1"[..], 10, 25, -2.0),
+                (&b"chr2"[..], 40, 45, 0.25),
+            ]
+        );
+        assert_eq!(
+            track.chromosomes(),
+            vec![b"chr2".to_vec(), b"chr1".to_vec()]
+        );
+    }
+
+    #[test]
+    fn wig_fixed_step_start_step_span() {
+        let text = "track type=wiggle_0\n\
+                    fixedStep chrom=chr1 start=11 step=10 span=5\n\
+                    1.0\n\
+                    2.0\n\
+                    3.0\n\
+                    fixedStep chrom=chr2 start=1\n\
+                    4.0\n\
+                    5.0\n";
+
+        let track = parse_text("fixed.wig", text).unwrap();
+
+        assert_eq!(
+            intervals(&track),
+            vec![
+                (&b"chr1"[..], 10, 15, 1.0),
+                (&b"chr1"[..], 20, 25, 2.0),
+                (&b"chr1"[..], 30, 35, 3.0),
+                // step and span default to 1
+                (&b"chr2"[..], 0, 1, 4.0),
+                (&b"chr2"[..], 1, 2, 5.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn wig_fixed_step_skips_unparsable_values() {
+        let text = "fixedStep chrom=chr1 start=1 step=2\n\
+                    1.0\n\
+                    NA\n\
+                    3.0\n";
+
+        let track = parse_text("fixed_na.wig", text).unwrap();
+
+        // the position still advances past the unparsable value
+        assert_eq!(
+            intervals(&track),
+            vec![(&b"chr1"[..], 0, 1, 1.0), (&b"chr1"[..], 4, 5, 3.0)]
+        );
+    }
+
+    #[test]
+    fn wig_variable_step_positions_and_span() {
+        let text = "variableStep chrom=chr3 span=3\n\
+                    1 0.5\n\
+                    101\t2.0\n\
+                    variableStep chrom=chr4\n\
+                    50 1.0\n";
+
+        let track = parse_text("variable.WIG", text).unwrap();
+
+        assert_eq!(
+            intervals(&track),
+            vec![
+                (&b"chr3"[..], 0, 3, 0.5),
+                (&b"chr3"[..], 100, 103, 2.0),
+                (&b"chr4"[..], 49, 50, 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn wig_errors() {
+        assert!(parse_text("no_step.wig", "1 0.5\n").is_err());
+        assert!(parse_text("no_chrom.wig", "variableStep span=2\n").is_err());
+        assert!(parse_text("no_start.wig", "fixedStep chrom=chr1\n").is_err());
+    }
+
+    #[test]
+    fn unknown_extension() {
+        assert!(parse_text("unknown.bed", "chr1\t0\t10\t1.0\n").is_err());
+    }
+}
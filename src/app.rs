@@ -1,8 +1,11 @@
+pub mod bookmarks;
 pub mod channels;
 pub mod mainview;
+pub mod measure;
 pub mod selection;
 pub mod settings;
 pub mod shared_state;
+pub mod tour;
 
 pub use channels::*;
 use handlegraph::pathhandlegraph::PathId;
@@ -27,9 +30,9 @@ use std::sync::Arc;
 use self::mainview::MainViewMsg;
 use crate::annotations::{
     AnnotationCollection, AnnotationLabelSet, Annotations, BedRecords,
-    Gff3Records, LabelSet, Labels,
+    GafRecords, Gff3Records, LabelSet, Labels,
 };
-use crate::app::selection::NodeSelection;
+use crate::app::selection::{GrowOp, NodeSelection};
 use crate::graph_query::GraphQuery;
 use crate::gui::GuiMsg;
 use crate::reactor::Reactor;
@@ -65,6 +68,26 @@ pub struct App {
 pub enum AppInput {
     KeyClearSelection,
     KeyToggleTheme,
+
+    KeyGrowSelection,
+    KeyGrowSelectionDistance,
+    KeyGrowSelectionPath,
+    KeySelectBubble,
+    KeyShrinkSelection,
+}
+
+impl AppInput {
+    /// The selection operation bound to the input, if any
+    fn grow_op(&self) -> Option<GrowOp> {
+        match self {
+            AppInput::KeyGrowSelection => Some(GrowOp::Hops),
+            AppInput::KeyGrowSelectionDistance => Some(GrowOp::Distance),
+            AppInput::KeyGrowSelectionPath => Some(GrowOp::PathSteps),
+            AppInput::KeySelectBubble => Some(GrowOp::Bubble),
+            AppInput::KeyShrinkSelection => Some(GrowOp::Shrink),
+            _ => None,
+        }
+    }
 }
 
 impl BindableInput for AppInput {
@@ -72,7 +95,7 @@ impl BindableInput for AppInput {
         use winit::event::VirtualKeyCode as Key;
         use AppInput as Input;
 
-        let key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
+        let mut key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
             (Key::Escape, Input::KeyClearSelection),
             (Key::F9, Input::KeyToggleTheme),
         ]
@@ -81,12 +104,55 @@ impl BindableInput for AppInput {
         .map(|(k, i)| (k, vec![KeyBind::new(i)]))
         .collect::<FxHashMap<_, _>>();
 
+        let shift_mod = winit::event::ModifiersState::SHIFT;
+        let ctrl_mod = winit::event::ModifiersState::CTRL;
+        let alt_mod = winit::event::ModifiersState::ALT;
+
+        key_binds.insert(
+            Key::Equals,
+            vec![
+                KeyBind::with_modifiers(Input::KeyGrowSelection, ctrl_mod),
+                KeyBind::with_modifiers(
+                    Input::KeyGrowSelectionDistance,
+                    ctrl_mod | shift_mod,
+                ),
+                KeyBind::with_modifiers(
+                    Input::KeyGrowSelectionPath,
+                    ctrl_mod | alt_mod,
+                ),
+            ],
+        );
+
+        key_binds.insert(
+            Key::Minus,
+            vec![KeyBind::with_modifiers(Input::KeyShrinkSelection, ctrl_mod)],
+        );
+
+        key_binds.insert(
+            Key::B,
+            vec![KeyBind::with_modifiers(Input::KeySelectBubble, ctrl_mod)],
+        );
+
         let mouse_binds = FxHashMap::default();
 
         let wheel_bind = None;
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    fn inputs() -> Vec<Self> {
+        use AppInput as Input;
+
+        vec![
+            Input::KeyClearSelection,
+            Input::KeyToggleTheme,
+            Input::KeyGrowSelection,
+            Input::KeyGrowSelectionDistance,
+            Input::KeyGrowSelectionPath,
+            Input::KeySelectBubble,
+            Input::KeyShrinkSelection,
+        ]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -100,6 +166,31 @@ pub enum Select {
         nodes: FxHashSet<NodeId>,
         clear: bool,
     },
+    Remove {
+        nodes: FxHashSet<NodeId>,
+    },
+}
+
+/// How the result of a region selection is combined with the current
+/// selection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectOp {
+    Replace,
+    Add,
+    Subtract,
+}
+
+impl SelectOp {
+    pub fn apply(&self, nodes: FxHashSet<NodeId>) -> Select {
+        match self {
+            SelectOp::Replace => Select::Many { nodes, clear: true },
+            SelectOp::Add => Select::Many {
+                nodes,
+                clear: false,
+            },
+            SelectOp::Subtract => Select::Remove { nodes },
+        }
+    }
 }
 
 // #[derive(Debug)]
@@ -108,7 +199,8 @@ pub enum AppMsg {
     Selection(Select),
 
     // TODO these two should not be here (see how they're handled in main)
-    RectSelect(Rect),
+    RectSelect(Rect, SelectOp),
+    PolygonSelect(Vec<Point>, SelectOp),
     TranslateSelected(Point),
 
     NewNodeLabels {
@@ -118,6 +210,8 @@ pub enum AppMsg {
 
     RequestSelection(crossbeam::channel::Sender<(Rect, FxHashSet<NodeId>)>),
 
+    GrowSelection(GrowOp),
+
     SetData {
         key: String,
         index: String,
@@ -165,6 +259,10 @@ impl AppMsg {
         Self::raw("add_bed_records", records)
     }
 
+    pub fn add_gaf_records(records: GafRecords) -> Self {
+        Self::raw("add_gaf_records", records)
+    }
+
     pub fn goto_node(id: NodeId) -> Self {
         Self::raw("goto_node", id)
     }
@@ -173,6 +271,11 @@ impl AppMsg {
         Self::raw("goto_rect", Some(rect))
     }
 
+    /// Animates the view to `view`
+    pub fn goto_view(view: View) -> Self {
+        Self::raw("goto_view", view)
+    }
+
     pub fn goto_selection() -> Self {
         Self::raw::<Option<Rect>>("goto_rect", None)
     }
@@ -241,6 +344,13 @@ impl App {
         let mut msg_handlers = HashMap::default();
         Self::add_msg_handlers(&mut msg_handlers);
 
+        let settings = AppSettings::default();
+        settings.store().add_app_settings(
+            &settings,
+            &shared_state,
+            &channels.gui_tx,
+        );
+
         Ok(Self {
             shared_state,
             channels,
@@ -254,7 +364,7 @@ impl App {
 
             selected_nodes_bounding_box: None,
 
-            settings: AppSettings::default(),
+            settings,
 
             annotations: Annotations::default(),
 
@@ -316,6 +426,40 @@ impl App {
         self.selection_changed
     }
 
+    /// The currently selected nodes
+    pub fn selection(&self) -> &FxHashSet<NodeId> {
+        &self.selected_nodes
+    }
+
+    /// Replaces the selection with the result of applying `op` to it;
+    /// the new selection is computed on the reactor
+    pub fn grow_selection(&self, op: GrowOp) {
+        let graph = self.reactor.graph_query.clone();
+        let settings = self.settings.grow_settings().clone();
+        let app_tx = self.channels.app_tx.clone();
+        let nodes = self.selected_nodes.clone();
+
+        let result = self.reactor.spawn_forget(async move {
+            match op.apply(&graph, &settings, &nodes) {
+                Ok(nodes) => {
+                    app_tx
+                        .send(AppMsg::Selection(Select::Many {
+                            nodes,
+                            clear: true,
+                        }))
+                        .unwrap();
+                }
+                Err(err) => {
+                    log::warn!("{}: {}", op.name(), err);
+                }
+            }
+        });
+
+        if let Err(err) = result {
+            log::error!("Error spawning selection task: {}", err);
+        }
+    }
+
     pub fn selected_nodes_(&self) -> Option<(Rect, &FxHashSet<NodeId>)> {
         log::warn!(
             "self.selected_nodes.is_empty() = {}",
@@ -405,6 +549,16 @@ impl App {
             }),
         );
 
+        new_handler(
+            "goto_view",
+            AppMsgHandler::from_fn(|app, _nodes, view: &View| {
+                app.channels
+                    .main_view_tx
+                    .send(MainViewMsg::GotoView(*view))
+                    .unwrap();
+            }),
+        );
+
         new_handler(
             "goto_rect",
             AppMsgHandler::from_fn(|app, _nodes, rect: &Option<Rect>| {
@@ -444,6 +598,14 @@ impl App {
             }),
         );
 
+        new_handler(
+            "add_gaf_records",
+            AppMsgHandler::from_fn(|app, _nodes, records: &Arc<GafRecords>| {
+                let file_name = records.file_name().to_string();
+                app.annotations.insert_gaf_arc(&file_name, records.clone());
+            }),
+        );
+
         new_handler(
             "toggle_dark_mode",
             AppMsgHandler::from_fn(|app, _nodes, _: &()| {
@@ -565,7 +727,10 @@ impl App {
         msg: AppMsg,
     ) {
         match msg {
-            AppMsg::RectSelect(_rect) => {
+            AppMsg::RectSelect(_rect, _op) => {
+                //
+            }
+            AppMsg::PolygonSelect(_polygon, _op) => {
                 //
             }
             AppMsg::TranslateSelected(delta) => {
@@ -691,6 +856,36 @@ impl App {
                     self.selected_nodes_bounding_box =
                         Some((top_left, bottom_right));
                 }
+                Select::Remove { nodes } => {
+                    self.selection_changed = true;
+                    self.selected_nodes.retain(|node| !nodes.contains(node));
+
+                    self.selected_nodes_bounding_box = None;
+
+                    for &node in self.selected_nodes.iter() {
+                        let pos = node_positions[(node.0 - 1) as usize];
+
+                        let min = Point {
+                            x: pos.p0.x.min(pos.p1.x),
+                            y: pos.p0.y.min(pos.p1.y),
+                        };
+
+                        let max = Point {
+                            x: pos.p0.x.max(pos.p1.x),
+                            y: pos.p0.y.max(pos.p1.y),
+                        };
+
+                        let bounds = match self.selected_nodes_bounding_box {
+                            Some((p0, p1)) => (
+                                Point::new(p0.x.min(min.x), p0.y.min(min.y)),
+                                Point::new(p1.x.max(max.x), p1.y.max(max.y)),
+                            ),
+                            None => (min, max),
+                        };
+
+                        self.selected_nodes_bounding_box = Some(bounds);
+                    }
+                }
             },
             AppMsg::NewNodeLabels { name, label_set } => {
                 let label_set_ = label_set.label_set();
@@ -712,6 +907,11 @@ impl App {
 
                 sender.send((rect, selection)).unwrap();
             }
+            AppMsg::GrowSelection(op) => {
+                if !self.selected_nodes.is_empty() {
+                    self.grow_selection(op);
+                }
+            }
 
             AppMsg::SetData { key, index, value } => {
                 self.send_msg(AppMsg::set_data(key, index, value)).unwrap();
@@ -767,7 +967,17 @@ impl App {
         if let SystemInput::Keyboard { state, payload } = input {
             match payload {
                 AppInput::KeyClearSelection => {
-                    if state.pressed() {
+                    let shared_state = &self.shared_state;
+
+                    // cancel the lasso or polygon being drawn, if any,
+                    // instead of clearing the selection
+                    if shared_state.is_started_mouse_polygon()
+                        || shared_state.is_started_mouse_rect()
+                    {
+                        if state.pressed() {
+                            shared_state.cancel_mouse_region();
+                        }
+                    } else if state.pressed() {
                         self.selection_changed = true;
                         self.selected_nodes.clear();
                         self.selected_nodes_bounding_box = None;
@@ -778,6 +988,17 @@ impl App {
                         self.toggle_dark_mode();
                     }
                 }
+                AppInput::KeyGrowSelection
+                | AppInput::KeyGrowSelectionDistance
+                | AppInput::KeyGrowSelectionPath
+                | AppInput::KeySelectBubble
+                | AppInput::KeyShrinkSelection => {
+                    if let Some(op) = payload.grow_op() {
+                        if state.pressed() && !self.selected_nodes.is_empty() {
+                            self.grow_selection(op);
+                        }
+                    }
+                }
             }
         }
     }
@@ -839,6 +1060,10 @@ pub struct Args {
     #[argh(option)]
     pub run_script: Option<String>,
 
+    /// the keybindings file to load at startup, defaults to keybindings.rhai in the working directory or the config directory
+    #[argh(option)]
+    pub keybindings: Option<String>,
+
     #[cfg(target_os = "linux")]
     /// force use of X11 window (only applicable in Wayland contexts)
     #[argh(switch)]
@@ -865,7 +1090,7 @@ pub struct Args {
     #[argh(option)]
     pub force_graphics_device: Option<String>,
 
-    /// path .gff3 and/or .bed file to load at startup, can be used multiple times to load several files
+    /// path .gff3, .gtf, .bed, and/or .gaf file to load at startup, can be used multiple times to load several files
     #[argh(
         option,
         long = "annotation-file",
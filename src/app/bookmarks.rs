@@ -0,0 +1,283 @@
+use handlegraph::handle::NodeId;
+
+use rustc_hash::FxHashSet;
+
+use anyhow::{anyhow, bail, Result};
+
+use std::path::Path;
+
+use crate::geometry::Point;
+use crate::view::View;
+
+/// A named view, optionally with a selection and notes; as the view
+/// is in layout coordinates, bookmarks are only meaningful for the
+/// layout they were created on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub view: View,
+    pub selection: Option<FxHashSet<NodeId>>,
+    pub notes: String,
+}
+
+impl Bookmark {
+    const TSV_HEADER: &'static str = "#name\tx\ty\tscale\tnodes\tnotes";
+
+    fn to_tsv_line(&self) -> String {
+        let nodes = self
+            .selection
+            .as_ref()
+            .map(|nodes| {
+                let mut nodes = nodes.iter().map(|n| n.0).collect::<Vec<_>>();
+                nodes.sort_unstable();
+                nodes
+                    .into_iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .unwrap_or_default();
+
+        let mut name = escape_field(&self.name);
+
+        // lines starting with # are comments
+        if name.starts_with('#') {
+            name.insert(0, '\\');
+        }
+
+        format!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            name,
+            self.view.center.x,
+            self.view.center.y,
+            self.view.scale,
+            nodes,
+            escape_field(&self.notes)
+        )
+    }
+
+    /// Parses a line written by `to_tsv_line`; node IDs outside
+    /// `1..=node_count` are rejected
+    fn from_tsv_line(line: &str, node_count: usize) -> Result<Self> {
+        let fields = line.split('\t').collect::<Vec<_>>();
+
+        if fields.len() < 4 {
+            bail!("expected at least 4 columns, found {}", fields.len());
+        }
+
+        let parse_f32 = |col: usize, name: &str| {
+            fields[col]
+                .trim()
+                .parse::<f32>()
+                .map_err(|_| anyhow!("invalid {}: \"{}\"", name, fields[col]))
+        };
+
+        let center = Point::new(parse_f32(1, "x")?, parse_f32(2, "y")?);
+        let scale = parse_f32(3, "scale")?;
+
+        if !scale.is_finite() || scale <= 0.0 {
+            bail!("scale must be positive");
+        }
+
+        let selection = match fields.get(4).map(|f| f.trim()) {
+            Some(nodes) if !nodes.is_empty() => {
+                let nodes = nodes
+                    .split(',')
+                    .map(|n| {
+                        let id = n
+                            .trim()
+                            .parse::<u64>()
+                            .map_err(|_| anyhow!("invalid node ID: \"{}\"", n))?;
+
+                        if id == 0 || id as usize > node_count {
+                            bail!(
+                                "node {} is not in the graph, which has {} nodes",
+                                id,
+                                node_count
+                            );
+                        }
+
+                        Ok(NodeId::from(id))
+                    })
+                    .collect::<Result<FxHashSet<_>>>()?;
+                Some(nodes)
+            }
+            _ => None,
+        };
+
+        let notes =
+            fields.get(5).map(|f| unescape_field(f)).unwrap_or_default();
+
+        Ok(Self {
+            name: unescape_field(fields[0]),
+            view: View { center, scale },
+            selection,
+            notes,
+        })
+    }
+}
+
+/// Reads bookmarks from a TSV file with the columns name, x, y,
+/// scale, nodes (comma-separated, may be empty), and notes
+pub fn read_bookmarks<P: AsRef<Path>>(
+    path: P,
+    node_count: usize,
+) -> Result<Vec<Bookmark>> {
+    let text = std::fs::read_to_string(path)?;
+
+    let mut bookmarks = Vec::new();
+
+    for (ix, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let bookmark = Bookmark::from_tsv_line(line, node_count)
+            .map_err(|err| anyhow!("Line {}: {}", ix + 1, err))?;
+
+        bookmarks.push(bookmark);
+    }
+
+    Ok(bookmarks)
+}
+
+pub fn write_bookmarks<P: AsRef<Path>>(
+    bookmarks: &[Bookmark],
+    path: P,
+) -> Result<()> {
+    let mut text = String::from(Bookmark::TSV_HEADER);
+    text.push('\n');
+
+    for bookmark in bookmarks {
+        text.push_str(&bookmark.to_tsv_line());
+        text.push('\n');
+    }
+
+    std::fs::write(path, text)?;
+
+    Ok(())
+}
+
+/// Tabs and newlines are escaped so that names and notes fit in a
+/// single TSV field
+fn escape_field(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn unescape_field(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(name: &str, nodes: Option<&[u64]>, notes: &str) -> Bookmark {
+        Bookmark {
+            name: name.to_string(),
+            view: View {
+                center: Point::new(-120.5, 3400.25),
+                scale: 12.5,
+            },
+            selection: nodes
+                .map(|nodes| nodes.iter().map(|&n| NodeId::from(n)).collect()),
+            notes: notes.to_string(),
+        }
+    }
+
+    fn roundtrip(bookmark: &Bookmark) -> Bookmark {
+        let line = bookmark.to_tsv_line();
+
+        assert!(!line.contains('\n'));
+        assert!(!line.starts_with('#'));
+        assert_eq!(line.split('\t').count(), 6);
+
+        Bookmark::from_tsv_line(&line, 100).unwrap()
+    }
+
+    #[test]
+    fn tsv_roundtrip() {
+        let bm = bookmark("region of interest", Some(&[5, 1, 42]), "a note");
+        assert_eq!(roundtrip(&bm), bm);
+    }
+
+    #[test]
+    fn tsv_roundtrip_hash_name() {
+        let bm = bookmark("#1 insertion", None, "");
+        assert_eq!(roundtrip(&bm), bm);
+
+        let bm = bookmark("\\#not a comment", None, "");
+        assert_eq!(roundtrip(&bm), bm);
+    }
+
+    #[test]
+    fn tsv_roundtrip_escapes() {
+        let bm = bookmark(
+            "tab\tin name",
+            Some(&[7]),
+            "first line\n\tindented\nback\\slash \\t",
+        );
+        assert_eq!(roundtrip(&bm), bm);
+    }
+
+    #[test]
+    fn tsv_empty_nodes() {
+        let bm = bookmark("no selection", None, "notes after empty nodes");
+        let line = bm.to_tsv_line();
+
+        assert!(line.contains("\t\tnotes after empty nodes"));
+        assert_eq!(roundtrip(&bm), bm);
+
+        // the trailing columns are optional
+        let short = Bookmark::from_tsv_line("name\t1\t2\t3", 100).unwrap();
+        assert_eq!(short.selection, None);
+        assert_eq!(short.notes, "");
+    }
+
+    #[test]
+    fn tsv_invalid_nodes() {
+        let line = "name\t1\t2\t3\t1,101\t";
+        assert!(Bookmark::from_tsv_line(line, 100).is_err());
+        assert!(Bookmark::from_tsv_line(line, 101).is_ok());
+
+        assert!(Bookmark::from_tsv_line("name\t1\t2\t3\t0", 100).is_err());
+        assert!(Bookmark::from_tsv_line("name\t1\t2\t3\t1,x", 100).is_err());
+    }
+
+    #[test]
+    fn tsv_invalid_view() {
+        assert!(Bookmark::from_tsv_line("name\t1\t2", 100).is_err());
+        assert!(Bookmark::from_tsv_line("name\tx\t2\t3", 100).is_err());
+        assert!(Bookmark::from_tsv_line("name\t1\t2\t0", 100).is_err());
+        assert!(Bookmark::from_tsv_line("name\t1\t2\t-1", 100).is_err());
+    }
+}
@@ -208,12 +208,11 @@ impl MainView {
         render_pass: vk::RenderPass,
         framebuffers: &Framebuffers,
         screen_dims: [f32; 2],
+        view: View,
         offset: Point,
         overlay_id: Option<usize>,
         color_scheme: &GradientTexture,
     ) -> Result<()> {
-        let view = self.shared_state.view();
-
         let node_width = {
             let min = self.node_width.min_node_width();
             let max = self.node_width.max_node_width();
@@ -340,6 +339,60 @@ impl MainView {
         }
     }
 
+    fn region_select(&self, pressed: bool, op: crate::app::SelectOp) {
+        use crate::app::{AppMsg, SelectionTool};
+
+        match self.shared_state.selection_tool() {
+            SelectionTool::Rectangle => {
+                if pressed {
+                    self.shared_state.start_mouse_rect(op);
+                } else if self.shared_state.is_started_mouse_rect() {
+                    if let Some((rect, op)) =
+                        self.shared_state.close_mouse_rect_world()
+                    {
+                        self.channels
+                            .app_tx
+                            .send(AppMsg::RectSelect(rect, op))
+                            .unwrap();
+                    }
+                }
+            }
+            SelectionTool::Lasso => {
+                if pressed {
+                    self.shared_state.start_mouse_polygon(op);
+                } else {
+                    self.close_mouse_polygon();
+                }
+            }
+            SelectionTool::Polygon => {
+                if !pressed {
+                    return;
+                }
+
+                if !self.shared_state.is_started_mouse_polygon() {
+                    self.shared_state.start_mouse_polygon(op);
+                } else if self.shared_state.mouse_near_polygon_start() {
+                    self.close_mouse_polygon();
+                } else {
+                    self.shared_state.push_mouse_polygon_point();
+                }
+            }
+        }
+    }
+
+    fn close_mouse_polygon(&self) {
+        use crate::app::AppMsg;
+
+        if let Some((polygon, op)) =
+            self.shared_state.close_mouse_polygon_world()
+        {
+            self.channels
+                .app_tx
+                .send(AppMsg::PolygonSelect(polygon, op))
+                .unwrap();
+        }
+    }
+
     pub fn apply_input<Dims: Into<ScreenDims>>(
         &self,
         screen_dims: Dims,
@@ -371,6 +424,11 @@ impl MainView {
                             self.reset_view();
                         }
                     }
+                    In::KeyClosePolygon => {
+                        if pressed {
+                            self.close_mouse_polygon();
+                        }
+                    }
                     _ => (),
                 }
             }
@@ -424,6 +482,10 @@ impl MainView {
                         use crate::app::AppMsg;
                         use crate::app::Select;
 
+                        if self.shared_state.measuring() {
+                            return;
+                        }
+
                         let selected_node = self
                             .read_node_id_at(pos)
                             .map(|nid| NodeId::from(nid as u64));
@@ -439,23 +501,20 @@ impl MainView {
                         }
                     }
 
-                    In::ButtonRectangleSelect => {
-                        use crate::app::AppMsg;
+                    In::ButtonRegionSelect
+                    | In::ButtonRegionSelectAdd
+                    | In::ButtonRegionSelectSubtract => {
+                        use crate::app::SelectOp;
 
-                        if pressed {
-                            self.shared_state.start_mouse_rect();
-                        } else {
-                            if self.shared_state.is_started_mouse_rect() {
-                                if let Some(rect) =
-                                    self.shared_state.close_mouse_rect_world()
-                                {
-                                    self.channels
-                                        .app_tx
-                                        .send(AppMsg::RectSelect(rect))
-                                        .unwrap();
-                                }
+                        let op = match payload {
+                            In::ButtonRegionSelectAdd => SelectOp::Add,
+                            In::ButtonRegionSelectSubtract => {
+                                SelectOp::Subtract
                             }
-                        }
+                            _ => SelectOp::Replace,
+                        };
+
+                        self.region_select(pressed, op);
                     }
                     _ => (),
                 }
@@ -477,12 +536,15 @@ impl MainView {
 pub enum MainViewInput {
     ButtonMousePan,
     ButtonSelect,
-    ButtonRectangleSelect,
+    ButtonRegionSelect,
+    ButtonRegionSelectAdd,
+    ButtonRegionSelectSubtract,
     KeyPanUp,
     KeyPanRight,
     KeyPanDown,
     KeyPanLeft,
     KeyResetView,
+    KeyClosePolygon,
     WheelZoom,
 }
 
@@ -498,6 +560,7 @@ impl BindableInput for MainViewInput {
             (Key::Left, Input::KeyPanLeft),
             (Key::Right, Input::KeyPanRight),
             (Key::Space, Input::KeyResetView),
+            (Key::Return, Input::KeyClosePolygon),
         ]
         .iter()
         .copied()
@@ -506,6 +569,7 @@ impl BindableInput for MainViewInput {
 
         let shift_mod = winit::event::ModifiersState::SHIFT;
         let ctrl_mod = winit::event::ModifiersState::CTRL;
+        let alt_mod = winit::event::ModifiersState::ALT;
 
         let mouse_binds: FxHashMap<
             event::MouseButton,
@@ -516,13 +580,21 @@ impl BindableInput for MainViewInput {
                 vec![
                     MouseButtonBind::new(Input::ButtonMousePan),
                     MouseButtonBind::with_modifiers(
-                        Input::ButtonRectangleSelect,
+                        Input::ButtonRegionSelect,
                         shift_mod,
                     ),
+                    MouseButtonBind::with_modifiers(
+                        Input::ButtonRegionSelectAdd,
+                        shift_mod | ctrl_mod,
+                    ),
+                    MouseButtonBind::with_modifiers(
+                        Input::ButtonRegionSelectSubtract,
+                        shift_mod | alt_mod,
+                    ),
                     MouseButtonBind::with_modifiers(
                         Input::ButtonSelect,
                         ctrl_mod,
-                    )
+                    ),
                 ],
             ),
             // (
@@ -538,4 +610,23 @@ impl BindableInput for MainViewInput {
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    fn inputs() -> Vec<Self> {
+        use MainViewInput as Input;
+
+        vec![
+            Input::ButtonMousePan,
+            Input::ButtonSelect,
+            Input::ButtonRegionSelect,
+            Input::ButtonRegionSelectAdd,
+            Input::ButtonRegionSelectSubtract,
+            Input::KeyPanUp,
+            Input::KeyPanRight,
+            Input::KeyPanDown,
+            Input::KeyPanLeft,
+            Input::KeyResetView,
+            Input::KeyClosePolygon,
+            Input::WheelZoom,
+        ]
+    }
 }
@@ -0,0 +1,200 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+use bstr::ByteSlice;
+use handlegraph::{
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::app::selection::grow::node_neighbors;
+use crate::graph_query::GraphQuery;
+use crate::universe::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphDistance {
+    /// The fewest edges between the nodes
+    pub hops: usize,
+    /// The shortest total length of the nodes between the nodes,
+    /// ignoring orientation
+    pub bp: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathDistance {
+    pub path: PathId,
+    pub name: String,
+    /// The distance between the starts of the closest steps on the
+    /// two nodes
+    pub bp: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeDistances {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub graph: Option<GraphDistance>,
+    /// The paths that visit both nodes
+    pub paths: Vec<PathDistance>,
+}
+
+impl NodeDistances {
+    pub fn compute(graph: &GraphQuery, from: NodeId, to: NodeId) -> Self {
+        let packed = graph.graph();
+
+        let graph_dist = graph_distance(packed, from, to);
+        let paths = path_distances(graph, from, to);
+
+        Self {
+            from,
+            to,
+            graph: graph_dist,
+            paths,
+        }
+    }
+}
+
+pub fn graph_distance(
+    graph: &PackedGraph,
+    from: NodeId,
+    to: NodeId,
+) -> Option<GraphDistance> {
+    if from == to {
+        return Some(GraphDistance { hops: 0, bp: 0 });
+    }
+
+    let hops = {
+        let mut visited: FxHashSet<NodeId> = FxHashSet::default();
+        let mut queue = VecDeque::new();
+
+        visited.insert(from);
+        queue.push_back((from, 0usize));
+
+        let mut found = None;
+
+        while let Some((node, hops)) = queue.pop_front() {
+            if node == to {
+                found = Some(hops);
+                break;
+            }
+
+            for other in node_neighbors(graph, node) {
+                if visited.insert(other) {
+                    queue.push_back((other, hops + 1));
+                }
+            }
+        }
+
+        found?
+    };
+
+    // same as in `grow_by_distance`, the length of a node is added
+    // when leaving it, and the endpoints don't count
+    let mut dists: FxHashMap<NodeId, usize> = FxHashMap::default();
+    let mut queue = BinaryHeap::new();
+
+    dists.insert(from, 0);
+    queue.push(Reverse((0usize, from)));
+
+    let mut bp = None;
+
+    while let Some(Reverse((dist, node))) = queue.pop() {
+        if node == to {
+            bp = Some(dist);
+            break;
+        }
+
+        if dists.get(&node).map(|&d| d < dist).unwrap_or(false) {
+            continue;
+        }
+
+        let len = if node == from {
+            0
+        } else {
+            graph.node_len(Handle::pack(node, false))
+        };
+
+        let next_dist = dist + len;
+
+        for other in node_neighbors(graph, node) {
+            let closer =
+                dists.get(&other).map(|&d| next_dist < d).unwrap_or(true);
+
+            if closer {
+                dists.insert(other, next_dist);
+                queue.push(Reverse((next_dist, other)));
+            }
+        }
+    }
+
+    Some(GraphDistance { hops, bp: bp? })
+}
+
+pub fn path_distances(
+    graph: &GraphQuery,
+    from: NodeId,
+    to: NodeId,
+) -> Vec<PathDistance> {
+    let positions = |node: NodeId| {
+        let mut positions: FxHashMap<PathId, Vec<usize>> = FxHashMap::default();
+
+        if let Some(steps) = graph.handle_positions(Handle::pack(node, false)) {
+            for (path, _, pos) in steps {
+                positions.entry(path).or_default().push(pos);
+            }
+        }
+
+        positions
+    };
+
+    let from_pos = positions(from);
+    let to_pos = positions(to);
+
+    let mut result = from_pos
+        .iter()
+        .filter_map(|(path, from_pos)| {
+            let to_pos = to_pos.get(path)?;
+
+            let bp = from_pos
+                .iter()
+                .flat_map(|&a| to_pos.iter().map(move |&b| a.max(b) - a.min(b)))
+                .min()?;
+
+            let name = graph.graph().get_path_name_vec(*path)?;
+
+            Some(PathDistance {
+                path: *path,
+                name: name.to_str_lossy().to_string(),
+                bp,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+
+    result
+}
+
+/// The approximate number of basepairs per world unit in the layout,
+/// found by comparing the total sequence length to the total length
+/// of the nodes in the layout
+pub fn layout_bp_per_unit(graph: &PackedGraph, nodes: &[Node]) -> Option<f64> {
+    let mut total_bp = 0usize;
+    let mut total_len = 0.0f64;
+
+    for (ix, node) in nodes.iter().enumerate() {
+        let handle = Handle::pack(NodeId::from((ix + 1) as u64), false);
+
+        total_bp += graph.node_len(handle);
+        total_len += node.p0.dist(node.p1) as f64;
+    }
+
+    if total_bp == 0 || total_len <= 0.0 {
+        return None;
+    }
+
+    Some(total_bp as f64 / total_len)
+}
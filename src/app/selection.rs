@@ -1,16 +1,26 @@
-use handlegraph::handle::NodeId;
+use handlegraph::{handle::NodeId, pathhandlegraph::*};
+
+use bstr::ByteSlice;
 
 use rustc_hash::FxHashSet;
 
 use ash::version::DeviceV1_0;
 use ash::{vk, Device};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+
+use std::path::Path;
 
+use crate::annotations::BedRecords;
 use crate::geometry::Rect;
+use crate::graph_query::GraphQuery;
 use crate::universe::Node;
 use crate::vulkan::GfaestusVk;
 
+pub mod grow;
+
+pub use grow::*;
+
 #[derive(Debug, Clone, Default)]
 pub struct NodeSelection {
     pub nodes: FxHashSet<NodeId>,
@@ -81,6 +91,127 @@ impl NodeSelection {
 
         bbox
     }
+
+    /// Reads a file with one node ID per line; IDs outside
+    /// `1..=node_count` are rejected
+    pub fn read_node_ids<P: AsRef<Path>>(
+        path: P,
+        node_count: usize,
+    ) -> Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        Self::parse_node_ids(&text, node_count)
+    }
+
+    fn parse_node_ids(text: &str, node_count: usize) -> Result<Self> {
+        let mut nodes = FxHashSet::default();
+
+        for (ix, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let id = line.parse::<u64>().map_err(|_| {
+                anyhow!("Line {}: \"{}\" is not a node ID", ix + 1, line)
+            })?;
+
+            if id == 0 || id as usize > node_count {
+                bail!(
+                    "Line {}: node {} is not in the graph, which has {} nodes",
+                    ix + 1,
+                    id,
+                    node_count
+                );
+            }
+
+            nodes.insert(NodeId::from(id));
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Writes the node IDs in ascending order, one per line
+    pub fn write_node_ids<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut nodes = self.nodes.iter().map(|n| n.0).collect::<Vec<_>>();
+        nodes.sort_unstable();
+
+        let mut text = String::with_capacity(nodes.len() * 8);
+
+        for node in nodes {
+            text.push_str(&node.to_string());
+            text.push('\n');
+        }
+
+        std::fs::write(path, text)?;
+
+        Ok(())
+    }
+
+    /// Selects the nodes covered by the ranges in a BED file; each
+    /// record's chromosome is resolved with
+    /// `GraphQuery::resolve_path_range`, and records that don't match
+    /// a path are read as ranges on `fallback_path`, if given
+    pub fn read_bed<P: AsRef<Path>>(
+        graph: &GraphQuery,
+        path: P,
+        fallback_path: Option<PathId>,
+    ) -> Result<Self> {
+        let records = BedRecords::parse_bed_file(path)?;
+
+        let mut nodes = FxHashSet::default();
+
+        for record in records.records.iter() {
+            let chr = record.chr.to_str_lossy();
+
+            let range = graph
+                .resolve_path_range(&chr, record.start, record.end)
+                .or_else(|err| {
+                    fallback_path
+                        .map(|p| (p, record.start, record.end))
+                        .ok_or(err)
+                })?;
+
+            let (path_id, start, end) = range;
+
+            if let Some(range_nodes) =
+                graph.path_range_nodes(path_id, start, end)
+            {
+                nodes.extend(range_nodes);
+            }
+        }
+
+        Ok(Self { nodes })
+    }
+
+    /// Writes the ranges of `path_id` that are covered by the
+    /// selection as a BED file, returning the number of ranges
+    pub fn write_bed<P: AsRef<Path>>(
+        &self,
+        graph: &GraphQuery,
+        path_id: PathId,
+        path: P,
+    ) -> Result<usize> {
+        let path_name = graph
+            .graph()
+            .get_path_name_vec(path_id)
+            .ok_or_else(|| anyhow!("Path not found"))?;
+        let path_name = path_name.to_str_lossy();
+
+        let ranges = graph
+            .path_node_ranges(path_id, &self.nodes)
+            .ok_or_else(|| anyhow!("Path not found"))?;
+
+        let mut text = String::new();
+
+        for (start, end) in ranges.iter() {
+            text.push_str(&format!("{}\t{}\t{}\n", path_name, start, end));
+        }
+
+        std::fs::write(path, text)?;
+
+        Ok(ranges.len())
+    }
 }
 
 pub struct SelectionBuffer {
@@ -303,3 +434,38 @@ impl SelectionBuffer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(selection: &NodeSelection) -> Vec<u64> {
+        let mut ids = selection.nodes.iter().map(|n| n.0).collect::<Vec<_>>();
+        ids.sort_unstable();
+        ids
+    }
+
+    #[test]
+    fn node_ids_comments_and_blanks() {
+        let text = "# selection\n3\n\n  1 \n3\n10\n";
+        let selection = NodeSelection::parse_node_ids(text, 10).unwrap();
+        assert_eq!(ids(&selection), vec![1, 3, 10]);
+    }
+
+    #[test]
+    fn node_ids_out_of_range() {
+        let err = NodeSelection::parse_node_ids("1\n11\n", 10).unwrap_err();
+        assert!(err.to_string().starts_with("Line 2:"));
+
+        let err = NodeSelection::parse_node_ids("# x\n0\n", 10).unwrap_err();
+        assert!(err.to_string().starts_with("Line 2:"));
+    }
+
+    #[test]
+    fn node_ids_not_numbers() {
+        let err = NodeSelection::parse_node_ids("1\n2\nabc\n", 10).unwrap_err();
+        assert!(err.to_string().starts_with("Line 3:"));
+
+        assert!(NodeSelection::parse_node_ids("-1\n", 10).is_err());
+    }
+}
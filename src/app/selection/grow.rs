@@ -0,0 +1,392 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crossbeam::atomic::AtomicCell;
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::{paths::StepPtr, PackedGraph},
+    pathhandlegraph::*,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use anyhow::{bail, Result};
+
+use crate::graph_query::GraphQuery;
+
+/// Ways to grow or shrink a selection; the amounts are taken from
+/// `GrowSettings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrowOp {
+    /// Adds the nodes within `hops` edges of the selection
+    Hops,
+    /// Adds the nodes that start within `distance` bp of the
+    /// selection, measured along the graph
+    Distance,
+    /// Adds the `path_steps` steps before and after each step on the
+    /// selection, on every path
+    PathSteps,
+    /// Selects the region between the closest nodes before and after
+    /// the selection that are shared by every path through it
+    Bubble,
+    /// Removes the nodes on the boundary of the selection, `shrink_hops`
+    /// times
+    Shrink,
+}
+
+#[derive(Debug)]
+pub struct GrowSettings {
+    pub hops: AtomicCell<usize>,
+    pub distance: AtomicCell<usize>,
+    pub path_steps: AtomicCell<usize>,
+    pub shrink_hops: AtomicCell<usize>,
+}
+
+impl std::default::Default for GrowSettings {
+    fn default() -> Self {
+        Self {
+            hops: AtomicCell::new(1),
+            distance: AtomicCell::new(10_000),
+            path_steps: AtomicCell::new(5),
+            shrink_hops: AtomicCell::new(1),
+        }
+    }
+}
+
+impl GrowOp {
+    pub const ALL: [GrowOp; 5] = [
+        GrowOp::Hops,
+        GrowOp::Distance,
+        GrowOp::PathSteps,
+        GrowOp::Bubble,
+        GrowOp::Shrink,
+    ];
+
+    /// How far to walk the paths when looking for the bubble
+    /// boundaries
+    const MAX_BUBBLE_STEPS: usize = 10_000;
+
+    /// The name of the context action
+    pub fn name(&self) -> &'static str {
+        match self {
+            GrowOp::Hops => "Grow selection by hops",
+            GrowOp::Distance => "Grow selection by distance",
+            GrowOp::PathSteps => "Grow selection along paths",
+            GrowOp::Bubble => "Select enclosing bubble",
+            GrowOp::Shrink => "Shrink selection to boundary",
+        }
+    }
+
+    pub fn apply(
+        &self,
+        graph: &GraphQuery,
+        settings: &GrowSettings,
+        nodes: &FxHashSet<NodeId>,
+    ) -> Result<FxHashSet<NodeId>> {
+        let packed = graph.graph();
+
+        let result = match self {
+            GrowOp::Hops => grow_by_hops(packed, nodes, settings.hops.load()),
+            GrowOp::Distance => {
+                grow_by_distance(packed, nodes, settings.distance.load())
+            }
+            GrowOp::PathSteps => {
+                grow_along_paths(packed, nodes, settings.path_steps.load())
+            }
+            GrowOp::Bubble => enclosing_bubble(graph, nodes)?,
+            GrowOp::Shrink => {
+                shrink_by_hops(packed, nodes, settings.shrink_hops.load())
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+pub(crate) fn node_neighbors(
+    graph: &PackedGraph,
+    node: NodeId,
+) -> impl Iterator<Item = NodeId> + '_ {
+    let handle = Handle::pack(node, false);
+
+    graph
+        .neighbors(handle, Direction::Left)
+        .chain(graph.neighbors(handle, Direction::Right))
+        .map(|h| h.id())
+}
+
+pub fn grow_by_hops(
+    graph: &PackedGraph,
+    nodes: &FxHashSet<NodeId>,
+    hops: usize,
+) -> FxHashSet<NodeId> {
+    let mut result = nodes.clone();
+    let mut frontier = nodes.iter().copied().collect::<Vec<_>>();
+
+    for _ in 0..hops {
+        let mut next = Vec::new();
+
+        for node in frontier {
+            for other in node_neighbors(graph, node) {
+                if result.insert(other) {
+                    next.push(other);
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+
+        frontier = next;
+    }
+
+    result
+}
+
+/// The distance to a node is the total length of the nodes between
+/// it and the selection
+pub fn grow_by_distance(
+    graph: &PackedGraph,
+    nodes: &FxHashSet<NodeId>,
+    distance: usize,
+) -> FxHashSet<NodeId> {
+    let mut dists: FxHashMap<NodeId, usize> = FxHashMap::default();
+    let mut queue = BinaryHeap::new();
+
+    for &node in nodes.iter() {
+        dists.insert(node, 0);
+        queue.push(Reverse((0usize, node)));
+    }
+
+    while let Some(Reverse((dist, node))) = queue.pop() {
+        if dists.get(&node).map(|&d| d < dist).unwrap_or(false) {
+            continue;
+        }
+
+        let len = if nodes.contains(&node) {
+            0
+        } else {
+            graph.node_len(Handle::pack(node, false))
+        };
+
+        let next_dist = dist + len;
+
+        if next_dist > distance {
+            continue;
+        }
+
+        for other in node_neighbors(graph, node) {
+            let closer =
+                dists.get(&other).map(|&d| next_dist < d).unwrap_or(true);
+
+            if closer {
+                dists.insert(other, next_dist);
+                queue.push(Reverse((next_dist, other)));
+            }
+        }
+    }
+
+    dists.into_iter().map(|(node, _)| node).collect()
+}
+
+pub fn grow_along_paths(
+    graph: &PackedGraph,
+    nodes: &FxHashSet<NodeId>,
+    steps: usize,
+) -> FxHashSet<NodeId> {
+    let mut result = nodes.clone();
+
+    for &node in nodes.iter() {
+        let path_steps = match graph.steps_on_handle(Handle::pack(node, false))
+        {
+            Some(path_steps) => path_steps,
+            None => continue,
+        };
+
+        for (path, step) in path_steps {
+            let mut prev = Some(step);
+            let mut next = Some(step);
+
+            for _ in 0..steps {
+                prev = prev.and_then(|s| graph.path_prev_step(path, s));
+                next = next.and_then(|s| graph.path_next_step(path, s));
+
+                for s in prev.into_iter().chain(next) {
+                    if let Some(handle) = graph.path_handle_at_step(path, s) {
+                        result.insert(handle.id());
+                    }
+                }
+
+                if prev.is_none() && next.is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Removes the selected nodes that have a neighbor outside the
+/// selection, `hops` times
+pub fn shrink_by_hops(
+    graph: &PackedGraph,
+    nodes: &FxHashSet<NodeId>,
+    hops: usize,
+) -> FxHashSet<NodeId> {
+    let mut result = nodes.clone();
+
+    for _ in 0..hops {
+        let boundary = result
+            .iter()
+            .copied()
+            .filter(|&node| {
+                node_neighbors(graph, node)
+                    .any(|other| !result.contains(&other))
+            })
+            .collect::<Vec<_>>();
+
+        if boundary.is_empty() {
+            break;
+        }
+
+        for node in boundary {
+            result.remove(&node);
+        }
+    }
+
+    result
+}
+
+/// Finds the closest nodes before and after the selection that are
+/// on every path through it, and selects every step between them
+pub fn enclosing_bubble(
+    graph: &GraphQuery,
+    nodes: &FxHashSet<NodeId>,
+) -> Result<FxHashSet<NodeId>> {
+    let packed = graph.graph();
+
+    // the first and last step on the selection on each path, with
+    // their positions
+    let mut traversals: FxHashMap<
+        PathId,
+        ((usize, StepPtr), (usize, StepPtr)),
+    > = FxHashMap::default();
+
+    for &node in nodes.iter() {
+        let handle = Handle::pack(node, false);
+
+        let positions = match graph.handle_positions_iter(handle) {
+            Some(positions) => positions,
+            None => continue,
+        };
+
+        for (path, step, pos) in positions {
+            let (first, last) =
+                traversals.entry(path).or_insert(((pos, step), (pos, step)));
+
+            if pos < first.0 {
+                *first = (pos, step);
+            }
+
+            if pos > last.0 {
+                *last = (pos, step);
+            }
+        }
+    }
+
+    if traversals.is_empty() {
+        bail!("No paths pass through the selection");
+    }
+
+    let walk = |path: PathId, step: StepPtr, forward: bool| {
+        let mut walked = Vec::new();
+        let mut cur = Some(step);
+
+        for _ in 0..GrowOp::MAX_BUBBLE_STEPS {
+            cur = cur.and_then(|s| {
+                if forward {
+                    packed.path_next_step(path, s)
+                } else {
+                    packed.path_prev_step(path, s)
+                }
+            });
+
+            match cur.and_then(|s| packed.path_handle_at_step(path, s)) {
+                Some(handle) => walked.push(handle.id()),
+                None => break,
+            }
+        }
+
+        walked
+    };
+
+    let mut paths = traversals.into_iter().collect::<Vec<_>>();
+    paths.sort_by_key(|(path, _)| *path);
+
+    let before = paths
+        .iter()
+        .map(|(path, ((_, first), _))| walk(*path, *first, false))
+        .collect::<Vec<_>>();
+
+    let after = paths
+        .iter()
+        .map(|(path, (_, (_, last)))| walk(*path, *last, true))
+        .collect::<Vec<_>>();
+
+    let find_anchor = |walks: &[Vec<NodeId>]| -> Option<NodeId> {
+        let sets = walks[1..]
+            .iter()
+            .map(|w| w.iter().copied().collect::<FxHashSet<_>>())
+            .collect::<Vec<_>>();
+
+        walks[0]
+            .iter()
+            .copied()
+            .filter(|node| !nodes.contains(node))
+            .find(|node| sets.iter().all(|set| set.contains(node)))
+    };
+
+    let (start, end) = match (find_anchor(&before), find_anchor(&after)) {
+        (Some(start), Some(end)) => (start, end),
+        _ => bail!(
+            "No enclosing bubble within {} steps",
+            GrowOp::MAX_BUBBLE_STEPS
+        ),
+    };
+
+    let mut result = nodes.clone();
+
+    for (walked, anchor) in before
+        .iter()
+        .zip(std::iter::repeat(start))
+        .chain(after.iter().zip(std::iter::repeat(end)))
+    {
+        for &node in walked.iter() {
+            result.insert(node);
+            if node == anchor {
+                break;
+            }
+        }
+    }
+
+    // the steps between the first and last step on the selection
+    for (path, ((_, first), (_, last))) in paths {
+        let mut cur = Some(first);
+
+        while let Some(step) = cur {
+            if let Some(handle) = packed.path_handle_at_step(path, step) {
+                result.insert(handle.id());
+            }
+
+            if step == last {
+                break;
+            }
+
+            cur = packed.path_next_step(path, step);
+        }
+    }
+
+    Ok(result)
+}
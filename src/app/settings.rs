@@ -1,8 +1,13 @@
 use crossbeam::atomic::AtomicCell;
 use std::sync::Arc;
 
+use crate::app::selection::GrowSettings;
 use crate::vulkan::draw_system::edges::EdgesUBO;
 
+pub mod store;
+
+pub use store::*;
+
 #[derive(Debug, Clone)]
 pub struct AppSettings {
     node_width: Arc<NodeWidth>,
@@ -13,6 +18,10 @@ pub struct AppSettings {
 
     background_color_light: Arc<AtomicCell<rgb::RGB<f32>>>,
     background_color_dark: Arc<AtomicCell<rgb::RGB<f32>>>,
+
+    grow_settings: Arc<GrowSettings>,
+
+    store: SettingsStore,
 }
 
 impl std::default::Default for AppSettings {
@@ -28,6 +37,10 @@ impl std::default::Default for AppSettings {
             background_color_dark: Arc::new(
                 rgb::RGB::new(0.1, 0.1, 0.2).into(),
             ),
+
+            grow_settings: Default::default(),
+
+            store: Default::default(),
         }
     }
 }
@@ -56,6 +69,14 @@ impl AppSettings {
     pub fn background_color_dark(&self) -> &Arc<AtomicCell<rgb::RGB<f32>>> {
         &self.background_color_dark
     }
+
+    pub fn grow_settings(&self) -> &Arc<GrowSettings> {
+        &self.grow_settings
+    }
+
+    pub fn store(&self) -> &SettingsStore {
+        &self.store
+    }
 }
 
 #[derive(Debug)]
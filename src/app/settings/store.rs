@@ -0,0 +1,724 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossbeam::{atomic::AtomicCell, channel::Sender};
+use parking_lot::Mutex;
+use rhai::EvalAltResult;
+use rustc_hash::FxHashMap;
+
+use crate::{app::SharedState, gui::GuiMsg};
+
+use super::AppSettings;
+
+/// The gfaestus directory in the user's config directory, i.e.
+/// `$XDG_CONFIG_HOME/gfaestus` or `~/.config/gfaestus`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+
+    Some(base.join("gfaestus"))
+}
+
+/// Types that can be stored in the settings file, and accessed from
+/// Rhai
+pub trait SettingValue: rhai::Variant + Clone {
+    fn parse_setting(value: &str) -> Option<Self>;
+
+    fn format_setting(&self) -> String;
+}
+
+impl SettingValue for bool {
+    fn parse_setting(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn format_setting(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for f32 {
+    fn parse_setting(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn format_setting(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl SettingValue for i64 {
+    fn parse_setting(value: &str) -> Option<Self> {
+        value.parse().ok()
+    }
+
+    fn format_setting(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl SettingValue for rgb::RGB<f32> {
+    fn parse_setting(value: &str) -> Option<Self> {
+        let floats = parse_floats(value, 3)?;
+        Some(rgb::RGB::new(floats[0], floats[1], floats[2]))
+    }
+
+    fn format_setting(&self) -> String {
+        format!("{:?}, {:?}, {:?}", self.r, self.g, self.b)
+    }
+}
+
+impl SettingValue for egui::Pos2 {
+    fn parse_setting(value: &str) -> Option<Self> {
+        let floats = parse_floats(value, 2)?;
+        Some(egui::Pos2::new(floats[0], floats[1]))
+    }
+
+    fn format_setting(&self) -> String {
+        format!("{:?}, {:?}", self.x, self.y)
+    }
+}
+
+fn parse_floats(value: &str, count: usize) -> Option<Vec<f32>> {
+    let floats = value
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    (floats.len() == count).then(|| floats)
+}
+
+struct Setting {
+    key: &'static str,
+
+    get: Box<dyn Fn() -> String + Send + Sync>,
+    set: Box<dyn Fn(&str) -> bool + Send + Sync>,
+
+    add_rhai_fns: Box<dyn Fn(&mut rhai::Module) + Send + Sync>,
+}
+
+struct StoreInner {
+    settings: Vec<Setting>,
+
+    global_path: Option<PathBuf>,
+    project_path: Option<PathBuf>,
+
+    global: BTreeMap<String, String>,
+    project: BTreeMap<String, String>,
+
+    // the values of the registered settings as of the last update,
+    // used to find the settings that have changed
+    snapshot: FxHashMap<&'static str, String>,
+
+    dirty: bool,
+    last_write: Instant,
+}
+
+/// Stores settings in `settings.conf` in the config directory, as
+/// `key = value` lines; a `<gfa file>.gfaestus.conf` file next to the
+/// GFA overrides the settings it contains, and changes to those
+/// settings are written back to it
+///
+/// When a file is written, comments and blank lines in it are kept,
+/// existing values are updated in place, and new settings are
+/// appended at the end
+///
+/// Settings registered with `add` are applied when the files are
+/// loaded, and written when they change; other values, such as the
+/// window layout, are read and written with `get` and `set`
+#[derive(Clone)]
+pub struct SettingsStore {
+    inner: Arc<Mutex<StoreInner>>,
+}
+
+impl std::fmt::Debug for SettingsStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("SettingsStore")
+            .field("global_path", &inner.global_path)
+            .field("project_path", &inner.project_path)
+            .finish()
+    }
+}
+
+impl std::default::Default for SettingsStore {
+    fn default() -> Self {
+        let inner = StoreInner {
+            settings: Vec::new(),
+
+            global_path: None,
+            project_path: None,
+
+            global: BTreeMap::new(),
+            project: BTreeMap::new(),
+
+            snapshot: FxHashMap::default(),
+
+            dirty: false,
+            last_write: Instant::now(),
+        };
+
+        Self {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+}
+
+impl SettingsStore {
+    pub const FILE_NAME: &'static str = "settings.conf";
+    pub const PROJECT_SUFFIX: &'static str = ".gfaestus.conf";
+
+    const WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Registers a setting; `key` is also the name of the Rhai
+    /// getter, and `set_<key>` of the setter, in the `settings` module
+    pub fn add<T, G, S>(&self, key: &'static str, get: G, set: S)
+    where
+        T: SettingValue,
+        G: Fn() -> T + Send + Sync + 'static,
+        S: Fn(T) + Send + Sync + 'static,
+    {
+        let get = Arc::new(get);
+        let set = Arc::new(set);
+
+        let get_str = {
+            let get = get.clone();
+            Box::new(move || get().format_setting()) as Box<_>
+        };
+
+        let set_str = {
+            let set = set.clone();
+            Box::new(move |value: &str| {
+                if let Some(value) = T::parse_setting(value) {
+                    set(value);
+                    true
+                } else {
+                    false
+                }
+            }) as Box<_>
+        };
+
+        let add_rhai_fns = Box::new(move |module: &mut rhai::Module| {
+            let get = get.clone();
+            module
+                .set_native_fn(key, move || Ok::<_, Box<EvalAltResult>>(get()));
+
+            let set = set.clone();
+            module.set_native_fn(format!("set_{}", key), move |value: T| {
+                set(value);
+                Ok::<_, Box<EvalAltResult>>(())
+            });
+        }) as Box<_>;
+
+        let setting = Setting {
+            key,
+            get: get_str,
+            set: set_str,
+            add_rhai_fns,
+        };
+
+        let mut inner = self.inner.lock();
+        inner.snapshot.insert(key, (setting.get)());
+        inner.settings.push(setting);
+    }
+
+    fn add_cell<T>(&self, key: &'static str, cell: &Arc<AtomicCell<T>>)
+    where
+        T: SettingValue + Copy,
+    {
+        let get = cell.clone();
+        let set = cell.clone();
+        self.add(key, move || get.load(), move |v| set.store(v));
+    }
+
+    /// Registers the `AppSettings`, and the edge and dark mode
+    /// toggles
+    pub fn add_app_settings(
+        &self,
+        settings: &AppSettings,
+        shared_state: &SharedState,
+        gui_tx: &Sender<GuiMsg>,
+    ) {
+        self.add_cell("label_radius", settings.label_radius());
+
+        macro_rules! add_node_width {
+            ($key:literal, $get:ident, $set:ident) => {
+                let get = settings.node_width().clone();
+                let set = settings.node_width().clone();
+                self.add($key, move || get.$get(), move |v| set.$set(v));
+            };
+        }
+
+        add_node_width!("min_node_width", min_node_width, set_min_node_width);
+        add_node_width!("max_node_width", max_node_width, set_max_node_width);
+        add_node_width!("min_node_scale", min_node_scale, set_min_node_scale);
+        add_node_width!("max_node_scale", max_node_scale, set_max_node_scale);
+
+        macro_rules! add_edge_field {
+            ($key:literal, $field:ident, $type:ty) => {
+                let get = settings.edge_renderer().clone();
+                let set = settings.edge_renderer().clone();
+                self.add(
+                    $key,
+                    move || get.load().$field,
+                    move |v: $type| {
+                        let mut ubo = set.load();
+                        ubo.$field = v;
+                        set.store(ubo);
+                    },
+                );
+            };
+        }
+
+        add_edge_field!("edge_color", edge_color, rgb::RGB<f32>);
+        add_edge_field!("edge_width", edge_width, f32);
+        add_edge_field!("curve_offset", curve_offset, f32);
+
+        self.add_cell("show_edges", &shared_state.edges_enabled);
+
+        self.add_cell(
+            "background_color_light",
+            settings.background_color_light(),
+        );
+        self.add_cell(
+            "background_color_dark",
+            settings.background_color_dark(),
+        );
+
+        // counts are exposed as integers, as that's what rhai uses
+        macro_rules! add_grow_setting {
+            ($key:literal, $field:ident) => {
+                let get = settings.grow_settings().clone();
+                let set = settings.grow_settings().clone();
+                self.add(
+                    $key,
+                    move || get.$field.load() as i64,
+                    move |v: i64| set.$field.store(v.max(0) as usize),
+                );
+            };
+        }
+
+        add_grow_setting!("grow_hops", hops);
+        add_grow_setting!("grow_distance", distance);
+        add_grow_setting!("grow_path_steps", path_steps);
+        add_grow_setting!("shrink_hops", shrink_hops);
+
+        let get = shared_state.dark_mode.clone();
+        let set = shared_state.dark_mode.clone();
+        let gui_tx = gui_tx.clone();
+        self.add(
+            "dark_mode",
+            move || get.load(),
+            move |dark: bool| {
+                set.store(dark);
+                let msg = if dark {
+                    GuiMsg::SetDarkMode
+                } else {
+                    GuiMsg::SetLightMode
+                };
+                gui_tx.send(msg).unwrap();
+            },
+        );
+    }
+
+    /// Loads the settings file from the config directory, and the
+    /// project settings next to the GFA, and applies them to the
+    /// registered settings
+    pub fn load(&self, gfa_path: Option<&Path>) -> Result<()> {
+        let global_path = config_dir().map(|dir| dir.join(Self::FILE_NAME));
+
+        let project_path = gfa_path.map(|gfa| {
+            let mut path = gfa.as_os_str().to_owned();
+            path.push(Self::PROJECT_SUFFIX);
+            PathBuf::from(path)
+        });
+
+        let global = global_path
+            .as_deref()
+            .map(read_settings_file)
+            .transpose()?
+            .unwrap_or_default();
+
+        let project = project_path
+            .as_deref()
+            .map(read_settings_file)
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut inner = self.inner.lock();
+
+        inner.global_path = global_path;
+        inner.project_path = project_path;
+        inner.global = global;
+        inner.project = project;
+
+        let mut snapshot = FxHashMap::default();
+
+        for setting in inner.settings.iter() {
+            let value = inner
+                .project
+                .get(setting.key)
+                .or_else(|| inner.global.get(setting.key));
+
+            if let Some(value) = value {
+                if !(setting.set)(value) {
+                    log::warn!(
+                        "Invalid value for setting {}: {}",
+                        setting.key,
+                        value
+                    );
+                }
+            }
+
+            snapshot.insert(setting.key, (setting.get)());
+        }
+
+        inner.snapshot = snapshot;
+        inner.dirty = false;
+
+        Ok(())
+    }
+
+    pub fn get<T: SettingValue>(&self, key: &str) -> Option<T> {
+        let inner = self.inner.lock();
+        let value = inner.project.get(key).or_else(|| inner.global.get(key))?;
+        T::parse_setting(value)
+    }
+
+    pub fn set<T: SettingValue>(&self, key: &str, value: &T) {
+        let mut inner = self.inner.lock();
+        inner.set_raw(key, value.format_setting());
+    }
+
+    /// Records the registered settings that have changed since the
+    /// last update, and writes the settings files if anything has
+    /// changed since they were last written, at most once per second
+    pub fn update(&self) {
+        let mut inner = self.inner.lock();
+
+        let changed = inner
+            .settings
+            .iter()
+            .filter_map(|setting| {
+                let value = (setting.get)();
+                if inner.snapshot.get(setting.key) != Some(&value) {
+                    Some((setting.key, value))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for (key, value) in changed {
+            inner.snapshot.insert(key, value.clone());
+            inner.set_raw(key, value);
+        }
+
+        if inner.dirty && inner.last_write.elapsed() >= Self::WRITE_INTERVAL {
+            if let Err(err) = inner.write() {
+                log::error!("Error writing settings: {:?}", err);
+            }
+        }
+    }
+
+    /// Writes any unsaved changes
+    pub fn flush(&self) -> Result<()> {
+        self.update();
+
+        let mut inner = self.inner.lock();
+        if inner.dirty {
+            inner.write()?;
+        }
+        Ok(())
+    }
+
+    /// A module with typed getters and setters for each registered
+    /// setting, e.g. `settings::label_radius()` and
+    /// `settings::set_label_radius(40.0)`, as well as `list()` and
+    /// `save()`
+    pub fn rhai_module(&self) -> rhai::Module {
+        let mut module = rhai::Module::new();
+
+        let inner = self.inner.lock();
+
+        for setting in inner.settings.iter() {
+            (setting.add_rhai_fns)(&mut module);
+        }
+
+        let keys = inner
+            .settings
+            .iter()
+            .map(|s| rhai::Dynamic::from(s.key.to_string()))
+            .collect::<rhai::Array>();
+
+        module.set_native_fn("list", move || {
+            Ok::<_, Box<EvalAltResult>>(keys.clone())
+        });
+
+        let store = self.clone();
+        module.set_native_fn(
+            "save",
+            move || -> std::result::Result<(), Box<EvalAltResult>> {
+                store.flush().map_err(|err| err.to_string().into())
+            },
+        );
+
+        module
+    }
+}
+
+impl StoreInner {
+    fn set_raw(&mut self, key: &str, value: String) {
+        let current = self.project.get(key).or_else(|| self.global.get(key));
+
+        if current == Some(&value) {
+            return;
+        }
+
+        if let Some(project_value) = self.project.get_mut(key) {
+            *project_value = value;
+        } else {
+            self.global.insert(key.to_string(), value);
+        }
+
+        self.dirty = true;
+    }
+
+    fn write(&mut self) -> Result<()> {
+        self.dirty = false;
+        self.last_write = Instant::now();
+
+        if let Some(path) = &self.global_path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            write_settings_file(path, &self.global)?;
+        }
+
+        if let Some(path) = &self.project_path {
+            if !self.project.is_empty() {
+                write_settings_file(path, &self.project)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn read_settings_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let text = std::fs::read_to_string(path)?;
+
+    let values = parse_settings(&text, path);
+
+    log::info!("Loaded settings from {:?}", path);
+
+    Ok(values)
+}
+
+fn parse_settings(text: &str, path: &Path) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+
+    for (ix, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            values.insert(key.trim().to_string(), value.trim().to_string());
+        } else {
+            log::warn!("{:?}:{}: expected `key = value`", path, ix + 1);
+        }
+    }
+
+    values
+}
+
+/// Updates the values in the text of a settings file, keeping the
+/// comments and other lines that aren't settings
+fn format_settings(
+    existing: &str,
+    values: &BTreeMap<String, String>,
+) -> String {
+    let mut text = String::new();
+    let mut written: BTreeSet<&str> = BTreeSet::new();
+
+    for line in existing.lines() {
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            text.push_str(line);
+            text.push('\n');
+            continue;
+        }
+
+        match trimmed.split_once('=') {
+            Some((key, _)) => {
+                let key = key.trim();
+
+                // settings that are no longer set, and repeated keys,
+                // are dropped
+                if let Some((key, value)) = values.get_key_value(key) {
+                    if written.insert(key) {
+                        text.push_str(&format!("{} = {}\n", key, value));
+                    }
+                }
+            }
+            None => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+
+    for (key, value) in values {
+        if !written.contains(key.as_str()) {
+            text.push_str(&format!("{} = {}\n", key, value));
+        }
+    }
+
+    text
+}
+
+fn write_settings_file(
+    path: &Path,
+    values: &BTreeMap<String, String>,
+) -> Result<()> {
+    let existing = if path.exists() {
+        std::fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+
+    std::fs::write(path, format_settings(&existing, values))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_settings_lines() {
+        let text = "# a comment\n\
+                    \n\
+                    label_radius = 40.0\n\
+                    \x20 edge_color=0.1, 0.2, 0.3  \n\
+                    not a setting\n\
+                    window.tour.pos = 10.0, 20.0\n\
+                    label_radius = 50.0\n";
+
+        let values = parse_settings(text, Path::new("settings.conf"));
+
+        assert_eq!(
+            values,
+            map(&[
+                ("edge_color", "0.1, 0.2, 0.3"),
+                ("label_radius", "50.0"),
+                ("window.tour.pos", "10.0, 20.0"),
+            ])
+        );
+    }
+
+    #[test]
+    fn read_settings_file_roundtrip() {
+        let dir = std::env::temp_dir()
+            .join(format!("gfaestus-settings-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SettingsStore::FILE_NAME);
+
+        assert!(read_settings_file(&path).unwrap().is_empty());
+
+        std::fs::write(&path, "# keep me\ndark_mode = true\n").unwrap();
+
+        let mut values = read_settings_file(&path).unwrap();
+        assert_eq!(values, map(&[("dark_mode", "true")]));
+
+        values.insert("dark_mode".to_string(), "false".to_string());
+        values.insert("show_edges".to_string(), "true".to_string());
+        write_settings_file(&path, &values).unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(text, "# keep me\ndark_mode = false\nshow_edges = true\n");
+        assert_eq!(read_settings_file(&path).unwrap(), values);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn format_settings_keeps_comments() {
+        let existing = "# colors\n\
+                        edge_color = 1.0, 1.0, 1.0\n\
+                        \n\
+                        # removed\n\
+                        old_key = 1\n\
+                        edge_color = 0.0, 0.0, 0.0\n";
+
+        let values =
+            map(&[("edge_color", "0.5, 0.5, 0.5"), ("label_radius", "40.0")]);
+
+        assert_eq!(
+            format_settings(existing, &values),
+            "# colors\n\
+             edge_color = 0.5, 0.5, 0.5\n\
+             \n\
+             # removed\n\
+             label_radius = 40.0\n"
+        );
+
+        assert_eq!(
+            format_settings("", &values),
+            "edge_color = 0.5, 0.5, 0.5\nlabel_radius = 40.0\n"
+        );
+    }
+
+    #[test]
+    fn parse_floats_count() {
+        assert_eq!(parse_floats("1.0, 2.5,3", 3), Some(vec![1.0, 2.5, 3.0]));
+        assert_eq!(parse_floats(" -1 , 0.5 ", 2), Some(vec![-1.0, 0.5]));
+
+        assert_eq!(parse_floats("1.0, 2.0", 3), None);
+        assert_eq!(parse_floats("1.0, 2.0, 3.0", 2), None);
+        assert_eq!(parse_floats("1.0, x, 3.0", 3), None);
+        assert_eq!(parse_floats("1.0,, 3.0", 3), None);
+        assert_eq!(parse_floats("", 1), None);
+    }
+
+    #[test]
+    fn setting_values() {
+        let color = rgb::RGB::new(0.1f32, 0.2, 0.3);
+        assert_eq!(
+            rgb::RGB::<f32>::parse_setting(&color.format_setting()),
+            Some(color)
+        );
+
+        let pos = egui::Pos2::new(-12.5, 300.0);
+        assert_eq!(egui::Pos2::parse_setting(&pos.format_setting()), Some(pos));
+
+        assert_eq!(f32::parse_setting(&0.1f32.format_setting()), Some(0.1));
+        assert_eq!(bool::parse_setting("true"), Some(true));
+        assert_eq!(i64::parse_setting("nope"), None);
+    }
+}
@@ -3,8 +3,10 @@ use std::sync::Arc;
 use crossbeam::atomic::AtomicCell;
 use handlegraph::handle::NodeId;
 use handlegraph::pathhandlegraph::PathId;
+use parking_lot::Mutex;
 
-use crate::{geometry::*, gui::GuiFocusState};
+use crate::input::Keybindings;
+use crate::{app::SelectOp, geometry::*, gui::GuiFocusState};
 use crate::{view::*, vulkan::texture::GradientName};
 
 #[derive(Clone)]
@@ -18,6 +20,14 @@ pub struct SharedState {
 
     pub mouse_rect: MouseRect,
 
+    pub mouse_polygon: MousePolygon,
+
+    pub selection_tool: Arc<AtomicCell<SelectionTool>>,
+
+    /// While set, clicks in the main view place measurement points
+    /// instead of selecting nodes
+    pub measuring: Arc<AtomicCell<bool>>,
+
     pub overlay_state: OverlayState,
 
     pub gui_focus_state: GuiFocusState,
@@ -27,6 +37,11 @@ pub struct SharedState {
     pub dark_mode: Arc<AtomicCell<bool>>,
 
     pub show_modal: Arc<AtomicCell<bool>>,
+
+    /// Paths drawn as lines over the graph
+    pub highlighted_paths: Arc<Mutex<Vec<PathId>>>,
+
+    pub keybindings: Keybindings,
 }
 
 impl SharedState {
@@ -41,6 +56,12 @@ impl SharedState {
 
             mouse_rect: MouseRect::default(),
 
+            mouse_polygon: MousePolygon::default(),
+
+            selection_tool: Arc::new(SelectionTool::Rectangle.into()),
+
+            measuring: Arc::new(false.into()),
+
             overlay_state: OverlayState::default(),
 
             gui_focus_state: GuiFocusState::default(),
@@ -48,6 +69,10 @@ impl SharedState {
             edges_enabled: Arc::new(true.into()),
             dark_mode: Arc::new(false.into()),
             show_modal: Arc::new(false.into()),
+
+            highlighted_paths: Arc::new(Mutex::new(Vec::new())),
+
+            keybindings: Keybindings::default(),
         }
     }
 
@@ -71,6 +96,10 @@ impl SharedState {
         &self.overlay_state
     }
 
+    pub fn keybindings(&self) -> &Keybindings {
+        &self.keybindings
+    }
+
     pub fn edges_enabled(&self) -> bool {
         self.edges_enabled.load()
     }
@@ -79,15 +108,52 @@ impl SharedState {
         &self.dark_mode
     }
 
-    pub fn start_mouse_rect(&self) {
+    pub fn highlighted_paths(&self) -> Vec<PathId> {
+        self.highlighted_paths.lock().clone()
+    }
+
+    pub fn is_path_highlighted(&self, path: PathId) -> bool {
+        self.highlighted_paths.lock().contains(&path)
+    }
+
+    pub fn toggle_path_highlight(&self, path: PathId) {
+        let mut paths = self.highlighted_paths.lock();
+
+        if let Some(ix) = paths.iter().position(|&p| p == path) {
+            paths.remove(ix);
+        } else {
+            paths.push(path);
+        }
+    }
+
+    pub fn selection_tool(&self) -> SelectionTool {
+        self.selection_tool.load()
+    }
+
+    pub fn set_selection_tool(&self, tool: SelectionTool) {
+        self.cancel_mouse_region();
+        self.selection_tool.store(tool);
+    }
+
+    pub fn measuring(&self) -> bool {
+        self.measuring.load()
+    }
+
+    fn mouse_world_pos(&self) -> Point {
         let view = self.view();
         let screen_pos = self.mouse_pos();
         let screen_dims = self.screen_dims();
 
-        let world_pos = view.screen_point_to_world(screen_dims, screen_pos);
+        view.screen_point_to_world(screen_dims, screen_pos)
+    }
+
+    pub fn start_mouse_rect(&self, op: SelectOp) {
+        let screen_pos = self.mouse_pos();
+        let world_pos = self.mouse_world_pos();
 
         self.mouse_rect.screen_pos.store(Some(screen_pos));
         self.mouse_rect.world_pos.store(Some(world_pos));
+        self.mouse_rect.op.store(op);
     }
 
     pub fn active_mouse_rect_screen(&self) -> Option<Rect> {
@@ -98,33 +164,145 @@ impl SharedState {
         Some(Rect::new(start_pos, end_pos))
     }
 
-    pub fn close_mouse_rect_world(&self) -> Option<Rect> {
+    pub fn close_mouse_rect_world(&self) -> Option<(Rect, SelectOp)> {
         let start_pos = self.mouse_rect.world_pos.load()?;
 
-        let screen_pos = self.mouse_pos();
-        let screen_dims = self.screen_dims();
-
-        let view = self.view();
-
-        let end_pos = view.screen_point_to_world(screen_dims, screen_pos);
+        let end_pos = self.mouse_world_pos();
 
         let rect = Rect::new(start_pos, end_pos);
 
         self.mouse_rect.world_pos.store(None);
         self.mouse_rect.screen_pos.store(None);
 
-        Some(rect)
+        Some((rect, self.mouse_rect.op.load()))
     }
 
     pub fn is_started_mouse_rect(&self) -> bool {
         self.mouse_rect.screen_pos.load().is_some()
     }
+
+    /// Starts a lasso or polygon at the current mouse position
+    pub fn start_mouse_polygon(&self, op: SelectOp) {
+        let world_pos = self.mouse_world_pos();
+
+        let mut points = self.mouse_polygon.points.lock();
+        points.clear();
+        points.push(world_pos);
+
+        self.mouse_polygon.op.store(op);
+    }
+
+    pub fn is_started_mouse_polygon(&self) -> bool {
+        !self.mouse_polygon.points.lock().is_empty()
+    }
+
+    /// Adds the current mouse position to the polygon
+    pub fn push_mouse_polygon_point(&self) {
+        let world_pos = self.mouse_world_pos();
+        self.mouse_polygon.points.lock().push(world_pos);
+    }
+
+    /// Adds the current mouse position to the lasso, if it's far
+    /// enough from the previous point; called every frame
+    pub fn extend_mouse_lasso(&self) {
+        if self.selection_tool() != SelectionTool::Lasso {
+            return;
+        }
+
+        let mut points = self.mouse_polygon.points.lock();
+
+        if let Some(last) = points.last().copied() {
+            let world_pos = self.mouse_world_pos();
+
+            let min_dist = MousePolygon::LASSO_SPACING * self.view().scale;
+
+            if world_pos.dist(last) >= min_dist {
+                points.push(world_pos);
+            }
+        }
+    }
+
+    /// Returns true if the mouse is close enough to the first point
+    /// of the polygon to close it
+    pub fn mouse_near_polygon_start(&self) -> bool {
+        let points = self.mouse_polygon.points.lock();
+
+        if points.len() < 3 {
+            return false;
+        }
+
+        let view = self.view();
+        let min_dist = MousePolygon::CLOSE_DISTANCE * view.scale;
+
+        points[0].dist(self.mouse_world_pos()) <= min_dist
+    }
+
+    /// The polygon in screen coordinates, including the current
+    /// mouse position as the last point
+    pub fn active_mouse_polygon_screen(&self) -> Option<Vec<Point>> {
+        let points = self.mouse_polygon.points.lock();
+
+        if points.is_empty() {
+            return None;
+        }
+
+        let view = self.view();
+        let dims = self.screen_dims();
+        let offset = Point::new(dims.width, dims.height) / 2.0;
+
+        let mut screen = points
+            .iter()
+            .map(|&p| view.world_point_to_screen(p) + offset)
+            .collect::<Vec<_>>();
+
+        screen.push(self.mouse_pos());
+
+        Some(screen)
+    }
+
+    /// Finishes the polygon, returning it if it has at least three
+    /// points
+    pub fn close_mouse_polygon_world(&self) -> Option<(Vec<Point>, SelectOp)> {
+        let points = std::mem::take(&mut *self.mouse_polygon.points.lock());
+
+        if points.len() < 3 {
+            return None;
+        }
+
+        Some((points, self.mouse_polygon.op.load()))
+    }
+
+    /// Cancels any rectangle, lasso, or polygon in progress
+    pub fn cancel_mouse_region(&self) {
+        self.mouse_rect.world_pos.store(None);
+        self.mouse_rect.screen_pos.store(None);
+        self.mouse_polygon.points.lock().clear();
+    }
+}
+
+/// The shape used when selecting a region with the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionTool {
+    Rectangle,
+    Lasso,
+    Polygon,
+}
+
+impl SelectionTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SelectionTool::Rectangle => "Rectangle",
+            SelectionTool::Lasso => "Lasso",
+            SelectionTool::Polygon => "Polygon",
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct MouseRect {
     pub(super) world_pos: Arc<AtomicCell<Option<Point>>>,
     pub(super) screen_pos: Arc<AtomicCell<Option<Point>>>,
+    pub(super) op: Arc<AtomicCell<SelectOp>>,
 }
 
 impl std::default::Default for MouseRect {
@@ -132,6 +310,32 @@ impl std::default::Default for MouseRect {
         Self {
             world_pos: Arc::new(AtomicCell::new(None)),
             screen_pos: Arc::new(AtomicCell::new(None)),
+            op: Arc::new(AtomicCell::new(SelectOp::Replace)),
+        }
+    }
+}
+
+/// The lasso or polygon being drawn, in world coordinates
+#[derive(Clone)]
+pub struct MousePolygon {
+    pub(super) points: Arc<Mutex<Vec<Point>>>,
+    pub(super) op: Arc<AtomicCell<SelectOp>>,
+}
+
+impl MousePolygon {
+    /// Minimum distance in pixels between lasso points
+    const LASSO_SPACING: f32 = 4.0;
+
+    /// Distance in pixels from the first point within which a click
+    /// closes the polygon
+    const CLOSE_DISTANCE: f32 = 8.0;
+}
+
+impl std::default::Default for MousePolygon {
+    fn default() -> Self {
+        Self {
+            points: Arc::new(Mutex::new(Vec::new())),
+            op: Arc::new(AtomicCell::new(SelectOp::Replace)),
         }
     }
 }
@@ -0,0 +1,455 @@
+use handlegraph::handle::NodeId;
+
+use rustc_hash::FxHashSet;
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::app::mainview::view::{
+    EasingCirc, EasingElasticOut, EasingExpoIn, EasingExpoOut, EasingFunction,
+    ViewLerp,
+};
+use crate::view::View;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    ExpoIn,
+    ExpoOut,
+    ElasticOut,
+    Circ,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 5] = [
+        Easing::Linear,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ElasticOut,
+        Easing::Circ,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::ExpoIn => "Expo in",
+            Easing::ExpoOut => "Expo out",
+            Easing::ElasticOut => "Elastic out",
+            Easing::Circ => "Circular in-out",
+        }
+    }
+
+    pub fn value_at(&self, time: f64) -> f64 {
+        match self {
+            Easing::Linear => time.clamp(0.0, 1.0),
+            Easing::ExpoIn => EasingExpoIn::value_at_normalized_time(time),
+            Easing::ExpoOut => EasingExpoOut::value_at_normalized_time(time),
+            Easing::ElasticOut => {
+                EasingElasticOut::value_at_normalized_time(time)
+            }
+            Easing::Circ => EasingCirc::value_at_normalized_time(time),
+        }
+    }
+}
+
+/// A stop on a tour; the view moves from the previous keyframe to
+/// this one over `transition`, and stays for `hold`
+#[derive(Debug, Clone)]
+pub struct Keyframe {
+    pub view: View,
+    pub transition: Duration,
+    pub hold: Duration,
+    pub easing: Easing,
+
+    /// The overlay and selection to switch to when the keyframe is
+    /// reached; `None` leaves the current one
+    pub overlay: Option<usize>,
+    pub selection: Option<FxHashSet<NodeId>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TourState {
+    pub view: View,
+    /// The last keyframe that has been reached
+    pub reached: usize,
+    pub done: bool,
+}
+
+/// A sequence of keyframes; the tour starts at the first keyframe,
+/// so its transition is ignored
+#[derive(Debug, Default, Clone)]
+pub struct Tour {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Tour {
+    pub fn duration(&self) -> Duration {
+        self.keyframes
+            .iter()
+            .enumerate()
+            .map(|(ix, kf)| {
+                if ix == 0 {
+                    kf.hold
+                } else {
+                    kf.transition + kf.hold
+                }
+            })
+            .sum()
+    }
+
+    pub fn state_at(&self, time: Duration) -> Option<TourState> {
+        let first = self.keyframes.first()?;
+
+        let mut end = first.hold;
+
+        if time < end {
+            return Some(TourState {
+                view: first.view,
+                reached: 0,
+                done: false,
+            });
+        }
+
+        for (ix, pair) in self.keyframes.windows(2).enumerate() {
+            let (prev, next) = (&pair[0], &pair[1]);
+
+            let start = end;
+            let arrival = start + next.transition;
+
+            if time < arrival {
+                let t = (time - start).as_secs_f64()
+                    / next.transition.as_secs_f64();
+
+                let lerp = ViewLerp::new(prev.view, next.view);
+
+                return Some(TourState {
+                    view: lerp.lerp(next.easing.value_at(t)),
+                    reached: ix,
+                    done: false,
+                });
+            }
+
+            end = arrival + next.hold;
+
+            if time < end {
+                return Some(TourState {
+                    view: next.view,
+                    reached: ix + 1,
+                    done: false,
+                });
+            }
+        }
+
+        let last = self.keyframes.len() - 1;
+
+        Some(TourState {
+            view: self.keyframes[last].view,
+            reached: last,
+            done: true,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackMode {
+    /// Plays the tour in real time
+    Preview,
+    /// Advances the tour by `1 / fps` seconds per rendered frame,
+    /// saving each frame as a numbered PNG in `dir`
+    Export { dir: PathBuf, fps: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TourFrame {
+    pub view: View,
+    /// Set when a keyframe was reached since the previous frame
+    pub reached: Option<usize>,
+    /// The file this frame should be saved to, when exporting
+    pub capture: Option<PathBuf>,
+    pub done: bool,
+}
+
+pub struct TourPlayback {
+    mode: PlaybackMode,
+
+    time: Duration,
+    last_update: Option<Instant>,
+
+    frame: usize,
+    reached: Option<usize>,
+}
+
+impl TourPlayback {
+    pub fn new(mode: PlaybackMode) -> Self {
+        Self {
+            mode,
+
+            time: Duration::from_secs(0),
+            last_update: None,
+
+            frame: 0,
+            reached: None,
+        }
+    }
+
+    pub fn mode(&self) -> &PlaybackMode {
+        &self.mode
+    }
+
+    pub fn time(&self) -> Duration {
+        self.time
+    }
+
+    /// The number of frames played
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn frame_path(dir: &std::path::Path, frame: usize) -> PathBuf {
+        dir.join(format!("frame_{:05}.png", frame))
+    }
+
+    /// Steps the playback forward by one frame
+    pub fn advance(&mut self, tour: &Tour) -> Option<TourFrame> {
+        match &self.mode {
+            PlaybackMode::Preview => {
+                let now = Instant::now();
+                if let Some(last) = self.last_update {
+                    self.time += now - last;
+                }
+                self.last_update = Some(now);
+            }
+            PlaybackMode::Export { fps, .. } => {
+                let fps = (*fps).max(1) as f64;
+                self.time = Duration::from_secs_f64(self.frame as f64 / fps);
+            }
+        }
+
+        let state = tour.state_at(self.time)?;
+
+        let reached = if self.reached != Some(state.reached) {
+            self.reached = Some(state.reached);
+            Some(state.reached)
+        } else {
+            None
+        };
+
+        let capture = match &self.mode {
+            PlaybackMode::Preview => None,
+            PlaybackMode::Export { dir, .. } => {
+                Some(Self::frame_path(dir, self.frame))
+            }
+        };
+
+        self.frame += 1;
+
+        Some(TourFrame {
+            view: state.view,
+            reached,
+            capture,
+            done: state.done,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::geometry::Point;
+
+    fn view(x: f32, y: f32, scale: f32) -> View {
+        View {
+            center: Point::new(x, y),
+            scale,
+        }
+    }
+
+    fn keyframe(view: View, transition_ms: u64, hold_ms: u64) -> Keyframe {
+        Keyframe {
+            view,
+            transition: Duration::from_millis(transition_ms),
+            hold: Duration::from_millis(hold_ms),
+            easing: Easing::Linear,
+            overlay: None,
+            selection: None,
+        }
+    }
+
+    // 0-1s: hold on the first keyframe, whose transition is ignored
+    // 1-3s: transition to the second keyframe, then hold until 4s
+    // 4s: jump to the third keyframe, and hold until 6s
+    // 6s: jump to the last keyframe, which has no hold
+    fn tour() -> Tour {
+        Tour {
+            keyframes: vec![
+                keyframe(view(0.0, 0.0, 1.0), 5000, 1000),
+                keyframe(view(100.0, 0.0, 3.0), 2000, 1000),
+                keyframe(view(100.0, 50.0, 1.0), 0, 2000),
+                keyframe(view(0.0, 0.0, 2.0), 0, 0),
+            ],
+        }
+    }
+
+    fn state(view: View, reached: usize, done: bool) -> Option<TourState> {
+        Some(TourState {
+            view,
+            reached,
+            done,
+        })
+    }
+
+    fn at(tour: &Tour, ms: u64) -> Option<TourState> {
+        tour.state_at(Duration::from_millis(ms))
+    }
+
+    #[test]
+    fn duration_skips_first_transition() {
+        assert_eq!(tour().duration(), Duration::from_secs(6));
+        assert_eq!(Tour::default().duration(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn state_first_hold() {
+        let tour = tour();
+        let first = view(0.0, 0.0, 1.0);
+
+        assert_eq!(at(&tour, 0), state(first, 0, false));
+        assert_eq!(at(&tour, 999), state(first, 0, false));
+        // the transition starts at the first keyframe's view
+        assert_eq!(at(&tour, 1000), state(first, 0, false));
+    }
+
+    #[test]
+    fn state_transition_interpolates() {
+        let tour = tour();
+
+        assert_eq!(at(&tour, 2000), state(view(50.0, 0.0, 2.0), 0, false));
+        assert_eq!(at(&tour, 2500), state(view(75.0, 0.0, 2.5), 0, false));
+    }
+
+    #[test]
+    fn state_reached_keyframes() {
+        let tour = tour();
+
+        assert_eq!(at(&tour, 3000), state(view(100.0, 0.0, 3.0), 1, false));
+        assert_eq!(at(&tour, 3999), state(view(100.0, 0.0, 3.0), 1, false));
+
+        // zero length transitions jump straight to the keyframe
+        assert_eq!(at(&tour, 4000), state(view(100.0, 50.0, 1.0), 2, false));
+        assert_eq!(at(&tour, 5999), state(view(100.0, 50.0, 1.0), 2, false));
+    }
+
+    #[test]
+    fn state_done_after_last_hold() {
+        let tour = tour();
+        let last = view(0.0, 0.0, 2.0);
+
+        assert_eq!(at(&tour, 6000), state(last, 3, true));
+        assert_eq!(at(&tour, 60_000), state(last, 3, true));
+    }
+
+    #[test]
+    fn state_edge_cases() {
+        assert_eq!(at(&Tour::default(), 0), None);
+
+        let single = Tour {
+            keyframes: vec![keyframe(view(1.0, 2.0, 3.0), 0, 0)],
+        };
+        assert_eq!(at(&single, 0), state(view(1.0, 2.0, 3.0), 0, true));
+
+        // no hold or transition anywhere
+        let instant = Tour {
+            keyframes: vec![
+                keyframe(view(0.0, 0.0, 1.0), 0, 0),
+                keyframe(view(1.0, 1.0, 1.0), 0, 0),
+            ],
+        };
+        assert_eq!(at(&instant, 0), state(view(1.0, 1.0, 1.0), 1, true));
+    }
+
+    #[test]
+    fn export_playback() {
+        let tour = tour();
+        let dir = PathBuf::from("tour");
+
+        let mut playback = TourPlayback::new(PlaybackMode::Export {
+            dir: dir.clone(),
+            fps: 2,
+        });
+
+        let mut frames = Vec::new();
+
+        loop {
+            let frame = playback.advance(&tour).unwrap();
+            let done = frame.done;
+            frames.push(frame);
+
+            if done {
+                break;
+            }
+
+            assert!(frames.len() < 100, "playback never finished");
+        }
+
+        // one frame every 0.5s, up to and including 6s
+        assert_eq!(frames.len(), 13);
+        assert_eq!(playback.frame(), 13);
+        assert_eq!(playback.time(), Duration::from_secs(6));
+
+        let reached = frames
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, frame)| Some((ix, frame.reached?)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(reached, vec![(0, 0), (6, 1), (8, 2), (12, 3)]);
+
+        assert_eq!(frames[4].view, view(50.0, 0.0, 2.0));
+
+        for (ix, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.capture, Some(TourPlayback::frame_path(&dir, ix)));
+        }
+
+        assert_eq!(
+            frames[3].capture,
+            Some(PathBuf::from("tour/frame_00003.png"))
+        );
+    }
+
+    #[test]
+    fn export_playback_zero_fps() {
+        let tour = tour();
+
+        let mut playback = TourPlayback::new(PlaybackMode::Export {
+            dir: PathBuf::from("tour"),
+            fps: 0,
+        });
+
+        playback.advance(&tour).unwrap();
+        playback.advance(&tour).unwrap();
+
+        // treated as 1 fps
+        assert_eq!(playback.time(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn preview_playback_reports_reached_once() {
+        let tour = tour();
+
+        let mut playback = TourPlayback::new(PlaybackMode::Preview);
+
+        let first = playback.advance(&tour).unwrap();
+        assert_eq!(first.reached, Some(0));
+        assert_eq!(first.capture, None);
+        assert!(!first.done);
+
+        let second = playback.advance(&tour).unwrap();
+        assert_eq!(second.reached, None);
+
+        assert_eq!(
+            TourPlayback::new(PlaybackMode::Preview).advance(&Tour::default()),
+            None
+        );
+    }
+}
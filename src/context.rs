@@ -25,7 +25,10 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use lazy_static::lazy_static;
 
 use crate::{
-    app::{selection::NodeSelection, App, AppChannels, AppMsg, SharedState},
+    app::{
+        selection::{GrowOp, NodeSelection},
+        App, AppChannels, AppMsg, SharedState,
+    },
     geometry::{Point, Rect},
     gui::console::Console,
     reactor::{ModalError, ModalHandler, ModalSuccess, Reactor},
@@ -150,6 +153,13 @@ pub struct ContextMgr {
     // context_order: RwLock<Vec<String>>,
     context_actions: RwLock<HashMap<String, ContextAction>>,
 
+    // actions triggered by keybindings, which are applied once the
+    // context has been loaded on the following frame
+    action_tx: channel::Sender<String>,
+    action_rx: channel::Receiver<String>,
+    queued_actions: Vec<String>,
+    context_loaded: bool,
+
     position: Arc<AtomicCell<Point>>,
 }
 
@@ -335,9 +345,25 @@ pub fn pan_to_node_action(app: &App) -> ContextAction {
     )
 }
 
+/// Grows or shrinks the selection in the background, the same way as
+/// the keybindings
+pub fn grow_selection_action(app: &App, op: GrowOp) -> ContextAction {
+    let req = [TypeId::of::<FxHashSet<NodeId>>()];
+
+    let app_tx = app.channels.app_tx.clone();
+
+    ContextAction::new(
+        &req,
+        Box::new(move |_ctx| {
+            app_tx.send(AppMsg::GrowSelection(op)).unwrap();
+        }),
+    )
+}
+
 impl std::default::Default for ContextMgr {
     fn default() -> Self {
         let (ctx_tx, ctx_rx) = channel::unbounded();
+        let (action_tx, action_rx) = channel::unbounded();
 
         Self {
             init: InitState::Null.into(),
@@ -349,6 +375,10 @@ impl std::default::Default for ContextMgr {
             frame_active: false.into(),
             // context_order: RwLock::new(Vec::default()),
             context_actions: RwLock::new(HashMap::default()),
+            action_tx,
+            action_rx,
+            queued_actions: Vec::new(),
+            context_loaded: false,
             // type_names: RwLock::new(FxHashMap::default()),
             position: Arc::new(Point::ZERO.into()),
             ctx_type_map: Arc::new(CtxTypeMap::default()),
@@ -388,6 +418,23 @@ impl ContextMgr {
         }
     }
 
+    pub fn action_names(&self) -> Vec<String> {
+        let mut names = self
+            .context_actions
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Sending an action name applies the action to the context as
+    /// it is on the next frame, if the action is applicable
+    pub fn action_queue(&self) -> channel::Sender<String> {
+        self.action_tx.clone()
+    }
+
     pub fn set_type_name_ez<T>(&self)
     where
         T: std::any::Any + Send + Sync + 'static,
@@ -470,10 +517,16 @@ impl ContextMgr {
             self.init.store(InitState::Ready);
         }
 
+        self.context_loaded = false;
+
         if self.load_context_this_frame.load() {
             let mut context = Arc::make_mut(&mut self.frame_context);
             let type_names = self.ctx_type_map.id_to_name.read();
 
+            // values that weren't produced this time, e.g. the hovered
+            // node, should not linger from an earlier load
+            context.values.clear();
+
             while let Ok((type_id, ctx_val)) = self.ctx_rx.try_recv() {
                 let name = if let Some(n) = type_names.get(&type_id) {
                     n.to_string()
@@ -483,6 +536,43 @@ impl ContextMgr {
                 context.values.insert(type_id, Arc::new(ctx_val));
             }
             self.load_context_this_frame.store(false);
+            self.context_loaded = true;
+        }
+    }
+
+    /// Applies the actions queued by keybindings, once the context
+    /// they were triggered in has been loaded
+    pub fn apply_queued_actions(&mut self, app: &App) {
+        if self.context_loaded && !self.queued_actions.is_empty() {
+            let actions = self.context_actions.read();
+
+            for name in self.queued_actions.drain(..) {
+                match actions.get(&name) {
+                    Some(action) => {
+                        if action
+                            .apply_action(app, &self.frame_context)
+                            .is_none()
+                        {
+                            log::debug!(
+                                "context action \"{}\" not applicable",
+                                name
+                            );
+                        }
+                    }
+                    None => log::warn!("unknown context action: {}", name),
+                }
+            }
+        }
+
+        let mut queued = false;
+
+        while let Ok(name) = self.action_rx.try_recv() {
+            self.queued_actions.push(name);
+            queued = true;
+        }
+
+        if queued {
+            self.load_context_this_frame.store(true);
         }
     }
 
@@ -17,7 +17,8 @@ use crossbeam::channel;
 
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use rustc_hash::FxHashSet;
 
 use crate::asynchronous::AsyncResult;
 
@@ -229,6 +230,204 @@ impl GraphQuery {
 
         self.path_range(path_id, start, end)
     }
+
+    /// The length of the path in basepairs
+    pub fn path_len(&self, path_id: PathId) -> Option<usize> {
+        let steps = self.graph.path_steps(path_id)?;
+        Some(steps.map(|step| self.graph.node_len(step.handle())).sum())
+    }
+
+    /// The nodes covering the basepair range `start..end` of the path;
+    /// the range is clamped to the path's length
+    pub fn path_range_nodes(
+        &self,
+        path_id: PathId,
+        start: usize,
+        end: usize,
+    ) -> Option<FxHashSet<NodeId>> {
+        let len = self.path_len(path_id)?;
+
+        if len == 0 || start >= len {
+            return None;
+        }
+
+        let end = end.min(len - 1).max(start);
+
+        let range = self.path_basepair_range(path_id, start, end)?;
+
+        Some(
+            range
+                .into_iter()
+                .map(|(handle, _, _)| handle.id())
+                .collect(),
+        )
+    }
+
+    /// The basepair ranges of the path that are covered by `nodes`,
+    /// as half-open `(start, end)` intervals; consecutive steps are
+    /// merged into one range
+    pub fn path_node_ranges(
+        &self,
+        path_id: PathId,
+        nodes: &FxHashSet<NodeId>,
+    ) -> Option<Vec<(usize, usize)>> {
+        let steps = self.graph.path_steps(path_id)?;
+
+        let mut ranges = Vec::new();
+        let mut current: Option<(usize, usize)> = None;
+
+        let mut offset = 0usize;
+
+        for step in steps {
+            let handle = step.handle();
+            let len = self.graph.node_len(handle);
+
+            if nodes.contains(&handle.id()) {
+                current = match current {
+                    Some((start, end)) if end == offset => {
+                        Some((start, offset + len))
+                    }
+                    Some(range) => {
+                        ranges.push(range);
+                        Some((offset, offset + len))
+                    }
+                    None => Some((offset, offset + len)),
+                };
+            }
+
+            offset += len;
+        }
+
+        ranges.extend(current);
+
+        Some(ranges)
+    }
+
+    /// Finds the path and path-local range for a range on a named
+    /// sequence. The name can be a full path name, including PanSN
+    /// names, or the name shared by subpaths named `name:start-end`,
+    /// in which case the subpath containing `start` is used. A name
+    /// that's unique as the last part of a PanSN name also matches
+    pub fn resolve_path_range(
+        &self,
+        name: &str,
+        start: usize,
+        end: usize,
+    ) -> Result<(PathId, usize, usize)> {
+        use bstr::ByteSlice;
+
+        let (start, end) = (start.min(end), start.max(end));
+
+        if let Some(path) = self.graph.get_path_id(name.as_bytes()) {
+            return Ok((path, start, end));
+        }
+
+        let mut suffix_matches = Vec::new();
+
+        let pansn_suffix = format!("#{}", name);
+
+        for path in self.graph.path_ids() {
+            let path_name = match self.graph.get_path_name_vec(path) {
+                Some(path_name) => path_name,
+                None => continue,
+            };
+
+            if let Some((base, s, e)) =
+                crate::annotations::path_name_range(&path_name)
+            {
+                if base == name.as_bytes() && start >= s && start < e {
+                    let end = end.min(e);
+                    return Ok((path, start - s, end - s));
+                }
+            }
+
+            if path_name.ends_with_str(&pansn_suffix) {
+                suffix_matches.push(path);
+            }
+        }
+
+        match suffix_matches.as_slice() {
+            [path] => Ok((*path, start, end)),
+            [] => bail!("No path matches \"{}\"", name),
+            _ => bail!(
+                "{} paths match \"{}\", use the full path name",
+                suffix_matches.len(),
+                name
+            ),
+        }
+    }
+
+    /// Resolves a location such as `chr1:1,234,000-1,240,000`, a
+    /// single position `chr1:1234`, or a whole path name, into a path
+    /// and a path-local range
+    pub fn resolve_location(
+        &self,
+        location: &str,
+    ) -> Result<(PathId, usize, usize)> {
+        let location = location.trim();
+
+        if location.is_empty() {
+            bail!("Empty location");
+        }
+
+        if let Some(path) = self.graph.get_path_id(location.as_bytes()) {
+            let len = self.path_len(path).unwrap_or(0);
+            return Ok((path, 0, len));
+        }
+
+        let (name, range) = parse_location(location).map_err(|err| {
+            anyhow!("Could not parse location \"{}\": {}", location, err)
+        })?;
+
+        self.resolve_path_range(name, range.0, range.1)
+    }
+}
+
+/// Splits a `name:start-end` or `name:pos` location into the name and
+/// range; commas and underscores in the coordinates are ignored
+///
+/// The coordinates are 1-based and inclusive, as in genome browsers,
+/// and are returned as a 0-based half-open range
+pub fn parse_location(location: &str) -> Result<(&str, (usize, usize))> {
+    let parse_pos = |s: &str| -> Result<usize> {
+        s.trim()
+            .replace(&[',', '_'][..], "")
+            .parse()
+            .map_err(|_| anyhow!("invalid position \"{}\"", s.trim()))
+    };
+
+    let ix = location
+        .rfind(':')
+        .ok_or_else(|| anyhow!("expected name:start-end"))?;
+    let (name, range) = (location[..ix].trim(), &location[ix + 1..]);
+
+    if name.is_empty() {
+        bail!("missing name");
+    }
+
+    let range = if let Some(mid) = range.find('-') {
+        let start = parse_pos(&range[..mid])?;
+        let end = parse_pos(&range[mid + 1..])?;
+        one_based_range(start, end)?
+    } else {
+        let pos = parse_pos(range)?;
+        one_based_range(pos, pos)?
+    };
+
+    Ok((name, range))
+}
+
+/// Converts a 1-based, inclusive range to a 0-based, half-open range
+pub fn one_based_range(start: usize, end: usize) -> Result<(usize, usize)> {
+    if start == 0 || end == 0 {
+        bail!("positions start at 1");
+    }
+
+    if start > end {
+        bail!("start {} is after end {}", start, end);
+    }
+
+    Ok((start - 1, end))
 }
 
 struct QueryThread {
@@ -345,3 +544,76 @@ pub enum GraphQueryResp {
     //     right: Vec<NodeId>,
     // },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_range() {
+        assert_eq!(parse_location("chr1:1-100").unwrap(), ("chr1", (0, 100)));
+        assert_eq!(parse_location("chr1:5-5").unwrap(), ("chr1", (4, 5)));
+    }
+
+    #[test]
+    fn location_separators() {
+        assert_eq!(
+            parse_location("chr1:1,000-2,000").unwrap(),
+            ("chr1", (999, 2000))
+        );
+        assert_eq!(
+            parse_location("chr1:1_000_001-1_000_100").unwrap(),
+            ("chr1", (1_000_000, 1_000_100))
+        );
+        assert_eq!(
+            parse_location(" chr1 : 10 - 20 ").unwrap(),
+            ("chr1", (9, 20))
+        );
+    }
+
+    #[test]
+    fn location_pansn_name() {
+        assert_eq!(
+            parse_location("HG002#1#chr1:101-200").unwrap(),
+            ("HG002#1#chr1", (100, 200))
+        );
+
+        // only the last colon separates the range
+        assert_eq!(
+            parse_location("grch38#chr1:0-1000:1-10").unwrap(),
+            ("grch38#chr1:0-1000", (0, 10))
+        );
+    }
+
+    #[test]
+    fn location_single_position() {
+        assert_eq!(parse_location("chr1:1").unwrap(), ("chr1", (0, 1)));
+        assert_eq!(
+            parse_location("chr1:12,345").unwrap(),
+            ("chr1", (12_344, 12_345))
+        );
+    }
+
+    #[test]
+    fn location_bad_input() {
+        assert!(parse_location("").is_err());
+        assert!(parse_location("chr1").is_err());
+        assert!(parse_location(":1-10").is_err());
+        assert!(parse_location("chr1:").is_err());
+        assert!(parse_location("chr1:a-10").is_err());
+        assert!(parse_location("chr1:1-").is_err());
+        assert!(parse_location("chr1:-5").is_err());
+        assert!(parse_location("chr1:0-10").is_err());
+        assert!(parse_location("chr1:0").is_err());
+        assert!(parse_location("chr1:20-10").is_err());
+    }
+
+    #[test]
+    fn one_based_ranges() {
+        assert_eq!(one_based_range(1, 100).unwrap(), (0, 100));
+        assert_eq!(one_based_range(7, 7).unwrap(), (6, 7));
+        assert!(one_based_range(0, 10).is_err());
+        assert!(one_based_range(10, 0).is_err());
+        assert!(one_based_range(20, 10).is_err());
+    }
+}
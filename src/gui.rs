@@ -13,6 +13,7 @@ use handlegraph::{
 
 use anyhow::Result;
 
+use parking_lot::{Mutex, MutexGuard};
 use rustc_hash::FxHashMap;
 
 use crossbeam::atomic::AtomicCell;
@@ -23,7 +24,8 @@ use crate::{
         Gff3Records, Labels,
     },
     app::{
-        App, AppChannels, AppMsg, AppSettings, OverlayCreatorMsg, SharedState,
+        App, AppChannels, AppMsg, AppSettings, OverlayCreatorMsg,
+        SelectionTool, SettingsStore, SharedState,
     },
     context::ContextMgr,
     reactor::Reactor,
@@ -36,6 +38,7 @@ use crate::{app::OverlayState, geometry::*};
 
 use crate::overlays::OverlayKind;
 
+use crate::asynchronous::AsyncResult;
 use crate::graph_query::GraphQuery;
 
 use crate::input::binds::{
@@ -50,15 +53,19 @@ use ash::vk;
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+pub mod command_palette;
 pub mod console;
 pub mod debug;
+pub mod minimap;
 pub mod text;
 pub mod util;
 pub mod widgets;
 pub mod windows;
 
+use command_palette::*;
 use console::*;
 use debug::*;
+use minimap::*;
 #[allow(unused_imports)]
 use util::*;
 use widgets::*;
@@ -89,11 +96,25 @@ pub struct Gui {
 
     annotation_file_list: AnnotationFileList,
 
+    signal_overlay_creator: SignalOverlayCreator,
+
     pub console: Console<'static>,
     console_down: bool,
 
+    command_palette: CommandPalette,
+
+    minimap: Minimap,
+
+    bookmarks: Arc<Mutex<BookmarkList>>,
+
+    tour: Arc<Mutex<TourWindow>>,
+
+    measure: Arc<Mutex<MeasureTool>>,
+
     windows: GuiWindows,
     gui_channels: GuiChannels,
+
+    selection_stats_tx: crossbeam::channel::Sender<AsyncResult<SelectionStats>>,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -219,7 +240,8 @@ impl AppViewState {
         let path_details =
             ViewStateChannel::<PathDetails, ()>::new(path_details_state);
 
-        let path_list_state = PathList::new(&graph_query, path_id_cell);
+        let path_list_state =
+            PathList::new(&graph_query, path_id_cell, shared_state);
         let path_list = ViewStateChannel::<PathList, ()>::new(path_list_state);
 
         let overlay_list_state = OverlayList::new(overlay_state);
@@ -304,6 +326,9 @@ pub struct OpenWindows {
     themes: bool,
     overlays: bool,
     overlay_creator: bool,
+    signal_tracks: bool,
+
+    minimap: bool,
 }
 
 impl std::default::Default for OpenWindows {
@@ -324,6 +349,46 @@ impl std::default::Default for OpenWindows {
             themes: false,
             overlays: false,
             overlay_creator: false,
+            signal_tracks: false,
+
+            minimap: true,
+        }
+    }
+}
+
+impl OpenWindows {
+    fn flags_mut(&mut self) -> [(&'static str, &mut bool); 13] {
+        [
+            ("window.settings.open", &mut self.settings),
+            ("window.annotation_files.open", &mut self.annotation_files),
+            (
+                "window.annotation_records.open",
+                &mut self.annotation_records,
+            ),
+            ("window.label_sets.open", &mut self.label_set_list),
+            ("window.nodes.open", &mut self.nodes),
+            ("window.node_details.open", &mut self.node_details),
+            ("window.paths.open", &mut self.paths),
+            ("window.path_details.open", &mut self.path_details),
+            ("window.themes.open", &mut self.themes),
+            ("window.overlays.open", &mut self.overlays),
+            ("window.overlay_creator.open", &mut self.overlay_creator),
+            ("window.signal_tracks.open", &mut self.signal_tracks),
+            ("window.minimap.open", &mut self.minimap),
+        ]
+    }
+
+    fn restore(&mut self, store: &SettingsStore) {
+        for (key, open) in self.flags_mut() {
+            if let Some(stored) = store.get::<bool>(key) {
+                *open = stored;
+            }
+        }
+    }
+
+    fn store(&mut self, store: &SettingsStore) {
+        for (key, open) in self.flags_mut() {
+            store.set(key, open);
         }
     }
 }
@@ -403,7 +468,8 @@ impl Gui {
         };
         ctx.set_fonts(font_defs);
 
-        let open_windows = OpenWindows::default();
+        let mut open_windows = OpenWindows::default();
+        open_windows.restore(settings.store());
 
         let frame_input = FrameInput::default();
 
@@ -417,18 +483,29 @@ impl Gui {
             dropped_file.clone(),
         );
 
-        let menu_bar = MenuBar::new(shared_state.overlay_state().clone());
+        let menu_bar = MenuBar::new(
+            shared_state.overlay_state().clone(),
+            shared_state.clone(),
+        );
 
         // let clipboard_ctx = ClipboardProvider::new().unwrap();
 
         let mut path_picker_source = PathPickerSource::new(&graph_query)?;
 
+        let command_palette =
+            CommandPalette::new(reactor, path_picker_source.paths().clone());
+
         let annotation_file_list = AnnotationFileList::new(
             reactor,
             channels.app_tx.clone(),
             channels.gui_tx.clone(),
         )?;
 
+        let signal_overlay_creator = SignalOverlayCreator::new(
+            reactor,
+            path_picker_source.create_picker(),
+        )?;
+
         let console = Console::new(
             reactor,
             channels.clone(),
@@ -448,7 +525,13 @@ impl Gui {
             use Gff3Column as Gff;
 
             list.set_default_columns(
-                [Gff::Source, Gff::Type, Gff::Frame],
+                [
+                    Gff::Source,
+                    Gff::Type,
+                    Gff::Frame,
+                    Gff::Attribute(b"gene_id".to_vec()),
+                    Gff::Attribute(b"transcript_id".to_vec()),
+                ],
                 [Gff::SeqId, Gff::Start, Gff::End, Gff::Strand],
             );
 
@@ -477,8 +560,11 @@ impl Gui {
             let path_view_id = egui::Id::new("path_view_window");
             let gui_id = GuiId::new(path_view_id);
 
-            let mut path_view_state =
-                PathPositionList::new(path_view_renderer.clone());
+            let mut path_view_state = PathPositionList::new(
+                reactor,
+                path_view_renderer.clone(),
+                path_picker_source.create_picker(),
+            );
 
             windows.add_window(
                 gui_id,
@@ -488,6 +574,7 @@ impl Gui {
                         reactor,
                         channels,
                         shared_state,
+                        annotations,
                         ..
                     } = app;
 
@@ -496,12 +583,143 @@ impl Gui {
                         reactor,
                         channels,
                         shared_state,
+                        annotations,
                         nodes,
                     );
                 },
             );
         }
 
+        {
+            let gui_id = GuiId::new(egui::Id::new(AlignmentList::ID));
+
+            let mut alignment_list =
+                AlignmentList::new(reactor, channels.app_tx.clone())?;
+
+            windows.add_window(
+                gui_id,
+                "Alignments",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    alignment_list.ui_impl(app, ui, nodes);
+                },
+            );
+        }
+
+        {
+            let gui_id = GuiId::new(egui::Id::new(NodeQueryWindow::ID));
+
+            let mut node_query = NodeQueryWindow::new(reactor);
+
+            windows.add_window(
+                gui_id,
+                "Select by query",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    node_query.ui_impl(app, ui, nodes);
+                },
+            );
+        }
+
+        let selection_stats_tx = {
+            let gui_id = GuiId::new(egui::Id::new(SelectionStatsWindow::ID));
+
+            let mut selection_stats = SelectionStatsWindow::new();
+            let stats_tx = selection_stats.stats_tx().clone();
+
+            windows.add_window(
+                gui_id,
+                "Selection statistics",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    selection_stats.ui_impl(app, ui, nodes);
+                },
+            );
+
+            stats_tx
+        };
+
+        {
+            let gui_id = GuiId::new(egui::Id::new(SelectionSets::ID));
+
+            let mut selection_sets =
+                SelectionSets::new(reactor, path_picker_source.create_picker());
+
+            windows.add_window(
+                gui_id,
+                "Selections",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    selection_sets.ui_impl(app, ui, nodes);
+                },
+            );
+        }
+
+        {
+            let gui_id = GuiId::new(egui::Id::new(GotoLocation::ID));
+
+            let mut goto_location = GotoLocation::new();
+
+            windows.add_window(
+                gui_id,
+                "Go to location",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    goto_location.ui_impl(app, ui, nodes);
+                },
+            );
+        }
+
+        // the bookmark markers are drawn even when the window is
+        // closed, so the list is shared with the window
+        let bookmarks = {
+            let gui_id = GuiId::new(egui::Id::new(BookmarkList::ID));
+
+            let bookmarks = Arc::new(Mutex::new(BookmarkList::new(reactor)));
+            let list = bookmarks.clone();
+
+            windows.add_window(
+                gui_id,
+                "Bookmarks",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    list.lock().ui_impl(app, ui, nodes);
+                },
+            );
+
+            bookmarks
+        };
+
+        // the tour is stepped by the main loop every frame
+        let tour = {
+            let gui_id = GuiId::new(egui::Id::new(TourWindow::ID));
+
+            let tour = Arc::new(Mutex::new(TourWindow::new()));
+            let window = tour.clone();
+
+            windows.add_window(
+                gui_id,
+                "Tour",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    window.lock().ui_impl(app, ui, nodes);
+                },
+            );
+
+            tour
+        };
+
+        // the measurement and scale bar are drawn outside the window
+        let measure = {
+            let gui_id = GuiId::new(egui::Id::new(MeasureTool::ID));
+
+            let measure = Arc::new(Mutex::new(MeasureTool::new(reactor)));
+            let tool = measure.clone();
+
+            windows.add_window(
+                gui_id,
+                "Measure",
+                move |app: &App, ui: &mut egui::Ui, nodes: &[Node]| {
+                    tool.lock().ui_impl(app, ui, nodes);
+                },
+            );
+
+            measure
+        };
+
         {
             /*
             let annotation_file_list = AnnotationFileList::new(
@@ -570,6 +788,8 @@ impl Gui {
 
         // windows.
 
+        windows.restore_open(settings.store());
+
         let gui = Self {
             ctx,
             frame_input,
@@ -594,20 +814,65 @@ impl Gui {
 
             annotation_file_list,
 
+            signal_overlay_creator,
+
             console_down: false,
             console,
 
+            command_palette,
+
+            minimap: Minimap::new(reactor),
+
+            bookmarks,
+
+            tour,
+
+            measure,
+
             windows,
             gui_channels: GuiChannels::new(),
+
+            selection_stats_tx,
         };
 
         Ok(gui)
     }
 
+    pub fn minimap_needs_render(&self, overlay: Option<usize>) -> bool {
+        self.open_windows.minimap && self.minimap.needs_render(overlay)
+    }
+
+    pub fn minimap_mut(&mut self) -> &mut Minimap {
+        &mut self.minimap
+    }
+
+    pub fn upload_minimap(&mut self, app: &GfaestusVk) -> Result<()> {
+        self.minimap.upload(app, &mut self.draw_system)
+    }
+
+    pub fn tour_mut(&mut self) -> MutexGuard<'_, TourWindow> {
+        self.tour.lock()
+    }
+
+    pub fn measure_mut(&mut self) -> MutexGuard<'_, MeasureTool> {
+        self.measure.lock()
+    }
+
     pub fn app_view_state(&self) -> &AppViewState {
         &self.view_state
     }
 
+    pub fn selection_stats_open(&self) -> bool {
+        let id = egui::Id::new(SelectionStatsWindow::ID);
+        self.windows.is_open(GuiId::new(id))
+    }
+
+    pub fn selection_stats_tx(
+        &self,
+    ) -> &crossbeam::channel::Sender<AsyncResult<SelectionStats>> {
+        &self.selection_stats_tx
+    }
+
     // TODO this should be handled better
     pub fn populate_overlay_list<'a>(
         &mut self,
@@ -623,6 +888,8 @@ impl Gui {
 
         self.console.populate_overlay_list(&names);
 
+        self.tour.lock().populate_overlay_list(&names);
+
         self.menu_bar.populate_overlay_list(
             &self.view_state.overlay_list.state.overlay_names,
         );
@@ -705,6 +972,29 @@ impl Gui {
 
         self.console.ui(&self.ctx, self.console_down, reactor);
 
+        let open_console =
+            self.command_palette
+                .ui(&self.ctx, app, ctx_mgr, &mut self.console);
+
+        if open_console {
+            self.console_down = true;
+        }
+
+        if self.open_windows.minimap {
+            self.minimap.ui(&self.ctx, app);
+        }
+
+        self.bookmarks
+            .lock()
+            .draw_markers(&self.ctx, self.shared_state.view());
+
+        {
+            let gui_id = GuiId::new(egui::Id::new(MeasureTool::ID));
+            let window_open = self.windows.is_open(gui_id);
+
+            self.measure.lock().draw(&self.ctx, app, nodes, window_open);
+        }
+
         self.view_state.apply_received();
 
         let scr = self.ctx.input().screen_rect();
@@ -727,6 +1017,9 @@ impl Gui {
                 .ui(&self.ctx, overlay_creator);
 
             view_state.overlay_list.state.gradient_picker_ui(&self.ctx);
+
+            self.signal_overlay_creator
+                .ui(&self.ctx, &mut self.open_windows.signal_tracks);
         }
 
         if let Some(rect) = self.shared_state.active_mouse_rect_screen() {
@@ -748,6 +1041,35 @@ impl Gui {
             paint_area.painter().rect_stroke(rect.into(), 0.0, stroke);
         }
 
+        if let Some(points) = self.shared_state.active_mouse_polygon_screen() {
+            let painter = self.ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Background,
+                egui::Id::new("gui_painter_background"),
+            ));
+
+            let stroke =
+                egui::Stroke::new(2.0, egui::Color32::from_rgb(128, 128, 128));
+
+            for (p0, p1) in points.iter().zip(points.iter().skip(1)) {
+                painter.line_segment([(*p0).into(), (*p1).into()], stroke);
+            }
+
+            // show the closing edge, and the vertices of a polygon
+            if let (Some(first), Some(last)) = (points.first(), points.last()) {
+                let faint = egui::Stroke::new(
+                    1.0,
+                    egui::Color32::from_rgb(160, 160, 160),
+                );
+                painter.line_segment([(*first).into(), (*last).into()], faint);
+            }
+
+            if self.shared_state.selection_tool() == SelectionTool::Polygon {
+                for p in points.iter().take(points.len() - 1) {
+                    painter.circle_stroke((*p).into(), 3.0, stroke);
+                }
+            }
+        }
+
         self.annotation_file_list.ui(
             &self.ctx,
             &mut self.open_windows.annotation_files,
@@ -772,6 +1094,102 @@ impl Gui {
             open.store(is_open);
         }
 
+        {
+            let alignments_id = egui::Id::new(AlignmentList::ID);
+            let gui_id = GuiId::new(alignments_id);
+
+            let window = egui::Window::new("Alignments")
+                .id(alignments_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let goto_id = egui::Id::new(GotoLocation::ID);
+            let gui_id = GuiId::new(goto_id);
+
+            let window = egui::Window::new("Go to location")
+                .id(goto_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let query_id = egui::Id::new(NodeQueryWindow::ID);
+            let gui_id = GuiId::new(query_id);
+
+            let window = egui::Window::new("Select by query")
+                .id(query_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let stats_id = egui::Id::new(SelectionStatsWindow::ID);
+            let gui_id = GuiId::new(stats_id);
+
+            let window = egui::Window::new("Selection statistics")
+                .id(stats_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let sets_id = egui::Id::new(SelectionSets::ID);
+            let gui_id = GuiId::new(sets_id);
+
+            let window = egui::Window::new("Selections")
+                .id(sets_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let bookmarks_id = egui::Id::new(BookmarkList::ID);
+            let gui_id = GuiId::new(bookmarks_id);
+
+            let window = egui::Window::new("Bookmarks")
+                .id(bookmarks_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let tour_id = egui::Id::new(TourWindow::ID);
+            let gui_id = GuiId::new(tour_id);
+
+            let window = egui::Window::new("Tour")
+                .id(tour_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
+        {
+            let measure_id = egui::Id::new(MeasureTool::ID);
+            let gui_id = GuiId::new(measure_id);
+
+            let window = egui::Window::new("Measure")
+                .id(measure_id)
+                .default_pos(egui::Pos2::new(600.0, 200.0));
+
+            self.windows
+                .show_in_window(&app, &self.ctx, nodes, gui_id, window);
+        }
+
         {
             let read = self.annotation_file_list.current_annotation();
             if let Some((annot_type, annot_name)) = read.as_ref() {
@@ -963,6 +1381,8 @@ impl Gui {
         &mut self,
         reactor: &mut Reactor,
     ) -> Vec<egui::ClippedMesh> {
+        self.open_windows.store(self.settings.store());
+
         let (output, shapes) = self.ctx.end_frame();
 
         if !output.copied_text.is_empty() {
@@ -1151,6 +1571,9 @@ impl Gui {
                         GuiInput::KeyConsoleUp => {
                             self.console_down = false;
                         }
+                        GuiInput::KeyCommandPalette => {
+                            self.command_palette.toggle();
+                        }
                         _ => (),
                     }
                 }
@@ -1243,6 +1666,7 @@ pub enum GuiInput {
     KeyToggleConsole,
     KeyConsoleDown,
     KeyConsoleUp,
+    KeyCommandPalette,
 }
 
 impl BindableInput for GuiInput {
@@ -1251,7 +1675,7 @@ impl BindableInput for GuiInput {
         use winit::event::VirtualKeyCode as Key;
         use GuiInput as Input;
 
-        let key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
+        let mut key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
             (Key::F1, Input::KeyEguiInspectionUi),
             (Key::F2, Input::KeyEguiSettingsUi),
             (Key::F3, Input::KeyEguiMemoryUi),
@@ -1264,6 +1688,14 @@ impl BindableInput for GuiInput {
         .map(|(k, i)| (k, vec![KeyBind::new(i)]))
         .collect::<FxHashMap<_, _>>();
 
+        key_binds.insert(
+            Key::K,
+            vec![KeyBind::with_modifiers(
+                Input::KeyCommandPalette,
+                event::ModifiersState::CTRL,
+            )],
+        );
+
         let mouse_binds: FxHashMap<
             event::MouseButton,
             Vec<MouseButtonBind<Input>>,
@@ -1285,4 +1717,21 @@ impl BindableInput for GuiInput {
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    fn inputs() -> Vec<Self> {
+        use GuiInput as Input;
+
+        vec![
+            Input::KeyEguiInspectionUi,
+            Input::KeyEguiSettingsUi,
+            Input::KeyEguiMemoryUi,
+            Input::ButtonLeft,
+            Input::ButtonRight,
+            Input::WheelScroll,
+            Input::KeyToggleConsole,
+            Input::KeyConsoleDown,
+            Input::KeyConsoleUp,
+            Input::KeyCommandPalette,
+        ]
+    }
 }
@@ -0,0 +1,846 @@
+use std::sync::Arc;
+
+use handlegraph::{
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    pathhandlegraph::*,
+};
+
+use bstr::ByteSlice;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    annotations::{
+        AnnotationCollection, AnnotationLabelSet, AnnotationRecord, BedRecords,
+        Gff3Records,
+    },
+    app::{App, AppMsg, Select},
+    context::ContextMgr,
+    graph_query::GraphQuery,
+    reactor::{Host, Outbox},
+};
+
+use super::console::Console;
+
+/// What's done when a palette entry is chosen
+#[derive(Debug, Clone)]
+pub enum PaletteTarget {
+    Node(NodeId),
+    Path(PathId),
+    Record {
+        annotation: String,
+        seq_id: String,
+        start: usize,
+        end: usize,
+    },
+    Label(NodeId),
+    ContextAction(String),
+    Console(String),
+    ConsoleFn {
+        name: String,
+        takes_args: bool,
+    },
+}
+
+impl PaletteTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PaletteTarget::Node(_) => "Node",
+            PaletteTarget::Path(_) => "Path",
+            PaletteTarget::Record { .. } => "Record",
+            PaletteTarget::Label(_) => "Label",
+            PaletteTarget::ContextAction(_) => "Action",
+            PaletteTarget::Console(_) => "Console",
+            PaletteTarget::ConsoleFn { .. } => "Function",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PaletteEntry {
+    pub target: PaletteTarget,
+    pub text: String,
+    pub detail: String,
+    score: i64,
+}
+
+/// Everything the palette searches other than node IDs, collected
+/// when the palette is opened
+#[derive(Default)]
+struct PaletteSources {
+    paths: Arc<Vec<(PathId, String)>>,
+
+    gff3: Vec<(String, Arc<Gff3Records>)>,
+    bed: Vec<(String, Arc<BedRecords>)>,
+    label_sets: Vec<Arc<AnnotationLabelSet>>,
+
+    actions: Vec<String>,
+    console: Vec<String>,
+    // (name, signature)
+    console_fns: Arc<Vec<(String, String)>>,
+}
+
+impl PaletteSources {
+    fn collect(
+        paths: &Arc<Vec<(PathId, String)>>,
+        app: &App,
+        ctx_mgr: &ContextMgr,
+        console: &Console<'static>,
+        console_fns: &Arc<Vec<(String, String)>>,
+    ) -> Self {
+        let annotations = &app.annotations;
+
+        let mut gff3 = Vec::new();
+        let mut bed = Vec::new();
+
+        for (name, _) in annotations.annot_names() {
+            if let Some(records) = annotations.get_gff3(name) {
+                gff3.push((name.to_string(), records.clone()));
+            } else if let Some(records) = annotations.get_bed(name) {
+                bed.push((name.to_string(), records.clone()));
+            }
+        }
+
+        let label_sets = annotations
+            .label_sets()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut actions = ctx_mgr.action_names();
+        actions.sort();
+
+        // the most recent lines first, without repeats
+        let mut console_lines: Vec<String> = Vec::new();
+
+        for line in console.input_history().iter().rev() {
+            if !console_lines.contains(line) {
+                console_lines.push(line.to_string());
+            }
+        }
+
+        for cmd in CommandPalette::CONSOLE_COMMANDS.iter() {
+            if !console_lines.iter().any(|l| l == cmd) {
+                console_lines.push(cmd.to_string());
+            }
+        }
+
+        Self {
+            paths: paths.clone(),
+            gff3,
+            bed,
+            label_sets,
+            actions,
+            console: console_lines,
+            console_fns: console_fns.clone(),
+        }
+    }
+}
+
+/// A single search box over nodes, paths, annotation records, label
+/// sets, context actions and console commands, opened with Ctrl+K
+pub struct CommandPalette {
+    open: bool,
+    request_focus: bool,
+
+    query: String,
+
+    paths: Arc<Vec<(PathId, String)>>,
+    sources: Arc<PaletteSources>,
+    sources_stale: bool,
+
+    search_host: Host<(String, Arc<PaletteSources>), Vec<PaletteEntry>>,
+    searching: bool,
+    searched_query: Option<String>,
+
+    results: Vec<PaletteEntry>,
+    selected: usize,
+
+    // the functions in the console, found the first time the palette
+    // is opened
+    console_fns: Option<Arc<Vec<(String, String)>>>,
+}
+
+impl CommandPalette {
+    pub const ID: &'static str = "command_palette";
+
+    const MAX_RESULTS: usize = 100;
+
+    /// The console commands that don't take any arguments
+    const CONSOLE_COMMANDS: [&'static str; 2] = [":clear", ":reset"];
+
+    pub fn new(
+        reactor: &crate::reactor::Reactor,
+        paths: Arc<Vec<(PathId, String)>>,
+    ) -> Self {
+        let graph = reactor.graph_query.clone();
+
+        let search_host = reactor.create_host(
+            move |_outbox: &Outbox<Vec<PaletteEntry>>,
+                  (query, sources): (String, Arc<PaletteSources>)| {
+                search(&graph, &sources, &query)
+            },
+        );
+
+        Self {
+            open: false,
+            request_focus: false,
+
+            query: String::new(),
+
+            paths,
+            sources: Default::default(),
+            sources_stale: true,
+
+            search_host,
+            searching: false,
+            searched_query: None,
+
+            results: Vec::new(),
+            selected: 0,
+
+            console_fns: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self) {
+        if self.open {
+            self.close();
+        } else {
+            self.open = true;
+            self.request_focus = true;
+            self.sources_stale = true;
+            self.query.clear();
+            self.searched_query = None;
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+        self.results.clear();
+        self.selected = 0;
+    }
+
+    /// Returns `true` if a console function was chosen, and the
+    /// console should be opened to fill in its arguments
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        app: &App,
+        ctx_mgr: &ContextMgr,
+        console: &mut Console<'static>,
+    ) -> bool {
+        if !self.open {
+            return false;
+        }
+
+        if self.sources_stale {
+            let console_fns = self
+                .console_fns
+                .get_or_insert_with(|| Arc::new(console.function_signatures()));
+
+            self.sources = Arc::new(PaletteSources::collect(
+                &self.paths,
+                app,
+                ctx_mgr,
+                console,
+                console_fns,
+            ));
+            self.sources_stale = false;
+        }
+
+        if let Some(results) = self.search_host.take() {
+            self.results = results;
+            self.searching = false;
+            self.selected = 0;
+        }
+
+        // only one search runs at a time; if the query changed while
+        // it ran, the latest query is searched once it's done
+        if !self.searching
+            && self.searched_query.as_deref() != Some(self.query.as_str())
+        {
+            let input = (self.query.clone(), self.sources.clone());
+            if self.search_host.call(input).is_ok() {
+                self.searching = true;
+                self.searched_query = Some(self.query.clone());
+            }
+        }
+
+        let mut activated: Option<PaletteTarget> = None;
+        let mut close = false;
+
+        let results = &self.results;
+        let selected = &mut self.selected;
+        let query = &mut self.query;
+        let request_focus = &mut self.request_focus;
+        let searching = self.searching;
+
+        egui::Window::new(Self::ID)
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0.0, 80.0))
+            .show(ctx, |ui| {
+                ui.set_width(600.0);
+
+                let text_box = ui.add(
+                    egui::TextEdit::singleline(query)
+                        .desired_width(600.0)
+                        .hint_text("Node ID, path, annotation, action..."),
+                );
+
+                if *request_focus {
+                    text_box.request_focus();
+                    *request_focus = false;
+                }
+
+                let mut moved = false;
+
+                {
+                    let input = ui.input();
+
+                    if input.key_pressed(egui::Key::ArrowDown)
+                        && *selected + 1 < results.len()
+                    {
+                        *selected += 1;
+                        moved = true;
+                    }
+
+                    if input.key_pressed(egui::Key::ArrowUp) && *selected > 0 {
+                        *selected -= 1;
+                        moved = true;
+                    }
+
+                    if input.key_pressed(egui::Key::Enter) {
+                        activated =
+                            results.get(*selected).map(|e| e.target.clone());
+                    }
+
+                    if input.key_pressed(egui::Key::Escape) {
+                        close = true;
+                    }
+                }
+
+                if results.is_empty() {
+                    if searching {
+                        ui.label("Searching...");
+                    } else if !query.trim().is_empty() {
+                        ui.label("No results");
+                    }
+                    return;
+                }
+
+                ui.separator();
+
+                egui::ScrollArea::from_max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("command_palette_results").show(ui, |ui| {
+                        for (ix, entry) in results.iter().enumerate() {
+                            ui.label(entry.target.kind());
+
+                            let row = ui
+                                .selectable_label(ix == *selected, &entry.text);
+
+                            ui.add(egui::Label::new(&entry.detail).weak());
+
+                            if row.clicked() {
+                                activated = Some(entry.target.clone());
+                            }
+
+                            if moved && ix == *selected {
+                                row.scroll_to_me(egui::Align::Center);
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        let mut open_console = false;
+
+        if let Some(target) = activated {
+            open_console = Self::activate(target, app, ctx_mgr, console);
+            close = true;
+        }
+
+        if close {
+            self.close();
+        }
+
+        open_console
+    }
+
+    fn activate(
+        target: PaletteTarget,
+        app: &App,
+        ctx_mgr: &ContextMgr,
+        console: &mut Console<'static>,
+    ) -> bool {
+        let app_tx = app.channels.app_tx.clone();
+        let graph = app.reactor.graph_query.clone();
+
+        match target {
+            PaletteTarget::Node(node) | PaletteTarget::Label(node) => {
+                let select = Select::One { node, clear: true };
+                app_tx.send(AppMsg::Selection(select)).unwrap();
+                app_tx.send(AppMsg::goto_node(node)).unwrap();
+            }
+            PaletteTarget::Path(path) => {
+                let result = app.reactor.spawn_forget(async move {
+                    if let Some(steps) = graph.graph().path_steps(path) {
+                        let nodes = steps
+                            .map(|step| step.handle().id())
+                            .collect::<FxHashSet<_>>();
+
+                        let select = Select::Many { nodes, clear: true };
+                        app_tx.send(AppMsg::Selection(select)).unwrap();
+                        app_tx.send(AppMsg::goto_selection()).unwrap();
+                    }
+                });
+
+                if let Err(err) = result {
+                    log::error!("Error selecting path: {}", err);
+                }
+            }
+            PaletteTarget::Record {
+                annotation,
+                seq_id,
+                start,
+                end,
+            } => {
+                let ref_path =
+                    app.annotations.get_default_ref_path(&annotation);
+
+                let result = app.reactor.spawn_forget(async move {
+                    match record_nodes(&graph, ref_path, &seq_id, start, end) {
+                        Some(nodes) => {
+                            let select = Select::Many { nodes, clear: true };
+                            app_tx.send(AppMsg::Selection(select)).unwrap();
+                            app_tx.send(AppMsg::goto_selection()).unwrap();
+                        }
+                        None => {
+                            log::warn!(
+                                "Could not find {}:{}-{} in the graph",
+                                seq_id,
+                                start,
+                                end
+                            );
+                        }
+                    }
+                });
+
+                if let Err(err) = result {
+                    log::error!("Error selecting record: {}", err);
+                }
+            }
+            PaletteTarget::ContextAction(name) => {
+                ctx_mgr.action_queue().send(name).unwrap();
+            }
+            PaletteTarget::Console(line) => {
+                if let Err(err) = console.run_line(&app.reactor, &line) {
+                    log::warn!("Error running \"{}\": {}", line, err);
+                }
+            }
+            PaletteTarget::ConsoleFn { name, takes_args } => {
+                if takes_args {
+                    console.set_input(&format!("{}(", name));
+                    return true;
+                }
+
+                let line = format!("{}()", name);
+                if let Err(err) = console.run_line(&app.reactor, &line) {
+                    log::warn!("Error running \"{}\": {}", line, err);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// The nodes covered by an annotation record; the record's sequence
+/// name is looked up among the paths first, then the annotation's
+/// reference path is used, if any
+fn record_nodes(
+    graph: &GraphQuery,
+    ref_path: Option<PathId>,
+    seq_id: &str,
+    start: usize,
+    end: usize,
+) -> Option<FxHashSet<NodeId>> {
+    if let Ok((path, start, end)) = graph.resolve_path_range(seq_id, start, end)
+    {
+        return graph.path_range_nodes(path, start, end);
+    }
+
+    let path = ref_path?;
+    let path_name = graph.graph().get_path_name_vec(path)?;
+    let offset = crate::annotations::path_name_offset(&path_name).unwrap_or(0);
+
+    graph.path_range_nodes(
+        path,
+        start.checked_sub(offset)?,
+        end.checked_sub(offset)?,
+    )
+}
+
+/// Scores how well `query` matches `text`, ignoring case; substring
+/// matches score highest, then subsequence matches with consecutive
+/// letters and letters at the start of words. Returns `None` if the
+/// query isn't a subsequence of the text
+pub fn fuzzy_score(query: &[u8], text: &[u8]) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+
+    if query.len() > text.len() {
+        return None;
+    }
+
+    let eq = |a: u8, b: u8| a.eq_ignore_ascii_case(&b);
+
+    let word_start =
+        |ix: usize| ix == 0 || !text[ix - 1].is_ascii_alphanumeric();
+
+    let substring = text
+        .windows(query.len())
+        .position(|w| w.iter().zip(query).all(|(&a, &b)| eq(a, b)));
+
+    if let Some(pos) = substring {
+        let mut score = 10_000 - text.len() as i64;
+
+        if text.len() == query.len() {
+            score += 10_000;
+        }
+
+        if word_start(pos) {
+            score += 1_000;
+        }
+
+        return Some(score);
+    }
+
+    let mut score = 0i64;
+    let mut query_ix = 0;
+    let mut prev_matched = false;
+
+    for (ix, &c) in text.iter().enumerate() {
+        if query_ix < query.len() && eq(c, query[query_ix]) {
+            score += 1;
+
+            if prev_matched {
+                score += 5;
+            }
+
+            if word_start(ix) {
+                score += 10;
+            }
+
+            query_ix += 1;
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+    }
+
+    if query_ix == query.len() {
+        Some(score - text.len() as i64 / 4)
+    } else {
+        None
+    }
+}
+
+fn console_fn_entry(name: &str, signature: &str, score: i64) -> PaletteEntry {
+    let takes_args = signature
+        .split_once('(')
+        .map(|(_, params)| !params.trim_start().starts_with(')'))
+        .unwrap_or(false);
+
+    PaletteEntry {
+        target: PaletteTarget::ConsoleFn {
+            name: name.to_string(),
+            takes_args,
+        },
+        text: name.to_string(),
+        detail: signature.to_string(),
+        score,
+    }
+}
+
+fn push_result(results: &mut Vec<PaletteEntry>, entry: PaletteEntry) {
+    results.push(entry);
+
+    // keep the list from growing unbounded when a short query matches
+    // most annotation records
+    if results.len() >= CommandPalette::MAX_RESULTS * 8 {
+        sort_results(results);
+    }
+}
+
+fn sort_results(results: &mut Vec<PaletteEntry>) {
+    results.sort_by(|a, b| b.score.cmp(&a.score));
+    results.truncate(CommandPalette::MAX_RESULTS);
+}
+
+fn search_records<C>(
+    name: &str,
+    records: &C,
+    query: &[u8],
+    results: &mut Vec<PaletteEntry>,
+) where
+    C: AnnotationCollection,
+{
+    let columns = records.all_columns();
+
+    for record in records.records() {
+        let best = columns
+            .iter()
+            .flat_map(|column| record.get_all(column))
+            .filter_map(|value| Some((fuzzy_score(query, value)?, value)))
+            .max_by_key(|(score, _)| *score);
+
+        if let Some((score, value)) = best {
+            let seq_id = record.seq_id().to_str_lossy().to_string();
+            let (start, end) = record.range();
+
+            let detail = format!("{} - {}:{}-{}", name, seq_id, start, end);
+
+            push_result(
+                results,
+                PaletteEntry {
+                    target: PaletteTarget::Record {
+                        annotation: name.to_string(),
+                        seq_id,
+                        start,
+                        end,
+                    },
+                    text: value.to_str_lossy().to_string(),
+                    detail,
+                    score,
+                },
+            );
+        }
+    }
+}
+
+fn search(
+    graph: &GraphQuery,
+    sources: &PaletteSources,
+    query: &str,
+) -> Vec<PaletteEntry> {
+    let query = query.trim();
+    let mut results = Vec::new();
+
+    // with an empty query, list the commands
+    if query.is_empty() {
+        for name in sources.actions.iter() {
+            results.push(PaletteEntry {
+                target: PaletteTarget::ContextAction(name.to_string()),
+                text: name.to_string(),
+                detail: String::new(),
+                score: 0,
+            });
+        }
+
+        for line in sources.console.iter() {
+            results.push(PaletteEntry {
+                target: PaletteTarget::Console(line.to_string()),
+                text: line.to_string(),
+                detail: String::new(),
+                score: 0,
+            });
+        }
+
+        for (name, sig) in sources.console_fns.iter() {
+            results.push(console_fn_entry(name, sig, 0));
+        }
+
+        results.truncate(CommandPalette::MAX_RESULTS);
+        return results;
+    }
+
+    let q = query.as_bytes();
+
+    if let Ok(id) = query.parse::<u64>() {
+        let node = NodeId::from(id);
+
+        if graph.graph().has_node(node) {
+            results.push(PaletteEntry {
+                target: PaletteTarget::Node(node),
+                text: format!("Node {}", id),
+                detail: format!(
+                    "{} bp",
+                    graph.graph().node_len(Handle::pack(node, false))
+                ),
+                score: i64::MAX,
+            });
+        }
+    }
+
+    for (path, name) in sources.paths.iter() {
+        if let Some(score) = fuzzy_score(q, name.as_bytes()) {
+            push_result(
+                &mut results,
+                PaletteEntry {
+                    target: PaletteTarget::Path(*path),
+                    text: name.to_string(),
+                    detail: String::new(),
+                    score,
+                },
+            );
+        }
+    }
+
+    for (name, records) in sources.gff3.iter() {
+        search_records(name, records.as_ref(), q, &mut results);
+    }
+
+    for (name, records) in sources.bed.iter() {
+        search_records(name, records.as_ref(), q, &mut results);
+    }
+
+    for label_set in sources.label_sets.iter() {
+        let strings = label_set.label_strings();
+
+        for (node, label_ixs) in label_set.labels().iter() {
+            for &ix in label_ixs.iter() {
+                let text = &strings[ix];
+
+                if let Some(score) = fuzzy_score(q, text.as_bytes()) {
+                    push_result(
+                        &mut results,
+                        PaletteEntry {
+                            target: PaletteTarget::Label(*node),
+                            text: text.to_string(),
+                            detail: format!(
+                                "{} - node {}",
+                                label_set.name(),
+                                node.0
+                            ),
+                            score,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    for name in sources.actions.iter() {
+        if let Some(score) = fuzzy_score(q, name.as_bytes()) {
+            push_result(
+                &mut results,
+                PaletteEntry {
+                    target: PaletteTarget::ContextAction(name.to_string()),
+                    text: name.to_string(),
+                    detail: String::new(),
+                    score,
+                },
+            );
+        }
+    }
+
+    for line in sources.console.iter() {
+        if let Some(score) = fuzzy_score(q, line.as_bytes()) {
+            push_result(
+                &mut results,
+                PaletteEntry {
+                    target: PaletteTarget::Console(line.to_string()),
+                    text: line.to_string(),
+                    detail: String::new(),
+                    score,
+                },
+            );
+        }
+    }
+
+    for (name, sig) in sources.console_fns.iter() {
+        if let Some(score) = fuzzy_score(q, name.as_bytes()) {
+            push_result(&mut results, console_fn_entry(name, sig, score));
+        }
+    }
+
+    sort_results(&mut results);
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_exact_beats_substring() {
+        let exact = fuzzy_score(b"goto", b"goto").unwrap();
+        let substring = fuzzy_score(b"goto", b"goto_node").unwrap();
+
+        assert!(exact > substring);
+
+        // case is ignored
+        assert_eq!(fuzzy_score(b"GoTo", b"goto"), Some(exact));
+    }
+
+    #[test]
+    fn fuzzy_substring_beats_subsequence() {
+        let substring = fuzzy_score(b"grow", b"grow_selection").unwrap();
+        let subsequence = fuzzy_score(b"grow", b"go_right_or_west").unwrap();
+
+        assert!(substring > subsequence);
+
+        // even when the substring is in a longer text
+        let long =
+            fuzzy_score(b"grow", b"select_and_then_grow_by_hops").unwrap();
+        assert!(long > subsequence);
+    }
+
+    #[test]
+    fn fuzzy_word_start_bonus() {
+        // same length, with and without a word boundary before the
+        // match
+        let word = fuzzy_score(b"path", b"goto_path").unwrap();
+        let inner = fuzzy_score(b"path", b"gotoxpath").unwrap();
+        assert!(word > inner);
+
+        let word = fuzzy_score(b"sn", b"select_nodes").unwrap();
+        let inner = fuzzy_score(b"sn", b"selectnodesx").unwrap();
+        assert!(word > inner);
+    }
+
+    #[test]
+    fn fuzzy_no_match() {
+        assert_eq!(fuzzy_score(b"", b"anything"), Some(0));
+        assert_eq!(fuzzy_score(b"xyz", b"goto_node"), None);
+        assert_eq!(fuzzy_score(b"ogto", b"goto"), None);
+        assert_eq!(fuzzy_score(b"goto_node", b"goto"), None);
+    }
+
+    #[test]
+    fn console_fn_args() {
+        let entry =
+            console_fn_entry("save_keybindings", "save_keybindings() -> ()", 0);
+        assert!(matches!(
+            entry.target,
+            PaletteTarget::ConsoleFn {
+                takes_args: false,
+                ..
+            }
+        ));
+
+        let entry = console_fn_entry(
+            "goto_path_range",
+            "goto_path_range(path: &str, start: i64, end: i64) -> ()",
+            0,
+        );
+        assert!(matches!(
+            entry.target,
+            PaletteTarget::ConsoleFn {
+                takes_args: true,
+                ..
+            }
+        ));
+    }
+}
@@ -29,7 +29,7 @@ use crate::{
     annotations::{
         path_name_chr_range, path_name_range, AnnotationCollection,
         AnnotationRecord, Annotations, BedColumn, BedRecord, BedRecords,
-        ColumnKey, Gff3Column, Gff3Record, Gff3Records, LabelSet,
+        ColumnKey, GafRecords, Gff3Column, Gff3Record, Gff3Records, LabelSet,
     },
     overlays::{OverlayData, OverlayKind},
     reactor::{ModalError, ModalHandler, ModalSuccess},
@@ -356,6 +356,61 @@ impl Console<'static> {
         self.output_history.extend(output.lines().map(String::from));
     }
 
+    /// The lines entered in the console, oldest first
+    pub fn input_history(&self) -> &[String] {
+        &self.input_history
+    }
+
+    /// Replaces the text in the input field, and focuses it
+    pub fn set_input(&mut self, line: &str) {
+        self.input_line = line.to_string();
+        self.input_history_ix.take();
+        self.request_focus = true;
+    }
+
+    /// The names and signatures of the functions registered in the
+    /// console's Rhai engine, not including the standard library,
+    /// sorted by name; overloads are only listed once
+    pub fn function_signatures(&self) -> Vec<(String, String)> {
+        let engine = self.create_engine();
+
+        let mut fns = engine
+            .gen_fn_signatures(false)
+            .into_iter()
+            .filter_map(|sig| {
+                let name = sig.split('(').next()?.trim().to_string();
+
+                // skip operators, and property getters and setters
+                let is_ident = !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == ':');
+
+                is_ident.then(|| (name, sig))
+            })
+            .collect::<Vec<_>>();
+
+        fns.sort();
+        fns.dedup_by(|a, b| a.0 == b.0);
+
+        fns
+    }
+
+    /// Evaluates `line` as if it was entered in the console
+    pub fn run_line(&mut self, reactor: &Reactor, line: &str) -> Result<()> {
+        self.input_line = line.to_string();
+
+        self.input_history.push(self.input_line.clone());
+        self.append_output(&format!("> {}", self.input_line));
+
+        let result = self.eval_input(reactor, true);
+
+        self.input_line.clear();
+        self.input_history_ix.take();
+
+        result
+    }
+
     pub fn input_tx(&self) -> &crossbeam::channel::Sender<String> {
         &self.input_tx
     }
@@ -1176,6 +1231,10 @@ impl ConsoleShared {
         engine.register_static_module("db", self.db_module());
         engine.register_static_module("geo", self.geometry_module());
         engine.register_static_module("modal", self.modal_module());
+        engine.register_static_module(
+            "settings",
+            Arc::new(self.settings.store().rhai_module()),
+        );
 
         // TODO this should be configurable in the app options
         engine.set_max_call_levels(16);
@@ -1191,6 +1250,9 @@ impl ConsoleShared {
         engine.register_type::<Point>();
 
         self.add_annotation_fns(&mut engine);
+        self.add_goto_fns(&mut engine);
+        self.add_query_fns(&mut engine);
+        self.add_keybinding_fns(&mut engine);
 
         // the cloned Arc containing the graph is moved into the
         // closure, which is registered as a regular function in Rhai
@@ -2112,6 +2174,114 @@ impl ConsoleShared {
         module
     }
 
+    fn add_goto_fns(&self, engine: &mut rhai::Engine) {
+        use crate::graph_query::one_based_range;
+        use crate::gui::windows::{goto_location, goto_path_range};
+
+        // selects the range of the path, using the same name
+        // resolution as the location box, and moves the view to it;
+        // `start` and `end` are 1-based and inclusive, like locations
+        // such as `chr1:101-200`
+        let graph = self.graph.clone();
+        let app_msg_tx = self.channels.app_tx.clone();
+        engine.register_result_fn(
+            "goto_path_range",
+            move |path: &str, start: i64, end: i64| {
+                let (start, end) = (start.max(0) as usize, end.max(0) as usize);
+
+                let result = one_based_range(start, end)
+                    .and_then(|(start, end)| {
+                        graph.resolve_path_range(path, start, end)
+                    })
+                    .and_then(|(path, start, end)| {
+                        goto_path_range(&graph, &app_msg_tx, path, start, end)
+                    });
+
+                match result {
+                    Ok(count) => Ok(count as i64),
+                    Err(err) => Err(err.to_string().into()),
+                }
+            },
+        );
+
+        let graph = self.graph.clone();
+        let app_msg_tx = self.channels.app_tx.clone();
+        engine.register_result_fn(
+            "goto_path_range",
+            move |path: PathId, start: i64, end: i64| {
+                let (start, end) = (start.max(0) as usize, end.max(0) as usize);
+
+                let result =
+                    one_based_range(start, end).and_then(|(start, end)| {
+                        goto_path_range(&graph, &app_msg_tx, path, start, end)
+                    });
+
+                match result {
+                    Ok(count) => Ok(count as i64),
+                    Err(err) => Err(err.to_string().into()),
+                }
+            },
+        );
+
+        let graph = self.graph.clone();
+        let app_msg_tx = self.channels.app_tx.clone();
+        engine.register_result_fn("goto_location", move |location: &str| {
+            match goto_location(&graph, &app_msg_tx, location) {
+                Ok(msg) => Ok(msg),
+                Err(err) => Err(err.to_string().into()),
+            }
+        });
+    }
+
+    fn add_query_fns(&self, engine: &mut rhai::Engine) {
+        use crate::script::query::NodeQuery;
+
+        // returns the nodes matching the query, using the same syntax
+        // as the query selection window
+        let graph = self.graph.clone();
+        engine.register_result_fn("nodes_where", move |query: &str| {
+            match NodeQuery::compile(&graph, query).and_then(|q| q.select()) {
+                Ok(selection) => Ok(selection),
+                Err(err) => Err(err.to_string().into()),
+            }
+        });
+
+        let graph = self.graph.clone();
+        let app_msg_tx = self.channels.app_tx.clone();
+        engine.register_result_fn("select_nodes_where", move |query: &str| {
+            match NodeQuery::compile(&graph, query).and_then(|q| q.select()) {
+                Ok(selection) => {
+                    let count = selection.nodes.len() as i64;
+
+                    let select = Select::Many {
+                        nodes: selection.nodes,
+                        clear: true,
+                    };
+                    app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+
+                    Ok(count)
+                }
+                Err(err) => Err(err.to_string().into()),
+            }
+        });
+    }
+
+    fn add_keybinding_fns(&self, engine: &mut rhai::Engine) {
+        // bind, unbind, bind_script, bind_action, etc., the same
+        // functions that are used in the keybindings file
+        let keybindings = self.shared_state.keybindings.clone();
+        keybindings.register_fns(engine);
+
+        // writes the current bindings to the keybindings file
+        engine.register_result_fn(
+            "save_keybindings",
+            move || match keybindings.save() {
+                Ok(()) => Ok(()),
+                Err(err) => Err(err.to_string().into()),
+            },
+        );
+    }
+
     fn add_view_fns(&self, engine: &mut Engine) {
         engine.register_type::<View>();
 
@@ -2338,6 +2508,24 @@ impl ConsoleShared {
                         return Err("Error parsing GFF3 file".into());
                     }
                 }
+            } else if ext == "gtf" {
+                let records = Gff3Records::parse_gtf_file(&file);
+                match records {
+                    Ok(records) => {
+                        app_msg_tx
+                            .send(AppMsg::add_gff3_records(records))
+                            .unwrap();
+
+                        result_tx
+                            .send(Ok(rhai::Dynamic::from("Loaded GTF file")))
+                            .unwrap();
+
+                        return Ok(());
+                    }
+                    Err(_err) => {
+                        return Err("Error parsing GTF file".into());
+                    }
+                }
             } else if ext == "bed" {
                 let records = BedRecords::parse_bed_file(&file);
                 match records {
@@ -2356,6 +2544,24 @@ impl ConsoleShared {
                         return Err("Error parsing BED file".into());
                     }
                 }
+            } else if ext == "gaf" {
+                let records = GafRecords::parse_gaf_file(&file);
+                match records {
+                    Ok(records) => {
+                        app_msg_tx
+                            .send(AppMsg::add_gaf_records(records))
+                            .unwrap();
+
+                        result_tx
+                            .send(Ok(rhai::Dynamic::from("Loaded GAF file")))
+                            .unwrap();
+
+                        return Ok(());
+                    }
+                    Err(_err) => {
+                        return Err("Error parsing GAF file".into());
+                    }
+                }
             } else {
                 return Err("Invalid file extension".into());
             }
@@ -2425,6 +2631,7 @@ impl ConsoleShared {
                     path_name,
                     &column,
                     label_set_name,
+                    false,
                 );
 
             if let Some(label_set) = label_set {
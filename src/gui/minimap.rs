@@ -0,0 +1,391 @@
+use anyhow::Result;
+use ash::vk;
+
+use crate::{
+    app::App,
+    geometry::*,
+    reactor::{Host, Outbox, Reactor},
+    universe::Node,
+    vulkan::{draw_system::gui::GuiPipeline, texture::Texture, GfaestusVk},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinimapColoring {
+    Density,
+    Overlay,
+}
+
+/// A rasterized overview of the layout, covering the square from
+/// `bounds.0` to `bounds.1` in world space
+pub struct MinimapImage {
+    pixels: Vec<u8>,
+    bounds: (Point, Point),
+}
+
+type RenderInput = (Vec<Node>, Option<Vec<rgb::RGBA<u8>>>);
+
+pub struct Minimap {
+    render_host: Host<RenderInput, MinimapImage>,
+    rendering: bool,
+
+    dirty: bool,
+    coloring: MinimapColoring,
+    rendered_overlay: Option<usize>,
+
+    pending: Option<MinimapImage>,
+
+    texture: Option<Texture>,
+    bounds: Option<(Point, Point)>,
+}
+
+impl Minimap {
+    pub const ID: &'static str = "minimap";
+
+    /// The GUI texture ID used by the minimap; fixed so that it
+    /// doesn't shift the IDs of the textures added in order
+    pub const TEXTURE_ID: u64 = u64::MAX - 1;
+
+    /// Width and height of the minimap image, in pixels
+    const SIZE: usize = 256;
+
+    /// Width and height of the minimap on screen
+    const DISPLAY_SIZE: f32 = 200.0;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let render_host = reactor.create_host(
+            move |_outbox: &Outbox<MinimapImage>,
+                  (nodes, colors): RenderInput| {
+                rasterize(&nodes, colors.as_deref(), Self::SIZE)
+            },
+        );
+
+        Self {
+            render_host,
+            rendering: false,
+
+            dirty: true,
+            coloring: MinimapColoring::Density,
+            rendered_overlay: None,
+
+            pending: None,
+
+            texture: None,
+            bounds: None,
+        }
+    }
+
+    pub fn coloring(&self) -> MinimapColoring {
+        self.coloring
+    }
+
+    /// Rerender the image, e.g. after the layout has changed
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn needs_render(&self, overlay: Option<usize>) -> bool {
+        if self.rendering {
+            return false;
+        }
+
+        self.dirty
+            || (self.coloring == MinimapColoring::Overlay
+                && overlay != self.rendered_overlay)
+    }
+
+    /// Rasterizes the layout on a background thread; `colors` should
+    /// hold the overlay color of each node when the coloring is
+    /// `Overlay`
+    pub fn render(
+        &mut self,
+        nodes: &[Node],
+        overlay: Option<usize>,
+        colors: Option<Vec<rgb::RGBA<u8>>>,
+    ) -> Result<()> {
+        let colors = match self.coloring {
+            MinimapColoring::Density => None,
+            MinimapColoring::Overlay => colors,
+        };
+
+        self.render_host.call((nodes.to_vec(), colors))?;
+
+        self.rendering = true;
+        self.dirty = false;
+        self.rendered_overlay = overlay;
+
+        Ok(())
+    }
+
+    /// Copies a finished image, if any, to the minimap texture,
+    /// allocating it on first use
+    pub fn upload(
+        &mut self,
+        app: &GfaestusVk,
+        draw_system: &mut GuiPipeline,
+    ) -> Result<()> {
+        if let Some(image) = self.render_host.take() {
+            self.rendering = false;
+            self.pending = Some(image);
+        }
+
+        let image = match self.pending.take() {
+            Some(image) => image,
+            None => return Ok(()),
+        };
+
+        let texture = match self.texture {
+            Some(texture) => texture,
+            None => {
+                let texture = Texture::allocate(
+                    app,
+                    app.transient_command_pool,
+                    app.graphics_queue,
+                    Self::SIZE,
+                    Self::SIZE,
+                    vk::Format::R8G8B8A8_UNORM,
+                    vk::ImageUsageFlags::TRANSFER_DST
+                        | vk::ImageUsageFlags::SAMPLED,
+                )?;
+
+                draw_system.set_texture(app, Self::TEXTURE_ID, texture)?;
+                self.texture = Some(texture);
+
+                texture
+            }
+        };
+
+        texture.copy_from_slice(
+            app,
+            app.transient_command_pool,
+            app.graphics_queue,
+            Self::SIZE,
+            Self::SIZE,
+            &image.pixels,
+        )?;
+
+        self.bounds = Some(image.bounds);
+
+        Ok(())
+    }
+
+    pub fn ui(&mut self, ctx: &egui::CtxRef, app: &App) {
+        let shared_state = app.shared_state();
+
+        let mut coloring = self.coloring;
+        let mut refresh = false;
+
+        let bounds = self.bounds;
+
+        egui::Window::new(Self::ID)
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::Vec2::new(-10.0, -10.0))
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.selectable_value(
+                        &mut coloring,
+                        MinimapColoring::Density,
+                        "Density",
+                    );
+                    ui.selectable_value(
+                        &mut coloring,
+                        MinimapColoring::Overlay,
+                        "Overlay",
+                    );
+
+                    if ui.button("Refresh").clicked() {
+                        refresh = true;
+                    }
+                });
+
+                let size = Point::new(Self::DISPLAY_SIZE, Self::DISPLAY_SIZE);
+
+                let (min, max) = match bounds {
+                    Some(bounds) => bounds,
+                    None => {
+                        let (rect, _) = ui.allocate_exact_size(
+                            size.into(),
+                            egui::Sense::hover(),
+                        );
+                        ui.put(rect, egui::Label::new("Rendering..."));
+                        return;
+                    }
+                };
+
+                let img = egui::Image::new(
+                    egui::TextureId::User(Self::TEXTURE_ID),
+                    size,
+                );
+
+                let img_rect = ui.add(img).rect;
+
+                let interact = ui.interact(
+                    img_rect,
+                    egui::Id::new(Self::ID).with("image"),
+                    egui::Sense::click_and_drag(),
+                );
+
+                let side = max.x - min.x;
+
+                let world_to_img = |p: Point| -> egui::Pos2 {
+                    let rel = (p - min) / side;
+                    let x = img_rect.min.x + rel.x * img_rect.width();
+                    let y = img_rect.min.y + rel.y * img_rect.height();
+                    egui::Pos2::new(x, y)
+                };
+
+                if interact.clicked() || interact.dragged() {
+                    if let Some(pos) = ui.input().pointer.interact_pos() {
+                        let rel = Point::new(
+                            (pos.x - img_rect.min.x) / img_rect.width(),
+                            (pos.y - img_rect.min.y) / img_rect.height(),
+                        );
+
+                        let mut view = shared_state.view();
+                        view.center = min + rel * side;
+                        shared_state.view.store(view);
+                    }
+                }
+
+                let view = shared_state.view();
+                let dims: Point = app.dims().into();
+
+                let top_left = view.screen_point_to_world(dims, Point::ZERO);
+                let bottom_right = view.screen_point_to_world(dims, dims);
+
+                let view_rect = egui::Rect::from_min_max(
+                    world_to_img(top_left),
+                    world_to_img(bottom_right),
+                );
+
+                let painter = ui.painter_at(img_rect);
+
+                let stroke = egui::Stroke::new(
+                    1.5,
+                    egui::Color32::from_rgb(255, 64, 64),
+                );
+
+                if view_rect.width() < 4.0 && view_rect.height() < 4.0 {
+                    // too small to see at the minimap scale
+                    painter.circle_stroke(view_rect.center(), 4.0, stroke);
+                } else {
+                    painter.rect_stroke(view_rect, 0.0, stroke);
+                }
+
+                painter.rect_stroke(
+                    img_rect,
+                    0.0,
+                    egui::Stroke::new(1.0, egui::Color32::from_gray(128)),
+                );
+            });
+
+        if coloring != self.coloring {
+            self.coloring = coloring;
+            self.dirty = true;
+        }
+
+        if refresh {
+            self.dirty = true;
+        }
+    }
+}
+
+/// The bounding box of the layout, as a square centered on it with
+/// a small margin
+fn layout_bounds(nodes: &[Node]) -> (Point, Point) {
+    let mut min = Point::new(std::f32::MAX, std::f32::MAX);
+    let mut max = Point::new(std::f32::MIN, std::f32::MIN);
+
+    for node in nodes {
+        for p in [node.p0, node.p1].iter() {
+            min.x = min.x.min(p.x);
+            min.y = min.y.min(p.y);
+            max.x = max.x.max(p.x);
+            max.y = max.y.max(p.y);
+        }
+    }
+
+    if nodes.is_empty() {
+        min = Point::ZERO;
+        max = Point::ZERO;
+    }
+
+    let center = (min + max) / 2.0;
+    let side = (max.x - min.x).max(max.y - min.y).max(1.0) * 1.05;
+    let half = Point::new(side / 2.0, side / 2.0);
+
+    (center - half, center + half)
+}
+
+/// Draws each node as a line into an RGBA image of `size` by `size`
+/// pixels; pixels are shaded by the log of the number of node
+/// samples in them, or by the average of their node colors if
+/// `colors` is provided
+fn rasterize(
+    nodes: &[Node],
+    colors: Option<&[rgb::RGBA<u8>]>,
+    size: usize,
+) -> MinimapImage {
+    let bounds = layout_bounds(nodes);
+    let (min, max) = bounds;
+    let scale = size as f32 / (max.x - min.x);
+
+    let mut counts = vec![0u32; size * size];
+    let mut color_sums = vec![[0u64; 3]; size * size];
+
+    for (ix, node) in nodes.iter().enumerate() {
+        let p0 = (node.p0 - min) * scale;
+        let p1 = (node.p1 - min) * scale;
+
+        let color = colors.and_then(|colors| colors.get(ix));
+
+        let steps = ((p1 - p0).length().ceil() as usize).max(1);
+
+        for step in 0..=steps {
+            let p = p0 + (p1 - p0) * (step as f32 / steps as f32);
+
+            let x = (p.x as usize).min(size - 1);
+            let y = (p.y as usize).min(size - 1);
+            let px = y * size + x;
+
+            counts[px] += 1;
+
+            if let Some(color) = color {
+                let sum = &mut color_sums[px];
+                sum[0] += color.r as u64;
+                sum[1] += color.g as u64;
+                sum[2] += color.b as u64;
+            }
+        }
+    }
+
+    let max_count = counts.iter().copied().max().unwrap_or(0).max(1);
+    let log_max = (1.0 + max_count as f32).ln();
+
+    let mut pixels = vec![0u8; size * size * 4];
+
+    for (px, &count) in counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+
+        let rgb = if colors.is_some() {
+            let sum = color_sums[px];
+            let count = count as u64;
+            [sum[0] / count, sum[1] / count, sum[2] / count]
+        } else {
+            let t = (1.0 + count as f32).ln() / log_max;
+            let v = (64.0 + 191.0 * t) as u64;
+            [v, v, v]
+        };
+
+        let out = &mut pixels[px * 4..px * 4 + 4];
+        out[0] = rgb[0] as u8;
+        out[1] = rgb[1] as u8;
+        out[2] = rgb[2] as u8;
+        out[3] = 255;
+    }
+
+    MinimapImage { pixels, bounds }
+}
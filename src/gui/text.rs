@@ -292,6 +292,40 @@ pub fn draw_rect_world(
     painter.rect_stroke(Rect::new(s0, s1).into(), 0.0, stroke);
 }
 
+pub fn draw_line_world(
+    ctx: &egui::CtxRef,
+    view: View,
+    p0: Point,
+    p1: Point,
+    color: Option<rgb::RGBA<f32>>,
+) {
+    let screen_rect = ctx.input().screen_rect();
+
+    let mut s0 = view.world_point_to_screen(p0);
+    let mut s1 = view.world_point_to_screen(p1);
+
+    let offset = Point::new(screen_rect.width(), screen_rect.height()) / 2.0;
+
+    s0 += offset;
+    s1 += offset;
+
+    let painter = ctx.layer_painter(painter_layer());
+
+    let color = color
+        .map(|c| {
+            let r = (c.r * 255.0) as u8;
+            let g = (c.g * 255.0) as u8;
+            let b = (c.b * 255.0) as u8;
+            let a = (c.a * 255.0) as u8;
+            egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+        })
+        .unwrap_or(egui::Color32::from_rgb(128, 128, 128));
+
+    let stroke = egui::Stroke::new(3.0, color);
+
+    painter.line_segment([s0.into(), s1.into()], stroke);
+}
+
 pub fn draw_rect<R: Into<egui::Rect>>(ctx: &egui::CtxRef, rect: R) {
     let painter = ctx.layer_painter(painter_layer());
 
@@ -14,7 +14,10 @@ use crate::{
     overlays::OverlayKind,
     window::{GuiId, GuiWindows},
 };
-use crate::{app::OverlayState, geometry::*};
+use crate::{
+    app::{OverlayState, SelectionTool, SharedState},
+    geometry::*,
+};
 
 pub trait Widget {
     fn id() -> &'static str;
@@ -29,6 +32,7 @@ pub trait Widget {
 
 pub struct MenuBar {
     overlay_state: OverlayState,
+    shared_state: SharedState,
 
     overlay_list: Vec<(usize, String)>,
 
@@ -38,9 +42,10 @@ pub struct MenuBar {
 impl MenuBar {
     pub const ID: &'static str = "app_menu_bar";
 
-    pub fn new(overlay_state: OverlayState) -> Self {
+    pub fn new(overlay_state: OverlayState, shared_state: SharedState) -> Self {
         Self {
             overlay_state,
+            shared_state,
             overlay_list: Vec::new(),
             height: AtomicCell::new(0.0),
         }
@@ -82,6 +87,9 @@ impl MenuBar {
 
         let _themes = &mut open_windows.themes;
         let overlays = &mut open_windows.overlays;
+        let signal_tracks = &mut open_windows.signal_tracks;
+
+        let minimap = &mut open_windows.minimap;
 
         let resp = egui::TopBottomPanel::top(Self::ID).show(ctx, |ui| {
             use egui::menu;
@@ -108,6 +116,45 @@ impl MenuBar {
                         // windows.toggle_open(gui_id);
                         // *path_view = !*path_view;
                     }
+
+                    ui.separator();
+
+                    let query_id =
+                        egui::Id::new(crate::gui::windows::NodeQueryWindow::ID);
+                    let gui_id = GuiId::new(query_id);
+
+                    let query_open = windows.is_open(gui_id);
+
+                    if ui
+                        .selectable_label(query_open, "Select by query")
+                        .clicked()
+                    {
+                        windows.set_open(gui_id, !query_open);
+                    }
+
+                    let sets_id =
+                        egui::Id::new(crate::gui::windows::SelectionSets::ID);
+                    let gui_id = GuiId::new(sets_id);
+
+                    let sets_open = windows.is_open(gui_id);
+
+                    if ui.selectable_label(sets_open, "Selections").clicked() {
+                        windows.set_open(gui_id, !sets_open);
+                    }
+
+                    let stats_id = egui::Id::new(
+                        crate::gui::windows::SelectionStatsWindow::ID,
+                    );
+                    let gui_id = GuiId::new(stats_id);
+
+                    let stats_open = windows.is_open(gui_id);
+
+                    if ui
+                        .selectable_label(stats_open, "Selection statistics")
+                        .clicked()
+                    {
+                        windows.set_open(gui_id, !stats_open);
+                    }
                 });
 
                 menu::menu(ui, "Annotations", |ui| {
@@ -129,6 +176,18 @@ impl MenuBar {
                     {
                         *label_set_list = !*label_set_list;
                     }
+
+                    ui.separator();
+
+                    let alignments_id =
+                        egui::Id::new(crate::gui::windows::AlignmentList::ID);
+                    let gui_id = GuiId::new(alignments_id);
+
+                    let alignments = windows.is_open(gui_id);
+
+                    if ui.selectable_label(alignments, "Alignments").clicked() {
+                        windows.set_open(gui_id, !alignments);
+                    }
                 });
 
                 menu::menu(ui, "Overlays", |ui| {
@@ -136,12 +195,71 @@ impl MenuBar {
                     {
                         *overlays = !*overlays;
                     }
+
+                    if ui
+                        .selectable_label(*signal_tracks, "Signal tracks")
+                        .clicked()
+                    {
+                        *signal_tracks = !*signal_tracks;
+                    }
                 });
 
                 menu::menu(ui, "View", |ui| {
                     if ui.button("Goto selection").clicked() {
                         app_msg_tx.send(AppMsg::goto_selection()).unwrap();
                     }
+
+                    let goto_id =
+                        egui::Id::new(crate::gui::windows::GotoLocation::ID);
+                    let gui_id = GuiId::new(goto_id);
+
+                    let goto_open = windows.is_open(gui_id);
+
+                    if ui
+                        .selectable_label(goto_open, "Go to location")
+                        .clicked()
+                    {
+                        windows.set_open(gui_id, !goto_open);
+                    }
+
+                    let bookmarks_id =
+                        egui::Id::new(crate::gui::windows::BookmarkList::ID);
+                    let gui_id = GuiId::new(bookmarks_id);
+
+                    let bookmarks_open = windows.is_open(gui_id);
+
+                    if ui
+                        .selectable_label(bookmarks_open, "Bookmarks")
+                        .clicked()
+                    {
+                        windows.set_open(gui_id, !bookmarks_open);
+                    }
+
+                    let tour_id =
+                        egui::Id::new(crate::gui::windows::TourWindow::ID);
+                    let gui_id = GuiId::new(tour_id);
+
+                    let tour_open = windows.is_open(gui_id);
+
+                    if ui.selectable_label(tour_open, "Tour").clicked() {
+                        windows.set_open(gui_id, !tour_open);
+                    }
+
+                    let measure_id =
+                        egui::Id::new(crate::gui::windows::MeasureTool::ID);
+                    let gui_id = GuiId::new(measure_id);
+
+                    let measure_open = windows.is_open(gui_id);
+
+                    if ui.selectable_label(measure_open, "Measure").clicked() {
+                        windows.set_open(gui_id, !measure_open);
+                    }
+
+                    ui.separator();
+
+                    if ui.selectable_label(*minimap, "Minimap").clicked() {
+                        *minimap = !*minimap;
+                    }
                 });
 
                 menu::menu(ui, "Tools", |ui| {
@@ -151,6 +269,33 @@ impl MenuBar {
 
                     ui.separator();
 
+                    ui.label("Selection tool").on_hover_text(
+                        "Shift+drag to select, or Shift+click to place \
+                         polygon points; add Ctrl to add to the selection, \
+                         or Alt to subtract from it.\n\
+                         Enter or clicking the first point closes a \
+                         polygon, Escape cancels it.",
+                    );
+
+                    let current_tool = self.shared_state.selection_tool();
+
+                    for &tool in [
+                        SelectionTool::Rectangle,
+                        SelectionTool::Lasso,
+                        SelectionTool::Polygon,
+                    ]
+                    .iter()
+                    {
+                        if ui
+                            .selectable_label(current_tool == tool, tool.name())
+                            .clicked()
+                        {
+                            self.shared_state.set_selection_tool(tool);
+                        }
+                    }
+
+                    ui.separator();
+
                     if ui.button("BED Label Wizard").clicked() {
                         let script = "bed_label_wizard()".to_string();
                         app_msg_tx
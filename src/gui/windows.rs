@@ -1,21 +1,39 @@
+pub mod alignments;
 pub mod annotations;
+pub mod bookmarks;
 pub mod file;
 pub mod filters;
+pub mod goto;
 pub mod graph_details;
 pub mod graph_picker;
+pub mod measure;
+pub mod node_query;
 pub mod overlays;
 pub mod path_position;
 pub mod paths;
+pub mod selection_stats;
+pub mod selections;
 pub mod settings;
+pub mod signal;
+pub mod tour;
 pub mod util;
 
+pub use alignments::*;
 pub use annotations::*;
+pub use bookmarks::*;
 pub use file::*;
 pub use filters::*;
+pub use goto::*;
 pub use graph_details::*;
 pub use graph_picker::*;
+pub use measure::*;
+pub use node_query::*;
 pub use overlays::*;
 pub use path_position::*;
 pub use paths::*;
+pub use selection_stats::*;
+pub use selections::*;
 pub use settings::*;
+pub use signal::*;
+pub use tour::*;
 pub use util::*;
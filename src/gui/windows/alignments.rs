@@ -0,0 +1,522 @@
+use std::{path::PathBuf, sync::Arc};
+
+use bstr::ByteSlice;
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    pathhandlegraph::*,
+};
+
+use crossbeam::channel::Sender;
+use rustc_hash::FxHashSet;
+
+use anyhow::Result;
+
+use crate::{
+    annotations::{alignment_node_depth, GafRecords},
+    app::{channels::OverlayCreatorMsg, App, AppMsg, Select},
+    gui::util::{self as gui_util, grid_row_label, ColumnWidths},
+    overlays::OverlayData,
+    reactor::{Host, Outbox, Reactor},
+    universe::Node,
+};
+
+use super::{file::FilePicker, AnnotMsg};
+
+type LoadResult = std::result::Result<String, AnnotMsg>;
+
+enum AlignmentOverlay {
+    Depth {
+        name: String,
+        records: Arc<GafRecords>,
+        indices: Vec<usize>,
+    },
+    Walk {
+        name: String,
+        steps: Vec<Handle>,
+    },
+}
+
+type OverlayResult = std::result::Result<(), String>;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct AlignmentFilter {
+    read_name: String,
+    min_mapq: u8,
+    min_identity: f32,
+}
+
+impl AlignmentFilter {
+    fn apply(&self, records: &GafRecords) -> Vec<usize> {
+        let name = self.read_name.as_bytes();
+
+        records
+            .records
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, record)| {
+                let name_match =
+                    name.is_empty() || record.query_name.contains_str(name);
+
+                if name_match
+                    && record.mapq >= self.min_mapq
+                    && record.identity() >= self.min_identity
+                {
+                    Some(ix)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+pub struct AlignmentList {
+    current_file: Option<String>,
+
+    file_picker: FilePicker,
+    file_picker_open: bool,
+
+    load_host: Host<PathBuf, LoadResult>,
+    latest_load: Option<LoadResult>,
+
+    overlay_host: Host<AlignmentOverlay, OverlayResult>,
+    latest_overlay: Option<OverlayResult>,
+
+    filter: AlignmentFilter,
+    applied_filter: Option<AlignmentFilter>,
+    filtered_records: Vec<usize>,
+
+    depth_overlay_name: String,
+
+    selected_record: Option<usize>,
+
+    col_widths: ColumnWidths<4>,
+}
+
+impl AlignmentList {
+    pub const ID: &'static str = "alignment_list_window";
+
+    pub fn new(reactor: &Reactor, app_msg_tx: Sender<AppMsg>) -> Result<Self> {
+        let pwd = std::fs::canonicalize("./").unwrap();
+
+        let mut file_picker = FilePicker::new(
+            egui::Id::with(egui::Id::new(Self::ID), "file_picker"),
+            pwd,
+        )?;
+
+        file_picker.set_visible_extensions(&["gaf"])?;
+
+        let load_host = reactor.create_host(
+            move |outbox: &Outbox<LoadResult>, file: PathBuf| {
+                outbox.insert_blocking(Err(AnnotMsg::Running(
+                    "Loading GAF".to_string(),
+                )));
+
+                match GafRecords::parse_gaf_file(&file) {
+                    Ok(records) => {
+                        let file_name = records.file_name().to_string();
+                        app_msg_tx
+                            .send(AppMsg::add_gaf_records(records))
+                            .unwrap();
+                        Ok(file_name)
+                    }
+                    Err(err) => Err(AnnotMsg::ParseError(format!(
+                        "Error parsing GAF file: {:?}",
+                        err
+                    ))),
+                }
+            },
+        );
+
+        let graph = reactor.graph_query.clone();
+        let overlay_tx = reactor.overlay_create_tx.clone();
+
+        let overlay_host = reactor.create_host(
+            move |_outbox: &Outbox<OverlayResult>, input: AlignmentOverlay| {
+                let node_count = graph.node_count();
+
+                let (name, data) = match input {
+                    AlignmentOverlay::Depth {
+                        name,
+                        records,
+                        indices,
+                    } => {
+                        let depth = alignment_node_depth(
+                            node_count,
+                            indices
+                                .iter()
+                                .filter_map(|&ix| records.records.get(ix)),
+                        );
+
+                        let max = depth.iter().copied().max().unwrap_or(0);
+
+                        if max == 0 {
+                            return Err(
+                                "No alignments cover any nodes".to_string()
+                            );
+                        }
+
+                        let max = max as f32;
+
+                        let data = depth
+                            .into_iter()
+                            .map(|d| d as f32 / max)
+                            .collect::<Vec<_>>();
+
+                        (name, OverlayData::Value(data))
+                    }
+                    AlignmentOverlay::Walk { name, steps } => {
+                        let mut data = vec![
+                            rgb::RGBA::new(0.3, 0.3, 0.3, 0.3);
+                            node_count
+                        ];
+
+                        // color the walk from blue to red, so that
+                        // the direction of the alignment is visible
+                        let len = (steps.len().max(2) - 1) as f32;
+
+                        for (ix, handle) in steps.iter().enumerate() {
+                            let t = ix as f32 / len;
+                            let color = rgb::RGBA::new(
+                                0.2 + 0.8 * t,
+                                0.2,
+                                1.0 - t,
+                                1.0,
+                            );
+
+                            // alignments to a different graph can
+                            // contain node IDs that aren't in this one
+                            let node_ix =
+                                (handle.id().0 as usize).checked_sub(1);
+                            if let Some(c) =
+                                node_ix.and_then(|ix| data.get_mut(ix))
+                            {
+                                *c = color;
+                            }
+                        }
+
+                        (name, OverlayData::RGB(data))
+                    }
+                };
+
+                overlay_tx
+                    .send(OverlayCreatorMsg::NewOverlay { name, data })
+                    .unwrap();
+
+                Ok(())
+            },
+        );
+
+        Ok(Self {
+            current_file: None,
+
+            file_picker,
+            file_picker_open: false,
+
+            load_host,
+            latest_load: None,
+
+            overlay_host,
+            latest_overlay: None,
+
+            filter: AlignmentFilter::default(),
+            applied_filter: None,
+            filtered_records: Vec::new(),
+
+            depth_overlay_name: String::new(),
+
+            selected_record: None,
+
+            col_widths: Default::default(),
+        })
+    }
+
+    fn select_walk(app: &App, steps: &[Handle]) {
+        let nodes = steps.iter().map(|h| h.id()).collect::<FxHashSet<_>>();
+
+        let select = Select::Many { nodes, clear: true };
+        app.channels.app_tx.send(AppMsg::Selection(select)).unwrap();
+    }
+
+    fn file_ui(&mut self, app: &App, ui: &mut egui::Ui) {
+        if let Some(result) = self.load_host.take() {
+            if let Ok(name) = &result {
+                self.current_file = Some(name.to_owned());
+                self.applied_filter = None;
+                self.selected_record = None;
+            }
+            self.latest_load = Some(result);
+        }
+
+        let is_running =
+            matches!(self.latest_load, Some(Err(AnnotMsg::Running(_))));
+
+        if self.file_picker.selected_path().is_some() {
+            self.file_picker_open = false;
+        }
+
+        self.file_picker.ui(ui.ctx(), &mut self.file_picker_open);
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!is_running, egui::Button::new("Choose GAF file"))
+                .clicked()
+            {
+                self.file_picker.reset_selection();
+                self.file_picker_open = true;
+            }
+
+            let selected_path = self.file_picker.selected_path();
+
+            if ui
+                .add_enabled(
+                    !is_running && selected_path.is_some(),
+                    egui::Button::new("Load"),
+                )
+                .clicked()
+            {
+                if let Some(path) = selected_path {
+                    self.load_host.call(path.to_owned()).unwrap();
+                }
+            }
+
+            match &self.latest_load {
+                Some(Err(AnnotMsg::Running(msg))) => {
+                    ui.label(msg);
+                }
+                Some(Err(AnnotMsg::ParseError(msg)))
+                | Some(Err(AnnotMsg::IOError(msg))) => {
+                    ui.label(msg);
+                }
+                _ => (),
+            }
+        });
+
+        let names = app.annotations().gaf_names();
+
+        let current_ix = self
+            .current_file
+            .as_ref()
+            .and_then(|cur| names.iter().position(|n| n == cur));
+
+        let mut selected = current_ix.unwrap_or(0);
+
+        let combo = egui::ComboBox::from_id_source(
+            egui::Id::new(Self::ID).with("file_combo"),
+        )
+        .show_index(ui, &mut selected, names.len(), |ix| {
+            names.get(ix).cloned().unwrap_or_default()
+        });
+
+        if combo.changed() || self.current_file.is_none() {
+            self.current_file = names.get(selected).cloned();
+            self.applied_filter = None;
+            self.selected_record = None;
+        }
+    }
+
+    fn filter_ui(&mut self, ui: &mut egui::Ui) {
+        let filter = &mut self.filter;
+
+        egui::Grid::new(egui::Id::new(Self::ID).with("filter_grid")).show(
+            ui,
+            |ui| {
+                ui.label("Read name");
+                ui.text_edit_singleline(&mut filter.read_name);
+                ui.end_row();
+
+                ui.label("Min. MAPQ");
+                ui.add(
+                    egui::DragValue::new::<u8>(&mut filter.min_mapq)
+                        .clamp_range(0u8..=255u8),
+                );
+                ui.end_row();
+
+                ui.label("Min. identity");
+                ui.add(egui::Slider::new::<f32>(
+                    &mut filter.min_identity,
+                    0.0..=1.0,
+                ));
+                ui.end_row();
+            },
+        );
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        if let Some(result) = self.overlay_host.take() {
+            if result.is_ok() {
+                self.depth_overlay_name.clear();
+            }
+            self.latest_overlay = Some(result);
+        }
+
+        self.file_ui(app, ui);
+
+        let records = if let Some(records) = self
+            .current_file
+            .as_ref()
+            .and_then(|name| app.annotations().get_gaf(name))
+        {
+            records.clone()
+        } else {
+            ui.label("No GAF file loaded");
+            return;
+        };
+
+        ui.separator();
+
+        self.filter_ui(ui);
+
+        if self.applied_filter.as_ref() != Some(&self.filter) {
+            self.filtered_records = self.filter.apply(&records);
+            self.applied_filter = Some(self.filter.clone());
+        }
+
+        ui.label(format!(
+            "Showing {} / {} alignments",
+            self.filtered_records.len(),
+            records.len()
+        ));
+
+        ui.horizontal(|ui| {
+            ui.label("Overlay name");
+            ui.text_edit_singleline(&mut self.depth_overlay_name);
+
+            if ui
+                .add_enabled(
+                    !self.depth_overlay_name.is_empty()
+                        && !self.filtered_records.is_empty(),
+                    egui::Button::new("Create depth overlay"),
+                )
+                .clicked()
+            {
+                let input = AlignmentOverlay::Depth {
+                    name: self.depth_overlay_name.clone(),
+                    records: records.clone(),
+                    indices: self.filtered_records.clone(),
+                };
+                self.overlay_host.call(input).unwrap();
+            }
+        });
+
+        if let Some(Err(msg)) = &self.latest_overlay {
+            ui.label(msg);
+        }
+
+        ui.separator();
+
+        let selected = self
+            .selected_record
+            .and_then(|ix| Some((ix, records.records.get(ix)?)));
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(selected.is_some(), egui::Button::new("Goto"))
+                .clicked()
+            {
+                app.channels.app_tx.send(AppMsg::goto_selection()).unwrap();
+            }
+
+            if ui
+                .add_enabled(
+                    selected.is_some(),
+                    egui::Button::new("Highlight walk"),
+                )
+                .clicked()
+            {
+                if let Some((_, record)) = selected {
+                    let name =
+                        format!("Alignment: {}", record.query_name.as_bstr());
+                    let input = AlignmentOverlay::Walk {
+                        name,
+                        steps: record.steps.clone(),
+                    };
+                    self.overlay_host.call(input).unwrap();
+                }
+            }
+        });
+
+        let scroll_align = gui_util::add_scroll_buttons(ui);
+
+        let num_rows = self.filtered_records.len();
+        let text_style = egui::TextStyle::Body;
+        let row_height = ui.fonts()[text_style].row_height();
+
+        let [w0, w1, w2, w3] = self.col_widths.get();
+
+        let header = egui::Grid::new(egui::Id::new(Self::ID).with("header"))
+            .show(ui, |ui| {
+                let inner = grid_row_label(
+                    ui,
+                    egui::Id::new(Self::ID).with("header__"),
+                    &["Read", "MAPQ", "Identity", "Steps"],
+                    false,
+                    Some(&[w0, w1, w2, w3]),
+                );
+                self.col_widths.set_hdr(&inner.inner);
+            });
+
+        let filtered_records = &self.filtered_records;
+        let col_widths = &self.col_widths;
+        let selected_record = &mut self.selected_record;
+
+        gui_util::scrolled_area(ui, num_rows, scroll_align).show_rows(
+            ui,
+            row_height,
+            num_rows,
+            |ui, range| {
+                ui.set_min_width(header.response.rect.width());
+
+                egui::Grid::new(egui::Id::new(Self::ID).with("list"))
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let take_n = range.start.max(range.end) - range.start;
+
+                        for &ix in filtered_records
+                            .iter()
+                            .skip(range.start)
+                            .take(take_n)
+                        {
+                            let record = &records.records[ix];
+
+                            let name =
+                                format!("{}", record.query_name.as_bstr());
+                            let mapq = format!("{}", record.mapq);
+                            let identity = format!("{:.3}", record.identity());
+                            let steps = format!("{}", record.steps.len());
+
+                            let fields: [&str; 4] =
+                                [&name, &mapq, &identity, &steps];
+
+                            let inner = grid_row_label(
+                                ui,
+                                egui::Id::new(ui.id().with(ix)),
+                                &fields,
+                                false,
+                                Some(&[w0, w1, w2, w3]),
+                            );
+
+                            col_widths.set(&inner.inner);
+
+                            let row = inner.response;
+
+                            if row.clicked() {
+                                *selected_record = Some(ix);
+                                Self::select_walk(app, &record.steps);
+                            }
+
+                            if row.double_clicked() {
+                                app.channels
+                                    .app_tx
+                                    .send(AppMsg::goto_selection())
+                                    .unwrap();
+                            }
+                        }
+                    });
+            },
+        );
+    }
+}
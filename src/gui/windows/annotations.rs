@@ -8,9 +8,11 @@ use bstr::ByteSlice;
 use crossbeam::{atomic::AtomicCell, channel::Sender};
 
 pub mod filter;
+pub mod lifted;
 pub mod records_list;
 
 pub use filter::*;
+pub use lifted::*;
 use parking_lot::{RwLock, RwLockReadGuard};
 pub use records_list::*;
 
@@ -169,7 +171,7 @@ impl AnnotationFileList {
         )
         .unwrap();
 
-        let extensions: [&str; 2] = ["gff3", "bed"];
+        let extensions: [&str; 3] = ["gff3", "gtf", "bed"];
         file_picker.set_visible_extensions(&extensions).unwrap();
 
         let load_host = reactor.create_host(
@@ -187,10 +189,14 @@ impl AnnotationFileList {
                         |ext| Ok(ext),
                     )?;
 
-                if ext == "gff3" {
-                    running_msg("Loading GFF3");
-
-                    let records = Gff3Records::parse_gff3_file(&file);
+                if ext == "gff3" || ext == "gtf" {
+                    let records = if ext == "gtf" {
+                        running_msg("Loading GTF");
+                        Gff3Records::parse_gtf_file(&file)
+                    } else {
+                        running_msg("Loading GFF3");
+                        Gff3Records::parse_gff3_file(&file)
+                    };
                     match records {
                         Ok(records) => {
                             let file_name = records.file_name().to_string();
@@ -209,7 +215,8 @@ impl AnnotationFileList {
                         }
                         Err(err) => {
                             return Err(AnnotMsg::ParseError(format!(
-                                "Error parsing GFF3 file: {:?}",
+                                "Error parsing {} file: {:?}",
+                                ext.to_uppercase(),
                                 err
                             )));
                         }
@@ -767,6 +774,7 @@ struct OverlayInput<C: AnnotationCollection + Send + Sync + 'static> {
     indices: Vec<usize>,
     path: PathId,
     records: Arc<C>,
+    exonic_only: bool,
 }
 
 enum OverlayFeedback {
@@ -790,6 +798,9 @@ where
 
     label_set_name: String,
 
+    // only map records to the nodes covered by their exons
+    exonic_only: bool,
+
     column_picker: ColumnPickerOne<C::ColumnKey>,
     column_picker_open: bool,
     current_annotation_file: Option<String>,
@@ -848,18 +859,27 @@ where
                                     &input.column,
                                 )?;
 
-                                let range =
+                                let ids = if input.exonic_only {
+                                    crate::annotations::record_exon_steps(
+                                        input.records.as_ref(),
+                                        ix,
+                                        &steps,
+                                        offset,
+                                    )?
+                                    .into_iter()
+                                    .map(|h| h.id())
+                                    .collect()
+                                } else {
                                     crate::annotations::path_step_range(
                                         &steps,
                                         offset,
                                         record.start(),
                                         record.end(),
-                                    )?;
-
-                                let ids = range
+                                    )?
                                     .into_iter()
                                     .map(|(h, _, _)| h.id())
-                                    .collect();
+                                    .collect()
+                                };
 
                                 Some((ids, color))
                             })
@@ -909,6 +929,8 @@ where
 
             label_set_name: String::new(),
 
+            exonic_only: false,
+
             column_picker: ColumnPickerOne::new(id.with("column_picker_one")),
             column_picker_open: false,
             current_annotation_file: None,
@@ -988,6 +1010,14 @@ where
                     *column_picker_open = !*column_picker_open;
                 }
 
+                ui.add_enabled(
+                    !is_running,
+                    egui::Checkbox::new(
+                        &mut self.exonic_only,
+                        "Exonic nodes only",
+                    ),
+                );
+
                 ui.separator();
 
                 let name = &mut self.overlay_name;
@@ -1046,6 +1076,7 @@ where
                             indices: indices.clone(),
                             path: path_id,
                             records: records.clone(),
+                            exonic_only: self.exonic_only,
                         };
 
                         self.host_data.call(input).unwrap();
@@ -1091,6 +1122,7 @@ where
                         &self.path_name,
                         column.unwrap(),
                         &self.label_set_name,
+                        self.exonic_only,
                     ) {
                         let name = std::mem::take(&mut self.label_set_name);
 
@@ -1111,6 +1143,7 @@ pub(crate) fn calculate_annotation_set<C>(
     path_name: &str,
     column: &C::ColumnKey,
     label_set_name: &str,
+    exonic_only: bool,
 ) -> Option<AnnotationLabelSet>
 where
     C: AnnotationCollection + Send + Sync + 'static,
@@ -1133,14 +1166,25 @@ where
         log::trace!("getting record");
         let record = records.records().get(record_ix)?;
 
-        if let Some(range) = crate::annotations::path_step_range(
-            &steps,
-            offset,
-            record.start(),
-            record.end(),
-        ) {
+        // with exonic_only, the label is placed on the middle exonic
+        // node, so e.g. gene labels don't end up inside introns
+        let handles = if exonic_only {
+            crate::annotations::record_exon_steps(
+                records, record_ix, &steps, offset,
+            )
+        } else {
+            crate::annotations::path_step_range(
+                &steps,
+                offset,
+                record.start(),
+                record.end(),
+            )
+            .map(|range| range.iter().map(|(h, _, _)| *h).collect())
+        };
+
+        if let Some(range) = handles {
             if let Some(value) = record.get_first(column) {
-                if let Some((mid, _, _)) = range.get(range.len() / 2) {
+                if let Some(mid) = range.get(range.len() / 2) {
                     let index = label_strings.len();
                     let label = format!("{}", value.as_bstr());
                     label_strings.push(label);
@@ -0,0 +1,417 @@
+use std::sync::Arc;
+
+use crossbeam::channel::Sender;
+use handlegraph::pathhandlegraph::PathId;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    annotations::{AnnotationCollection, LiftStatus, LiftedAnnotations},
+    app::{AppMsg, Select},
+    graph_query::GraphQuery,
+    gui::util::{self as gui_util, grid_row_label, ColumnWidths},
+    reactor::{Host, Outbox, Reactor},
+};
+
+struct LiftInput<C: AnnotationCollection + Send + Sync + 'static> {
+    records: Arc<C>,
+    indices: Vec<usize>,
+    ref_path: PathId,
+    name_column: Option<C::ColumnKey>,
+}
+
+enum LiftFeedback {
+    Error(String),
+    Running(String),
+}
+
+type LiftResult = std::result::Result<Arc<LiftedAnnotations>, LiftFeedback>;
+
+/// Shows annotation records lifted from the reference path onto every
+/// path in the graph, with their coordinates and presence on each
+pub struct LiftedRecordList<C>
+where
+    C: AnnotationCollection + Send + Sync + 'static,
+{
+    id: egui::Id,
+
+    lift_host: Host<LiftInput<C>, LiftResult>,
+    latest_feedback: Option<LiftFeedback>,
+
+    lifted: Option<Arc<LiftedAnnotations>>,
+    filtered: Vec<usize>,
+
+    path_filter: String,
+    show_present: bool,
+    show_partial: bool,
+    show_absent: bool,
+
+    export_path: String,
+
+    col_widths: ColumnWidths<6>,
+}
+
+impl<C> LiftedRecordList<C>
+where
+    C: AnnotationCollection + Send + Sync + 'static,
+{
+    pub fn new(reactor: &Reactor, id: egui::Id) -> Self {
+        let graph = reactor.graph_query.clone();
+        let rayon_pool = reactor.rayon_pool.clone();
+
+        let lift_host = reactor.create_host(
+            move |outbox: &Outbox<LiftResult>, input: LiftInput<C>| {
+                outbox.insert_blocking(Err(LiftFeedback::Running(format!(
+                    "Lifting {} records",
+                    input.indices.len()
+                ))));
+
+                let lifted = rayon_pool.install(|| {
+                    LiftedAnnotations::lift_records(
+                        &graph,
+                        input.records.as_ref(),
+                        &input.indices,
+                        input.ref_path,
+                        input.name_column.as_ref(),
+                    )
+                });
+
+                lifted.map(Arc::new).ok_or_else(|| {
+                    LiftFeedback::Error(
+                        "Could not map the records onto the reference path"
+                            .to_string(),
+                    )
+                })
+            },
+        );
+
+        Self {
+            id,
+
+            lift_host,
+            latest_feedback: None,
+
+            lifted: None,
+            filtered: Vec::new(),
+
+            path_filter: String::new(),
+            show_present: true,
+            show_partial: true,
+            show_absent: true,
+
+            export_path: String::new(),
+
+            col_widths: ColumnWidths::default(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.latest_feedback, Some(LiftFeedback::Running(_)))
+    }
+
+    pub fn lift(
+        &mut self,
+        records: Arc<C>,
+        indices: Vec<usize>,
+        ref_path: PathId,
+        name_column: Option<C::ColumnKey>,
+    ) {
+        if self.is_running() {
+            return;
+        }
+
+        let input = LiftInput {
+            records,
+            indices,
+            ref_path,
+            name_column,
+        };
+
+        self.lift_host.call(input).unwrap();
+    }
+
+    fn apply_filter(&mut self) {
+        self.filtered.clear();
+
+        let lifted = if let Some(lifted) = &self.lifted {
+            lifted
+        } else {
+            return;
+        };
+
+        let path_filter = self.path_filter.as_str();
+
+        let show_present = self.show_present;
+        let show_partial = self.show_partial;
+        let show_absent = self.show_absent;
+
+        self.filtered
+            .extend(lifted.lifted.iter().enumerate().filter_map(|(ix, l)| {
+                let status_match = match l.status() {
+                    LiftStatus::Present => show_present,
+                    LiftStatus::Partial => show_partial,
+                    LiftStatus::Absent => show_absent,
+                };
+
+                let path_match = path_filter.is_empty()
+                    || lifted.path_name(l).contains(path_filter);
+
+                if status_match && path_match {
+                    Some(ix)
+                } else {
+                    None
+                }
+            }));
+    }
+
+    fn export_bed(&self) -> std::io::Result<()> {
+        use std::io::Write;
+
+        if let Some(lifted) = &self.lifted {
+            let file = std::fs::File::create(&self.export_path)?;
+            let mut writer = std::io::BufWriter::new(file);
+            lifted.write_bed(&mut writer)?;
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn select_lifted(
+        app_msg_tx: &Sender<AppMsg>,
+        graph_query: &GraphQuery,
+        path: PathId,
+        path_name: &str,
+        range: (usize, usize),
+    ) {
+        let offset = crate::annotations::path_name_offset(path_name.as_bytes())
+            .unwrap_or(0);
+
+        let (start, end) = range;
+
+        let start = start.saturating_sub(offset);
+        let end = end.saturating_sub(offset).max(start);
+
+        if let Some(range) = graph_query.path_basepair_range(path, start, end) {
+            let nodes = range
+                .into_iter()
+                .map(|(handle, _, _)| handle.id())
+                .collect::<FxHashSet<_>>();
+
+            let select = Select::Many { nodes, clear: true };
+            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        app_msg_tx: &Sender<AppMsg>,
+        graph_query: &GraphQuery,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        if let Some(result) = self.lift_host.take() {
+            match result {
+                Ok(lifted) => {
+                    self.lifted = Some(lifted);
+                    self.latest_feedback = None;
+                    self.apply_filter();
+                }
+                Err(feedback) => {
+                    self.latest_feedback = Some(feedback);
+                }
+            }
+        }
+
+        egui::Window::new("Lifted annotations")
+            .id(self.id)
+            .default_pos(egui::Pos2::new(600.0, 300.0))
+            .open(open)
+            .show(ctx, |ui| {
+                match &self.latest_feedback {
+                    Some(LiftFeedback::Running(msg)) => {
+                        ui.label(msg);
+                    }
+                    Some(LiftFeedback::Error(err)) => {
+                        ui.label(err);
+                    }
+                    None => (),
+                }
+
+                let lifted = if let Some(lifted) = self.lifted.clone() {
+                    lifted
+                } else {
+                    ui.label("No lifted annotations");
+                    return;
+                };
+
+                let ref_name = lifted
+                    .paths
+                    .iter()
+                    .find(|(p, _)| *p == lifted.ref_path)
+                    .map(|(_, name)| name.as_str())
+                    .unwrap_or_default();
+
+                ui.label(format!(
+                    "{} - {} records from {} lifted onto {} paths",
+                    lifted.file_name,
+                    lifted.records.len(),
+                    ref_name,
+                    lifted.paths.len()
+                ));
+
+                ui.separator();
+
+                let mut apply_filter = false;
+
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    let path_edit =
+                        ui.text_edit_singleline(&mut self.path_filter);
+                    apply_filter |= path_edit.changed();
+                });
+
+                ui.horizontal(|ui| {
+                    apply_filter |= ui
+                        .checkbox(&mut self.show_present, "Present")
+                        .changed();
+                    apply_filter |= ui
+                        .checkbox(&mut self.show_partial, "Partial")
+                        .changed();
+                    apply_filter |=
+                        ui.checkbox(&mut self.show_absent, "Absent").changed();
+                });
+
+                if apply_filter {
+                    self.apply_filter();
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("BED file");
+                    ui.text_edit_singleline(&mut self.export_path);
+
+                    if ui
+                        .add_enabled(
+                            !self.export_path.is_empty(),
+                            egui::Button::new("Export"),
+                        )
+                        .clicked()
+                    {
+                        if let Err(err) = self.export_bed() {
+                            self.latest_feedback = Some(LiftFeedback::Error(
+                                format!("Error exporting BED: {:?}", err),
+                            ));
+                        }
+                    }
+                });
+
+                ui.separator();
+
+                let scroll_align = gui_util::add_scroll_buttons(ui);
+
+                let num_rows = self.filtered.len();
+                let text_style = egui::TextStyle::Body;
+                let row_height = ui.fonts()[text_style].row_height();
+
+                let widths = self.col_widths.get();
+
+                let header =
+                    egui::Grid::new(self.id.with("header")).show(ui, |ui| {
+                        let inner = grid_row_label(
+                            ui,
+                            self.id.with("header__"),
+                            &[
+                                "Record", "Path", "Start", "End", "Coverage",
+                                "Status",
+                            ],
+                            false,
+                            Some(&widths),
+                        );
+                        self.col_widths.set_hdr(&inner.inner);
+                    });
+
+                let filtered = &self.filtered;
+                let col_widths = &self.col_widths;
+                let id = self.id;
+
+                gui_util::scrolled_area(ui, num_rows, scroll_align).show_rows(
+                    ui,
+                    row_height,
+                    num_rows,
+                    |ui, range| {
+                        ui.set_min_width(header.response.rect.width());
+
+                        egui::Grid::new(id.with("list")).striped(true).show(
+                            ui,
+                            |ui| {
+                                let take_n =
+                                    range.start.max(range.end) - range.start;
+
+                                for &ix in filtered
+                                    .iter()
+                                    .skip(range.start)
+                                    .take(take_n)
+                                {
+                                    let l = &lifted.lifted[ix];
+
+                                    let (start, end) = match l.range {
+                                        Some((s, e)) => {
+                                            (s.to_string(), e.to_string())
+                                        }
+                                        None => {
+                                            ("-".to_string(), "-".to_string())
+                                        }
+                                    };
+
+                                    let coverage =
+                                        format!("{:.1}%", l.coverage * 100.0);
+                                    let status = l.status().to_string();
+
+                                    let fields: [&str; 6] = [
+                                        lifted.record_name(l),
+                                        lifted.path_name(l),
+                                        &start,
+                                        &end,
+                                        &coverage,
+                                        &status,
+                                    ];
+
+                                    let inner = grid_row_label(
+                                        ui,
+                                        egui::Id::new(ui.id().with(ix)),
+                                        &fields,
+                                        false,
+                                        Some(&widths),
+                                    );
+
+                                    col_widths.set(&inner.inner);
+
+                                    let row = inner.response;
+
+                                    if let Some(range) = l.range {
+                                        if row.clicked() {
+                                            let (path, path_name) =
+                                                &lifted.paths[l.path];
+
+                                            Self::select_lifted(
+                                                app_msg_tx,
+                                                graph_query,
+                                                *path,
+                                                path_name,
+                                                range,
+                                            );
+                                        }
+
+                                        if row.double_clicked() {
+                                            app_msg_tx
+                                                .send(AppMsg::goto_selection())
+                                                .unwrap();
+                                        }
+                                    }
+                                }
+                            },
+                        );
+                    },
+                );
+            })
+    }
+}
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use bstr::ByteSlice;
 use crossbeam::atomic::AtomicCell;
 use handlegraph::pathhandlegraph::PathId;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
@@ -20,7 +20,10 @@ use crate::{
 
 use crate::gui::util::{self as gui_util, ColumnWidthsVec};
 
-use super::{filter::RecordFilter, ColumnPickerMany, OverlayLabelSetCreator};
+use super::{
+    filter::RecordFilter, ColumnPickerMany, LiftedRecordList,
+    OverlayLabelSetCreator,
+};
 
 pub struct RecordList<C>
 where
@@ -30,6 +33,14 @@ where
     current_file: Option<String>,
 
     filtered_records: Vec<usize>,
+    // distinguishes a filter that matches nothing from no filter
+    filter_applied: bool,
+
+    // when the collection has a feature hierarchy, the records can be
+    // shown as a tree, with (record index, depth) for each visible row
+    show_hierarchy: bool,
+    expanded_records: FxHashSet<usize>,
+    hierarchy_rows: Vec<(usize, usize)>,
 
     filter_open: bool,
     filters: HashMap<String, RecordFilter<C::ColumnKey>>,
@@ -45,6 +56,9 @@ where
     creator_open: bool,
     creator: OverlayLabelSetCreator<C>,
 
+    lifted_open: bool,
+    lifted: LiftedRecordList<C>,
+
     pub(super) scroll_to_index: Arc<AtomicCell<Option<usize>>>,
 
     col_widths: ColumnWidthsVec,
@@ -66,6 +80,11 @@ where
             current_file: None,
 
             filtered_records,
+            filter_applied: false,
+
+            show_hierarchy: false,
+            expanded_records: FxHashSet::default(),
+            hierarchy_rows: Vec::new(),
 
             filter_open: false,
             filters: HashMap::default(),
@@ -84,6 +103,9 @@ where
                 egui::Id::new("overlay_label_set_creator"),
             ),
 
+            lifted_open: false,
+            lifted: LiftedRecordList::new(reactor, id.with("lifted_records")),
+
             col_widths: ColumnWidthsVec::default(),
 
             scroll_to_index: Arc::new(None.into()),
@@ -145,9 +167,27 @@ where
         records: &C,
         record: &C::Record,
         index: usize,
+        tree_node: Option<(usize, bool, bool)>,
     ) -> egui::Response {
+        let seq_id = if let Some((depth, has_children, expanded)) = tree_node {
+            let marker = match (has_children, expanded) {
+                (false, _) => " ",
+                (true, false) => "+",
+                (true, true) => "-",
+            };
+            format!(
+                "{:indent$}{} {}",
+                "",
+                marker,
+                record.seq_id().as_bstr(),
+                indent = depth * 2
+            )
+        } else {
+            format!("{}", record.seq_id().as_bstr())
+        };
+
         let mut fields: Vec<String> = vec![
-            format!("{}", record.seq_id().as_bstr()),
+            seq_id,
             format!("{}", record.start()),
             format!("{}", record.end()),
         ];
@@ -253,6 +293,8 @@ where
                 }
             },
         ));
+        self.filter_applied = true;
+
         let filtered = self.filtered_records.len();
         debug!(
             "filter complete, showing {} out of {} records",
@@ -262,6 +304,85 @@ where
 
     fn clear_filter(&mut self) {
         self.filtered_records.clear();
+        self.filter_applied = false;
+    }
+
+    /// Rebuilds the visible tree rows; descendants of a record are
+    /// shown below it when it's expanded. With a filter, only the
+    /// matching records and their ancestors are shown, so that
+    /// matching child records (e.g. exons) appear under their parents
+    fn update_hierarchy_rows(&mut self, records: &C) {
+        self.hierarchy_rows.clear();
+
+        if !self.show_hierarchy {
+            return;
+        }
+
+        let visible: Option<FxHashSet<usize>> = if self.filter_applied {
+            let mut parents: FxHashMap<usize, usize> = FxHashMap::default();
+
+            for ix in 0..records.len() {
+                for &child in records.children(ix) {
+                    parents.entry(child).or_insert(ix);
+                }
+            }
+
+            let mut visible = FxHashSet::default();
+
+            for &ix in self.filtered_records.iter() {
+                let mut current = Some(ix);
+
+                while let Some(ix) = current {
+                    if !visible.insert(ix) {
+                        break;
+                    }
+                    current = parents.get(&ix).copied();
+                }
+            }
+
+            Some(visible)
+        } else {
+            None
+        };
+
+        let is_visible =
+            |ix: usize| visible.as_ref().map_or(true, |v| v.contains(&ix));
+
+        let mut stack: Vec<(usize, usize)> = Vec::new();
+
+        // records are only listed once, so a Parent cycle can't
+        // loop forever
+        let mut emitted: FxHashSet<usize> = FxHashSet::default();
+
+        let roots = (0..records.len())
+            .filter(|&ix| !records.has_parent(ix) && is_visible(ix));
+
+        for root in roots {
+            stack.push((root, 0));
+
+            while let Some((ix, depth)) = stack.pop() {
+                if !emitted.insert(ix) {
+                    continue;
+                }
+
+                self.hierarchy_rows.push((ix, depth));
+
+                let expanded = self.expanded_records.contains(&ix);
+
+                stack.extend(
+                    records
+                        .children(ix)
+                        .iter()
+                        .rev()
+                        .filter(|&&child| {
+                            !emitted.contains(&child)
+                                && (expanded
+                                    || (visible.is_some() && is_visible(child)))
+                        })
+                        .map(|&child| (child, depth + 1)),
+                );
+            }
+        }
     }
 
     pub fn active_path_id(&self) -> Option<PathId> {
@@ -288,8 +409,14 @@ where
 
             enabled_columns.update_columns(records.as_ref());
 
+            // the defaults can include columns that only exist in
+            // some files, e.g. GTF attributes
+            let all_columns = records.all_columns();
+
             for col in self.default_enabled_columns.iter() {
-                enabled_columns.set_column(col, true);
+                if all_columns.contains(col) {
+                    enabled_columns.set_column(col, true);
+                }
             }
 
             for col in self.default_hidden_columns.iter() {
@@ -320,7 +447,12 @@ where
 
         if self.current_file.as_ref().map(|s| s.as_str()) != Some(file_name) {
             self.current_file = Some(file_name.to_string());
+            self.expanded_records.clear();
+            if !records.has_hierarchy() {
+                self.show_hierarchy = false;
+            }
             self.apply_filter(file_name, records.as_ref());
+            self.update_hierarchy_rows(records.as_ref());
         }
 
         self.path_picker.ui(ui.ctx(), &mut self.path_picker_open);
@@ -350,6 +482,13 @@ where
             );
         }
 
+        self.lifted.ui(
+            ui.ctx(),
+            &mut self.lifted_open,
+            app_msg_tx,
+            graph_query,
+        );
+
         ui.set_min_height(200.0);
         ui.set_max_height(ui.input().screen_rect.height() - 100.0);
 
@@ -411,10 +550,21 @@ where
         ui.horizontal(|ui| {
             if ui.button("Apply filter").clicked() || apply_filter {
                 self.apply_filter(file_name, records.as_ref());
+                self.update_hierarchy_rows(records.as_ref());
             }
 
             if ui.button("Clear filter").clicked() {
                 self.clear_filter();
+                self.update_hierarchy_rows(records.as_ref());
+            }
+
+            if records.has_hierarchy() {
+                let hierarchy_box =
+                    ui.checkbox(&mut self.show_hierarchy, "Show hierarchy");
+
+                if hierarchy_box.changed() {
+                    self.update_hierarchy_rows(records.as_ref());
+                }
             }
         });
 
@@ -440,6 +590,44 @@ where
             }
         });
 
+        ui.horizontal(|ui| {
+            let ref_path = self.path_picker.active_path().map(|(p, _)| p);
+
+            let lift_btn = ui.add_enabled(
+                ref_path.is_some() && !self.lifted.is_running(),
+                egui::Button::new("Lift to all paths"),
+            );
+
+            if let Some(ref_path) = ref_path {
+                if lift_btn.clicked() {
+                    let indices = if !self.filter_applied {
+                        (0..records.len()).collect()
+                    } else {
+                        self.filtered_records.clone()
+                    };
+
+                    // records are named using the label column, if set
+                    let name_column =
+                        self.creator.column_picker.chosen_column().cloned();
+
+                    self.lifted.lift(
+                        records.clone(),
+                        indices,
+                        ref_path,
+                        name_column,
+                    );
+                    self.lifted_open = true;
+                }
+            }
+
+            if ui
+                .selectable_label(self.lifted_open, "Lifted annotations")
+                .clicked()
+            {
+                self.lifted_open = !self.lifted_open;
+            }
+        });
+
         ui.horizontal(|ui| {
             let path_name_range = if let Some(name) = &active_path_name {
                 let n = name.as_bytes();
@@ -463,7 +651,9 @@ where
 
         let enabled_columns = self.enabled_columns.get(file_name).unwrap();
 
-        let record_count = if self.filtered_records.is_empty() {
+        let record_count = if self.show_hierarchy {
+            self.hierarchy_rows.len()
+        } else if !self.filter_applied {
             records.records().len()
         } else {
             self.filtered_records.len()
@@ -534,29 +724,49 @@ where
 
             let take_n = range.start.max(range.end) - range.start;
 
+            let mut toggled_record: Option<usize> = None;
+
             egui::Grid::new("record_list_grid").show(ui, |ui| {
-                let records_iter = if self.filtered_records.is_empty() {
-                    Box::new(records.records().iter())
-                        as Box<dyn Iterator<Item = _>>
-                } else {
-                    let indices = &self.filtered_records;
+                let rows_iter = if self.show_hierarchy {
                     Box::new(
-                        indices
+                        self.hierarchy_rows
                             .iter()
-                            .copied()
-                            .filter_map(|ix| records.records().get(ix)),
+                            .map(|&(ix, depth)| (ix, Some(depth))),
                     ) as Box<dyn Iterator<Item = _>>
+                } else if !self.filter_applied {
+                    Box::new((0..records.len()).map(|ix| (ix, None)))
+                        as Box<dyn Iterator<Item = _>>
+                } else {
+                    Box::new(self.filtered_records.iter().map(|&ix| (ix, None)))
+                        as Box<dyn Iterator<Item = _>>
                 };
 
-                for (ix, record) in
-                    records_iter.enumerate().skip(range.start).take(take_n)
+                for (ix, (record_ix, depth)) in
+                    rows_iter.enumerate().skip(range.start).take(take_n)
                 {
+                    let record = if let Some(record) =
+                        records.records().get(record_ix)
+                    {
+                        record
+                    } else {
+                        continue;
+                    };
+
+                    let has_children = !records.children(record_ix).is_empty();
+
+                    let tree_node = depth.map(|depth| {
+                        let expanded =
+                            self.expanded_records.contains(&record_ix);
+                        (depth, has_children, expanded)
+                    });
+
                     let row = self.ui_row(
                         ui,
                         file_name,
                         records.as_ref(),
                         record,
                         ix,
+                        tree_node,
                     );
 
                     let row_interact = ui.interact(
@@ -567,12 +777,23 @@ where
 
                     if row_interact.clicked() {
                         self.select_record(app_msg_tx, graph_query, record);
+
+                        if tree_node.is_some() && has_children {
+                            toggled_record = Some(record_ix);
+                        }
                     }
                     if row_interact.double_clicked() {
                         app_msg_tx.send(AppMsg::goto_selection()).unwrap();
                     }
                 }
             });
+
+            if let Some(record_ix) = toggled_record {
+                if !self.expanded_records.remove(&record_ix) {
+                    self.expanded_records.insert(record_ix);
+                }
+                self.update_hierarchy_rows(records.as_ref());
+            }
         });
 
         let enabled_columns = self.enabled_columns.get_mut(file_name).unwrap();
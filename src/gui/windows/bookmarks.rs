@@ -0,0 +1,450 @@
+use std::path::PathBuf;
+
+use crate::{
+    app::{
+        bookmarks::{read_bookmarks, write_bookmarks, Bookmark},
+        App, AppMsg, Select,
+    },
+    geometry::Point,
+    gui::text::draw_text_at_world_point_offset,
+    reactor::{Host, Outbox, Reactor},
+    universe::Node,
+    view::View,
+};
+
+enum BookmarksIo {
+    Export {
+        file: PathBuf,
+        bookmarks: Vec<Bookmark>,
+    },
+    Import {
+        file: PathBuf,
+    },
+}
+
+enum IoFeedback {
+    Running(String),
+    Exported(String),
+    Imported(Vec<Bookmark>),
+    Error(String),
+}
+
+/// Named views, with optional selections and notes, that can be
+/// stepped through and shared as TSV files
+pub struct BookmarkList {
+    bookmarks: Vec<Bookmark>,
+    // the bookmark shown in the editor, and the last one visited
+    current: Option<usize>,
+
+    new_name: String,
+    include_selection: bool,
+    created_count: usize,
+
+    show_markers: bool,
+
+    file_path: String,
+
+    io_host: Host<BookmarksIo, IoFeedback>,
+    latest_io: Option<IoFeedback>,
+    status: Option<Result<String, String>>,
+}
+
+impl BookmarkList {
+    pub const ID: &'static str = "bookmarks_window";
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let node_count = reactor.graph_query.node_count();
+
+        let io_host = reactor.create_host(
+            move |outbox: &Outbox<IoFeedback>, io: BookmarksIo| {
+                let result =
+                    match io {
+                        BookmarksIo::Export { file, bookmarks } => {
+                            outbox.insert_blocking(IoFeedback::Running(
+                                format!("Exporting to {}", file.display()),
+                            ));
+
+                            write_bookmarks(&bookmarks, &file).map(|_| {
+                                IoFeedback::Exported(format!(
+                                    "Exported {} bookmarks to {}",
+                                    bookmarks.len(),
+                                    file.display()
+                                ))
+                            })
+                        }
+                        BookmarksIo::Import { file } => {
+                            outbox.insert_blocking(IoFeedback::Running(
+                                format!("Importing {}", file.display()),
+                            ));
+
+                            read_bookmarks(&file, node_count)
+                                .map(IoFeedback::Imported)
+                        }
+                    };
+
+                result.unwrap_or_else(|err| IoFeedback::Error(err.to_string()))
+            },
+        );
+
+        Self {
+            bookmarks: Vec::new(),
+            current: None,
+
+            new_name: String::new(),
+            include_selection: false,
+            created_count: 0,
+
+            show_markers: true,
+
+            file_path: String::new(),
+
+            io_host,
+            latest_io: None,
+            status: None,
+        }
+    }
+
+    pub fn bookmarks(&self) -> &[Bookmark] {
+        &self.bookmarks
+    }
+
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) {
+        self.bookmarks.push(bookmark);
+        self.current = Some(self.bookmarks.len() - 1);
+    }
+
+    /// Animates the view to the bookmark, and replaces the selection
+    /// if the bookmark has one
+    pub fn goto(&mut self, app: &App, ix: usize) {
+        let bookmark = match self.bookmarks.get(ix) {
+            Some(bookmark) => bookmark,
+            None => return,
+        };
+
+        let app_msg_tx = &app.channels.app_tx;
+
+        app_msg_tx.send(AppMsg::goto_view(bookmark.view)).unwrap();
+
+        if let Some(nodes) = &bookmark.selection {
+            let select = Select::Many {
+                nodes: nodes.clone(),
+                clear: true,
+            };
+            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+        }
+
+        self.current = Some(ix);
+    }
+
+    /// Steps to the next (or previous) bookmark, wrapping around
+    pub fn goto_next(&mut self, app: &App, forward: bool) {
+        let count = self.bookmarks.len();
+
+        if count == 0 {
+            return;
+        }
+
+        let ix = match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(ix), true) => (ix + 1) % count,
+            (Some(ix), false) => (ix + count - 1) % count,
+        };
+
+        self.goto(app, ix);
+    }
+
+    fn remove(&mut self, ix: usize) {
+        if ix >= self.bookmarks.len() {
+            return;
+        }
+
+        self.bookmarks.remove(ix);
+
+        self.current = match self.current {
+            Some(i) if i == ix => None,
+            Some(i) if i > ix => Some(i - 1),
+            other => other,
+        };
+    }
+
+    /// Draws a marker with the bookmark name at the center of each
+    /// bookmarked view
+    pub fn draw_markers(&self, ctx: &egui::CtxRef, view: View) {
+        if !self.show_markers {
+            return;
+        }
+
+        let screen_rect = ctx.input().screen_rect();
+        let offset =
+            Point::new(screen_rect.width(), screen_rect.height()) / 2.0;
+
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Background,
+            egui::Id::new("bookmark_markers"),
+        ));
+
+        for (ix, bookmark) in self.bookmarks.iter().enumerate() {
+            let center = bookmark.view.center;
+
+            let screen = view.world_point_to_screen(center) + offset;
+
+            if !screen_rect.contains(screen.into()) {
+                continue;
+            }
+
+            let color = if self.current == Some(ix) {
+                egui::Color32::from_rgb(255, 160, 0)
+            } else {
+                egui::Color32::from_rgb(0, 160, 255)
+            };
+
+            painter.circle_filled(screen.into(), 5.0, color);
+            painter.circle_stroke(
+                screen.into(),
+                5.0,
+                egui::Stroke::new(1.0, egui::Color32::BLACK),
+            );
+
+            draw_text_at_world_point_offset(
+                ctx,
+                view,
+                center,
+                Point::new(0.0, -16.0),
+                &bookmark.name,
+            );
+        }
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        if let Some(feedback) = self.io_host.take() {
+            self.latest_io = Some(feedback);
+        }
+
+        match self.latest_io.take() {
+            Some(IoFeedback::Exported(msg)) => {
+                self.status = Some(Ok(msg));
+            }
+            Some(IoFeedback::Imported(bookmarks)) => {
+                self.status =
+                    Some(Ok(format!("Imported {} bookmarks", bookmarks.len())));
+                self.bookmarks.extend(bookmarks);
+            }
+            Some(IoFeedback::Error(err)) => {
+                self.status = Some(Err(err));
+            }
+            feedback => {
+                self.latest_io = feedback;
+            }
+        }
+
+        let mut goto: Option<usize> = None;
+        let mut step: Option<bool> = None;
+        let mut to_remove: Option<usize> = None;
+
+        let selection = app.selection();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_name)
+                    .desired_width(150.0)
+                    .hint_text("Name"),
+            );
+
+            if ui.button("Add current view").clicked() {
+                self.created_count += 1;
+
+                let name = if self.new_name.trim().is_empty() {
+                    format!("Bookmark {}", self.created_count)
+                } else {
+                    self.new_name.trim().to_string()
+                };
+                self.new_name.clear();
+
+                let selection =
+                    if self.include_selection && !selection.is_empty() {
+                        Some(selection.clone())
+                    } else {
+                        None
+                    };
+
+                self.add_bookmark(Bookmark {
+                    name,
+                    view: app.shared_state().view(),
+                    selection,
+                    notes: String::new(),
+                });
+            }
+        });
+
+        ui.checkbox(
+            &mut self.include_selection,
+            format!("Include selection ({} nodes)", selection.len()),
+        );
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            let any = !self.bookmarks.is_empty();
+
+            if ui.add_enabled(any, egui::Button::new("Previous")).clicked() {
+                step = Some(false);
+            }
+
+            if ui.add_enabled(any, egui::Button::new("Next")).clicked() {
+                step = Some(true);
+            }
+
+            ui.checkbox(&mut self.show_markers, "Show markers");
+        });
+
+        egui::ScrollArea::from_max_height(200.0).show(ui, |ui| {
+            egui::Grid::new("bookmarks_grid")
+                .striped(true)
+                .show(ui, |ui| {
+                    for (ix, bookmark) in self.bookmarks.iter().enumerate() {
+                        let label = ui.selectable_label(
+                            self.current == Some(ix),
+                            &bookmark.name,
+                        );
+
+                        let label = if bookmark.notes.is_empty() {
+                            label
+                        } else {
+                            label.on_hover_text(&bookmark.notes)
+                        };
+
+                        if label.clicked() {
+                            goto = Some(ix);
+                        }
+
+                        match &bookmark.selection {
+                            Some(nodes) => {
+                                ui.label(format!("{} nodes", nodes.len()))
+                            }
+                            None => ui.label(""),
+                        };
+
+                        if ui
+                            .small_button("x")
+                            .on_hover_text("Remove")
+                            .clicked()
+                        {
+                            to_remove = Some(ix);
+                        }
+
+                        ui.end_row();
+                    }
+                });
+        });
+
+        let current = self.current;
+
+        if let Some(bookmark) =
+            current.and_then(|ix| self.bookmarks.get_mut(ix))
+        {
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut bookmark.name);
+            });
+
+            ui.label("Notes");
+            ui.add(
+                egui::TextEdit::multiline(&mut bookmark.notes)
+                    .desired_rows(3)
+                    .desired_width(300.0),
+            );
+
+            ui.horizontal(|ui| {
+                if ui.button("Update view").clicked() {
+                    bookmark.view = app.shared_state().view();
+                }
+
+                if ui
+                    .add_enabled(
+                        !selection.is_empty(),
+                        egui::Button::new("Update selection"),
+                    )
+                    .clicked()
+                {
+                    bookmark.selection = Some(selection.clone());
+                }
+
+                if ui
+                    .add_enabled(
+                        bookmark.selection.is_some(),
+                        egui::Button::new("Clear selection"),
+                    )
+                    .clicked()
+                {
+                    bookmark.selection = None;
+                }
+            });
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.file_path)
+                    .desired_width(250.0)
+                    .hint_text("File (TSV)"),
+            );
+
+            let idle = !matches!(self.latest_io, Some(IoFeedback::Running(_)));
+            let has_file = !self.file_path.trim().is_empty();
+
+            if ui
+                .add_enabled(
+                    idle && has_file && !self.bookmarks.is_empty(),
+                    egui::Button::new("Export"),
+                )
+                .clicked()
+            {
+                let io = BookmarksIo::Export {
+                    file: PathBuf::from(self.file_path.trim()),
+                    bookmarks: self.bookmarks.clone(),
+                };
+                self.io_host.call(io).unwrap();
+            }
+
+            if ui
+                .add_enabled(idle && has_file, egui::Button::new("Import"))
+                .on_hover_text("Append the bookmarks in the file")
+                .clicked()
+            {
+                let io = BookmarksIo::Import {
+                    file: PathBuf::from(self.file_path.trim()),
+                };
+                self.io_host.call(io).unwrap();
+            }
+        });
+
+        if let Some(IoFeedback::Running(msg)) = &self.latest_io {
+            ui.label(msg);
+        } else if let Some(status) = &self.status {
+            match status {
+                Ok(msg) => {
+                    ui.label(msg);
+                }
+                Err(err) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+            }
+        }
+
+        if let Some(ix) = to_remove {
+            self.remove(ix);
+        }
+
+        if let Some(ix) = goto {
+            self.goto(app, ix);
+        }
+
+        if let Some(forward) = step {
+            self.goto_next(app, forward);
+        }
+    }
+}
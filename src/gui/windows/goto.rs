@@ -0,0 +1,115 @@
+use crossbeam::channel::Sender;
+use handlegraph::pathhandlegraph::{GraphPathNames, PathId};
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    app::{App, AppMsg, Select},
+    graph_query::GraphQuery,
+    universe::Node,
+};
+
+/// Selects the nodes covering the basepair range of the path, and
+/// moves the view to them; returns the number of selected nodes
+pub fn goto_path_range(
+    graph_query: &GraphQuery,
+    app_msg_tx: &Sender<AppMsg>,
+    path: PathId,
+    start: usize,
+    end: usize,
+) -> Result<usize> {
+    let nodes = graph_query
+        .path_range_nodes(path, start, end)
+        .ok_or_else(|| anyhow!("Range {}-{} is not on the path", start, end))?;
+
+    let count = nodes.len();
+
+    let select = Select::Many { nodes, clear: true };
+    app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+    app_msg_tx.send(AppMsg::goto_selection()).unwrap();
+
+    Ok(count)
+}
+
+/// Resolves a location string and goes to it, see
+/// `GraphQuery::resolve_location` for the accepted formats
+pub fn goto_location(
+    graph_query: &GraphQuery,
+    app_msg_tx: &Sender<AppMsg>,
+    location: &str,
+) -> Result<String> {
+    use bstr::ByteSlice;
+
+    let (path, start, end) = graph_query.resolve_location(location)?;
+
+    let count = goto_path_range(graph_query, app_msg_tx, path, start, end)?;
+
+    let name = graph_query
+        .graph()
+        .get_path_name_vec(path)
+        .unwrap_or_default();
+
+    // reported in the same 1-based, inclusive convention as the input
+    Ok(format!(
+        "{}:{}-{} - {} nodes",
+        name.as_bstr(),
+        start + 1,
+        end,
+        count
+    ))
+}
+
+pub struct GotoLocation {
+    location: String,
+    feedback: Option<String>,
+}
+
+impl GotoLocation {
+    pub const ID: &'static str = "goto_location_window";
+
+    pub fn new() -> Self {
+        Self {
+            location: String::new(),
+            feedback: None,
+        }
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        let mut go = false;
+
+        ui.horizontal(|ui| {
+            let text = ui.add(
+                egui::TextEdit::singleline(&mut self.location)
+                    .hint_text("chr1:1,234,000-1,240,000"),
+            );
+
+            go |= text.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+            go |= ui
+                .add_enabled(
+                    !self.location.trim().is_empty(),
+                    egui::Button::new("Go"),
+                )
+                .clicked();
+        });
+
+        ui.label("Path name, name:start-end, or name:position (1-based)");
+
+        if go {
+            let result = goto_location(
+                &app.reactor.graph_query,
+                &app.channels.app_tx,
+                &self.location,
+            );
+
+            self.feedback = Some(match result {
+                Ok(msg) => msg,
+                Err(err) => err.to_string(),
+            });
+        }
+
+        if let Some(feedback) = &self.feedback {
+            ui.label(feedback);
+        }
+    }
+}
@@ -60,6 +60,10 @@ impl PathPickerSource {
         })
     }
 
+    pub fn paths(&self) -> &Arc<Vec<(PathId, String)>> {
+        &self.paths
+    }
+
     pub fn create_picker(&mut self) -> PathPicker {
         let paths = self.paths.clone();
         let filtered_paths = Vec::with_capacity(paths.len());
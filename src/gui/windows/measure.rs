@@ -0,0 +1,397 @@
+use handlegraph::{handle::NodeId, pathhandlegraph::PathId};
+
+use crate::{
+    app::{
+        measure::{layout_bp_per_unit, NodeDistances},
+        App,
+    },
+    geometry::{Point, Rect},
+    gui::text::{
+        draw_circle_world, draw_line_world, draw_rect_world,
+        draw_text_at_world_point_offset,
+    },
+    reactor::{Host, Outbox, Reactor},
+    universe::Node,
+    view::View,
+};
+
+use super::path_position::format_bp;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MeasurePoint {
+    world: Point,
+    node: Option<NodeId>,
+}
+
+/// Measures the distance between two points clicked in the main
+/// view, and draws a scale bar in the corner of the screen
+pub struct MeasureTool {
+    points: Vec<MeasurePoint>,
+
+    distance_host: Host<(NodeId, NodeId), NodeDistances>,
+    distances: Option<NodeDistances>,
+    path: Option<PathId>,
+
+    show_scale_bar: bool,
+    // cleared when the layout changes
+    bp_per_unit: Option<Option<f64>>,
+}
+
+impl MeasureTool {
+    pub const ID: &'static str = "measure_window";
+
+    const SCALE_BAR_WIDTH: f32 = 120.0;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let graph = reactor.graph_query.clone();
+
+        let distance_host = reactor.create_host(
+            move |_outbox: &Outbox<NodeDistances>, (from, to)| {
+                NodeDistances::compute(&graph, from, to)
+            },
+        );
+
+        Self {
+            points: Vec::new(),
+
+            distance_host,
+            distances: None,
+            path: None,
+
+            show_scale_bar: false,
+            bp_per_unit: None,
+        }
+    }
+
+    pub fn mark_dirty(&mut self) {
+        self.bp_per_unit = None;
+    }
+
+    fn bp_per_unit(&mut self, app: &App, nodes: &[Node]) -> Option<f64> {
+        if self.bp_per_unit.is_none() {
+            let graph = app.reactor.graph_query.graph();
+            self.bp_per_unit = Some(layout_bp_per_unit(graph, nodes));
+        }
+
+        self.bp_per_unit.flatten()
+    }
+
+    fn clear(&mut self) {
+        self.points.clear();
+        self.distances = None;
+    }
+
+    fn add_point(&mut self, point: MeasurePoint) {
+        if self.points.len() >= 2 {
+            self.clear();
+        }
+
+        self.points.push(point);
+
+        if let [a, b] = self.points.as_slice() {
+            if let (Some(from), Some(to)) = (a.node, b.node) {
+                self.distance_host.call((from, to)).unwrap();
+            }
+        }
+    }
+
+    fn node_pair(&self) -> Option<(NodeId, NodeId)> {
+        match self.points.as_slice() {
+            [a, b] => Some((a.node?, b.node?)),
+            _ => None,
+        }
+    }
+
+    /// Adds a point when the main view is clicked
+    fn handle_click(&mut self, ctx: &egui::CtxRef, app: &App) {
+        if ctx.is_pointer_over_area() || !ctx.input().pointer.any_click() {
+            return;
+        }
+
+        let pos = ctx.input().events.iter().find_map(|event| match event {
+            egui::Event::PointerButton {
+                pos,
+                button: egui::PointerButton::Primary,
+                pressed: false,
+                ..
+            } => Some(*pos),
+            _ => None,
+        });
+
+        if let Some(pos) = pos {
+            let view = app.shared_state().view();
+            let world = view.screen_point_to_world(app.dims(), pos.into());
+
+            self.add_point(MeasurePoint {
+                world,
+                node: app.hover_node(),
+            });
+        }
+    }
+
+    /// Handles clicks while measuring, and draws the measurement, if
+    /// the window is open, and the scale bar
+    pub fn draw(
+        &mut self,
+        ctx: &egui::CtxRef,
+        app: &App,
+        nodes: &[Node],
+        window_open: bool,
+    ) {
+        if let Some(distances) = self.distance_host.take() {
+            if self.node_pair() == Some((distances.from, distances.to)) {
+                let has_path = self
+                    .path
+                    .map(|p| distances.paths.iter().any(|d| d.path == p))
+                    .unwrap_or(false);
+
+                if !has_path {
+                    self.path = distances.paths.first().map(|d| d.path);
+                }
+
+                self.distances = Some(distances);
+            }
+        }
+
+        if !window_open {
+            app.shared_state().measuring.store(false);
+        } else if app.shared_state().measuring() {
+            self.handle_click(ctx, app);
+        }
+
+        let view = app.shared_state().view();
+
+        let measured = window_open && self.points.len() == 2;
+
+        let bp_per_unit = if self.show_scale_bar || measured {
+            self.bp_per_unit(app, nodes)
+        } else {
+            None
+        };
+
+        let points: &[MeasurePoint] =
+            if window_open { &self.points } else { &[] };
+
+        let color = Some(rgb::RGBA::new(1.0, 0.6, 0.0, 1.0));
+
+        for point in points.iter() {
+            draw_circle_world(ctx, view, point.world, 5.0, color);
+        }
+
+        if let [a, b] = points {
+            draw_line_world(ctx, view, a.world, b.world, color);
+
+            let mid = a.world + (b.world - a.world) / 2.0;
+
+            draw_text_at_world_point_offset(
+                ctx,
+                view,
+                mid,
+                Point::new(0.0, -16.0),
+                &Self::layout_distance_text(a.world.dist(b.world), bp_per_unit),
+            );
+        }
+
+        if self.show_scale_bar {
+            Self::draw_scale_bar(ctx, view, app, bp_per_unit);
+        }
+    }
+
+    fn layout_distance_text(dist: f32, bp_per_unit: Option<f64>) -> String {
+        match bp_per_unit {
+            Some(bp_per_unit) => {
+                let bp = (dist as f64 * bp_per_unit).round() as usize;
+                format!("{:.1} units (~{} bp)", dist, format_bp(bp))
+            }
+            None => format!("{:.1} units", dist),
+        }
+    }
+
+    /// Rounds down to 1, 2, or 5 times a power of ten
+    fn nice_length(length: f64) -> f64 {
+        let magnitude = 10f64.powf(length.log10().floor());
+        let norm = length / magnitude;
+
+        let nice = if norm >= 5.0 {
+            5.0
+        } else if norm >= 2.0 {
+            2.0
+        } else {
+            1.0
+        };
+
+        nice * magnitude
+    }
+
+    fn draw_scale_bar(
+        ctx: &egui::CtxRef,
+        view: View,
+        app: &App,
+        bp_per_unit: Option<f64>,
+    ) {
+        let screen_rect = ctx.input().screen_rect();
+
+        let max_units = (Self::SCALE_BAR_WIDTH * view.scale) as f64;
+
+        // the bar is a round number of basepairs if the scale is
+        // known, otherwise a round number of world units
+        let (units, label) = match bp_per_unit {
+            Some(bp_per_unit) => {
+                let bp = Self::nice_length(max_units * bp_per_unit).max(1.0);
+                (bp / bp_per_unit, format!("~{} bp", format_bp(bp as usize)))
+            }
+            None => {
+                let units = Self::nice_length(max_units);
+                (units, format!("{} units", units))
+            }
+        };
+
+        if !units.is_finite() || units <= 0.0 {
+            return;
+        }
+
+        let width = units as f32 / view.scale;
+
+        let p0 = Point::new(20.0, screen_rect.height() - 30.0);
+        let p1 = Point::new(20.0 + width, screen_rect.height() - 26.0);
+
+        let dims = app.dims();
+        let w0 = view.screen_point_to_world(dims, p0);
+        let w1 = view.screen_point_to_world(dims, p1);
+
+        draw_rect_world(ctx, view, Rect::new(w0, w1), None);
+
+        draw_text_at_world_point_offset(
+            ctx,
+            view,
+            Rect::new(w0, w1).center(),
+            Point::new(0.0, -14.0),
+            &label,
+        );
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, nodes: &[Node]) {
+        let bp_per_unit = if self.points.len() == 2 {
+            self.bp_per_unit(app, nodes)
+        } else {
+            None
+        };
+
+        let mut measuring = app.shared_state().measuring();
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut measuring, "Measuring").on_hover_text(
+                "Click two points or nodes in the view to measure \
+                 the distance between them",
+            );
+
+            if ui
+                .add_enabled(
+                    !self.points.is_empty(),
+                    egui::Button::new("Clear"),
+                )
+                .clicked()
+            {
+                self.clear();
+            }
+        });
+
+        app.shared_state().measuring.store(measuring);
+
+        ui.checkbox(&mut self.show_scale_bar, "Show scale bar");
+
+        ui.separator();
+
+        for (label, point) in ["A", "B"].iter().zip(self.points.iter()) {
+            let node = point
+                .node
+                .map(|n| format!("node {}", n.0))
+                .unwrap_or_default();
+
+            ui.label(format!(
+                "{}: ({:.1}, {:.1}) {}",
+                label, point.world.x, point.world.y, node
+            ));
+        }
+
+        let (a, b) = match self.points.as_slice() {
+            [a, b] => (*a, *b),
+            _ => {
+                ui.label("Click in the view to place points");
+                return;
+            }
+        };
+
+        ui.separator();
+
+        egui::Grid::new("measure_distances").show(ui, |ui| {
+            ui.label("Layout");
+            ui.label(Self::layout_distance_text(
+                a.world.dist(b.world),
+                bp_per_unit,
+            ));
+            ui.end_row();
+
+            if self.node_pair().is_none() {
+                ui.label("Graph");
+                ui.label("Both points must be on nodes");
+                ui.end_row();
+                return;
+            }
+
+            let distances = match &self.distances {
+                Some(distances) => distances,
+                None => {
+                    ui.label("Graph");
+                    ui.label("Computing...");
+                    ui.end_row();
+                    return;
+                }
+            };
+
+            ui.label("Graph");
+            match distances.graph {
+                Some(dist) => ui.label(format!(
+                    "{} edges, {} bp between",
+                    dist.hops,
+                    format_bp(dist.bp)
+                )),
+                None => ui.label("Not connected"),
+            };
+            ui.end_row();
+
+            ui.label("Path");
+
+            if distances.paths.is_empty() {
+                ui.label("No path visits both nodes");
+                ui.end_row();
+                return;
+            }
+
+            let path = &mut self.path;
+
+            let current = distances
+                .paths
+                .iter()
+                .find(|d| Some(d.path) == *path)
+                .unwrap_or(&distances.paths[0]);
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("measure_path")
+                    .selected_text(&current.name)
+                    .show_ui(ui, |ui| {
+                        for dist in distances.paths.iter() {
+                            ui.selectable_value(
+                                path,
+                                Some(dist.path),
+                                &dist.name,
+                            );
+                        }
+                    });
+
+                ui.label(format!("{} bp", format_bp(current.bp)));
+            });
+            ui.end_row();
+        });
+    }
+}
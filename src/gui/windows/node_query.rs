@@ -0,0 +1,230 @@
+use handlegraph::handle::NodeId;
+use rustc_hash::FxHashSet;
+
+use crate::{
+    app::{App, AppMsg, SelectOp},
+    reactor::{Host, Outbox, Reactor},
+    script::query::NodeQuery,
+    universe::Node,
+};
+
+enum QueryFeedback {
+    Running(String),
+    Error(String),
+    Done {
+        nodes: FxHashSet<NodeId>,
+        op: SelectOp,
+    },
+}
+
+/// Selects the nodes matching a query, see `NodeQuery` for the syntax
+pub struct NodeQueryWindow {
+    query: String,
+
+    history: Vec<String>,
+    history_ix: Option<usize>,
+
+    op: SelectOp,
+
+    query_host: Host<(String, SelectOp), QueryFeedback>,
+    latest_feedback: Option<QueryFeedback>,
+    status: Option<Result<String, String>>,
+}
+
+impl NodeQueryWindow {
+    pub const ID: &'static str = "node_query_window";
+
+    const MAX_HISTORY: usize = 64;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let graph = reactor.graph_query.clone();
+        let rayon_pool = reactor.rayon_pool.clone();
+
+        let query_host = reactor.create_host(
+            move |outbox: &Outbox<QueryFeedback>,
+                  (query, op): (String, SelectOp)| {
+                outbox.insert_blocking(QueryFeedback::Running(format!(
+                    "Running query on {} nodes",
+                    graph.node_count()
+                )));
+
+                let result = NodeQuery::compile(&graph, &query)
+                    .and_then(|query| rayon_pool.install(|| query.select()));
+
+                match result {
+                    Ok(selection) => QueryFeedback::Done {
+                        nodes: selection.nodes,
+                        op,
+                    },
+                    Err(err) => QueryFeedback::Error(err.to_string()),
+                }
+            },
+        );
+
+        Self {
+            query: String::new(),
+
+            history: Vec::new(),
+            history_ix: None,
+
+            op: SelectOp::Replace,
+
+            query_host,
+            latest_feedback: None,
+            status: None,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.latest_feedback, Some(QueryFeedback::Running(_)))
+    }
+
+    fn run_query(&mut self) {
+        let query = self.query.trim().to_string();
+
+        if self.is_running() || query.is_empty() {
+            return;
+        }
+
+        self.history.retain(|q| q != &query);
+        self.history.push(query.clone());
+
+        if self.history.len() > Self::MAX_HISTORY {
+            self.history.remove(0);
+        }
+
+        self.history_ix = None;
+
+        self.query_host.call((query, self.op)).unwrap();
+    }
+
+    fn step_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let last = self.history.len() - 1;
+
+        let ix = match (self.history_ix, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(ix), true) => Some(ix.saturating_sub(1)),
+            (Some(ix), false) if ix < last => Some(ix + 1),
+            (Some(_), false) => None,
+        };
+
+        self.history_ix = ix;
+
+        self.query = ix
+            .and_then(|ix| self.history.get(ix).cloned())
+            .unwrap_or_default();
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        if let Some(feedback) = self.query_host.take() {
+            self.latest_feedback = Some(feedback);
+        }
+
+        match self.latest_feedback.take() {
+            Some(QueryFeedback::Done { nodes, op }) => {
+                self.status =
+                    Some(Ok(format!("{} matching nodes", nodes.len())));
+
+                app.channels
+                    .app_tx
+                    .send(AppMsg::Selection(op.apply(nodes)))
+                    .unwrap();
+            }
+            Some(QueryFeedback::Error(err)) => {
+                self.status = Some(Err(err));
+            }
+            feedback => {
+                self.latest_feedback = feedback;
+            }
+        }
+
+        let mut run = false;
+
+        let text = ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .desired_width(400.0)
+                .hint_text("len > 1000 and depth < 3 and on_path(\"chr6\")"),
+        );
+
+        if text.has_focus() {
+            if ui.input().key_pressed(egui::Key::ArrowUp) {
+                self.step_history(true);
+            } else if ui.input().key_pressed(egui::Key::ArrowDown) {
+                self.step_history(false);
+            }
+        }
+
+        run |= text.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.op, SelectOp::Replace, "Select");
+            ui.radio_value(&mut self.op, SelectOp::Add, "Add");
+            ui.radio_value(&mut self.op, SelectOp::Subtract, "Subtract");
+
+            ui.separator();
+
+            run |= ui
+                .add_enabled(
+                    !self.is_running() && !self.query.trim().is_empty(),
+                    egui::Button::new("Run"),
+                )
+                .clicked();
+
+            let mut picked = None;
+
+            egui::ComboBox::from_id_source("node_query_history")
+                .selected_text("History")
+                .width(200.0)
+                .show_ui(ui, |ui| {
+                    for query in self.history.iter().rev() {
+                        if ui.selectable_label(false, query).clicked() {
+                            picked = Some(query.clone());
+                        }
+                    }
+                });
+
+            if let Some(query) = picked {
+                self.query = query;
+                self.history_ix = None;
+            }
+        });
+
+        if run {
+            self.run_query();
+        }
+
+        if let Some(QueryFeedback::Running(msg)) = &self.latest_feedback {
+            ui.label(msg);
+        } else if let Some(status) = &self.status {
+            match status {
+                Ok(msg) => {
+                    ui.label(msg);
+                }
+                Err(err) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+            }
+        }
+
+        ui.collapsing("Help", |ui| {
+            ui.label(
+                "The query is a Rhai expression evaluated for each node; \
+                 `and`, `or`, and `not` can be used in place of \
+                 `&&`, `||`, and `!`.",
+            );
+            ui.label(
+                "Variables: node, id, len, degree, in_degree, out_degree, \
+                 depth, paths, graph",
+            );
+            ui.label(
+                "Functions: on_path(name), on_path_prefix(prefix), \
+                 seq_contains(seq), and the console graph functions",
+            );
+        });
+    }
+}
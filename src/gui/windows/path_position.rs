@@ -1,5 +1,6 @@
 use crossbeam::atomic::AtomicCell;
 use futures::future::RemoteHandle;
+use handlegraph::packedgraph::PackedGraph;
 use handlegraph::pathhandlegraph::{
     GraphPathNames, GraphPaths, IntoPathIds, PathId,
 };
@@ -14,14 +15,28 @@ use bstr::ByteSlice;
 use crate::gui::util as gui_util;
 
 use crate::{
-    app::{AppChannels, AppMsg, SharedState},
+    annotations::Annotations,
+    app::{AppChannels, AppMsg, Select, SharedState},
     geometry::{Point, Rect},
-    gui::console::Console,
-    reactor::Reactor,
+    gui::{
+        console::Console,
+        text::{draw_line_world, draw_rect_world},
+    },
+    reactor::{Host, Outbox, Reactor},
     universe::Node,
-    vulkan::compute::path_view::PathViewRenderer,
+    vulkan::compute::path_view::{
+        Path1DLayout, PathViewExport, PathViewMode, PathViewRenderer,
+    },
 };
 
+use super::graph_picker::PathPicker;
+
+pub mod groups;
+pub mod tracks;
+
+pub use groups::*;
+pub use tracks::*;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum SortOrder {
     PathId,
@@ -45,12 +60,74 @@ pub struct PathPositionList {
     mouse_over_img: AtomicCell<bool>,
 
     path_view_renderer: Arc<PathViewRenderer>,
+
+    path_picker: PathPicker,
+    path_picker_open: bool,
+
+    // when enabled, the 1D layout follows the chosen reference path
+    // instead of node ID order
+    sort_by_reference: bool,
+    layout_reference: Option<PathId>,
+    layout_host: Host<Option<PathId>, Option<Arc<Path1DLayout>>>,
+    layout_loading: bool,
+
+    tracks: AnnotationTracks,
+
+    // highlight the nodes in the visible range in the graph view, and
+    // mark the hovered node in the path rows
+    link_views: bool,
+    // the path and layout position where a shift-drag range selection
+    // started
+    range_drag: AtomicCell<Option<(PathId, usize)>>,
+    // bounding box of the nodes in the visible layout range
+    highlight_bounds: Mutex<Option<((usize, usize), Rect)>>,
+
+    groups: PathGroups,
+    group_prefix: String,
+    summaries: GroupSummaries,
+
+    export_width: usize,
+    export_row_height: usize,
+    export_png: String,
+    export_tsv: String,
+}
+
+const MAX_LINK_HIGHLIGHT: usize = 2048;
+
+/// Formats a basepair coordinate for the ruler
+pub(crate) fn format_bp(bp: usize) -> String {
+    if bp < 10_000 {
+        format!("{}", bp)
+    } else if bp < 10_000_000 {
+        format!("{:.1}k", bp as f64 / 1_000.0)
+    } else {
+        format!("{:.2}M", bp as f64 / 1_000_000.0)
+    }
 }
 
 impl PathPositionList {
     pub const ID: &'static str = "path_position_list";
 
-    pub fn new(path_view_renderer: Arc<PathViewRenderer>) -> Self {
+    pub fn new(
+        reactor: &Reactor,
+        path_view_renderer: Arc<PathViewRenderer>,
+        path_picker: PathPicker,
+    ) -> Self {
+        let graph = reactor.graph_query.clone();
+
+        let groups = PathGroups::new(graph.graph(), PathGrouping::None);
+
+        let layout_host = reactor.create_host(
+            move |_outbox: &Outbox<_>, ref_path: Option<PathId>| {
+                if let Some(path) = ref_path {
+                    Path1DLayout::new_reference_sorted(&graph, path)
+                        .map(Arc::new)
+                } else {
+                    Some(Arc::new(Path1DLayout::new(graph.graph())))
+                }
+            },
+        );
+
         Self {
             zoom_update: Mutex::new(None),
             filtered_ids: FxHashSet::default(),
@@ -61,9 +138,315 @@ impl PathPositionList {
             rev_sort: false.into(),
             mouse_over_img: false.into(),
             path_view_renderer,
+
+            path_picker,
+            path_picker_open: false,
+
+            sort_by_reference: false,
+            layout_reference: None,
+            layout_host,
+            layout_loading: false,
+
+            tracks: AnnotationTracks::new(reactor),
+
+            link_views: true,
+            range_drag: AtomicCell::new(None),
+            highlight_bounds: Mutex::new(None),
+
+            groups,
+            group_prefix: String::new(),
+            summaries: GroupSummaries::new(reactor),
+
+            export_width: 2048,
+            export_row_height: 8,
+            export_png: String::new(),
+            export_tsv: String::new(),
+        }
+    }
+
+    fn update_layout(&mut self) {
+        if let Some(layout) = self.layout_host.take() {
+            self.layout_loading = false;
+
+            if let Some(layout) = layout {
+                self.path_view_renderer.set_layout_1d(layout);
+                self.path_view_renderer.reset_zoom();
+                self.mark_paths.store(true);
+            } else {
+                log::warn!("Could not create reference-sorted path layout");
+            }
+        }
+
+        if self.layout_loading {
+            return;
+        }
+
+        let reference = if self.sort_by_reference {
+            self.path_picker.active_path().map(|(path, _)| path)
+        } else {
+            None
+        };
+
+        if reference != self.layout_reference {
+            self.layout_reference = reference;
+            self.layout_loading = true;
+            self.layout_host.call(reference).unwrap();
+        }
+    }
+
+    fn ruler_ui(&self, ui: &mut egui::Ui, view: (f64, f64)) {
+        let layout = self.path_view_renderer.layout_1d();
+
+        let (rect, _) = ui
+            .allocate_exact_size(egui::vec2(512.0, 20.0), egui::Sense::hover());
+
+        let painter = ui.painter();
+        let color = ui.visuals().text_color();
+        let stroke = egui::Stroke::new(1.0, color);
+
+        painter.line_segment([rect.left_top(), rect.right_top()], stroke);
+
+        let (left, right) = view;
+        let len = layout.total_len as f64;
+
+        let tick_count = 5;
+
+        for i in 0..=tick_count {
+            let t = i as f32 / tick_count as f32;
+            let x = rect.left() + t * rect.width();
+
+            let pos = ((left + (right - left) * t as f64) * len) as usize;
+            let pos = pos.min(layout.total_len.saturating_sub(1));
+
+            let bp = layout.reference_pos(pos).unwrap_or(pos);
+
+            let p0 = egui::pos2(x, rect.top());
+            let p1 = egui::pos2(x, rect.top() + 4.0);
+            painter.line_segment([p0, p1], stroke);
+
+            let align = if i == 0 {
+                egui::Align2::LEFT_TOP
+            } else if i == tick_count {
+                egui::Align2::RIGHT_TOP
+            } else {
+                egui::Align2::CENTER_TOP
+            };
+
+            painter.text(
+                egui::pos2(x, rect.top() + 5.0),
+                align,
+                format_bp(bp),
+                egui::TextStyle::Small,
+                color,
+            );
         }
     }
 
+    /// Maps a horizontal position in a path row to a position in the
+    /// 1D layout
+    fn layout_pos(
+        layout: &Path1DLayout,
+        view: (f64, f64),
+        rect: egui::Rect,
+        x: f32,
+    ) -> usize {
+        let n = ((x - rect.left()) / rect.width()).clamp(0.0, 1.0) as f64;
+        let (left, right) = view;
+        let pos = (left + (right - left) * n) * layout.total_len as f64;
+        (pos as usize).min(layout.total_len.saturating_sub(1))
+    }
+
+    /// Selects the nodes on `path` in the given layout range, and
+    /// moves the graph view to them
+    fn select_layout_range(
+        layout: &Path1DLayout,
+        channels: &AppChannels,
+        nodes: &[Node],
+        path: PathId,
+        range: (usize, usize),
+    ) {
+        let (start, end) = (range.0.min(range.1), range.0.max(range.1) + 1);
+
+        let mut bounds: Option<Rect> = None;
+
+        let selected = layout
+            .nodes_in_range(start, end)
+            .iter()
+            .copied()
+            .filter(|&node| {
+                layout
+                    .node_range(node)
+                    .map(|r| layout.path_at_pos(path, r.start))
+                    .unwrap_or(false)
+            })
+            .inspect(|node| {
+                if let Some(n) = nodes.get((node.0 - 1) as usize) {
+                    let rect = Rect::new(n.p0, n.p1);
+                    bounds = Some(bounds.map_or(rect, |b| b.union(rect)));
+                }
+            })
+            .collect::<FxHashSet<_>>();
+
+        if selected.is_empty() {
+            return;
+        }
+
+        let select = Select::Many {
+            nodes: selected,
+            clear: true,
+        };
+        channels.app_tx.send(AppMsg::Selection(select)).unwrap();
+
+        if let Some(rect) = bounds {
+            channels.app_tx.send(AppMsg::goto_rect(rect)).unwrap();
+        }
+    }
+
+    /// Draws the nodes in the visible part of the 1D layout in the
+    /// graph view; when there are many, only their bounding box
+    fn highlight_visible_nodes(
+        &self,
+        ctx: &egui::CtxRef,
+        shared_state: &SharedState,
+        layout: &Path1DLayout,
+        nodes: &[Node],
+    ) {
+        let (left, right) = self.path_view_renderer.view();
+
+        if left <= 0.0 && right >= 1.0 {
+            return;
+        }
+
+        let len = layout.total_len as f64;
+        let range = ((left * len) as usize, (right * len) as usize);
+
+        let visible = layout.nodes_in_range(range.0, range.1);
+
+        let view = shared_state.view();
+        let color = Some(rgb::RGBA::new(0.9, 0.5, 0.1, 0.8));
+
+        if visible.len() <= MAX_LINK_HIGHLIGHT {
+            for node in visible {
+                if let Some(n) = nodes.get((node.0 - 1) as usize) {
+                    draw_line_world(ctx, view, n.p0, n.p1, color);
+                }
+            }
+            return;
+        }
+
+        let mut cached = self.highlight_bounds.lock();
+
+        let bounds = match *cached {
+            Some((cached_range, bounds)) if cached_range == range => bounds,
+            _ => {
+                let bounds = visible
+                    .iter()
+                    .filter_map(|node| nodes.get((node.0 - 1) as usize))
+                    .fold(Rect::nowhere(), |acc, n| {
+                        acc.union(Rect::new(n.p0, n.p1))
+                    });
+                *cached = Some((range, bounds));
+                bounds
+            }
+        };
+
+        draw_rect_world(ctx, view, bounds, color);
+    }
+
+    fn grouping_ui(&mut self, ui: &mut egui::Ui, graph: &PackedGraph) {
+        ui.horizontal(|ui| {
+            ui.label("Group by");
+
+            let current = self.groups.grouping.clone();
+            let mut grouping = current.clone();
+
+            ui.selectable_value(&mut grouping, PathGrouping::None, "None");
+            ui.selectable_value(&mut grouping, PathGrouping::Sample, "Sample");
+
+            let is_prefix = matches!(grouping, PathGrouping::Prefix(_));
+
+            if ui.selectable_label(is_prefix, "Name prefix").clicked() {
+                grouping = PathGrouping::Prefix(self.group_prefix.clone());
+            }
+
+            let delim = ui.add_enabled(
+                is_prefix,
+                egui::TextEdit::singleline(&mut self.group_prefix)
+                    .desired_width(32.0),
+            );
+
+            if is_prefix && delim.changed() {
+                grouping = PathGrouping::Prefix(self.group_prefix.clone());
+            }
+
+            if grouping != current {
+                self.groups = PathGroups::new(graph, grouping);
+            }
+        });
+    }
+
+    /// Exports the current view of the given paths, in list order
+    fn export_ui(&mut self, ui: &mut egui::Ui, paths: &[PathId]) {
+        ui.collapsing("Export", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Width");
+                ui.add(
+                    egui::DragValue::new(&mut self.export_width)
+                        .clamp_range(1..=65536),
+                );
+
+                ui.label("Row height");
+                ui.add(
+                    egui::DragValue::new(&mut self.export_row_height)
+                        .clamp_range(1..=256),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("PNG file");
+                ui.text_edit_singleline(&mut self.export_png);
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("TSV file");
+                ui.text_edit_singleline(&mut self.export_tsv);
+            });
+
+            let to_path =
+                |s: &str| (!s.is_empty()).then(|| std::path::PathBuf::from(s));
+
+            let png = to_path(&self.export_png);
+            let tsv = to_path(&self.export_tsv);
+
+            ui.horizontal(|ui| {
+                let enabled =
+                    (png.is_some() || tsv.is_some()) && !paths.is_empty();
+
+                if ui
+                    .add_enabled(enabled, egui::Button::new("Export"))
+                    .clicked()
+                {
+                    let request = PathViewExport {
+                        paths: paths.to_vec(),
+                        width: self.export_width,
+                        row_height: self.export_row_height,
+                        mode: self.path_view_renderer.mode(),
+                        png,
+                        tsv,
+                    };
+
+                    self.path_view_renderer.request_export(request);
+                }
+
+                ui.label(format!("{} paths", paths.len()));
+            });
+
+            if let Some(status) = self.path_view_renderer.export_status() {
+                ui.label(status);
+            }
+        });
+    }
+
     fn apply_filter(&mut self, reactor: &Reactor) {
         let needle = &self.name_filter;
 
@@ -97,6 +480,7 @@ impl PathPositionList {
         reactor: &Reactor,
         channels: &AppChannels,
         shared_state: &SharedState,
+        annotations: &Annotations,
         nodes: &[Node],
     ) {
         let graph_query = reactor.graph_query.clone();
@@ -128,6 +512,67 @@ impl PathPositionList {
             self.apply_filter(reactor);
         }
 
+        self.path_picker.ui(ui.ctx(), &mut self.path_picker_open);
+
+        ui.horizontal(|ui| {
+            let ref_name = self.path_picker.active_path().map(|(_, n)| n);
+
+            let label = if let Some(name) = ref_name {
+                format!("Reference: {}", name)
+            } else {
+                "Select reference path".to_string()
+            };
+
+            if ui.button(label).clicked() {
+                self.path_picker_open = !self.path_picker_open;
+            }
+
+            ui.add_enabled(
+                ref_name.is_some(),
+                egui::Checkbox::new(
+                    &mut self.sort_by_reference,
+                    "Sort by reference",
+                ),
+            );
+
+            if self.layout_loading {
+                ui.label("Updating layout");
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.link_views, "Link to graph view");
+
+            if self.link_views {
+                ui.label("Shift-drag on a path to select a range");
+            }
+        });
+
+        self.update_layout();
+
+        ui.horizontal(|ui| {
+            ui.label("Color by");
+
+            let mut mode = self.path_view_renderer.mode();
+
+            for &m in PathViewMode::ALL.iter() {
+                ui.selectable_value(&mut mode, m, m.to_string());
+            }
+
+            if mode != self.path_view_renderer.mode() {
+                self.path_view_renderer.set_mode(mode);
+            }
+        });
+
+        {
+            let layout = self.path_view_renderer.layout_1d();
+            let fallback_ref = self.path_picker.active_path().map(|(p, _)| p);
+
+            self.tracks.update(&layout);
+            self.tracks
+                .controls_ui(ui, annotations, &layout, fallback_ref);
+        }
+
         let scroll_align = gui_util::add_scroll_buttons(ui);
 
         ui.horizontal(|ui| {
@@ -146,16 +591,12 @@ impl PathPositionList {
         let sort_order = self.sort_order.load();
         let rev_sort = self.rev_sort.load();
 
-        let (paths_to_show, num_rows) = {
-            let path_ids = match sort_order {
-                SortOrder::PathId => &self.path_view_renderer.path_id_order,
-                SortOrder::Name => &self.path_view_renderer.path_name_order,
-                SortOrder::LengthBp => {
-                    &self.path_view_renderer.path_length_order
-                }
-            };
+        self.grouping_ui(ui, graph);
 
-            (path_ids, graph.path_count())
+        let paths_to_show = match sort_order {
+            SortOrder::PathId => &self.path_view_renderer.path_id_order,
+            SortOrder::Name => &self.path_view_renderer.path_name_order,
+            SortOrder::LengthBp => &self.path_view_renderer.path_length_order,
         };
 
         let paths_to_show: Box<dyn Iterator<Item = _>> = if name.is_empty() {
@@ -186,6 +627,24 @@ impl PathPositionList {
             }
         };
 
+        let ordered = paths_to_show.copied().collect::<Vec<_>>();
+
+        self.export_ui(ui, &ordered);
+
+        let list_rows = self.groups.rows(ordered.into_iter());
+        let num_rows = list_rows.len();
+
+        {
+            let layout = self.path_view_renderer.layout_1d();
+            let (left, right) = self.path_view_renderer.view();
+            let len = layout.total_len as f64;
+            let view = ((left * len) as usize, (right * len) as usize);
+
+            self.summaries.update(&layout, view, &self.groups);
+        }
+
+        let mut toggle_group: Option<usize> = None;
+
         let mut path_range = 0..num_rows;
 
         let row_height = 32.0;
@@ -267,11 +726,81 @@ impl PathPositionList {
 
                         ui.end_row();
 
-                        for (i_ix, &path) in paths_to_show
+                        let ruler_label = if self
+                            .path_view_renderer
+                            .layout_1d()
+                            .reference
+                            .is_some()
+                        {
+                            "Reference bp"
+                        } else {
+                            "Position bp"
+                        };
+
+                        ui.label(ruler_label);
+                        ui.label("");
+                        self.ruler_ui(ui, (left, right));
+                        ui.label("");
+                        ui.end_row();
+
+                        let layout = self.path_view_renderer.layout_1d();
+
+                        let hover_node = if self.link_views {
+                            shared_state.hover_node()
+                        } else {
+                            None
+                        };
+
+                        let hover_range =
+                            hover_node.and_then(|node| layout.node_range(node));
+
+                        for (i_ix, &list_row) in list_rows
+                            .iter()
                             .enumerate()
                             .skip(path_range.start)
                             .take(take_n)
                         {
+                            let path = match list_row {
+                                PathRow::Path(path) => path,
+                                PathRow::GroupHeader(group_ix) => {
+                                    let group = &self.groups.groups[group_ix];
+                                    let marker =
+                                        if group.collapsed { "+" } else { "-" };
+
+                                    let header = ui.selectable_label(
+                                        false,
+                                        format!(
+                                            "{} {} ({})",
+                                            marker,
+                                            group.name,
+                                            group.paths.len()
+                                        ),
+                                    );
+
+                                    if header.clicked() {
+                                        toggle_group = Some(group_ix);
+                                    }
+
+                                    ui.end_row();
+                                    continue;
+                                }
+                                PathRow::GroupSummary(group_ix) => {
+                                    let group = &self.groups.groups[group_ix];
+
+                                    ui.label("  coverage");
+                                    ui.label("");
+                                    self.summaries.ui(
+                                        ui,
+                                        egui::vec2(512.0, 32.0),
+                                        group,
+                                        group_ix,
+                                    );
+                                    ui.label("");
+                                    ui.end_row();
+                                    continue;
+                                }
+                            };
+
                             to_mark.push(path);
 
                             let path_name =
@@ -282,10 +811,9 @@ impl PathPositionList {
 
                             ui.label(format!("{}", path_name.as_bstr()));
 
-                            let ix = self
-                                .path_view_renderer
-                                .find_path_row(path)
-                                .unwrap_or(0);
+                            let row_ix =
+                                self.path_view_renderer.find_path_row(path);
+                            let ix = row_ix.unwrap_or(0);
 
                             let y = oy + (dy * ix as f32);
 
@@ -302,7 +830,9 @@ impl PathPositionList {
                                 |ui| ui.label(left_pos),
                             );
 
-                            let row = if self.path_view_renderer.initialized() {
+                            let row = if self.path_view_renderer.initialized()
+                                && row_ix.is_some()
+                            {
                                 let img = egui::Image::new(
                                     egui::TextureId::User(1),
                                     Point { x: 512.0, y: 32.0 },
@@ -321,13 +851,119 @@ impl PathPositionList {
 
                             ui.end_row();
 
+                            if let Some(range) = hover_range.as_ref() {
+                                if layout.path_at_pos(path, range.start) {
+                                    let len = layout.total_len as f64;
+                                    let to_x = |pos: usize| {
+                                        let t = (pos as f64 / len - left)
+                                            / (right - left);
+                                        row.rect.left()
+                                            + (t as f32) * row.rect.width()
+                                    };
+
+                                    let x0 = to_x(range.start);
+                                    let x1 = to_x(range.end).max(x0 + 2.0);
+
+                                    if x1 >= row.rect.left()
+                                        && x0 <= row.rect.right()
+                                    {
+                                        let marker = egui::Rect::from_min_max(
+                                            egui::pos2(x0, row.rect.top()),
+                                            egui::pos2(x1, row.rect.bottom()),
+                                        )
+                                        .intersect(row.rect);
+
+                                        ui.painter().rect_filled(
+                                            marker,
+                                            0.0,
+                                            egui::Color32::from_rgb(
+                                                230, 120, 20,
+                                            ),
+                                        );
+                                    }
+                                }
+                            }
+
                             let interact = ui.interact(
                                 row.rect,
                                 egui::Id::new(Self::ID).with(i_ix),
                                 egui::Sense::click_and_drag(),
                             );
 
-                            if interact.dragged() {
+                            let pointer_pos = ui.input().pointer.interact_pos();
+
+                            if interact.drag_started()
+                                && self.link_views
+                                && ui.input().modifiers.shift
+                            {
+                                if let Some(pos) = pointer_pos {
+                                    let start = Self::layout_pos(
+                                        &layout,
+                                        (left, right),
+                                        row.rect,
+                                        pos.x,
+                                    );
+                                    self.range_drag.store(Some((path, start)));
+                                }
+                            }
+
+                            let range_drag = self
+                                .range_drag
+                                .load()
+                                .filter(|(drag_path, _)| *drag_path == path);
+
+                            if let (Some((_, start)), Some(pos)) =
+                                (range_drag, pointer_pos)
+                            {
+                                let end = Self::layout_pos(
+                                    &layout,
+                                    (left, right),
+                                    row.rect,
+                                    pos.x,
+                                );
+
+                                if interact.drag_released() {
+                                    self.range_drag.store(None);
+                                    Self::select_layout_range(
+                                        &layout,
+                                        channels,
+                                        nodes,
+                                        path,
+                                        (start, end),
+                                    );
+                                } else if interact.dragged() {
+                                    let len = layout.total_len as f64;
+                                    let t = (start as f64 / len - left)
+                                        / (right - left);
+                                    let x0 = row.rect.left()
+                                        + (t as f32) * row.rect.width();
+
+                                    let x0 = x0.clamp(
+                                        row.rect.left(),
+                                        row.rect.right(),
+                                    );
+                                    let x1 = pos.x.clamp(
+                                        row.rect.left(),
+                                        row.rect.right(),
+                                    );
+
+                                    let drag_rect = egui::Rect::from_min_max(
+                                        egui::pos2(x0.min(x1), row.rect.top()),
+                                        egui::pos2(
+                                            x0.max(x1),
+                                            row.rect.bottom(),
+                                        ),
+                                    );
+
+                                    ui.painter().rect_filled(
+                                        drag_rect,
+                                        0.0,
+                                        egui::Color32::from_rgba_unmultiplied(
+                                            100, 150, 230, 80,
+                                        ),
+                                    );
+                                }
+                            } else if interact.dragged() {
                                 let delta = interact.drag_delta();
 
                                 // the pan() function uses pixels in
@@ -468,10 +1104,41 @@ impl PathPositionList {
 
                             rows.push(interact);
                         }
+
+                        for track in self.tracks.tracks() {
+                            ui.label(&track.name);
+                            ui.label("");
+                            track.ui(
+                                ui,
+                                512.0,
+                                (left, right),
+                                &graph_query,
+                                &channels.app_tx,
+                            );
+                            ui.label("");
+                            ui.end_row();
+                        }
                     })
                 },
             );
 
+        if self.link_views {
+            let layout = self.path_view_renderer.layout_1d();
+            self.highlight_visible_nodes(
+                ui.ctx(),
+                shared_state,
+                &layout,
+                nodes,
+            );
+        }
+
+        if let Some(group_ix) = toggle_group {
+            self.groups.toggle_collapsed(group_ix);
+        }
+
+        // rows that were scrolled into view need to be loaded
+        self.path_view_renderer.mark_visible_paths(&to_mark);
+
         if self.mark_paths.load() {
             self.path_view_renderer.mark_load_paths(to_mark).unwrap();
             self.mark_paths.store(false);
@@ -486,6 +1153,7 @@ impl PathPositionList {
         reactor: &Reactor,
         channels: &AppChannels,
         shared_state: &SharedState,
+        annotations: &Annotations,
         nodes: &[Node],
     ) {
         let _inner_resp = egui::Window::new("Path View")
@@ -498,6 +1166,7 @@ impl PathPositionList {
                     reactor,
                     channels,
                     shared_state,
+                    annotations,
                     nodes,
                 );
             });
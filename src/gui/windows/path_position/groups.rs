@@ -0,0 +1,343 @@
+use std::sync::Arc;
+
+use bstr::ByteSlice;
+use handlegraph::{
+    packedgraph::PackedGraph,
+    pathhandlegraph::{GraphPathNames, IntoPathIds, PathId},
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    reactor::{Host, Outbox, Reactor},
+    vulkan::compute::path_view::Path1DLayout,
+};
+
+/// How the rows of the path view are grouped
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathGrouping {
+    None,
+    /// By the sample in PanSN path names, `sample#haplotype#contig`
+    Sample,
+    /// By the part of the path name before the delimiter
+    Prefix(String),
+}
+
+impl PathGrouping {
+    fn group_key<'a>(&self, name: &'a [u8]) -> Option<&'a [u8]> {
+        match self {
+            PathGrouping::None => None,
+            PathGrouping::Sample => name.find_byte(b'#').map(|ix| &name[..ix]),
+            PathGrouping::Prefix(delim) => {
+                if delim.is_empty() {
+                    None
+                } else {
+                    name.find(delim.as_bytes()).map(|ix| &name[..ix])
+                }
+            }
+        }
+    }
+}
+
+/// A row in the path view list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathRow {
+    GroupHeader(usize),
+    GroupSummary(usize),
+    Path(PathId),
+}
+
+#[derive(Debug, Clone)]
+pub struct PathGroup {
+    pub name: String,
+    pub paths: Vec<PathId>,
+    pub collapsed: bool,
+}
+
+/// The groups of paths for a grouping; paths that don't fit any
+/// group are listed on their own after the groups
+#[derive(Debug, Clone)]
+pub struct PathGroups {
+    pub grouping: PathGrouping,
+    pub groups: Vec<PathGroup>,
+    group_of: FxHashMap<PathId, usize>,
+}
+
+impl PathGroups {
+    pub fn new(graph: &PackedGraph, grouping: PathGrouping) -> Self {
+        let mut groups: Vec<PathGroup> = Vec::new();
+        let mut group_of = FxHashMap::default();
+
+        let mut group_ixs: FxHashMap<Vec<u8>, usize> = FxHashMap::default();
+
+        let mut path_ids = graph.path_ids().collect::<Vec<_>>();
+        path_ids.sort();
+
+        for path in path_ids {
+            let name = if let Some(name) = graph.get_path_name_vec(path) {
+                name
+            } else {
+                continue;
+            };
+
+            if let Some(key) = grouping.group_key(&name) {
+                let ix = *group_ixs.entry(key.to_vec()).or_insert_with(|| {
+                    groups.push(PathGroup {
+                        name: format!("{}", key.as_bstr()),
+                        paths: Vec::new(),
+                        collapsed: true,
+                    });
+                    groups.len() - 1
+                });
+
+                groups[ix].paths.push(path);
+                group_of.insert(path, ix);
+            }
+        }
+
+        Self {
+            grouping,
+            groups,
+            group_of,
+        }
+    }
+
+    pub fn toggle_collapsed(&mut self, group_ix: usize) {
+        if let Some(group) = self.groups.get_mut(group_ix) {
+            group.collapsed = !group.collapsed;
+        }
+    }
+
+    /// Builds the list rows from the paths to show, in order. Groups
+    /// are ordered by their first path in `paths`, and each group has
+    /// a header and a summary row, followed by its paths if the group
+    /// is expanded
+    pub fn rows(&self, paths: impl Iterator<Item = PathId>) -> Vec<PathRow> {
+        if self.groups.is_empty() {
+            return paths.map(PathRow::Path).collect();
+        }
+
+        let mut group_order: Vec<usize> = Vec::new();
+        let mut members: Vec<Vec<PathId>> = vec![Vec::new(); self.groups.len()];
+        let mut ungrouped: Vec<PathId> = Vec::new();
+
+        for path in paths {
+            if let Some(&ix) = self.group_of.get(&path) {
+                if members[ix].is_empty() {
+                    group_order.push(ix);
+                }
+                members[ix].push(path);
+            } else {
+                ungrouped.push(path);
+            }
+        }
+
+        let mut rows = Vec::new();
+
+        for ix in group_order {
+            rows.push(PathRow::GroupHeader(ix));
+            rows.push(PathRow::GroupSummary(ix));
+
+            if !self.groups[ix].collapsed {
+                rows.extend(members[ix].iter().copied().map(PathRow::Path));
+            }
+        }
+
+        rows.extend(ungrouped.into_iter().map(PathRow::Path));
+
+        rows
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SummaryKey {
+    layout: usize,
+    view: (usize, usize),
+    grouping: PathGrouping,
+}
+
+struct SummaryInput {
+    layout: Arc<Path1DLayout>,
+    view: (usize, usize),
+    groups: Vec<Vec<PathId>>,
+}
+
+/// Per-group path coverage across the visible part of the 1D layout,
+/// computed in the background
+pub struct GroupSummaries {
+    host: Host<SummaryInput, Vec<Vec<f32>>>,
+
+    requested: Option<SummaryKey>,
+    running: bool,
+
+    coverage: Vec<Vec<f32>>,
+}
+
+impl GroupSummaries {
+    pub const BINS: usize = 256;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let rayon_pool = reactor.rayon_pool.clone();
+
+        let host = reactor.create_host(
+            move |_outbox: &Outbox<_>, input: SummaryInput| {
+                use rayon::prelude::*;
+
+                let SummaryInput {
+                    layout,
+                    view,
+                    groups,
+                } = input;
+
+                rayon_pool.install(|| {
+                    groups
+                        .par_iter()
+                        .map(|paths| Self::group_coverage(&layout, view, paths))
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+
+        Self {
+            host,
+
+            requested: None,
+            running: false,
+
+            coverage: Vec::new(),
+        }
+    }
+
+    /// The fraction of the group's paths that cover each bin of the
+    /// layout range
+    fn group_coverage(
+        layout: &Path1DLayout,
+        view: (usize, usize),
+        paths: &[PathId],
+    ) -> Vec<f32> {
+        let (start, end) = view;
+        let len = end.saturating_sub(start) as f64;
+
+        let mut coverage = vec![0.0; Self::BINS];
+
+        if paths.is_empty() {
+            return coverage;
+        }
+
+        for (bin, value) in coverage.iter_mut().enumerate() {
+            let t = (bin as f64 + 0.5) / Self::BINS as f64;
+            let pos = start + (t * len) as usize;
+
+            let count = paths
+                .iter()
+                .filter(|&&path| layout.path_at_pos(path, pos))
+                .count();
+
+            *value = count as f32 / paths.len() as f32;
+        }
+
+        coverage
+    }
+
+    pub fn coverage(&self, group_ix: usize) -> Option<&[f32]> {
+        self.coverage.get(group_ix).map(|c| c.as_slice())
+    }
+
+    pub fn update(
+        &mut self,
+        layout: &Arc<Path1DLayout>,
+        view: (usize, usize),
+        groups: &PathGroups,
+    ) {
+        if let Some(coverage) = self.host.take() {
+            self.coverage = coverage;
+            self.running = false;
+        }
+
+        if self.running || groups.groups.is_empty() {
+            return;
+        }
+
+        let key = SummaryKey {
+            layout: Arc::as_ptr(layout) as usize,
+            view,
+            grouping: groups.grouping.clone(),
+        };
+
+        if self.requested.as_ref() == Some(&key) {
+            return;
+        }
+
+        let input = SummaryInput {
+            layout: layout.clone(),
+            view,
+            groups: groups.groups.iter().map(|g| g.paths.clone()).collect(),
+        };
+
+        if self.host.call(input).is_ok() {
+            self.requested = Some(key);
+            self.running = true;
+        }
+    }
+
+    /// Draws the group's coverage as a bar for each bin
+    pub fn ui(
+        &self,
+        ui: &mut egui::Ui,
+        size: egui::Vec2,
+        group: &PathGroup,
+        group_ix: usize,
+    ) -> egui::Response {
+        let (rect, response) =
+            ui.allocate_exact_size(size, egui::Sense::hover());
+
+        let coverage = if let Some(coverage) = self.coverage(group_ix) {
+            coverage
+        } else {
+            return response;
+        };
+
+        let painter = ui.painter_at(rect);
+
+        let color = egui::Color32::from_rgb(70, 130, 180);
+        let bin_width = rect.width() / coverage.len() as f32;
+
+        for (bin, &value) in coverage.iter().enumerate() {
+            if value <= 0.0 {
+                continue;
+            }
+
+            let x0 = rect.left() + bin as f32 * bin_width;
+            let y0 = rect.bottom() - value * rect.height();
+
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(x0, y0),
+                egui::pos2(x0 + bin_width, rect.bottom()),
+            );
+
+            painter.rect_filled(bar, 0.0, color);
+        }
+
+        if let Some(pos) = response.hover_pos() {
+            let t = (pos.x - rect.left()) / rect.width();
+            let bin =
+                ((t * coverage.len() as f32) as usize).min(coverage.len() - 1);
+
+            let value = coverage[bin];
+
+            egui::show_tooltip_at_pointer(
+                ui.ctx(),
+                egui::Id::new("path_view_group_summary_tooltip"),
+                |ui| {
+                    ui.label(format!(
+                        "{}: {:.1}% of {} paths",
+                        group.name,
+                        value * 100.0,
+                        group.paths.len()
+                    ));
+                },
+            );
+        }
+
+        response
+    }
+}
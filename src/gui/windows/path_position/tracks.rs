@@ -0,0 +1,632 @@
+use std::sync::Arc;
+
+use bstr::ByteSlice;
+use crossbeam::channel::Sender;
+use handlegraph::{
+    handle::NodeId,
+    pathhandlegraph::{GraphPathNames, PathId},
+};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    annotations::{
+        self, AnnotationCollection, AnnotationFileType, AnnotationRecord,
+        Annotations, BedRecords, ColumnKey, Gff3Records,
+    },
+    app::{AppMsg, Select},
+    graph_query::GraphQuery,
+    reactor::{Host, Outbox, Reactor},
+    vulkan::compute::path_view::Path1DLayout,
+};
+
+const MAX_LANES: usize = 6;
+const LANE_HEIGHT: f32 = 8.0;
+const MAX_TOOLTIP_COLUMNS: usize = 12;
+
+#[derive(Clone)]
+pub enum TrackRecords {
+    Gff3(Arc<Gff3Records>),
+    Bed(Arc<BedRecords>),
+}
+
+impl TrackRecords {
+    fn from_annotations(
+        annotations: &Annotations,
+        name: &str,
+        file_type: AnnotationFileType,
+    ) -> Option<Self> {
+        match file_type {
+            AnnotationFileType::Gff3 => {
+                annotations.get_gff3(name).cloned().map(TrackRecords::Gff3)
+            }
+            AnnotationFileType::Bed => {
+                annotations.get_bed(name).cloned().map(TrackRecords::Bed)
+            }
+        }
+    }
+
+    fn build_features(
+        &self,
+        graph: &GraphQuery,
+        layout: &Path1DLayout,
+        ref_path: PathId,
+    ) -> Option<Vec<TrackFeature>> {
+        match self {
+            TrackRecords::Gff3(records) => {
+                build_features(graph, layout, records.as_ref(), ref_path)
+            }
+            TrackRecords::Bed(records) => {
+                build_features(graph, layout, records.as_ref(), ref_path)
+            }
+        }
+    }
+
+    fn record_range(&self, record_ix: usize) -> Option<(usize, usize)> {
+        match self {
+            TrackRecords::Gff3(records) => {
+                records.records().get(record_ix).map(|r| r.range())
+            }
+            TrackRecords::Bed(records) => {
+                records.records().get(record_ix).map(|r| r.range())
+            }
+        }
+    }
+
+    fn tooltip_lines(&self, record_ix: usize) -> Vec<String> {
+        match self {
+            TrackRecords::Gff3(records) => {
+                record_tooltip(records.as_ref(), record_ix)
+            }
+            TrackRecords::Bed(records) => {
+                record_tooltip(records.as_ref(), record_ix)
+            }
+        }
+    }
+}
+
+/// One record's footprint in the 1D layout; `span` covers all of the
+/// record's nodes, and `blocks` only the exonic ones
+#[derive(Debug, Clone)]
+pub struct TrackFeature {
+    pub record_ix: usize,
+    pub span: (usize, usize),
+    pub blocks: Vec<(usize, usize)>,
+    lane: usize,
+}
+
+/// An annotation collection drawn as a track under the path rows,
+/// in the coordinates of the 1D layout it was built for
+pub struct AnnotationTrack {
+    pub name: String,
+    pub ref_path: PathId,
+
+    records: TrackRecords,
+    layout: Arc<Path1DLayout>,
+
+    features: Vec<TrackFeature>,
+    lane_count: usize,
+}
+
+fn build_features<C>(
+    graph: &GraphQuery,
+    layout: &Path1DLayout,
+    records: &C,
+    ref_path: PathId,
+) -> Option<Vec<TrackFeature>>
+where
+    C: AnnotationCollection,
+{
+    let steps = graph.path_pos_steps(ref_path)?;
+    let path_name = graph.graph().get_path_name_vec(ref_path)?;
+    let offset = annotations::path_name_offset(&path_name);
+
+    let node_ranges = layout.node_ranges();
+
+    let node_range = |id: NodeId| {
+        let range = node_ranges.get((id.0 - 1) as usize)?;
+        (!range.is_empty()).then(|| (range.start, range.end))
+    };
+
+    let mut features = Vec::new();
+
+    for (record_ix, record) in records.records().iter().enumerate() {
+        if record.end() < record.start() {
+            continue;
+        }
+
+        let range = annotations::path_step_range(
+            &steps,
+            offset,
+            record.start(),
+            record.end(),
+        );
+
+        let mut span_intervals = range
+            .into_iter()
+            .flatten()
+            .filter_map(|(h, _, _)| node_range(h.id()))
+            .collect::<Vec<_>>();
+
+        if span_intervals.is_empty() {
+            continue;
+        }
+
+        annotations::merge_intervals(&mut span_intervals);
+
+        let span = (
+            span_intervals.first().map(|(s, _)| *s).unwrap_or(0),
+            span_intervals.iter().map(|(_, e)| *e).max().unwrap_or(0),
+        );
+
+        let mut blocks =
+            annotations::record_exon_steps(records, record_ix, &steps, offset)
+                .into_iter()
+                .flatten()
+                .filter_map(|h| node_range(h.id()))
+                .collect::<Vec<_>>();
+
+        annotations::merge_intervals(&mut blocks);
+
+        features.push(TrackFeature {
+            record_ix,
+            span,
+            blocks,
+            lane: 0,
+        });
+    }
+
+    Some(features)
+}
+
+fn record_tooltip<C>(records: &C, record_ix: usize) -> Vec<String>
+where
+    C: AnnotationCollection,
+{
+    let mut lines = Vec::new();
+
+    let record = if let Some(record) = records.records().get(record_ix) {
+        record
+    } else {
+        return lines;
+    };
+
+    lines.push(format!(
+        "{}:{}-{}",
+        record.seq_id().as_bstr(),
+        record.start(),
+        record.end()
+    ));
+
+    let skip = [
+        C::ColumnKey::seq_id(),
+        C::ColumnKey::start(),
+        C::ColumnKey::end(),
+    ];
+
+    for column in record
+        .columns()
+        .into_iter()
+        .filter(|c| !skip.contains(c))
+        .take(MAX_TOOLTIP_COLUMNS)
+    {
+        let values = record.get_all(&column);
+
+        if values.is_empty() {
+            continue;
+        }
+
+        let values = values
+            .into_iter()
+            .map(|v| format!("{}", v.as_bstr()))
+            .collect::<Vec<_>>();
+
+        lines.push(format!("{}: {}", column, values.join(", ")));
+    }
+
+    lines
+}
+
+impl AnnotationTrack {
+    /// Assigns the features to lanes so that overlapping features are
+    /// drawn on separate lanes; past `MAX_LANES`, the remaining
+    /// features share the last lane
+    fn assign_lanes(&mut self) {
+        self.features.sort_by_key(|f| f.span);
+
+        let mut lane_ends: Vec<usize> = Vec::new();
+
+        for feature in self.features.iter_mut() {
+            let free_lane =
+                lane_ends.iter().position(|&end| end <= feature.span.0);
+
+            let lane = match free_lane {
+                Some(lane) => lane,
+                None if lane_ends.len() < MAX_LANES => {
+                    lane_ends.push(0);
+                    lane_ends.len() - 1
+                }
+                None => MAX_LANES - 1,
+            };
+
+            lane_ends[lane] = lane_ends[lane].max(feature.span.1);
+            feature.lane = lane;
+        }
+
+        self.lane_count = lane_ends.len().max(1);
+    }
+
+    pub fn height(&self) -> f32 {
+        self.lane_count as f32 * LANE_HEIGHT
+    }
+
+    fn is_current(&self, layout: &Arc<Path1DLayout>) -> bool {
+        Arc::ptr_eq(&self.layout, layout)
+    }
+
+    fn feature_at(&self, pos: usize, lane: usize) -> Option<&TrackFeature> {
+        self.features.iter().find(|f| {
+            f.lane == lane
+                && pos >= f.span.0
+                && pos < f.span.1.max(f.span.0 + 1)
+        })
+    }
+
+    fn select_record(
+        &self,
+        graph_query: &GraphQuery,
+        app_msg_tx: &Sender<AppMsg>,
+        record_ix: usize,
+    ) {
+        let (start, end) =
+            if let Some(range) = self.records.record_range(record_ix) {
+                range
+            } else {
+                return;
+            };
+
+        let offset = graph_query
+            .graph()
+            .get_path_name_vec(self.ref_path)
+            .and_then(|name| annotations::path_name_offset(&name))
+            .unwrap_or(0);
+
+        let start = start.saturating_sub(offset);
+        let end = end.saturating_sub(offset).max(start);
+
+        if let Some(range) =
+            graph_query.path_basepair_range(self.ref_path, start, end)
+        {
+            let nodes = range
+                .into_iter()
+                .map(|(handle, _, _)| handle.id())
+                .collect::<FxHashSet<_>>();
+
+            let select = Select::Many { nodes, clear: true };
+            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+        }
+    }
+
+    /// Draws the track for the visible part of the layout, given as
+    /// fractions of the layout length
+    pub fn ui(
+        &self,
+        ui: &mut egui::Ui,
+        width: f32,
+        view: (f64, f64),
+        graph_query: &GraphQuery,
+        app_msg_tx: &Sender<AppMsg>,
+    ) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(width, self.height()),
+            egui::Sense::click(),
+        );
+
+        let len = self.layout.total_len as f64;
+        let (left, right) = view;
+        let view_start = left * len;
+        let view_len = ((right - left) * len).max(1.0);
+
+        let to_x = |pos: usize| -> f32 {
+            let t = (pos as f64 - view_start) / view_len;
+            rect.left() + (t as f32).clamp(0.0, 1.0) * rect.width()
+        };
+
+        let lane_rect = |lane: usize, x0: f32, x1: f32, thick: bool| {
+            let y0 = rect.top() + lane as f32 * LANE_HEIGHT;
+            let (y0, y1) = if thick {
+                (y0 + 1.0, y0 + LANE_HEIGHT - 1.0)
+            } else {
+                let mid = y0 + LANE_HEIGHT / 2.0;
+                (mid - 0.5, mid + 0.5)
+            };
+
+            egui::Rect::from_min_max(
+                egui::pos2(x0, y0),
+                egui::pos2(x1.max(x0 + 1.0), y1),
+            )
+        };
+
+        let painter = ui.painter_at(rect);
+
+        let color = ui.visuals().text_color();
+        let block_color = egui::Color32::from_rgb(60, 110, 190);
+
+        let view_end = (view_start + view_len) as usize;
+        let view_start_pos = view_start as usize;
+
+        for feature in self.features.iter() {
+            let (start, end) = feature.span;
+
+            if end < view_start_pos || start > view_end {
+                continue;
+            }
+
+            let span_rect =
+                lane_rect(feature.lane, to_x(start), to_x(end), false);
+            painter.rect_filled(span_rect, 0.0, color);
+
+            for &(s, e) in feature.blocks.iter() {
+                if e < view_start_pos || s > view_end {
+                    continue;
+                }
+
+                let block_rect =
+                    lane_rect(feature.lane, to_x(s), to_x(e), true);
+                painter.rect_filled(block_rect, 0.0, block_color);
+            }
+        }
+
+        let hovered = response.hover_pos().and_then(|pos| {
+            let t = ((pos.x - rect.left()) / rect.width()) as f64;
+            let layout_pos = (view_start + t * view_len) as usize;
+            let lane = ((pos.y - rect.top()) / LANE_HEIGHT) as usize;
+
+            self.feature_at(layout_pos, lane)
+        });
+
+        if let Some(feature) = hovered {
+            let lines = self.records.tooltip_lines(feature.record_ix);
+
+            egui::show_tooltip_at_pointer(
+                ui.ctx(),
+                egui::Id::new("path_view_track_tooltip"),
+                |ui| {
+                    for line in lines {
+                        ui.label(line);
+                    }
+                },
+            );
+
+            if response.clicked() {
+                self.select_record(graph_query, app_msg_tx, feature.record_ix);
+            }
+
+            if response.double_clicked() {
+                app_msg_tx.send(AppMsg::goto_selection()).unwrap();
+            }
+        }
+
+        response
+    }
+}
+
+struct TrackInput {
+    name: String,
+    records: TrackRecords,
+    ref_path: PathId,
+    layout: Arc<Path1DLayout>,
+}
+
+/// The built track, or the name and layout of the track that
+/// couldn't be built
+type TrackOutput = Result<AnnotationTrack, (String, Arc<Path1DLayout>)>;
+
+/// The annotation tracks shown in the path view, and the controls
+/// for adding and removing them
+pub struct AnnotationTracks {
+    tracks: Vec<AnnotationTrack>,
+
+    track_host: Host<TrackInput, TrackOutput>,
+    pending: usize,
+    // tracks that couldn't be built for a layout, which aren't
+    // requested again until the layout changes
+    failed: Vec<(String, Arc<Path1DLayout>)>,
+
+    selected_annotation: usize,
+}
+
+impl AnnotationTracks {
+    pub fn new(reactor: &Reactor) -> Self {
+        let graph = reactor.graph_query.clone();
+
+        let track_host = reactor.create_host(
+            move |_outbox: &Outbox<_>, input: TrackInput| {
+                let features = match input.records.build_features(
+                    &graph,
+                    &input.layout,
+                    input.ref_path,
+                ) {
+                    Some(features) => features,
+                    None => return Err((input.name, input.layout)),
+                };
+
+                let mut track = AnnotationTrack {
+                    name: input.name,
+                    ref_path: input.ref_path,
+                    records: input.records,
+                    layout: input.layout,
+                    features,
+                    lane_count: 1,
+                };
+
+                track.assign_lanes();
+
+                Ok(track)
+            },
+        );
+
+        Self {
+            tracks: Vec::new(),
+
+            track_host,
+            pending: 0,
+            failed: Vec::new(),
+
+            selected_annotation: 0,
+        }
+    }
+
+    pub fn tracks(&self) -> &[AnnotationTrack] {
+        &self.tracks
+    }
+
+    fn request_track(
+        &mut self,
+        name: String,
+        records: TrackRecords,
+        ref_path: PathId,
+        layout: Arc<Path1DLayout>,
+    ) {
+        let input = TrackInput {
+            name,
+            records,
+            ref_path,
+            layout,
+        };
+
+        if self.track_host.call(input).is_ok() {
+            self.pending += 1;
+        }
+    }
+
+    /// Receives finished tracks, and rebuilds any tracks that were
+    /// built for a different layout than the current one, unless
+    /// they already failed to build for it
+    pub fn update(&mut self, layout: &Arc<Path1DLayout>) {
+        while let Some(result) = self.track_host.take() {
+            self.pending = self.pending.saturating_sub(1);
+
+            match result {
+                Ok(track) => {
+                    self.failed.retain(|(name, _)| name != &track.name);
+
+                    if let Some(existing) =
+                        self.tracks.iter_mut().find(|t| t.name == track.name)
+                    {
+                        *existing = track;
+                    } else {
+                        self.tracks.push(track);
+                    }
+                }
+                Err((name, failed_layout)) => {
+                    log::warn!("Could not build annotation track {}", name);
+                    self.failed.push((name, failed_layout));
+                }
+            }
+        }
+
+        if self.pending > 0 {
+            return;
+        }
+
+        self.failed.retain(|(_, l)| Arc::ptr_eq(l, layout));
+
+        let failed = &self.failed;
+
+        let outdated = self
+            .tracks
+            .iter()
+            .filter(|t| !t.is_current(layout))
+            .filter(|t| !failed.iter().any(|(name, _)| name == &t.name))
+            .map(|t| (t.name.clone(), t.records.clone(), t.ref_path))
+            .collect::<Vec<_>>();
+
+        for (name, records, ref_path) in outdated {
+            self.request_track(name, records, ref_path, layout.clone());
+        }
+    }
+
+    /// Controls for adding and removing tracks; `fallback_ref` is used
+    /// as the reference path for annotations without a default path
+    pub fn controls_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        annotations: &Annotations,
+        layout: &Arc<Path1DLayout>,
+        fallback_ref: Option<PathId>,
+    ) {
+        let names = annotations.annot_names();
+
+        ui.horizontal(|ui| {
+            ui.label("Tracks");
+
+            if names.is_empty() {
+                ui.label("No annotations loaded");
+                return;
+            }
+
+            self.selected_annotation =
+                self.selected_annotation.min(names.len() - 1);
+
+            egui::ComboBox::from_id_source("path_view_track_annotation")
+                .show_index(
+                    ui,
+                    &mut self.selected_annotation,
+                    names.len(),
+                    |ix| names[ix].0.clone(),
+                );
+
+            let (name, file_type) = &names[self.selected_annotation];
+
+            let ref_path =
+                annotations.get_default_ref_path(name).or(fallback_ref);
+
+            let already_added = self.tracks.iter().any(|t| &t.name == name);
+
+            let add_btn = ui.add_enabled(
+                ref_path.is_some() && !already_added,
+                egui::Button::new("Add track"),
+            );
+
+            if add_btn.clicked() {
+                let records = TrackRecords::from_annotations(
+                    annotations,
+                    name,
+                    *file_type,
+                );
+
+                if let (Some(records), Some(ref_path)) = (records, ref_path) {
+                    self.request_track(
+                        name.to_string(),
+                        records,
+                        ref_path,
+                        layout.clone(),
+                    );
+                }
+            }
+
+            if ref_path.is_none() {
+                ui.label("Select a reference path");
+            }
+
+            if self.pending > 0 {
+                ui.label("Building track");
+            }
+        });
+
+        let mut to_remove = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (ix, track) in self.tracks.iter().enumerate() {
+                if ui.small_button(format!("{} x", track.name)).clicked() {
+                    to_remove = Some(ix);
+                }
+            }
+        });
+
+        if let Some(ix) = to_remove {
+            let track = self.tracks.remove(ix);
+            self.failed.retain(|(name, _)| name != &track.name);
+        }
+    }
+}
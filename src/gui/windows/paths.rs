@@ -28,8 +28,9 @@ use crate::gui::util as gui_util;
 
 use crate::graph_query::GraphQuery;
 use crate::{
-    app::{AppMsg, Select},
+    app::{AppMsg, Select, SharedState},
     geometry::*,
+    vulkan::draw_system::paths::path_line_color,
 };
 
 pub struct PathList {
@@ -37,6 +38,8 @@ pub struct PathList {
 
     path_details_id: Arc<AtomicCell<Option<PathId>>>,
 
+    shared_state: SharedState,
+
     col_widths: ColumnWidths<3>,
 }
 
@@ -217,12 +220,29 @@ impl PathList {
         egui::Window::new("Paths")
             .id(egui::Id::new(Self::ID))
             .show(ctx, |ui| {
-                if ui
-                    .selectable_label(*open_path_details, "Path Details")
-                    .clicked()
-                {
-                    *open_path_details = !*open_path_details;
-                }
+                let shared_state = &self.shared_state;
+
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(*open_path_details, "Path Details")
+                        .clicked()
+                    {
+                        *open_path_details = !*open_path_details;
+                    }
+
+                    let highlighted =
+                        shared_state.highlighted_paths.lock().len();
+
+                    if ui
+                        .add_enabled(
+                            highlighted > 0,
+                            egui::Button::new("Clear path lines"),
+                        )
+                        .clicked()
+                    {
+                        shared_state.highlighted_paths.lock().clear();
+                    }
+                });
 
                 let scroll_align = gui_util::add_scroll_buttons(ui);
 
@@ -234,8 +254,14 @@ impl PathList {
 
                 let [w0, w1, w2] = self.col_widths.get();
 
+                // the checkbox column toggles drawing the path as a
+                // line in the graph view
+                let toggle_size = egui::vec2(24.0, row_height);
+
                 let header =
                     egui::Grid::new("path_list_grid_header").show(ui, |ui| {
+                        ui.add_sized(toggle_size, egui::Label::new(""));
+
                         let inner = grid_row_label(
                             ui,
                             egui::Id::new("path_list_grid_header__"),
@@ -294,6 +320,44 @@ impl PathList {
                                     let fields: [&str; 3] =
                                         [&path_name, &step_count, &base_count];
 
+                                    let mut shown = shared_state
+                                        .is_path_highlighted(path_id);
+
+                                    let color = path_line_color(path_id);
+                                    let color = egui::Color32::from_rgb(
+                                        color.r, color.g, color.b,
+                                    );
+
+                                    let toggle = ui
+                                        .scope(|ui| {
+                                            if shown {
+                                                // tint the box with the
+                                                // line color
+                                                let widgets = &mut ui
+                                                    .visuals_mut()
+                                                    .widgets;
+                                                widgets.inactive.bg_fill =
+                                                    color;
+                                                widgets.hovered.bg_fill = color;
+                                            }
+
+                                            ui.add_sized(
+                                                toggle_size,
+                                                egui::Checkbox::new(
+                                                    &mut shown, "",
+                                                ),
+                                            )
+                                        })
+                                        .inner
+                                        .on_hover_text(
+                                            "Draw the path in the graph view",
+                                        );
+
+                                    if toggle.changed() {
+                                        shared_state
+                                            .toggle_path_highlight(path_id);
+                                    }
+
                                     let inner = grid_row_label(
                                         ui,
                                         egui::Id::new(ui.id().with(ix)),
@@ -335,6 +399,7 @@ impl PathList {
     pub fn new(
         graph_query: &GraphQuery,
         path_details_id: Arc<AtomicCell<Option<PathId>>>,
+        shared_state: &SharedState,
     ) -> Self {
         let graph = graph_query.graph();
 
@@ -346,6 +411,8 @@ impl PathList {
 
             path_details_id,
 
+            shared_state: shared_state.clone(),
+
             col_widths: Default::default(),
         }
     }
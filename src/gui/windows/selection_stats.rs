@@ -0,0 +1,247 @@
+use bstr::ByteSlice;
+use crossbeam::channel::{Receiver, Sender};
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    pathhandlegraph::*,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    app::{App, AppMsg},
+    asynchronous::AsyncResult,
+    graph_query::GraphQuery,
+    universe::Node,
+};
+
+/// The range of a path that passes through a selection
+#[derive(Debug, Clone)]
+pub struct PathTraversal {
+    pub path: PathId,
+    pub name: String,
+    pub steps: usize,
+    /// The path position of the start of the first step in the
+    /// selection
+    pub entry: usize,
+    /// The path position of the end of the last step in the selection
+    pub exit: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SelectionStats {
+    pub node_count: usize,
+    pub total_bp: usize,
+
+    pub internal_edges: usize,
+    pub boundary_edges: usize,
+
+    pub gc_count: usize,
+
+    pub paths: Vec<PathTraversal>,
+}
+
+impl SelectionStats {
+    pub fn compute(graph: &GraphQuery, nodes: &FxHashSet<NodeId>) -> Self {
+        let packed = graph.graph();
+
+        let mut stats = Self {
+            node_count: nodes.len(),
+            ..Self::default()
+        };
+
+        // edges are stored in the canonical orientation, so that an
+        // edge seen from both of its nodes is only counted once
+        let canonical = |l: Handle, r: Handle| {
+            let flipped = (r.flip(), l.flip());
+            if (l, r) <= flipped {
+                (l, r)
+            } else {
+                flipped
+            }
+        };
+
+        let mut edges: FxHashSet<(Handle, Handle)> = FxHashSet::default();
+
+        let mut traversals: FxHashMap<PathId, (usize, usize, usize)> =
+            FxHashMap::default();
+
+        for &node in nodes.iter() {
+            let handle = Handle::pack(node, false);
+
+            let len = packed.node_len(handle);
+            stats.total_bp += len;
+
+            stats.gc_count += packed
+                .sequence_vec(handle)
+                .iter()
+                .filter(|&&b| matches!(b, b'G' | b'C' | b'g' | b'c'))
+                .count();
+
+            for other in packed.neighbors(handle, Direction::Right) {
+                edges.insert(canonical(handle, other));
+            }
+
+            for other in packed.neighbors(handle, Direction::Left) {
+                edges.insert(canonical(other, handle));
+            }
+
+            if let Some(positions) = graph.handle_positions_iter(handle) {
+                for (path, _step, pos) in positions {
+                    let entry =
+                        traversals.entry(path).or_insert((0, usize::MAX, 0));
+
+                    entry.0 += 1;
+                    entry.1 = entry.1.min(pos);
+                    entry.2 = entry.2.max(pos + len);
+                }
+            }
+        }
+
+        for (l, r) in edges {
+            if nodes.contains(&l.id()) && nodes.contains(&r.id()) {
+                stats.internal_edges += 1;
+            } else {
+                stats.boundary_edges += 1;
+            }
+        }
+
+        stats.paths = traversals
+            .into_iter()
+            .map(|(path, (steps, entry, exit))| {
+                let name = packed
+                    .get_path_name_vec(path)
+                    .map(|name| name.to_str_lossy().to_string())
+                    .unwrap_or_default();
+
+                PathTraversal {
+                    path,
+                    name,
+                    steps,
+                    entry,
+                    exit,
+                }
+            })
+            .collect();
+
+        stats.paths.sort_by(|a, b| a.name.cmp(&b.name));
+
+        stats
+    }
+
+    pub fn gc_content(&self) -> Option<f32> {
+        if self.total_bp == 0 {
+            None
+        } else {
+            Some(self.gc_count as f32 / self.total_bp as f32)
+        }
+    }
+}
+
+/// Shows the `SelectionStats` of the current selection; the stats are
+/// computed outside the GUI and sent to the window whenever the
+/// selection changes
+pub struct SelectionStatsWindow {
+    stats_tx: Sender<AsyncResult<SelectionStats>>,
+    stats_rx: Receiver<AsyncResult<SelectionStats>>,
+
+    pending: Option<AsyncResult<SelectionStats>>,
+    stats: Option<SelectionStats>,
+}
+
+impl SelectionStatsWindow {
+    pub const ID: &'static str = "selection_stats_window";
+
+    pub fn new() -> Self {
+        let (stats_tx, stats_rx) = crossbeam::channel::unbounded();
+
+        Self {
+            stats_tx,
+            stats_rx,
+
+            pending: None,
+            stats: None,
+        }
+    }
+
+    pub fn stats_tx(&self) -> &Sender<AsyncResult<SelectionStats>> {
+        &self.stats_tx
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        // only the latest selection matters
+        while let Ok(pending) = self.stats_rx.try_recv() {
+            self.pending = Some(pending);
+        }
+
+        if let Some(pending) = self.pending.as_mut() {
+            if let Some(stats) = pending.take_result_if_ready() {
+                self.stats = Some(stats);
+                self.pending = None;
+            }
+        }
+
+        if self.pending.is_some() {
+            ui.label("Computing...");
+        }
+
+        let stats = match &self.stats {
+            Some(stats) if stats.node_count > 0 => stats,
+            _ => {
+                ui.label("No nodes selected");
+                return;
+            }
+        };
+
+        let gc = stats
+            .gc_content()
+            .map(|gc| format!("{:.2}%", gc * 100.0))
+            .unwrap_or_default();
+
+        let rows = [
+            ("Nodes", stats.node_count.to_string()),
+            ("Total length", format!("{} bp", stats.total_bp)),
+            ("GC content", gc),
+            ("Internal edges", stats.internal_edges.to_string()),
+            ("Boundary edges", stats.boundary_edges.to_string()),
+        ];
+
+        egui::Grid::new("selection_stats_grid")
+            .striped(true)
+            .show(ui, |ui| {
+                for (label, value) in rows.iter() {
+                    ui.label(*label);
+                    ui.label(value);
+                    ui.end_row();
+                }
+            });
+
+        ui.separator();
+
+        ui.label(format!("{} paths", stats.paths.len()));
+
+        egui::ScrollArea::from_max_height(300.0).show(ui, |ui| {
+            egui::Grid::new("selection_stats_paths").striped(true).show(
+                ui,
+                |ui| {
+                    ui.label("Path");
+                    ui.label("Steps");
+                    ui.label("Entry");
+                    ui.label("Exit");
+                    ui.end_row();
+
+                    for traversal in stats.paths.iter() {
+                        ui.label(&traversal.name);
+                        ui.label(traversal.steps.to_string());
+                        ui.label(traversal.entry.to_string());
+                        ui.label(traversal.exit.to_string());
+                        ui.end_row();
+                    }
+                },
+            );
+        });
+
+        if ui.button("Goto selection").clicked() {
+            app.channels.app_tx.send(AppMsg::goto_selection()).unwrap();
+        }
+    }
+}
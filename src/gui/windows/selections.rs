@@ -0,0 +1,598 @@
+use std::path::PathBuf;
+
+use crossbeam::channel::Sender;
+use handlegraph::pathhandlegraph::PathId;
+
+use crate::{
+    app::{
+        channels::OverlayCreatorMsg, selection::NodeSelection, App, AppMsg,
+        Select,
+    },
+    overlays::OverlayData,
+    reactor::{Host, Outbox, Reactor},
+    universe::Node,
+};
+
+use super::graph_picker::PathPicker;
+
+/// A saved selection
+pub struct SelectionSet {
+    pub name: String,
+    pub selection: NodeSelection,
+    pub color: rgb::RGB<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetOp {
+    Union,
+    Intersection,
+    Difference,
+}
+
+impl SetOp {
+    fn apply(&self, a: &NodeSelection, b: &NodeSelection) -> NodeSelection {
+        match self {
+            SetOp::Union => a.union(b),
+            SetOp::Intersection => a.intersection(b),
+            SetOp::Difference => a.difference(b),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            SetOp::Union => "or",
+            SetOp::Intersection => "and",
+            SetOp::Difference => "minus",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetFileFormat {
+    NodeIds,
+    Bed,
+}
+
+enum SetsIo {
+    Save {
+        file: PathBuf,
+        format: SetFileFormat,
+        path: Option<PathId>,
+        selection: NodeSelection,
+    },
+    Load {
+        file: PathBuf,
+        format: SetFileFormat,
+        path: Option<PathId>,
+    },
+}
+
+enum IoFeedback {
+    Running(String),
+    Saved(String),
+    Loaded {
+        name: String,
+        selection: NodeSelection,
+    },
+    Error(String),
+}
+
+/// Named selection sets, which can be combined, shown as overlays,
+/// and saved to and loaded from node ID lists or BED files
+pub struct SelectionSets {
+    sets: Vec<SelectionSet>,
+    // the set that's saved to file
+    active_set: Option<usize>,
+
+    new_name: String,
+    created_count: usize,
+
+    op: SetOp,
+    lhs: Option<usize>,
+    rhs: Option<usize>,
+
+    format: SetFileFormat,
+    file_path: String,
+
+    path_picker: PathPicker,
+    path_picker_open: bool,
+
+    io_host: Host<SetsIo, IoFeedback>,
+    latest_io: Option<IoFeedback>,
+    status: Option<Result<String, String>>,
+
+    overlay_tx: Sender<OverlayCreatorMsg>,
+    node_count: usize,
+}
+
+impl SelectionSets {
+    pub const ID: &'static str = "selection_sets_window";
+
+    const PALETTE: [(f32, f32, f32); 8] = [
+        (0.12, 0.47, 0.71),
+        (1.0, 0.5, 0.05),
+        (0.17, 0.63, 0.17),
+        (0.84, 0.15, 0.16),
+        (0.58, 0.4, 0.74),
+        (0.55, 0.34, 0.29),
+        (0.89, 0.47, 0.76),
+        (0.09, 0.75, 0.81),
+    ];
+
+    pub fn new(reactor: &Reactor, path_picker: PathPicker) -> Self {
+        let graph = reactor.graph_query.clone();
+
+        let io_host = reactor.create_host(
+            move |outbox: &Outbox<IoFeedback>, io: SetsIo| {
+                let result = match io {
+                    SetsIo::Save {
+                        file,
+                        format,
+                        path,
+                        selection,
+                    } => {
+                        outbox.insert_blocking(IoFeedback::Running(format!(
+                            "Saving {}",
+                            file.display()
+                        )));
+
+                        let saved = match (format, path) {
+                            (SetFileFormat::NodeIds, _) => selection
+                                .write_node_ids(&file)
+                                .map(|_| selection.nodes.len()),
+                            (SetFileFormat::Bed, Some(path)) => {
+                                selection.write_bed(&graph, path, &file)
+                            }
+                            (SetFileFormat::Bed, None) => Err(anyhow::anyhow!(
+                                "Select a path to save BED ranges on"
+                            )),
+                        };
+
+                        saved.map(|count| {
+                            let unit = match format {
+                                SetFileFormat::NodeIds => "nodes",
+                                SetFileFormat::Bed => "ranges",
+                            };
+                            IoFeedback::Saved(format!(
+                                "Saved {} {} to {}",
+                                count,
+                                unit,
+                                file.display()
+                            ))
+                        })
+                    }
+                    SetsIo::Load { file, format, path } => {
+                        outbox.insert_blocking(IoFeedback::Running(format!(
+                            "Loading {}",
+                            file.display()
+                        )));
+
+                        let loaded = match format {
+                            SetFileFormat::NodeIds => {
+                                NodeSelection::read_node_ids(
+                                    &file,
+                                    graph.node_count(),
+                                )
+                            }
+                            SetFileFormat::Bed => {
+                                NodeSelection::read_bed(&graph, &file, path)
+                            }
+                        };
+
+                        loaded.map(|selection| {
+                            let name = file
+                                .file_stem()
+                                .map(|s| s.to_string_lossy().to_string())
+                                .unwrap_or_else(|| "loaded".to_string());
+
+                            IoFeedback::Loaded { name, selection }
+                        })
+                    }
+                };
+
+                result.unwrap_or_else(|err| IoFeedback::Error(err.to_string()))
+            },
+        );
+
+        Self {
+            sets: Vec::new(),
+            active_set: None,
+
+            new_name: String::new(),
+            created_count: 0,
+
+            op: SetOp::Union,
+            lhs: None,
+            rhs: None,
+
+            format: SetFileFormat::NodeIds,
+            file_path: String::new(),
+
+            path_picker,
+            path_picker_open: false,
+
+            io_host,
+            latest_io: None,
+            status: None,
+
+            overlay_tx: reactor.overlay_create_tx.clone(),
+            node_count: reactor.graph_query.node_count(),
+        }
+    }
+
+    pub fn sets(&self) -> &[SelectionSet] {
+        &self.sets
+    }
+
+    pub fn add_set(&mut self, name: &str, selection: NodeSelection) {
+        let (r, g, b) = Self::PALETTE[self.created_count % Self::PALETTE.len()];
+        self.created_count += 1;
+
+        let name = if name.trim().is_empty() {
+            format!("set_{}", self.created_count)
+        } else {
+            name.trim().to_string()
+        };
+
+        self.sets.push(SelectionSet {
+            name,
+            selection,
+            color: rgb::RGB::new(r, g, b),
+        });
+
+        self.active_set = Some(self.sets.len() - 1);
+    }
+
+    fn remove_set(&mut self, ix: usize) {
+        if ix >= self.sets.len() {
+            return;
+        }
+
+        self.sets.remove(ix);
+
+        let fix = |index: &mut Option<usize>| {
+            *index = match *index {
+                Some(i) if i == ix => None,
+                Some(i) if i > ix => Some(i - 1),
+                other => other,
+            };
+        };
+
+        fix(&mut self.active_set);
+        fix(&mut self.lhs);
+        fix(&mut self.rhs);
+    }
+
+    fn create_overlay(&self, name: String, sets: &[&SelectionSet]) {
+        let mut data =
+            vec![rgb::RGBA::new(0.3, 0.3, 0.3, 0.3); self.node_count];
+
+        for set in sets {
+            let color = set.color.alpha(1.0);
+
+            for node in set.selection.nodes.iter() {
+                let ix = (node.0 as usize).checked_sub(1);
+
+                if let Some(c) = ix.and_then(|ix| data.get_mut(ix)) {
+                    *c = color;
+                }
+            }
+        }
+
+        self.overlay_tx
+            .send(OverlayCreatorMsg::NewOverlay {
+                name,
+                data: OverlayData::RGB(data),
+            })
+            .unwrap();
+    }
+
+    fn set_combo(
+        ui: &mut egui::Ui,
+        id: &str,
+        sets: &[SelectionSet],
+        index: &mut Option<usize>,
+    ) {
+        let selected = index
+            .and_then(|ix| sets.get(ix))
+            .map(|set| set.name.as_str())
+            .unwrap_or("");
+
+        egui::ComboBox::from_id_source(id)
+            .selected_text(selected)
+            .width(120.0)
+            .show_ui(ui, |ui| {
+                for (ix, set) in sets.iter().enumerate() {
+                    ui.selectable_value(index, Some(ix), &set.name);
+                }
+            });
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        if let Some(feedback) = self.io_host.take() {
+            self.latest_io = Some(feedback);
+        }
+
+        match self.latest_io.take() {
+            Some(IoFeedback::Saved(msg)) => {
+                self.status = Some(Ok(msg));
+            }
+            Some(IoFeedback::Loaded { name, selection }) => {
+                self.status = Some(Ok(format!(
+                    "Loaded {} nodes into {}",
+                    selection.nodes.len(),
+                    name
+                )));
+                self.add_set(&name, selection);
+            }
+            Some(IoFeedback::Error(err)) => {
+                self.status = Some(Err(err));
+            }
+            feedback => {
+                self.latest_io = feedback;
+            }
+        }
+
+        let app_msg_tx = &app.channels.app_tx;
+
+        let current = app.selection();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_name)
+                    .desired_width(150.0)
+                    .hint_text("Name"),
+            );
+
+            if ui
+                .add_enabled(
+                    !current.is_empty(),
+                    egui::Button::new("Save current selection"),
+                )
+                .on_hover_text(format!("{} nodes selected", current.len()))
+                .clicked()
+            {
+                let selection = NodeSelection {
+                    nodes: current.clone(),
+                };
+                let name = std::mem::take(&mut self.new_name);
+                self.add_set(&name, selection);
+            }
+        });
+
+        ui.separator();
+
+        let mut to_remove = None;
+        let mut to_overlay = None;
+
+        egui::ScrollArea::from_max_height(250.0).show(ui, |ui| {
+            egui::Grid::new("selection_sets_grid").striped(true).show(
+                ui,
+                |ui| {
+                    for (ix, set) in self.sets.iter_mut().enumerate() {
+                        let mut color = [set.color.r, set.color.g, set.color.b];
+                        if ui.color_edit_button_rgb(&mut color).changed() {
+                            set.color =
+                                rgb::RGB::new(color[0], color[1], color[2]);
+                        }
+
+                        if ui
+                            .selectable_label(
+                                self.active_set == Some(ix),
+                                &set.name,
+                            )
+                            .clicked()
+                        {
+                            self.active_set = Some(ix);
+                        }
+
+                        ui.label(format!(
+                            "{} nodes",
+                            set.selection.nodes.len()
+                        ));
+
+                        let nodes = &set.selection.nodes;
+
+                        if ui.small_button("Select").clicked() {
+                            let select = Select::Many {
+                                nodes: nodes.clone(),
+                                clear: true,
+                            };
+                            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+                        }
+
+                        if ui.small_button("Add").clicked() {
+                            let select = Select::Many {
+                                nodes: nodes.clone(),
+                                clear: false,
+                            };
+                            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+                        }
+
+                        if ui.small_button("Subtract").clicked() {
+                            let select = Select::Remove {
+                                nodes: nodes.clone(),
+                            };
+                            app_msg_tx.send(AppMsg::Selection(select)).unwrap();
+                        }
+
+                        if ui.small_button("Overlay").clicked() {
+                            to_overlay = Some(ix);
+                        }
+
+                        if ui
+                            .small_button("x")
+                            .on_hover_text("Remove")
+                            .clicked()
+                        {
+                            to_remove = Some(ix);
+                        }
+
+                        ui.end_row();
+                    }
+                },
+            );
+        });
+
+        if let Some(ix) = to_overlay {
+            let set = &self.sets[ix];
+            self.create_overlay(format!("Selection: {}", set.name), &[set]);
+        }
+
+        if let Some(ix) = to_remove {
+            self.remove_set(ix);
+        }
+
+        if ui
+            .add_enabled(
+                !self.sets.is_empty(),
+                egui::Button::new("Overlay all sets"),
+            )
+            .clicked()
+        {
+            let sets = self.sets.iter().collect::<Vec<_>>();
+            self.create_overlay("Selection sets".to_string(), &sets);
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            Self::set_combo(
+                ui,
+                "selection_sets_lhs",
+                &self.sets,
+                &mut self.lhs,
+            );
+
+            egui::ComboBox::from_id_source("selection_sets_op")
+                .selected_text(self.op.symbol())
+                .width(70.0)
+                .show_ui(ui, |ui| {
+                    for op in
+                        [SetOp::Union, SetOp::Intersection, SetOp::Difference]
+                    {
+                        ui.selectable_value(&mut self.op, op, op.symbol());
+                    }
+                });
+
+            Self::set_combo(
+                ui,
+                "selection_sets_rhs",
+                &self.sets,
+                &mut self.rhs,
+            );
+
+            let operands = self
+                .lhs
+                .zip(self.rhs)
+                .filter(|&(a, b)| a < self.sets.len() && b < self.sets.len());
+
+            if ui
+                .add_enabled(operands.is_some(), egui::Button::new("Combine"))
+                .on_hover_text("Save the result as a new set")
+                .clicked()
+            {
+                if let Some((a, b)) = operands {
+                    let (a, b) = (&self.sets[a], &self.sets[b]);
+
+                    let name =
+                        format!("{} {} {}", a.name, self.op.symbol(), b.name);
+                    let selection = self.op.apply(&a.selection, &b.selection);
+
+                    self.add_set(&name, selection);
+                }
+            }
+        });
+
+        ui.separator();
+
+        self.path_picker.ui(ui.ctx(), &mut self.path_picker_open);
+
+        ui.horizontal(|ui| {
+            ui.radio_value(
+                &mut self.format,
+                SetFileFormat::NodeIds,
+                "Node IDs",
+            );
+            ui.radio_value(&mut self.format, SetFileFormat::Bed, "BED");
+
+            if self.format == SetFileFormat::Bed {
+                let label = match self.path_picker.active_path() {
+                    Some((_, name)) => format!("Path: {}", name),
+                    None => "Select path".to_string(),
+                };
+
+                if ui
+                    .button(label)
+                    .on_hover_text(
+                        "Ranges are saved on this path; when loading, \
+                         it's used for records that don't match a path",
+                    )
+                    .clicked()
+                {
+                    self.path_picker_open = !self.path_picker_open;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.file_path)
+                    .desired_width(250.0)
+                    .hint_text("File"),
+            );
+
+            let idle = !matches!(self.latest_io, Some(IoFeedback::Running(_)));
+            let has_file = !self.file_path.trim().is_empty();
+
+            let path = self.path_picker.active_path().map(|(p, _)| p);
+
+            let sets = &self.sets;
+            let active = self.active_set.and_then(|ix| sets.get(ix));
+
+            if ui
+                .add_enabled(
+                    idle && has_file && active.is_some(),
+                    egui::Button::new("Save"),
+                )
+                .on_hover_text("Save the highlighted set")
+                .clicked()
+            {
+                if let Some(set) = active {
+                    let io = SetsIo::Save {
+                        file: PathBuf::from(self.file_path.trim()),
+                        format: self.format,
+                        path,
+                        selection: set.selection.clone(),
+                    };
+                    self.io_host.call(io).unwrap();
+                }
+            }
+
+            if ui
+                .add_enabled(idle && has_file, egui::Button::new("Load"))
+                .clicked()
+            {
+                let io = SetsIo::Load {
+                    file: PathBuf::from(self.file_path.trim()),
+                    format: self.format,
+                    path,
+                };
+                self.io_host.call(io).unwrap();
+            }
+        });
+
+        if let Some(IoFeedback::Running(msg)) = &self.latest_io {
+            ui.label(msg);
+        } else if let Some(status) = &self.status {
+            match status {
+                Ok(msg) => {
+                    ui.label(msg);
+                }
+                Err(err) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+            }
+        }
+    }
+}
@@ -5,10 +5,12 @@ use crate::{
 
 pub mod debug;
 pub mod gui;
+pub mod keybindings;
 pub mod main_view;
 
 use debug::*;
 use gui::*;
+use keybindings::*;
 use main_view::*;
 
 pub struct SettingsWindow {
@@ -16,6 +18,7 @@ pub struct SettingsWindow {
 
     pub(crate) debug: DebugSettings,
     pub(crate) gui: GuiSettings,
+    pub(crate) keybindings: KeybindingSettings,
     pub(crate) main_view: MainViewSettings,
 }
 
@@ -24,6 +27,7 @@ enum SettingsTab {
     MainView,
     Debug,
     Gui,
+    Keybindings,
 }
 
 impl SettingsWindow {
@@ -35,11 +39,15 @@ impl SettingsWindow {
         let main_view =
             MainViewSettings::new(settings, shared_state.edges_enabled.clone());
 
+        let keybindings =
+            KeybindingSettings::new(shared_state.keybindings().clone());
+
         Self {
             current_tab,
 
             debug: Default::default(),
             gui: Default::default(),
+            keybindings,
             main_view,
         }
     }
@@ -66,6 +74,11 @@ impl SettingsWindow {
                         SettingsTab::Gui,
                         "GUI",
                     );
+                    ui.selectable_value(
+                        &mut self.current_tab,
+                        SettingsTab::Keybindings,
+                        "Keybindings",
+                    );
                     ui.selectable_value(
                         &mut self.current_tab,
                         SettingsTab::Debug,
@@ -83,6 +96,9 @@ impl SettingsWindow {
                     SettingsTab::Gui => {
                         self.gui.ui(ui);
                     }
+                    SettingsTab::Keybindings => {
+                        self.keybindings.ui(ui);
+                    }
                 }
             })
     }
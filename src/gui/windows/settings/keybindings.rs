@@ -0,0 +1,333 @@
+use parking_lot::RwLock;
+
+use crate::input::{
+    binds::{BindSpec, BindableInput},
+    keybindings::check_trigger,
+    BindAction, Keybindings, SystemInputBindings,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CustomKind {
+    Script,
+    ContextAction,
+}
+
+pub struct KeybindingSettings {
+    keybindings: Keybindings,
+
+    // the input a binding is being added to, as (subsystem, input name)
+    editing: Option<(&'static str, String)>,
+    new_keys: String,
+
+    custom_keys: String,
+    custom_kind: CustomKind,
+    custom_text: String,
+
+    status: Option<Result<String, String>>,
+}
+
+impl KeybindingSettings {
+    pub fn new(keybindings: Keybindings) -> Self {
+        Self {
+            keybindings,
+
+            editing: None,
+            new_keys: String::new(),
+
+            custom_keys: String::new(),
+            custom_kind: CustomKind::Script,
+            custom_text: String::new(),
+
+            status: None,
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let conflicts = self.keybindings.conflicts();
+
+        if !conflicts.is_empty() {
+            ui.label("Conflicts");
+            for conflict in conflicts {
+                ui.colored_label(egui::Color32::RED, conflict);
+            }
+            ui.separator();
+        }
+
+        let app = self.keybindings.app.clone();
+        let main_view = self.keybindings.main_view.clone();
+        let gui = self.keybindings.gui.clone();
+
+        egui::ScrollArea::from_max_height(400.0).show(ui, |ui| {
+            ui.collapsing("App", |ui| {
+                self.subsystem_ui(ui, "app", &app);
+            });
+            ui.collapsing("Main view", |ui| {
+                self.subsystem_ui(ui, "main_view", &main_view);
+            });
+            ui.collapsing("GUI", |ui| {
+                self.subsystem_ui(ui, "gui", &gui);
+            });
+            ui.collapsing("Scripts and context actions", |ui| {
+                self.custom_ui(ui);
+            });
+        });
+
+        ui.separator();
+
+        let path = self.keybindings.path();
+
+        ui.horizontal(|ui| {
+            if ui.button("Save").clicked() {
+                self.status = Some(
+                    self.keybindings
+                        .save()
+                        .map(|_| format!("Saved to {}", path.display()))
+                        .map_err(|err| err.to_string()),
+                );
+            }
+
+            if ui.button("Reload").clicked() {
+                self.status = Some(
+                    self.keybindings
+                        .load(&path)
+                        .map(|_| format!("Loaded {}", path.display()))
+                        .map_err(|err| err.to_string()),
+                );
+            }
+
+            if ui
+                .button("Reset to defaults")
+                .on_hover_text(
+                    "The keybindings file is not changed until saved",
+                )
+                .clicked()
+            {
+                self.keybindings.reset();
+                self.status = None;
+            }
+        });
+
+        ui.label(format!("Keybindings file: {}", path.display()));
+
+        match &self.status {
+            Some(Ok(msg)) => {
+                ui.label(msg);
+            }
+            Some(Err(err)) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            None => (),
+        }
+    }
+
+    fn subsystem_ui<T: BindableInput>(
+        &mut self,
+        ui: &mut egui::Ui,
+        subsystem: &'static str,
+        bindings: &RwLock<SystemInputBindings<T>>,
+    ) {
+        let mut to_remove: Vec<(T, BindSpec)> = Vec::new();
+        let mut to_add: Option<(T, BindSpec)> = None;
+        let mut wheel_change: Option<(T, bool, f32)> = None;
+
+        egui::Grid::new(format!("keybindings_{}", subsystem))
+            .striped(true)
+            .show(ui, |ui| {
+                for input in T::inputs() {
+                    let name = input.name();
+
+                    let (specs, wheel) = {
+                        let bindings = bindings.read();
+                        (
+                            bindings.input_specs(input),
+                            bindings.wheel_settings(input),
+                        )
+                    };
+
+                    let editing = match &self.editing {
+                        Some((s, n)) => *s == subsystem && n == &name,
+                        None => false,
+                    };
+
+                    ui.label(&name);
+
+                    ui.horizontal(|ui| {
+                        for spec in specs {
+                            if ui
+                                .small_button(format!("{} x", spec))
+                                .on_hover_text("Remove binding")
+                                .clicked()
+                            {
+                                to_remove.push((input, spec));
+                            }
+                        }
+
+                        if editing {
+                            let text = ui.add(
+                                egui::TextEdit::singleline(&mut self.new_keys)
+                                    .desired_width(120.0)
+                                    .hint_text("Ctrl+Shift+A"),
+                            );
+
+                            let add = ui.small_button("Add").clicked()
+                                || (text.lost_focus()
+                                    && ui
+                                        .input()
+                                        .key_pressed(egui::Key::Enter));
+
+                            if add {
+                                let parsed = BindSpec::parse(&self.new_keys)
+                                    .and_then(|spec| {
+                                        check_trigger(input, spec).map(|_| spec)
+                                    });
+
+                                match parsed {
+                                    Ok(spec) => {
+                                        to_add = Some((input, spec));
+                                        self.editing = None;
+                                    }
+                                    Err(err) => {
+                                        self.status = Some(Err(err.to_string()))
+                                    }
+                                }
+                            }
+
+                            if ui.small_button("Cancel").clicked() {
+                                self.editing = None;
+                            }
+                        } else if ui
+                            .small_button("+")
+                            .on_hover_text("Add binding")
+                            .clicked()
+                        {
+                            self.editing = Some((subsystem, name.clone()));
+                            self.new_keys.clear();
+                        }
+
+                        if let Some((mut invert, mut mult)) = wheel {
+                            ui.separator();
+
+                            let invert_box = ui.checkbox(&mut invert, "Invert");
+                            let mult_drag = ui.add(
+                                egui::DragValue::new(&mut mult)
+                                    .speed(0.01)
+                                    .clamp_range(0.01..=10.0)
+                                    .prefix("Speed: "),
+                            );
+
+                            if invert_box.changed() || mult_drag.changed() {
+                                wheel_change = Some((input, invert, mult));
+                            }
+                        }
+                    });
+
+                    ui.end_row();
+                }
+            });
+
+        if !to_remove.is_empty() || to_add.is_some() || wheel_change.is_some() {
+            let mut bindings = bindings.write();
+
+            for (input, spec) in to_remove {
+                bindings.remove_spec(input, spec);
+            }
+
+            if let Some((input, spec)) = to_add {
+                bindings.add_spec(input, spec);
+            }
+
+            if let Some((input, invert, mult)) = wheel_change {
+                bindings.set_wheel_settings(input, invert, mult);
+            }
+        }
+    }
+
+    fn custom_ui(&mut self, ui: &mut egui::Ui) {
+        let mut custom = self
+            .keybindings
+            .custom
+            .read()
+            .iter()
+            .map(|(spec, action)| (*spec, action.describe()))
+            .collect::<Vec<_>>();
+
+        custom.sort_by_key(|(spec, _)| spec.to_string());
+
+        let mut to_remove = None;
+
+        egui::Grid::new("keybindings_custom")
+            .striped(true)
+            .show(ui, |ui| {
+                for (spec, description) in custom {
+                    ui.label(spec.to_string());
+                    ui.label(description);
+
+                    if ui.small_button("x").on_hover_text("Remove").clicked() {
+                        to_remove = Some(spec);
+                    }
+
+                    ui.end_row();
+                }
+            });
+
+        if let Some(spec) = to_remove {
+            self.keybindings.custom.write().remove(&spec);
+        }
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.custom_kind, CustomKind::Script, "Script");
+            ui.radio_value(
+                &mut self.custom_kind,
+                CustomKind::ContextAction,
+                "Context action",
+            );
+        });
+
+        let hint = match self.custom_kind {
+            CustomKind::Script => "goto_selection()",
+            CustomKind::ContextAction => "Copy node ID",
+        };
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.custom_keys)
+                    .desired_width(100.0)
+                    .hint_text("Ctrl+J"),
+            );
+
+            ui.add(
+                egui::TextEdit::singleline(&mut self.custom_text)
+                    .desired_width(250.0)
+                    .hint_text(hint),
+            );
+
+            let can_bind = !self.custom_keys.trim().is_empty()
+                && !self.custom_text.trim().is_empty();
+
+            if ui
+                .add_enabled(can_bind, egui::Button::new("Bind"))
+                .clicked()
+            {
+                let text = self.custom_text.trim().to_string();
+
+                let action = match self.custom_kind {
+                    CustomKind::Script => BindAction::Script(text),
+                    CustomKind::ContextAction => {
+                        BindAction::ContextAction(text)
+                    }
+                };
+
+                match self.keybindings.bind_custom(&self.custom_keys, action) {
+                    Ok(()) => {
+                        self.custom_keys.clear();
+                        self.custom_text.clear();
+                        self.status = None;
+                    }
+                    Err(err) => self.status = Some(Err(err.to_string())),
+                }
+            }
+        });
+    }
+}
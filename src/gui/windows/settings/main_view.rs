@@ -2,7 +2,7 @@ use crossbeam::atomic::AtomicCell;
 use std::sync::Arc;
 
 use crate::{
-    app::{AppSettings, NodeWidth},
+    app::{selection::GrowSettings, AppSettings, NodeWidth},
     vulkan::draw_system::edges::EdgesUBO,
 };
 
@@ -12,6 +12,8 @@ pub struct MainViewSettings {
 
     edges_enabled: Arc<AtomicCell<bool>>,
     edges_ubo: Arc<AtomicCell<EdgesUBO>>,
+
+    grow_settings: Arc<GrowSettings>,
 }
 
 impl MainViewSettings {
@@ -24,12 +26,16 @@ impl MainViewSettings {
 
         let edges_ubo = settings.edge_renderer().clone();
 
+        let grow_settings = settings.grow_settings().clone();
+
         Self {
             node_width,
             label_radius,
 
             edges_enabled,
             edges_ubo,
+
+            grow_settings,
         }
     }
 
@@ -126,5 +132,62 @@ impl MainViewSettings {
         if label_radius_slider.changed() {
             self.label_radius.store(label_radius);
         }
+
+        ui.separator();
+
+        let grow = &self.grow_settings;
+
+        let mut hops = grow.hops.load();
+        let mut distance = grow.distance.load();
+        let mut path_steps = grow.path_steps.load();
+        let mut shrink_hops = grow.shrink_hops.load();
+
+        let hops_slider = ui
+            .add(
+                egui::Slider::new::<usize>(&mut hops, 1..=100)
+                    .text("Grow selection hops"),
+            )
+            .on_hover_text("The number of edges to follow when growing the selection. Default: 1");
+
+        let distance_slider = ui
+            .add(
+                egui::Slider::new::<usize>(&mut distance, 1..=1_000_000)
+                    .logarithmic(true)
+                    .text("Grow selection distance"),
+            )
+            .on_hover_text(
+                "The distance in bp to grow the selection by. Default: 10000",
+            );
+
+        let path_steps_slider = ui
+            .add(
+                egui::Slider::new::<usize>(&mut path_steps, 1..=1000)
+                    .logarithmic(true)
+                    .text("Grow selection path steps"),
+            )
+            .on_hover_text("The number of steps to follow each path when growing the selection. Default: 5");
+
+        let shrink_hops_slider = ui
+            .add(
+                egui::Slider::new::<usize>(&mut shrink_hops, 1..=100)
+                    .text("Shrink selection hops"),
+            )
+            .on_hover_text("The number of boundary layers to remove when shrinking the selection. Default: 1");
+
+        if hops_slider.changed() {
+            grow.hops.store(hops);
+        }
+
+        if distance_slider.changed() {
+            grow.distance.store(distance);
+        }
+
+        if path_steps_slider.changed() {
+            grow.path_steps.store(path_steps);
+        }
+
+        if shrink_hops_slider.changed() {
+            grow.shrink_hops.store(shrink_hops);
+        }
     }
 }
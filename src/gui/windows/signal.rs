@@ -0,0 +1,400 @@
+use std::{path::PathBuf, sync::Arc};
+
+use bstr::ByteSlice;
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    pathhandlegraph::*,
+};
+
+use anyhow::Result;
+
+use crate::{
+    annotations::{SignalAggregate, SignalTrack},
+    app::channels::OverlayCreatorMsg,
+    overlays::OverlayData,
+    reactor::{Host, Outbox, Reactor},
+};
+
+use super::{file::FilePicker, graph_picker::PathPicker};
+
+enum SignalFeedback {
+    Error(String),
+    Running(String),
+}
+
+type LoadResult = std::result::Result<Arc<SignalTrack>, SignalFeedback>;
+type OverlayResult = std::result::Result<(), SignalFeedback>;
+
+struct SignalOverlayInput {
+    name: String,
+    track: Arc<SignalTrack>,
+    path: PathId,
+    chr: Vec<u8>,
+    aggregate: SignalAggregate,
+}
+
+/// Creates value overlays from bedGraph and WIG signal tracks, by
+/// aggregating the signal per node along a reference path
+pub struct SignalOverlayCreator {
+    file_picker: FilePicker,
+    file_picker_open: bool,
+
+    path_picker: PathPicker,
+    path_picker_open: bool,
+
+    load_host: Host<PathBuf, LoadResult>,
+    overlay_host: Host<SignalOverlayInput, OverlayResult>,
+
+    latest_feedback: Option<SignalFeedback>,
+
+    track: Option<Arc<SignalTrack>>,
+    chromosomes: Vec<Vec<u8>>,
+    chr_index: Option<usize>,
+    // the reference path the sequence was last chosen for
+    chr_path: Option<PathId>,
+
+    aggregate: SignalAggregate,
+
+    overlay_name: String,
+}
+
+impl SignalOverlayCreator {
+    pub const ID: &'static str = "signal_overlay_creator_window";
+
+    pub fn new(reactor: &Reactor, path_picker: PathPicker) -> Result<Self> {
+        let pwd = std::fs::canonicalize("./").unwrap();
+
+        let mut file_picker = FilePicker::new(
+            egui::Id::with(egui::Id::new(Self::ID), "file_picker"),
+            pwd,
+        )?;
+
+        file_picker.set_visible_extensions(&["bedgraph", "bdg", "wig"])?;
+
+        let load_host = reactor.create_host(
+            move |outbox: &Outbox<LoadResult>, file: PathBuf| {
+                outbox.insert_blocking(Err(SignalFeedback::Running(
+                    "Loading signal track".to_string(),
+                )));
+
+                let track =
+                    SignalTrack::parse_signal_file(&file).map_err(|err| {
+                        SignalFeedback::Error(format!(
+                            "Error parsing signal file: {:?}",
+                            err
+                        ))
+                    })?;
+
+                Ok(Arc::new(track))
+            },
+        );
+
+        let graph = reactor.graph_query.clone();
+        let overlay_tx = reactor.overlay_create_tx.clone();
+
+        let overlay_host = reactor.create_host(
+            move |outbox: &Outbox<OverlayResult>, input: SignalOverlayInput| {
+                outbox.insert_blocking(Err(SignalFeedback::Running(
+                    "Aggregating signal".to_string(),
+                )));
+
+                let values = input
+                    .track
+                    .node_values(
+                        &graph,
+                        input.path,
+                        &input.chr,
+                        input.aggregate,
+                    )
+                    .ok_or_else(|| {
+                        SignalFeedback::Error(format!(
+                            "Path {} does not exist",
+                            input.path.0
+                        ))
+                    })?;
+
+                let mut min = std::f32::MAX;
+                let mut max = std::f32::MIN;
+
+                for v in values.iter().filter_map(|v| *v) {
+                    min = min.min(v);
+                    max = max.max(v);
+                }
+
+                if min > max {
+                    return Err(SignalFeedback::Error(
+                        "The signal doesn't cover any nodes on the path"
+                            .to_string(),
+                    ));
+                }
+
+                let range = max - min;
+
+                // nodes outside the signal get the bottom of the
+                // gradient, same as the lowest signal value
+                let data = values
+                    .into_iter()
+                    .map(|v| match v {
+                        Some(v) if range > 0.0 => (v - min) / range,
+                        Some(_) => 1.0,
+                        None => 0.0,
+                    })
+                    .collect::<Vec<_>>();
+
+                log::debug!(
+                    "Signal overlay values, min: {}, max: {}",
+                    min,
+                    max
+                );
+
+                overlay_tx
+                    .send(OverlayCreatorMsg::NewOverlay {
+                        name: input.name,
+                        data: OverlayData::Value(data),
+                    })
+                    .unwrap();
+
+                Ok(())
+            },
+        );
+
+        Ok(Self {
+            file_picker,
+            file_picker_open: false,
+
+            path_picker,
+            path_picker_open: false,
+
+            load_host,
+            overlay_host,
+
+            latest_feedback: None,
+
+            track: None,
+            chromosomes: Vec::new(),
+            chr_index: None,
+            chr_path: None,
+
+            aggregate: SignalAggregate::Mean,
+
+            overlay_name: String::new(),
+        })
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        if let Some(result) = self.load_host.take() {
+            match result {
+                Ok(track) => {
+                    self.chromosomes = track.chromosomes();
+                    self.chr_index = if self.chromosomes.len() == 1 {
+                        Some(0)
+                    } else {
+                        None
+                    };
+                    self.chr_path = None;
+                    self.track = Some(track);
+                    self.latest_feedback = None;
+                }
+                Err(feedback) => {
+                    self.latest_feedback = Some(feedback);
+                }
+            }
+        }
+
+        if let Some(result) = self.overlay_host.take() {
+            match result {
+                Ok(_) => {
+                    self.overlay_name.clear();
+                    self.latest_feedback = None;
+                }
+                Err(feedback) => {
+                    self.latest_feedback = Some(feedback);
+                }
+            }
+        }
+
+        let is_running =
+            matches!(self.latest_feedback, Some(SignalFeedback::Running(_)));
+
+        if self.file_picker.selected_path().is_some() {
+            self.file_picker_open = false;
+        }
+
+        self.file_picker.ui(ctx, &mut self.file_picker_open);
+        self.path_picker.ui(ctx, &mut self.path_picker_open);
+
+        self.select_path_chromosome();
+
+        egui::Window::new("Signal Tracks")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !is_running,
+                            egui::Button::new("Choose signal file"),
+                        )
+                        .clicked()
+                    {
+                        self.file_picker.reset_selection();
+                        self.file_picker_open = true;
+                    }
+
+                    let selected_path = self.file_picker.selected_path();
+
+                    if ui
+                        .add_enabled(
+                            !is_running && selected_path.is_some(),
+                            egui::Button::new("Load"),
+                        )
+                        .clicked()
+                    {
+                        if let Some(path) = selected_path {
+                            self.load_host.call(path.to_owned()).unwrap();
+                        }
+                    }
+                });
+
+                if let Some(track) = &self.track {
+                    ui.label(format!(
+                        "{} - {} intervals",
+                        track.file_name(),
+                        track.len()
+                    ));
+                } else {
+                    ui.label("No signal track loaded");
+                }
+
+                ui.separator();
+
+                let path_label =
+                    if let Some((_, name)) = self.path_picker.active_path() {
+                        format!("Path: {}", name)
+                    } else {
+                        "Select a reference path".to_string()
+                    };
+
+                if ui.button(path_label).clicked() {
+                    self.path_picker_open = !self.path_picker_open;
+                }
+
+                let chromosomes = &self.chromosomes;
+                let chr_index = &mut self.chr_index;
+
+                let selected = chr_index
+                    .and_then(|ix| chromosomes.get(ix))
+                    .map(|chr| format!("{}", chr.as_bstr()))
+                    .unwrap_or_else(|| "Choose a sequence".to_string());
+
+                egui::ComboBox::from_label("Sequence")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (ix, chr) in chromosomes.iter().enumerate() {
+                            ui.selectable_value(
+                                chr_index,
+                                Some(ix),
+                                format!("{}", chr.as_bstr()),
+                            );
+                        }
+                    });
+
+                ui.horizontal(|ui| {
+                    ui.label("Aggregate");
+
+                    let aggregate = &mut self.aggregate;
+
+                    for agg in [
+                        SignalAggregate::Mean,
+                        SignalAggregate::Max,
+                        SignalAggregate::Sum,
+                    ]
+                    .iter()
+                    {
+                        ui.radio_value(aggregate, *agg, agg.to_string());
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Overlay name");
+                    ui.separator();
+                    ui.text_edit_singleline(&mut self.overlay_name);
+                });
+
+                let path = self.path_picker.active_path().map(|(p, _)| p);
+
+                let chr = self
+                    .chr_index
+                    .and_then(|ix| self.chromosomes.get(ix))
+                    .cloned();
+
+                let can_create = !is_running
+                    && !self.overlay_name.is_empty()
+                    && self.track.is_some()
+                    && path.is_some()
+                    && chr.is_some();
+
+                if ui
+                    .add_enabled(
+                        can_create,
+                        egui::Button::new("Create overlay"),
+                    )
+                    .clicked()
+                {
+                    if let (Some(track), Some(path), Some(chr)) =
+                        (&self.track, path, chr)
+                    {
+                        let input = SignalOverlayInput {
+                            name: self.overlay_name.clone(),
+                            track: track.clone(),
+                            path,
+                            chr,
+                            aggregate: self.aggregate,
+                        };
+
+                        self.overlay_host.call(input).unwrap();
+                    }
+                }
+
+                match &self.latest_feedback {
+                    Some(SignalFeedback::Running(msg)) => {
+                        ui.label(msg);
+                    }
+                    Some(SignalFeedback::Error(err)) => {
+                        ui.label(err);
+                    }
+                    None => (),
+                }
+            })
+    }
+
+    /// Defaults the sequence to the one named by the reference path,
+    /// whenever a different path is chosen or a new track is loaded
+    fn select_path_chromosome(&mut self) {
+        let (path, name) = match self.path_picker.active_path() {
+            Some(active) => active,
+            None => return,
+        };
+
+        if self.chr_path == Some(path) {
+            return;
+        }
+
+        self.chr_path = Some(path);
+
+        let seq_name = crate::annotations::path_name_sequence(name.as_bytes());
+
+        if let Some(ix) =
+            self.chromosomes.iter().position(|chr| chr == seq_name)
+        {
+            self.chr_index = Some(ix);
+        }
+    }
+}
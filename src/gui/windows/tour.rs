@@ -0,0 +1,462 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    app::{
+        tour::{Easing, Keyframe, PlaybackMode, Tour, TourPlayback},
+        App, AppMsg, Select,
+    },
+    overlays::OverlayKind,
+    universe::Node,
+};
+
+/// Records keyframes for a tour through the graph, and plays it back
+/// or exports it as an image sequence
+pub struct TourWindow {
+    tour: Tour,
+
+    overlay_names: FxHashMap<usize, String>,
+
+    include_selection: bool,
+    transition: f32,
+    hold: f32,
+    easing: Easing,
+
+    playback: Option<TourPlayback>,
+    // set when the last frame of the tour has been played
+    finished: bool,
+
+    export_dir: String,
+    fps: u32,
+    export_width: u32,
+    export_height: u32,
+
+    status: Option<Result<String, String>>,
+}
+
+impl TourWindow {
+    pub const ID: &'static str = "tour_window";
+
+    pub fn new() -> Self {
+        Self {
+            tour: Tour::default(),
+
+            overlay_names: FxHashMap::default(),
+
+            include_selection: false,
+            transition: 2.0,
+            hold: 1.0,
+            easing: Easing::Circ,
+
+            playback: None,
+            finished: false,
+
+            export_dir: String::new(),
+            fps: 30,
+            export_width: 1920,
+            export_height: 1080,
+
+            status: None,
+        }
+    }
+
+    pub fn populate_overlay_list(
+        &mut self,
+        names: &[(usize, OverlayKind, &str)],
+    ) {
+        self.overlay_names = names
+            .iter()
+            .map(|(id, _, name)| (*id, name.to_string()))
+            .collect();
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// The resolution of exported frames
+    pub fn export_extent(&self) -> (u32, u32) {
+        (self.export_width, self.export_height)
+    }
+
+    fn stop(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            if let PlaybackMode::Export { dir, .. } = playback.mode() {
+                self.status = Some(Ok(format!(
+                    "Exported {} frames to {}",
+                    playback.frame(),
+                    dir.display()
+                )));
+            }
+        }
+
+        self.finished = false;
+    }
+
+    /// Advances the playback, if any, by one frame, updating the view
+    /// and applying the state of the keyframes that are reached;
+    /// returns the file the frame should be saved to when exporting
+    pub fn step(&mut self, app: &App) -> Option<PathBuf> {
+        if self.finished {
+            self.stop();
+            return None;
+        }
+
+        let frame = self.playback.as_mut()?.advance(&self.tour);
+
+        let frame = match frame {
+            Some(frame) => frame,
+            None => {
+                self.stop();
+                return None;
+            }
+        };
+
+        app.shared_state().view.store(frame.view);
+
+        if let Some(kf) =
+            frame.reached.and_then(|ix| self.tour.keyframes.get(ix))
+        {
+            if let Some(overlay) = kf.overlay {
+                app.shared_state()
+                    .overlay_state()
+                    .set_current_overlay(Some(overlay));
+            }
+
+            if let Some(nodes) = &kf.selection {
+                let select = Select::Many {
+                    nodes: nodes.clone(),
+                    clear: true,
+                };
+                app.channels.app_tx.send(AppMsg::Selection(select)).unwrap();
+            }
+        }
+
+        self.finished = frame.done;
+
+        frame.capture
+    }
+
+    /// Stops the playback, showing the error in the window
+    pub fn abort(&mut self, err: String) {
+        self.playback = None;
+        self.finished = false;
+        self.status = Some(Err(format!("Tour export failed: {}", err)));
+    }
+
+    pub fn ui_impl(&mut self, app: &App, ui: &mut egui::Ui, _nodes: &[Node]) {
+        let mut play: Option<PlaybackMode> = None;
+        let mut stop = false;
+
+        let mut to_remove: Option<usize> = None;
+        let mut to_swap: Option<(usize, usize)> = None;
+
+        let playing = self.playback.is_some();
+        let selection = app.selection();
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!playing, egui::Button::new("Add keyframe"))
+                .on_hover_text("Add the current view and overlay as a keyframe")
+                .clicked()
+            {
+                let selection =
+                    if self.include_selection && !selection.is_empty() {
+                        Some(selection.clone())
+                    } else {
+                        None
+                    };
+
+                self.tour.keyframes.push(Keyframe {
+                    view: app.shared_state().view(),
+                    transition: Duration::from_secs_f32(self.transition),
+                    hold: Duration::from_secs_f32(self.hold),
+                    easing: self.easing,
+                    overlay: app
+                        .shared_state()
+                        .overlay_state()
+                        .current_overlay(),
+                    selection,
+                });
+            }
+
+            ui.checkbox(&mut self.include_selection, "With selection");
+        });
+
+        ui.separator();
+
+        egui::ScrollArea::from_max_height(250.0).show(ui, |ui| {
+            egui::Grid::new("tour_keyframes")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label("Transition (s)");
+                    ui.label("Hold (s)");
+                    ui.label("Easing");
+                    ui.label("Overlay");
+                    ui.label("Selection");
+                    ui.end_row();
+
+                    let count = self.tour.keyframes.len();
+
+                    for (ix, kf) in self.tour.keyframes.iter_mut().enumerate() {
+                        if ui
+                            .button(format!("{}", ix + 1))
+                            .on_hover_text("Go to this keyframe")
+                            .clicked()
+                        {
+                            app.channels
+                                .app_tx
+                                .send(AppMsg::goto_view(kf.view))
+                                .unwrap();
+                        }
+
+                        let mut transition = kf.transition.as_secs_f32();
+                        if ui
+                            .add_enabled(
+                                ix > 0,
+                                egui::DragValue::new(&mut transition)
+                                    .clamp_range(0.0..=60.0)
+                                    .speed(0.05),
+                            )
+                            .changed()
+                        {
+                            kf.transition = Duration::from_secs_f32(transition);
+                        }
+
+                        let mut hold = kf.hold.as_secs_f32();
+                        if ui
+                            .add(
+                                egui::DragValue::new(&mut hold)
+                                    .clamp_range(0.0..=60.0)
+                                    .speed(0.05),
+                            )
+                            .changed()
+                        {
+                            kf.hold = Duration::from_secs_f32(hold);
+                        }
+
+                        egui::ComboBox::from_id_source(
+                            egui::Id::new(Self::ID).with(ix),
+                        )
+                        .selected_text(kf.easing.name())
+                        .show_ui(ui, |ui| {
+                            for easing in Easing::ALL.iter() {
+                                ui.selectable_value(
+                                    &mut kf.easing,
+                                    *easing,
+                                    easing.name(),
+                                );
+                            }
+                        });
+
+                        let overlay = kf
+                            .overlay
+                            .and_then(|id| self.overlay_names.get(&id))
+                            .map(|name| name.as_str())
+                            .unwrap_or("");
+                        ui.label(overlay);
+
+                        let nodes = kf
+                            .selection
+                            .as_ref()
+                            .map(|nodes| format!("{} nodes", nodes.len()))
+                            .unwrap_or_default();
+                        ui.label(nodes);
+
+                        ui.horizontal(|ui| {
+                            if ui
+                                .add_enabled(
+                                    ix > 0,
+                                    egui::Button::new("^").small(),
+                                )
+                                .clicked()
+                            {
+                                to_swap = Some((ix - 1, ix));
+                            }
+
+                            if ui
+                                .add_enabled(
+                                    ix + 1 < count,
+                                    egui::Button::new("v").small(),
+                                )
+                                .clicked()
+                            {
+                                to_swap = Some((ix, ix + 1));
+                            }
+
+                            if ui
+                                .small_button("x")
+                                .on_hover_text("Remove")
+                                .clicked()
+                            {
+                                to_remove = Some(ix);
+                            }
+                        });
+
+                        ui.end_row();
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("New keyframes:");
+            ui.add(
+                egui::DragValue::new(&mut self.transition)
+                    .clamp_range(0.0..=60.0)
+                    .speed(0.05)
+                    .suffix(" s"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut self.hold)
+                    .clamp_range(0.0..=60.0)
+                    .speed(0.05)
+                    .suffix(" s hold"),
+            );
+
+            egui::ComboBox::from_id_source("tour_new_easing")
+                .selected_text(self.easing.name())
+                .show_ui(ui, |ui| {
+                    for easing in Easing::ALL.iter() {
+                        ui.selectable_value(
+                            &mut self.easing,
+                            *easing,
+                            easing.name(),
+                        );
+                    }
+                });
+        });
+
+        ui.separator();
+
+        let has_keyframes = !self.tour.keyframes.is_empty();
+
+        ui.horizontal(|ui| {
+            if playing {
+                if ui.button("Stop").clicked() {
+                    stop = true;
+                }
+            } else if ui
+                .add_enabled(has_keyframes, egui::Button::new("Play"))
+                .clicked()
+            {
+                play = Some(PlaybackMode::Preview);
+            }
+
+            let total = self.tour.duration().as_secs_f32();
+
+            match &self.playback {
+                Some(playback) => {
+                    ui.label(format!(
+                        "{:.1} / {:.1} s",
+                        playback.time().as_secs_f32(),
+                        total
+                    ));
+                }
+                None => {
+                    ui.label(format!("{:.1} s", total));
+                }
+            }
+        });
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.export_dir)
+                    .desired_width(250.0)
+                    .hint_text("Export directory"),
+            );
+
+            ui.add(
+                egui::DragValue::new(&mut self.fps)
+                    .clamp_range(1..=120)
+                    .suffix(" fps"),
+            );
+        });
+
+        ui.horizontal(|ui| {
+            ui.add_enabled(
+                !playing,
+                egui::DragValue::new(&mut self.export_width)
+                    .clamp_range(16..=8192)
+                    .suffix(" px"),
+            );
+            ui.label("x");
+            ui.add_enabled(
+                !playing,
+                egui::DragValue::new(&mut self.export_height)
+                    .clamp_range(16..=8192)
+                    .suffix(" px"),
+            );
+
+            let has_dir = !self.export_dir.trim().is_empty();
+
+            if ui
+                .add_enabled(
+                    !playing && has_keyframes && has_dir,
+                    egui::Button::new("Export frames"),
+                )
+                .on_hover_text(format!(
+                    "Render each frame of the tour to a PNG, \
+                     without the GUI; encode them with e.g.\n\
+                     ffmpeg -framerate {} -i frame_%05d.png \
+                     -pix_fmt yuv420p tour.mp4",
+                    self.fps
+                ))
+                .clicked()
+            {
+                let dir = PathBuf::from(self.export_dir.trim());
+
+                match std::fs::create_dir_all(&dir) {
+                    Ok(_) => {
+                        play =
+                            Some(PlaybackMode::Export { dir, fps: self.fps });
+                    }
+                    Err(err) => {
+                        self.status = Some(Err(format!(
+                            "Error creating {}: {}",
+                            dir.display(),
+                            err
+                        )));
+                    }
+                }
+            }
+        });
+
+        if let Some(playback) = &self.playback {
+            if let PlaybackMode::Export { .. } = playback.mode() {
+                ui.label(format!("Exporting frame {}", playback.frame()));
+            }
+        } else if let Some(status) = &self.status {
+            match status {
+                Ok(msg) => {
+                    ui.label(msg);
+                }
+                Err(err) => {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+            }
+        }
+
+        if let Some((a, b)) = to_swap {
+            self.tour.keyframes.swap(a, b);
+        }
+
+        if let Some(ix) = to_remove {
+            self.tour.keyframes.remove(ix);
+        }
+
+        if stop {
+            self.stop();
+        }
+
+        if let Some(mode) = play {
+            self.status = None;
+            self.finished = false;
+            self.playback = Some(TourPlayback::new(mode));
+        }
+    }
+}
@@ -1,4 +1,3 @@
-use rustc_hash::FxHashMap;
 use winit::event::{ElementState, VirtualKeyCode};
 #[allow(unused_imports)]
 use winit::{
@@ -12,29 +11,30 @@ use std::sync::Arc;
 
 use crate::gui::GuiInput;
 use crate::{app::mainview::MainViewInput, gui::GuiMsg};
-use crate::{app::AppInput, reactor::Reactor};
 use crate::{app::SharedState, geometry::*};
+use crate::{
+    app::{AppInput, AppMsg},
+    reactor::Reactor,
+};
 
 pub mod binds;
+pub mod keybindings;
 
 pub use binds::{BindableInput, DigitalState, SystemInputBindings};
+pub use keybindings::{BindAction, Keybindings};
 
 use binds::*;
 
 struct SubsystemInput<T: InputPayload + BindableInput> {
-    bindings: SystemInputBindings<T>,
-
     tx: channel::Sender<SystemInput<T>>,
     rx: channel::Receiver<SystemInput<T>>,
 }
 
 impl<T: InputPayload + BindableInput> SubsystemInput<T> {
-    fn from_default_binds() -> Self {
-        let bindings = T::default_binds();
-
+    fn new() -> Self {
         let (tx, rx) = channel::unbounded::<SystemInput<T>>();
 
-        Self { bindings, tx, rx }
+        Self { tx, rx }
     }
 
     pub fn clone_rx(&self) -> channel::Receiver<SystemInput<T>> {
@@ -55,15 +55,11 @@ pub struct InputManager {
 
     gui_focus_state: crate::gui::GuiFocusState,
 
-    custom_binds: FxHashMap<
-        winit::event::VirtualKeyCode,
-        Arc<dyn Fn() + Send + Sync + 'static>,
-        // Box<dyn Fn() + Send + Sync + 'static>,
-    >,
-    // custom_binds: Arc<Mutex<FxHashMap<
-    //     winit::event::VirtualKeyCode,
-    //     Box<dyn Fn() + Send + Sync + 'static>,
-    // >>>,
+    keybindings: Keybindings,
+
+    // used by custom bindings that run scripts or context actions
+    app_tx: channel::Sender<AppMsg>,
+    context_action_tx: channel::Sender<String>,
 }
 
 impl InputManager {
@@ -100,7 +96,37 @@ impl InputManager {
         // log::warn!("calling boxed binding command");
         // boxed();
 
-        self.custom_binds.insert(key_code, boxed);
+        let spec = BindSpec::key(key_code, Default::default());
+
+        self.keybindings
+            .custom
+            .write()
+            .insert(spec, BindAction::Function(boxed));
+    }
+
+    fn run_custom_bind(&self, reactor: &mut Reactor, spec: BindSpec) {
+        let action =
+            if let Some(action) = self.keybindings.custom.read().get(&spec) {
+                action.clone()
+            } else {
+                return;
+            };
+
+        log::debug!("running {} bound to {}", action.describe(), spec);
+
+        match action {
+            BindAction::Script(script) => {
+                self.app_tx.send(AppMsg::ConsoleEval { script }).unwrap();
+            }
+            BindAction::ContextAction(name) => {
+                self.context_action_tx.send(name).unwrap();
+            }
+            BindAction::Function(command) => {
+                if let Ok(handle) = reactor.spawn(async move { command() }) {
+                    handle.forget();
+                }
+            }
+        }
     }
 
     pub fn handle_events(
@@ -108,6 +134,10 @@ impl InputManager {
         reactor: &mut Reactor,
         gui_msg_tx: &channel::Sender<GuiMsg>,
     ) {
+        let app_binds = self.keybindings.app.read();
+        let main_view_binds = self.keybindings.main_view.read();
+        let gui_binds = self.keybindings.gui.read();
+
         while let Ok(winit_ev) = self.winit_rx.try_recv() {
             if let event::WindowEvent::CursorMoved { position, .. } = winit_ev {
                 self.mouse_screen_pos.store(Point {
@@ -175,7 +205,7 @@ impl InputManager {
             }
 
             if let Some(app_inputs) =
-                self.app.bindings.apply(&winit_ev, modifiers, mouse_pos)
+                app_binds.apply(&winit_ev, modifiers, mouse_pos)
             {
                 for input in app_inputs {
                     if !(input.is_keyboard() && gui_wants_keyboard) {
@@ -185,17 +215,15 @@ impl InputManager {
             }
 
             if let Some(gui_inputs) =
-                self.gui.bindings.apply(&winit_ev, modifiers, mouse_pos)
+                gui_binds.apply(&winit_ev, modifiers, mouse_pos)
             {
                 for input in gui_inputs {
                     self.gui.tx.send(input).unwrap();
                 }
             }
 
-            if let Some(main_view_inputs) = self
-                .main_view
-                .bindings
-                .apply(&winit_ev, modifiers, mouse_pos)
+            if let Some(main_view_inputs) =
+                main_view_binds.apply(&winit_ev, modifiers, mouse_pos)
             {
                 for input in main_view_inputs {
                     if (input.is_keyboard() && !gui_wants_keyboard)
@@ -210,36 +238,42 @@ impl InputManager {
             if let event::WindowEvent::KeyboardInput { input, .. } = winit_ev {
                 let pressed = input.state == ElementState::Pressed;
                 if pressed && !gui_wants_keyboard {
-                    if let Some(command) = input
-                        .virtual_keycode
-                        .and_then(|kc| self.custom_binds.get(&kc))
-                    {
-                        log::warn!("executing bound command!");
-
-                        let command = command.clone();
-
-                        if let Ok(handle) =
-                            reactor.spawn(async move { command() })
-                        {
-                            handle.forget();
-                        }
+                    if let Some(key) = input.virtual_keycode {
+                        let spec = BindSpec::key(key, modifiers);
+                        self.run_custom_bind(reactor, spec);
                     }
                 }
             }
+
+            if let event::WindowEvent::MouseInput { state, button, .. } =
+                winit_ev
+            {
+                if state == ElementState::Pressed && !mouse_over_gui {
+                    let spec = BindSpec {
+                        trigger: BindTrigger::Mouse(button),
+                        modifiers,
+                    };
+                    self.run_custom_bind(reactor, spec);
+                }
+            }
         }
     }
 
     pub fn new(
         winit_rx: channel::Receiver<event::WindowEvent<'static>>,
         shared_state: &SharedState,
+        app_tx: channel::Sender<AppMsg>,
+        context_action_tx: channel::Sender<String>,
     ) -> Self {
         let mouse_screen_pos = shared_state.mouse_pos.clone();
 
         let gui_focus_state = shared_state.gui_focus_state.clone();
 
-        let app = SubsystemInput::<AppInput>::from_default_binds();
-        let main_view = SubsystemInput::<MainViewInput>::from_default_binds();
-        let gui = SubsystemInput::<GuiInput>::from_default_binds();
+        let keybindings = shared_state.keybindings.clone();
+
+        let app = SubsystemInput::<AppInput>::new();
+        let main_view = SubsystemInput::<MainViewInput>::new();
+        let gui = SubsystemInput::<GuiInput>::new();
 
         Self {
             mouse_screen_pos,
@@ -253,7 +287,10 @@ impl InputManager {
 
             gui_focus_state,
 
-            custom_binds: FxHashMap::default(),
+            keybindings,
+
+            app_tx,
+            context_action_tx,
         }
     }
 }
@@ -4,6 +4,7 @@ use winit::{
     event_loop::ControlFlow,
 };
 
+use anyhow::{anyhow, bail, Result};
 use rustc_hash::FxHashMap;
 
 use crate::geometry::*;
@@ -19,8 +20,304 @@ impl<T> InputPayload for T where
 }
 
 /// Trait for app subsystem inputs that can be bound to keys and other user input
-pub trait BindableInput: InputPayload {
+pub trait BindableInput: InputPayload + std::fmt::Debug {
     fn default_binds() -> SystemInputBindings<Self>;
+
+    /// All inputs of the subsystem, in the order they're listed in
+    /// the keybinding settings
+    fn inputs() -> Vec<Self>;
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::inputs()
+            .into_iter()
+            .find(|input| input.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// The key, mouse button, or wheel that triggers a binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindTrigger {
+    Key(event::VirtualKeyCode),
+    Mouse(event::MouseButton),
+    Wheel,
+}
+
+/// A trigger together with the modifiers that must be held, written
+/// as e.g. `Ctrl+Shift+A`, `Alt+MouseLeft`, or `Ctrl+Wheel`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BindSpec {
+    pub trigger: BindTrigger,
+    pub modifiers: event::ModifiersState,
+}
+
+impl BindSpec {
+    pub fn key(
+        key: event::VirtualKeyCode,
+        modifiers: event::ModifiersState,
+    ) -> Self {
+        Self {
+            trigger: BindTrigger::Key(key),
+            modifiers,
+        }
+    }
+
+    pub fn is_wheel(&self) -> bool {
+        self.trigger == BindTrigger::Wheel
+    }
+
+    pub fn parse(spec: &str) -> Result<Self> {
+        use event::ModifiersState as Mods;
+
+        let mut modifiers = Mods::empty();
+        let mut trigger = None;
+
+        for part in spec.split('+').map(str::trim) {
+            if trigger.is_some() {
+                bail!("The key or button must come last in \"{}\"", spec);
+            }
+
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= Mods::CTRL,
+                "shift" => modifiers |= Mods::SHIFT,
+                "alt" => modifiers |= Mods::ALT,
+                "logo" | "super" | "cmd" | "win" => modifiers |= Mods::LOGO,
+                "" => bail!("Missing key or modifier in \"{}\"", spec),
+                _ => {
+                    let parsed = parse_trigger(part).ok_or_else(|| {
+                        anyhow!("Unknown key or button \"{}\"", part)
+                    })?;
+                    trigger = Some(parsed);
+                }
+            }
+        }
+
+        let trigger = trigger
+            .ok_or_else(|| anyhow!("No key or button in \"{}\"", spec))?;
+
+        Ok(Self { trigger, modifiers })
+    }
+}
+
+impl std::fmt::Display for BindSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use event::ModifiersState as Mods;
+
+        let mods = [
+            (Mods::CTRL, "Ctrl"),
+            (Mods::SHIFT, "Shift"),
+            (Mods::ALT, "Alt"),
+            (Mods::LOGO, "Logo"),
+        ];
+
+        for (modifier, name) in mods.iter() {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.trigger {
+            BindTrigger::Key(key) => {
+                let name = key_name(key);
+                // digit keys are written without the `Key` prefix
+                if name.len() == 4 && name.starts_with("Key") {
+                    write!(f, "{}", &name[3..])
+                } else {
+                    write!(f, "{}", name)
+                }
+            }
+            BindTrigger::Mouse(button) => match button {
+                event::MouseButton::Left => write!(f, "MouseLeft"),
+                event::MouseButton::Right => write!(f, "MouseRight"),
+                event::MouseButton::Middle => write!(f, "MouseMiddle"),
+                event::MouseButton::Other(ix) => write!(f, "Mouse{}", ix),
+            },
+            BindTrigger::Wheel => write!(f, "Wheel"),
+        }
+    }
+}
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        const KEY_NAMES: &[(event::VirtualKeyCode, &str)] = &[
+            $((event::VirtualKeyCode::$key, stringify!($key)),)*
+        ];
+    };
+}
+
+key_names!(
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    Snapshot,
+    Scroll,
+    Pause,
+    Insert,
+    Home,
+    Delete,
+    End,
+    PageDown,
+    PageUp,
+    Left,
+    Up,
+    Right,
+    Down,
+    Back,
+    Return,
+    Space,
+    Tab,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadComma,
+    NumpadEnter,
+    NumpadEquals,
+    NumpadMultiply,
+    NumpadSubtract,
+    Apostrophe,
+    Asterisk,
+    At,
+    Backslash,
+    Colon,
+    Comma,
+    Equals,
+    Grave,
+    LBracket,
+    Minus,
+    Period,
+    Plus,
+    RBracket,
+    Semicolon,
+    Slash,
+    Underline,
+);
+
+const KEY_ALIASES: &[(&str, event::VirtualKeyCode)] = &[
+    ("Esc", event::VirtualKeyCode::Escape),
+    ("Enter", event::VirtualKeyCode::Return),
+    ("Backspace", event::VirtualKeyCode::Back),
+    ("Del", event::VirtualKeyCode::Delete),
+    ("PrintScreen", event::VirtualKeyCode::Snapshot),
+];
+
+fn key_name(key: event::VirtualKeyCode) -> String {
+    KEY_NAMES
+        .iter()
+        .find(|(k, _)| *k == key)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{:?}", key))
+}
+
+fn parse_trigger(name: &str) -> Option<BindTrigger> {
+    let lower = name.to_ascii_lowercase();
+
+    match lower.as_str() {
+        "wheel" => return Some(BindTrigger::Wheel),
+        "mouseleft" => {
+            return Some(BindTrigger::Mouse(event::MouseButton::Left))
+        }
+        "mouseright" => {
+            return Some(BindTrigger::Mouse(event::MouseButton::Right))
+        }
+        "mousemiddle" => {
+            return Some(BindTrigger::Mouse(event::MouseButton::Middle))
+        }
+        _ => (),
+    }
+
+    if let Some(ix) = lower.strip_prefix("mouse") {
+        let ix = ix.parse::<u16>().ok()?;
+        return Some(BindTrigger::Mouse(event::MouseButton::Other(ix)));
+    }
+
+    let name = if name.len() == 1 && name.chars().all(|c| c.is_ascii_digit()) {
+        format!("Key{}", name)
+    } else {
+        name.to_string()
+    };
+
+    KEY_NAMES
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(&name))
+        .map(|(key, _)| *key)
+        .or_else(|| {
+            KEY_ALIASES
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(&name))
+                .map(|(_, key)| *key)
+        })
+        .map(BindTrigger::Key)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -209,7 +506,11 @@ where
 {
     key_binds: FxHashMap<event::VirtualKeyCode, Vec<KeyBind<Inputs>>>,
     mouse_binds: FxHashMap<event::MouseButton, Vec<MouseButtonBind<Inputs>>>,
-    wheel_bind: Option<WheelBind<Inputs>>,
+    wheel_binds: Vec<WheelBind<Inputs>>,
+
+    // the direction and speed of wheel bindings, kept when an
+    // input's wheel bindings are removed
+    wheel_settings: FxHashMap<Inputs, (bool, f32)>,
 }
 
 impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
@@ -224,7 +525,173 @@ impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
         Self {
             key_binds,
             mouse_binds,
-            wheel_bind,
+            wheel_binds: wheel_bind.into_iter().collect(),
+            wheel_settings: FxHashMap::default(),
+        }
+    }
+
+    /// All bindings, as (binding, input) pairs
+    pub fn bind_specs(&self) -> Vec<(BindSpec, Inputs)> {
+        let keys = self.key_binds.iter().flat_map(|(&key, binds)| {
+            binds.iter().map(move |bind| {
+                (BindSpec::key(key, bind.modifiers), bind.payload)
+            })
+        });
+
+        let mouse = self.mouse_binds.iter().flat_map(|(&button, binds)| {
+            binds.iter().map(move |bind| {
+                let spec = BindSpec {
+                    trigger: BindTrigger::Mouse(button),
+                    modifiers: bind.modifiers,
+                };
+                (spec, bind.payload)
+            })
+        });
+
+        let wheel = self.wheel_binds.iter().map(|bind| {
+            let spec = BindSpec {
+                trigger: BindTrigger::Wheel,
+                modifiers: bind.modifiers,
+            };
+            (spec, bind.payload)
+        });
+
+        keys.chain(mouse).chain(wheel).collect()
+    }
+
+    pub fn input_specs(&self, input: Inputs) -> Vec<BindSpec> {
+        let mut specs = self
+            .bind_specs()
+            .into_iter()
+            .filter(|(_, i)| *i == input)
+            .map(|(spec, _)| spec)
+            .collect::<Vec<_>>();
+
+        specs.sort_by_key(|spec| spec.to_string());
+        specs
+    }
+
+    /// Bindings that trigger more than one input
+    pub fn conflicts(&self) -> Vec<(BindSpec, Vec<Inputs>)> {
+        let mut by_spec: FxHashMap<BindSpec, Vec<Inputs>> =
+            FxHashMap::default();
+
+        for (spec, input) in self.bind_specs() {
+            by_spec.entry(spec).or_default().push(input);
+        }
+
+        let mut conflicts = by_spec
+            .into_iter()
+            .filter(|(_, inputs)| inputs.len() > 1)
+            .map(|(spec, mut inputs)| {
+                inputs.sort();
+                (spec, inputs)
+            })
+            .collect::<Vec<_>>();
+
+        conflicts.sort_by_key(|(spec, _)| spec.to_string());
+        conflicts
+    }
+
+    /// Adds a binding unless it already exists; wheel bindings use
+    /// the direction and speed of the input's existing wheel binding
+    pub fn add_spec(&mut self, input: Inputs, spec: BindSpec) {
+        let modifiers = spec.modifiers;
+
+        match spec.trigger {
+            BindTrigger::Key(key) => {
+                let binds = self.key_binds.entry(key).or_default();
+                let bind = KeyBind::with_modifiers(input, modifiers);
+                if !binds.contains(&bind) {
+                    binds.push(bind);
+                }
+            }
+            BindTrigger::Mouse(button) => {
+                let binds = self.mouse_binds.entry(button).or_default();
+                let bind = MouseButtonBind::with_modifiers(input, modifiers);
+                if !binds.contains(&bind) {
+                    binds.push(bind);
+                }
+            }
+            BindTrigger::Wheel => {
+                let (invert, mult) =
+                    self.wheel_settings(input).unwrap_or((false, 1.0));
+
+                let exists = self.wheel_binds.iter().any(|bind| {
+                    bind.payload == input && bind.modifiers == modifiers
+                });
+
+                if !exists {
+                    self.wheel_binds.push(WheelBind::with_modifiers(
+                        invert, mult, input, modifiers,
+                    ));
+                }
+            }
+        }
+    }
+
+    pub fn remove_spec(&mut self, input: Inputs, spec: BindSpec) {
+        let modifiers = spec.modifiers;
+
+        match spec.trigger {
+            BindTrigger::Key(key) => {
+                if let Some(binds) = self.key_binds.get_mut(&key) {
+                    binds.retain(|b| {
+                        b.payload != input || b.modifiers != modifiers
+                    });
+                }
+            }
+            BindTrigger::Mouse(button) => {
+                if let Some(binds) = self.mouse_binds.get_mut(&button) {
+                    binds.retain(|b| {
+                        b.payload != input || b.modifiers != modifiers
+                    });
+                }
+            }
+            BindTrigger::Wheel => {
+                if let Some(settings) = self.wheel_settings(input) {
+                    self.wheel_settings.insert(input, settings);
+                }
+
+                self.wheel_binds
+                    .retain(|b| b.payload != input || b.modifiers != modifiers);
+            }
+        }
+
+        self.key_binds.retain(|_, binds| !binds.is_empty());
+        self.mouse_binds.retain(|_, binds| !binds.is_empty());
+    }
+
+    /// Removes every binding of `input`
+    pub fn clear_input(&mut self, input: Inputs) {
+        for spec in self.input_specs(input) {
+            self.remove_spec(input, spec);
+        }
+    }
+
+    /// The direction and speed of the input's wheel bindings, as
+    /// (invert, multiplier)
+    pub fn wheel_settings(&self, input: Inputs) -> Option<(bool, f32)> {
+        self.wheel_binds
+            .iter()
+            .find(|bind| bind.payload == input)
+            .map(|bind| (bind.invert, bind.mult))
+            .or_else(|| self.wheel_settings.get(&input).copied())
+    }
+
+    pub fn set_wheel_settings(
+        &mut self,
+        input: Inputs,
+        invert: bool,
+        mult: f32,
+    ) {
+        self.wheel_settings.insert(input, (invert, mult));
+
+        for bind in self.wheel_binds.iter_mut() {
+            if bind.payload == input {
+                bind.invert = invert;
+                bind.mult = mult;
+            }
         }
     }
 
@@ -287,16 +754,7 @@ impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
                 phase: _phase,
                 ..
             } => {
-                if let Some(bind) = self.wheel_bind {
-                    if bind.modifiers != modifiers {
-                        return None;
-                    }
-
-                    let mut mult = bind.mult;
-                    if bind.invert {
-                        mult *= -1.0;
-                    }
-
+                if self.wheel_binds.iter().any(|b| b.modifiers == modifiers) {
                     let delta = match delta {
                         event::MouseScrollDelta::LineDelta(_x, y) => {
                             // eprintln!("LineDelta({}, {}", x, y);
@@ -312,10 +770,24 @@ impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
                         }
                     };
 
-                    Some(vec![SystemInput::Wheel {
-                        delta: delta * mult,
-                        payload: bind.payload,
-                    }])
+                    let inputs = self
+                        .wheel_binds
+                        .iter()
+                        .filter(|&bind| bind.modifiers == modifiers)
+                        .map(|&bind| {
+                            let mut mult = bind.mult;
+                            if bind.invert {
+                                mult *= -1.0;
+                            }
+
+                            SystemInput::Wheel {
+                                delta: delta * mult,
+                                payload: bind.payload,
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    Some(inputs)
                 } else {
                     None
                 }
@@ -324,3 +796,111 @@ impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use event::ModifiersState as Mods;
+
+    fn round_trip(spec: BindSpec) {
+        let text = spec.to_string();
+        let parsed = BindSpec::parse(&text).unwrap();
+        assert_eq!(parsed, spec, "\"{}\" didn't round trip", text);
+    }
+
+    #[test]
+    fn spec_round_trip_keys() {
+        for &(key, _) in KEY_NAMES.iter() {
+            round_trip(BindSpec::key(key, Mods::empty()));
+        }
+    }
+
+    #[test]
+    fn spec_round_trip_modifiers() {
+        let all_mods = [
+            Mods::CTRL,
+            Mods::SHIFT,
+            Mods::ALT,
+            Mods::LOGO,
+            Mods::CTRL | Mods::SHIFT,
+            Mods::CTRL | Mods::ALT | Mods::SHIFT | Mods::LOGO,
+        ];
+
+        let triggers = [
+            BindTrigger::Key(event::VirtualKeyCode::A),
+            BindTrigger::Key(event::VirtualKeyCode::Key1),
+            BindTrigger::Mouse(event::MouseButton::Left),
+            BindTrigger::Wheel,
+        ];
+
+        for &modifiers in all_mods.iter() {
+            for &trigger in triggers.iter() {
+                round_trip(BindSpec { trigger, modifiers });
+            }
+        }
+    }
+
+    #[test]
+    fn spec_round_trip_mouse_and_wheel() {
+        let buttons = [
+            event::MouseButton::Left,
+            event::MouseButton::Right,
+            event::MouseButton::Middle,
+            event::MouseButton::Other(4),
+            event::MouseButton::Other(12),
+        ];
+
+        for &button in buttons.iter() {
+            round_trip(BindSpec {
+                trigger: BindTrigger::Mouse(button),
+                modifiers: Mods::empty(),
+            });
+        }
+
+        round_trip(BindSpec {
+            trigger: BindTrigger::Wheel,
+            modifiers: Mods::empty(),
+        });
+    }
+
+    #[test]
+    fn spec_display() {
+        let spec = BindSpec::key(event::VirtualKeyCode::Key5, Mods::CTRL);
+        assert_eq!(spec.to_string(), "Ctrl+5");
+
+        let spec = BindSpec {
+            trigger: BindTrigger::Mouse(event::MouseButton::Other(4)),
+            modifiers: Mods::SHIFT | Mods::ALT,
+        };
+        assert_eq!(spec.to_string(), "Shift+Alt+Mouse4");
+    }
+
+    #[test]
+    fn spec_parse_aliases_and_case() {
+        assert_eq!(
+            BindSpec::parse("control + shift + esc").unwrap(),
+            BindSpec::key(
+                event::VirtualKeyCode::Escape,
+                Mods::CTRL | Mods::SHIFT
+            )
+        );
+        assert_eq!(
+            BindSpec::parse("Cmd+wheel").unwrap(),
+            BindSpec {
+                trigger: BindTrigger::Wheel,
+                modifiers: Mods::LOGO,
+            }
+        );
+    }
+
+    #[test]
+    fn spec_parse_errors() {
+        assert!(BindSpec::parse("").is_err());
+        assert!(BindSpec::parse("Ctrl").is_err());
+        assert!(BindSpec::parse("Ctrl+").is_err());
+        assert!(BindSpec::parse("A+Ctrl").is_err());
+        assert!(BindSpec::parse("Ctrl+NotAKey").is_err());
+        assert!(BindSpec::parse("MouseX").is_err());
+    }
+}
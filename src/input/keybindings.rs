@@ -0,0 +1,597 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use parking_lot::{Mutex, RwLock};
+use rhai::EvalAltResult;
+use rustc_hash::FxHashMap;
+
+use crate::app::{mainview::MainViewInput, AppInput};
+use crate::gui::GuiInput;
+
+use super::binds::*;
+
+/// What a custom binding does when its keys are pressed
+#[derive(Clone)]
+pub enum BindAction {
+    /// Rhai code, evaluated in the console
+    Script(String),
+    /// The name of a context action, applied to the context (hovered
+    /// node, selection) at the time of the key press
+    ContextAction(String),
+    /// A function bound from Rust; these are not saved
+    Function(Arc<dyn Fn() + Send + Sync + 'static>),
+}
+
+impl BindAction {
+    pub fn describe(&self) -> String {
+        match self {
+            BindAction::Script(script) => format!("script `{}`", script),
+            BindAction::ContextAction(name) => {
+                format!("context action \"{}\"", name)
+            }
+            BindAction::Function(_) => "a bound function".to_string(),
+        }
+    }
+}
+
+macro_rules! with_subsystem {
+    ($self:ident, $subsystem:expr, $bindings:ident => $body:expr) => {
+        match $subsystem {
+            "app" => {
+                let $bindings = &$self.app;
+                $body
+            }
+            "main_view" => {
+                let $bindings = &$self.main_view;
+                $body
+            }
+            "gui" => {
+                let $bindings = &$self.gui;
+                $body
+            }
+            other => Err(anyhow!(
+                "Unknown input subsystem \"{}\", expected one of {}",
+                other,
+                Keybindings::SUBSYSTEMS.join(", ")
+            )),
+        }
+    };
+}
+
+/// The input bindings of each subsystem, and the custom bindings
+/// that run scripts or context actions, shared between the input
+/// manager and the keybinding settings
+///
+/// Bindings can be configured in a Rhai file, loaded at startup, that
+/// uses the functions added by `Keybindings::register_fns`
+#[derive(Clone)]
+pub struct Keybindings {
+    pub app: Arc<RwLock<SystemInputBindings<AppInput>>>,
+    pub main_view: Arc<RwLock<SystemInputBindings<MainViewInput>>>,
+    pub gui: Arc<RwLock<SystemInputBindings<GuiInput>>>,
+
+    pub custom: Arc<RwLock<FxHashMap<BindSpec, BindAction>>>,
+
+    path: Arc<Mutex<PathBuf>>,
+}
+
+impl std::default::Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            app: Arc::new(RwLock::new(AppInput::default_binds())),
+            main_view: Arc::new(RwLock::new(MainViewInput::default_binds())),
+            gui: Arc::new(RwLock::new(GuiInput::default_binds())),
+
+            custom: Arc::new(RwLock::new(FxHashMap::default())),
+
+            path: Arc::new(Mutex::new(PathBuf::from(Self::DEFAULT_PATH))),
+        }
+    }
+}
+
+impl Keybindings {
+    pub const DEFAULT_PATH: &'static str = "keybindings.rhai";
+
+    pub const SUBSYSTEMS: [&'static str; 3] = ["app", "main_view", "gui"];
+
+    /// `keybindings.rhai` in the working directory if it exists,
+    /// otherwise in the config directory
+    pub fn default_path() -> PathBuf {
+        let local = PathBuf::from(Self::DEFAULT_PATH);
+
+        if local.exists() {
+            return local;
+        }
+
+        crate::app::config_dir()
+            .map(|dir| dir.join(Self::DEFAULT_PATH))
+            .unwrap_or(local)
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.path.lock().clone()
+    }
+
+    pub fn bind(&self, subsystem: &str, input: &str, keys: &str) -> Result<()> {
+        let spec = BindSpec::parse(keys)?;
+        with_subsystem!(self, subsystem, bindings => {
+            let input = find_input(input)?;
+            check_trigger(input, spec)?;
+            bindings.write().add_spec(input, spec);
+            Ok(())
+        })
+    }
+
+    pub fn unbind(&self, subsystem: &str, input: &str) -> Result<()> {
+        with_subsystem!(self, subsystem, bindings => {
+            let input = find_input(input)?;
+            bindings.write().clear_input(input);
+            Ok(())
+        })
+    }
+
+    /// Sets the direction and speed of an input's wheel bindings
+    pub fn set_wheel(
+        &self,
+        subsystem: &str,
+        input: &str,
+        invert: bool,
+        mult: f32,
+    ) -> Result<()> {
+        with_subsystem!(self, subsystem, bindings => {
+            let input = find_input(input)?;
+            bindings.write().set_wheel_settings(input, invert, mult);
+            Ok(())
+        })
+    }
+
+    pub fn bind_custom(&self, keys: &str, action: BindAction) -> Result<()> {
+        let spec = BindSpec::parse(keys)?;
+
+        if spec.is_wheel() {
+            bail!("Scripts and actions can't be bound to the mouse wheel");
+        }
+
+        self.custom.write().insert(spec, action);
+        Ok(())
+    }
+
+    pub fn unbind_custom(&self, keys: &str) -> Result<()> {
+        let spec = BindSpec::parse(keys)?;
+        self.custom.write().remove(&spec);
+        Ok(())
+    }
+
+    /// Bindings that trigger more than one input in a subsystem, and
+    /// custom bindings that share keys with a subsystem input
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        let mut specs: Vec<(BindSpec, String)> = Vec::new();
+
+        for &subsystem in Self::SUBSYSTEMS.iter() {
+            let _ = with_subsystem!(self, subsystem, bindings => {
+                let bindings = bindings.read();
+
+                for (spec, inputs) in bindings.conflicts() {
+                    let names = inputs
+                        .iter()
+                        .map(|input| input.name())
+                        .collect::<Vec<_>>();
+
+                    conflicts.push(format!(
+                        "{}: {} triggers {}",
+                        subsystem,
+                        spec,
+                        names.join(", ")
+                    ));
+                }
+
+                specs.extend(bindings.bind_specs().into_iter().map(
+                    |(spec, input)| {
+                        (spec, format!("{}::{}", subsystem, input.name()))
+                    },
+                ));
+
+                Ok(())
+            });
+        }
+
+        let custom = self.custom.read();
+
+        let mut custom_conflicts = custom
+            .iter()
+            .flat_map(|(spec, action)| {
+                specs.iter().filter(move |(s, _)| s == spec).map(
+                    move |(_, input)| {
+                        format!(
+                            "{} runs {} and triggers {}",
+                            spec,
+                            action.describe(),
+                            input
+                        )
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        custom_conflicts.sort();
+        conflicts.extend(custom_conflicts);
+
+        conflicts
+    }
+
+    /// Restores the default bindings, and removes all custom
+    /// bindings other than those bound from Rust
+    pub fn reset(&self) {
+        *self.app.write() = AppInput::default_binds();
+        *self.main_view.write() = MainViewInput::default_binds();
+        *self.gui.write() = GuiInput::default_binds();
+
+        self.custom
+            .write()
+            .retain(|_, action| matches!(action, BindAction::Function(_)));
+    }
+
+    /// Resets the bindings, then applies the keybindings file at
+    /// `path`, if it exists; the path is also used when saving
+    pub fn load<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        *self.path.lock() = path.to_owned();
+
+        self.reset();
+
+        if !path.exists() {
+            log::debug!("No keybindings file at {:?}", path);
+            return Ok(());
+        }
+
+        let script = std::fs::read_to_string(path)?;
+
+        let mut engine = rhai::Engine::new();
+        self.register_fns(&mut engine);
+
+        engine
+            .run(&script)
+            .map_err(|err| anyhow!("Error in {:?}: {}", path, err))?;
+
+        log::info!("Loaded keybindings from {:?}", path);
+
+        Ok(())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = self.path();
+        if let Some(dir) =
+            path.parent().filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, self.to_script())?;
+        log::info!("Saved keybindings to {:?}", path);
+        Ok(())
+    }
+
+    /// The current bindings as a keybindings file
+    pub fn to_script(&self) -> String {
+        let mut script = String::from(
+            "// Each input is cleared with `unbind` before its bindings are added,
+// so inputs left out of this file keep their default bindings.
+//
+// Keys are written as modifiers and a key, mouse button, or the wheel,
+// joined by `+`, e.g. \"Ctrl+Shift+A\", \"Alt+MouseLeft\", \"Ctrl+Wheel\"
+",
+        );
+
+        for &subsystem in Self::SUBSYSTEMS.iter() {
+            let _ = with_subsystem!(self, subsystem, bindings => {
+                script.push('\n');
+                subsystem_script(subsystem, &bindings.read(), &mut script);
+                Ok(())
+            });
+        }
+
+        let custom = self.custom.read();
+
+        let mut custom_binds = custom
+            .iter()
+            .filter_map(|(spec, action)| {
+                let line = match action {
+                    BindAction::Script(code) => format!(
+                        "bind_script({}, {});",
+                        rhai_string(&spec.to_string()),
+                        rhai_string(code)
+                    ),
+                    BindAction::ContextAction(name) => format!(
+                        "bind_action({}, {});",
+                        rhai_string(&spec.to_string()),
+                        rhai_string(name)
+                    ),
+                    BindAction::Function(_) => return None,
+                };
+                Some(line)
+            })
+            .collect::<Vec<_>>();
+
+        custom_binds.sort();
+
+        if !custom_binds.is_empty() {
+            script.push('\n');
+        }
+
+        for line in custom_binds {
+            script.push_str(&line);
+            script.push('\n');
+        }
+
+        script
+    }
+
+    /// Adds the keybinding functions to a Rhai engine:
+    ///
+    /// * `bind(subsystem, input, keys)`
+    /// * `unbind(subsystem, input)`
+    /// * `set_wheel(subsystem, input, invert, multiplier)`
+    /// * `bind_script(keys, script)`
+    /// * `bind_action(keys, context_action_name)`
+    /// * `unbind_keys(keys)`, removes a script or action binding
+    pub fn register_fns(&self, engine: &mut rhai::Engine) {
+        let binds = self.clone();
+        engine.register_result_fn(
+            "bind",
+            move |subsystem: &str, input: &str, keys: &str| {
+                to_rhai(binds.bind(subsystem, input, keys))
+            },
+        );
+
+        let binds = self.clone();
+        engine.register_result_fn(
+            "unbind",
+            move |subsystem: &str, input: &str| {
+                to_rhai(binds.unbind(subsystem, input))
+            },
+        );
+
+        let binds = self.clone();
+        engine.register_result_fn(
+            "set_wheel",
+            move |subsystem: &str, input: &str, invert: bool, mult: f32| {
+                to_rhai(binds.set_wheel(subsystem, input, invert, mult))
+            },
+        );
+
+        let binds = self.clone();
+        engine.register_result_fn(
+            "bind_script",
+            move |keys: &str, script: &str| {
+                let action = BindAction::Script(script.to_string());
+                to_rhai(binds.bind_custom(keys, action))
+            },
+        );
+
+        let binds = self.clone();
+        engine.register_result_fn(
+            "bind_action",
+            move |keys: &str, name: &str| {
+                let action = BindAction::ContextAction(name.to_string());
+                to_rhai(binds.bind_custom(keys, action))
+            },
+        );
+
+        let binds = self.clone();
+        engine.register_result_fn("unbind_keys", move |keys: &str| {
+            to_rhai(binds.unbind_custom(keys))
+        });
+    }
+}
+
+fn find_input<T: BindableInput>(name: &str) -> Result<T> {
+    T::from_name(name).ok_or_else(|| {
+        let names = T::inputs()
+            .iter()
+            .map(|input| input.name())
+            .collect::<Vec<_>>();
+        anyhow!(
+            "Unknown input \"{}\", expected one of {}",
+            name,
+            names.join(", ")
+        )
+    })
+}
+
+/// Inputs are named after the kind of trigger their subsystem
+/// expects, e.g. `KeyPanUp` only reacts to keyboard events
+pub fn check_trigger<T: BindableInput>(input: T, spec: BindSpec) -> Result<()> {
+    let name = input.name();
+
+    let valid = match spec.trigger {
+        BindTrigger::Key(_) => name.starts_with("Key"),
+        BindTrigger::Mouse(_) => name.starts_with("Button"),
+        BindTrigger::Wheel => name.starts_with("Wheel"),
+    };
+
+    if !valid {
+        bail!("{} can't be bound to {}", name, spec);
+    }
+
+    Ok(())
+}
+
+fn subsystem_script<T: BindableInput>(
+    subsystem: &str,
+    bindings: &SystemInputBindings<T>,
+    script: &mut String,
+) {
+    let subsystem = rhai_string(subsystem);
+
+    for input in T::inputs() {
+        let name = rhai_string(&input.name());
+
+        script.push_str(&format!("unbind({}, {});\n", subsystem, name));
+
+        for spec in bindings.input_specs(input) {
+            script.push_str(&format!(
+                "bind({}, {}, {});\n",
+                subsystem,
+                name,
+                rhai_string(&spec.to_string())
+            ));
+        }
+
+        if let Some((invert, mult)) = bindings.wheel_settings(input) {
+            script.push_str(&format!(
+                "set_wheel({}, {}, {}, {:?});\n",
+                subsystem, name, invert, mult
+            ));
+        }
+    }
+}
+
+fn rhai_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
+
+fn to_rhai<T>(result: Result<T>) -> std::result::Result<T, Box<EvalAltResult>> {
+    result.map_err(|err| err.to_string().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use winit::event::{ModifiersState as Mods, VirtualKeyCode as Key};
+
+    #[test]
+    fn rhai_string_escapes() {
+        assert_eq!(rhai_string("Ctrl+A"), "\"Ctrl+A\"");
+        assert_eq!(rhai_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(rhai_string("a\\b"), "\"a\\\\b\"");
+        assert_eq!(rhai_string("a\nb\tc\r"), "\"a\\nb\\tc\\r\"");
+    }
+
+    #[test]
+    fn rhai_string_evaluates_to_input() {
+        let engine = rhai::Engine::new();
+
+        let inputs = [
+            "",
+            "plain",
+            "print(\"quoted\");",
+            "C:\\path\\to\\file",
+            "trailing backslash \\",
+            "two\nlines\tand a tab\r",
+            "let x = '\\''; // ünïcode",
+        ];
+
+        for input in inputs.iter() {
+            let value: String = engine.eval(&rhai_string(input)).unwrap();
+            assert_eq!(&value, input);
+        }
+    }
+
+    fn specs<T: BindableInput>(
+        bindings: &SystemInputBindings<T>,
+    ) -> Vec<(T, String, Option<(bool, f32)>)> {
+        let mut specs = T::inputs()
+            .into_iter()
+            .flat_map(|input| {
+                let wheel = bindings.wheel_settings(input);
+                bindings
+                    .input_specs(input)
+                    .into_iter()
+                    .map(move |spec| (input, spec.to_string(), wheel))
+            })
+            .collect::<Vec<_>>();
+
+        specs.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        specs
+    }
+
+    fn custom_binds(binds: &Keybindings) -> Vec<(String, String)> {
+        let mut custom = binds
+            .custom
+            .read()
+            .iter()
+            .map(|(spec, action)| (spec.to_string(), action.describe()))
+            .collect::<Vec<_>>();
+        custom.sort();
+        custom
+    }
+
+    #[test]
+    fn saved_keybindings_reload() {
+        let binds = Keybindings::default();
+
+        binds.bind("main_view", "KeyPanUp", "Ctrl+Shift+W").unwrap();
+        binds.bind("main_view", "WheelZoom", "Ctrl+Wheel").unwrap();
+        binds
+            .set_wheel("main_view", "WheelZoom", true, 2.5)
+            .unwrap();
+        binds
+            .bind_custom(
+                "Alt+Mouse4",
+                BindAction::Script(
+                    "print(\"a \\\"quoted\\\" path: C:\\\\x\");\nfoo()"
+                        .to_string(),
+                ),
+            )
+            .unwrap();
+        binds
+            .bind_custom(
+                "Ctrl+MouseMiddle",
+                BindAction::ContextAction("Copy \"name\"".to_string()),
+            )
+            .unwrap();
+        binds
+            .bind_custom("Logo+F5", BindAction::Function(Arc::new(|| ())))
+            .unwrap();
+
+        let dir = std::env::temp_dir()
+            .join(format!("gfaestus-keybindings-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(Keybindings::DEFAULT_PATH);
+
+        std::fs::write(&path, binds.to_script()).unwrap();
+
+        let loaded = Keybindings::default();
+        loaded.load(&path).unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(specs(&loaded.app.read()), specs(&binds.app.read()));
+        assert_eq!(
+            specs(&loaded.main_view.read()),
+            specs(&binds.main_view.read())
+        );
+        assert_eq!(specs(&loaded.gui.read()), specs(&binds.gui.read()));
+
+        // functions bound from Rust aren't saved
+        let mut expected = custom_binds(&binds);
+        expected.retain(|(spec, _)| spec != "Logo+F5");
+        assert_eq!(custom_binds(&loaded), expected);
+
+        let spec = BindSpec::parse("Ctrl+Shift+W").unwrap();
+        assert_eq!(spec, BindSpec::key(Key::W, Mods::CTRL | Mods::SHIFT));
+        assert!(loaded
+            .main_view
+            .read()
+            .input_specs(MainViewInput::KeyPanUp)
+            .contains(&spec));
+    }
+}
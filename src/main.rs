@@ -1,19 +1,22 @@
 #[allow(unused_imports)]
 use compute::EdgePreprocess;
 use crossbeam::atomic::AtomicCell;
-use gfaestus::context::{debug_context_action, pan_to_node_action, ContextMgr};
+use gfaestus::context::{
+    debug_context_action, grow_selection_action, pan_to_node_action, ContextMgr,
+};
 use gfaestus::quad_tree::QuadTree;
 use gfaestus::reactor::{ModalError, ModalHandler, ModalSuccess, Reactor};
 use gfaestus::script::plugins::colors::{hash_bytes, hash_color};
-use gfaestus::vulkan::compute::path_view::{Path1DLayout, PathViewRenderer};
+use gfaestus::vulkan::compute::path_view::PathViewRenderer;
 use gfaestus::vulkan::context::EdgeRendererType;
 use gfaestus::vulkan::draw_system::edges::EdgeRenderer;
+use gfaestus::vulkan::draw_system::paths::PathRenderer;
 use gfaestus::vulkan::texture::{Gradients, Gradients_, Texture};
 
 use parking_lot::RwLock;
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use winit::event::{ElementState, Event, MouseButton, WindowEvent};
 use winit::event_loop::ControlFlow;
@@ -22,18 +25,19 @@ use winit::event_loop::ControlFlow;
 use winit::window::{Window, WindowBuilder};
 
 use gfaestus::app::{
-    mainview::*, Args, OverlayCreatorMsg, OverlayState, Select,
+    mainview::*, Args, OverlayCreatorMsg, OverlayState, SelectOp,
 };
-use gfaestus::app::{App, AppMsg};
+use gfaestus::app::{selection::GrowOp, App, AppMsg};
 use gfaestus::geometry::*;
 use gfaestus::graph_query::*;
 use gfaestus::input::*;
 use gfaestus::overlays::*;
 use gfaestus::universe::*;
 use gfaestus::view::View;
+use gfaestus::vulkan::capture::FrameCapture;
 use gfaestus::vulkan::render_pass::Framebuffers;
 
-use gfaestus::gui::{widgets::*, windows::*, *};
+use gfaestus::gui::{minimap::MinimapColoring, widgets::*, windows::*, *};
 
 use gfaestus::vulkan::debug;
 
@@ -171,8 +175,6 @@ fn main() -> Result<()> {
 
     let graph_query = Arc::new(GraphQuery::load_gfa(gfa_file)?);
 
-    let layout_1d = Arc::new(Path1DLayout::new(graph_query.graph()));
-
     let graph_query_worker =
         GraphQueryWorker::new(graph_query.clone(), thread_pool.clone());
 
@@ -200,6 +202,10 @@ fn main() -> Result<()> {
     )
     .expect("error when creating App");
 
+    if let Err(err) = app.settings.store().load(Some(Path::new(gfa_file))) {
+        error!("Error loading settings: {}", err);
+    }
+
     let _center = Point {
         x: top_left.x + (bottom_right.x - top_left.x) / 2.0,
         y: top_left.y + (bottom_right.y - top_left.y) / 2.0,
@@ -234,16 +240,36 @@ fn main() -> Result<()> {
     let node_translation =
         NodeTranslation::new(&gfaestus, graph_query.node_count())?;
 
-    let mut select_fence_id: Option<usize> = None;
+    let mut select_fence_id: Option<(usize, SelectOp)> = None;
     let mut translate_fence_id: Option<usize> = None;
 
+    // the selection stats are only computed when the window is open
+    let mut selection_stats_dirty = false;
+
     let mut prev_overlay: Option<usize> = None;
     let mut prev_gradient = app.shared_state().overlay_state().gradient();
 
     let (winit_tx, winit_rx) =
         crossbeam::channel::unbounded::<WindowEvent<'static>>();
 
-    let mut input_manager = InputManager::new(winit_rx, app.shared_state());
+    let mut context_mgr = ContextMgr::default();
+
+    let keybindings_path = args
+        .keybindings
+        .as_ref()
+        .map(PathBuf::from)
+        .unwrap_or_else(Keybindings::default_path);
+
+    if let Err(err) = app.shared_state().keybindings().load(&keybindings_path) {
+        error!("Error loading keybindings: {}", err);
+    }
+
+    let mut input_manager = InputManager::new(
+        winit_rx,
+        app.shared_state(),
+        app.channels().app_tx.clone(),
+        context_mgr.action_queue(),
+    );
 
     let app_rx = input_manager.clone_app_rx();
     let main_view_rx = input_manager.clone_main_view_rx();
@@ -369,8 +395,13 @@ fn node_color(id) {
         Some(edge_renderer)
     };
 
+    let mut path_renderer = PathRenderer::new(&gfaestus)?;
+
     let mut dirty_swapchain = false;
 
+    // only allocated while a tour is being exported
+    let mut frame_capture: Option<FrameCapture> = None;
+
     let mut selection_edge = SelectionOutlineEdgePipeline::new(&gfaestus, 1)?;
 
     let mut selection_blur = SelectionOutlineBlurPipeline::new(&gfaestus, 1)?;
@@ -417,9 +448,11 @@ fn node_color(id) {
     // whenever the window resizes, so we use a timeout instead
     let initial_resize_timer = std::time::Instant::now();
 
-    gui_msg_tx.send(GuiMsg::SetLightMode)?;
-
-    let mut context_mgr = ContextMgr::default();
+    if app.shared_state().dark_mode().load() {
+        gui_msg_tx.send(GuiMsg::SetDarkMode)?;
+    } else {
+        gui_msg_tx.send(GuiMsg::SetLightMode)?;
+    }
 
     {
         macro_rules! set_type_name {
@@ -437,6 +470,10 @@ fn node_color(id) {
 
     context_mgr.register_action("Debug print", dbg_action);
 
+    for &op in GrowOp::ALL.iter() {
+        context_mgr.register_action(op.name(), grow_selection_action(&app, op));
+    }
+
     if let Err(e) = context_mgr
         .load_rhai_modules("./scripts/context_actions/".into(), &gui.console)
     {
@@ -508,6 +545,27 @@ fn node_color(id) {
             return;
         };
 
+        // an exported tour frame is read back before anything can
+        // write to the buffers it was rendered from
+        if let Some(capture) = frame_capture.as_mut() {
+            match capture.finish(&gfaestus) {
+                Ok(Some((path, pixels))) => {
+                    let extent = capture.extent();
+                    let result = app.reactor.spawn_forget(async move {
+                        if let Err(err) = FrameCapture::write_png(&path, extent, &pixels) {
+                            log::error!("Error writing {}: {:?}", path.display(), err);
+                        }
+                    });
+
+                    if let Err(err) = result {
+                        log::error!("Error exporting tour frame: {:?}", err);
+                    }
+                }
+                Ok(None) => (),
+                Err(err) => gui.tour_mut().abort(err.to_string()),
+            }
+        }
+
         if let Event::WindowEvent { event, .. } = &event {
             if let WindowEvent::MouseInput { state, button, .. } = event {
                 if *state == ElementState::Pressed &&
@@ -550,6 +608,8 @@ fn node_color(id) {
 
                 app.shared_state().hover_node.store(hover_node);
 
+                app.shared_state().extend_mouse_lasso();
+
                 if app.selection_changed() {
                     if let Some(selected) = app.selected_nodes() {
 
@@ -579,6 +639,22 @@ fn node_color(id) {
 
                         main_view.clear_node_selection().unwrap();
                     }
+
+                    selection_stats_dirty = true;
+                }
+
+                if selection_stats_dirty && gui.selection_stats_open() {
+                    let nodes =
+                        main_view.selection_buffer.selection_set().clone();
+
+                    let stats = graph_query_worker.run_query(
+                        move |graph| async move {
+                            SelectionStats::compute(&graph, &nodes)
+                        },
+                    );
+
+                    gui.selection_stats_tx().send(stats).unwrap();
+                    selection_stats_dirty = false;
                 }
 
 
@@ -597,7 +673,7 @@ fn node_color(id) {
                 while let Ok(app_msg) = app.channels().app_rx.try_recv() {
 
 
-                    if let AppMsg::RectSelect(rect) = &app_msg {
+                    if let AppMsg::RectSelect(rect, op) = &app_msg {
 
                         if select_fence_id.is_none() && translate_fence_id.is_none() {
                             let fence_id = gpu_selection.rectangle_select(
@@ -606,7 +682,21 @@ fn node_color(id) {
                                 *rect
                             ).unwrap();
 
-                            select_fence_id = Some(fence_id);
+                            select_fence_id = Some((fence_id, *op));
+                        }
+
+                    }
+
+                    if let AppMsg::PolygonSelect(polygon, op) = &app_msg {
+
+                        if select_fence_id.is_none() && translate_fence_id.is_none() {
+                            let fence_id = gpu_selection.polygon_select(
+                                &mut compute_manager,
+                                &main_view.node_draw_system.vertices,
+                                polygon
+                            ).unwrap();
+
+                            select_fence_id = Some((fence_id, *op));
                         }
 
                     }
@@ -679,10 +769,64 @@ fn node_color(id) {
 
 
                 if path_view.should_reload() {
-                    path_view.load_paths_1d(&mut app.reactor, &layout_1d).unwrap();
+                    path_view.load_paths_1d(&mut app.reactor).unwrap();
                     // path_view.load_paths(&mut app.reactor).unwrap();
                 }
 
+                if let Some(request) = path_view.take_export_request() {
+                    if let Err(err) = path_view.export(&app.reactor, request) {
+                        log::error!("Error exporting path view: {:?}", err);
+                    }
+                }
+
+                // the exported PNG is rendered with the same shaders
+                // and overlay as the path view itself
+                if let Some(overlay) = app.shared_state().overlay_state().current_overlay() {
+                    let pipelines = &main_view.node_draw_system.pipelines;
+
+                    if let Some(overlay_kind) = pipelines.overlay_kind(overlay) {
+                        let result = path_view.render_export(
+                            &gfaestus,
+                            &app.reactor,
+                            pipelines.pipeline_rgb.overlay_set,
+                            pipelines.pipeline_value.overlay_set,
+                            overlay_kind,
+                        );
+
+                        if let Err(err) = result {
+                            log::error!("Error exporting path view: {:?}", err);
+                        }
+                    }
+                }
+
+                {
+                    let overlay_state = app.shared_state().overlay_state();
+                    let overlay = overlay_state.current_overlay();
+
+                    if gui.minimap_needs_render(overlay) {
+                        let node_colors = match gui.minimap_mut().coloring() {
+                            MinimapColoring::Density => None,
+                            MinimapColoring::Overlay => {
+                                let gradient = overlay_state.gradient().gradient();
+                                let node_count = app.reactor.graph_query.node_count();
+
+                                overlay.and_then(|id| {
+                                    main_view
+                                        .node_draw_system
+                                        .pipelines
+                                        .overlay_node_colors(id, gradient, node_count)
+                                })
+                            }
+                        };
+
+                        let nodes = universe.layout().nodes();
+
+                        if let Err(err) = gui.minimap_mut().render(nodes, overlay, node_colors) {
+                            log::error!("Error rendering minimap: {:?}", err);
+                        }
+                    }
+                }
+
                 app.reactor
                     .gpu_tasks
                     .execute_all(&gfaestus,
@@ -755,6 +899,10 @@ fn node_color(id) {
                         universe.update_positions_from_gpu(&gfaestus,
                                                            &main_view.node_draw_system.vertices).unwrap();
 
+                        path_renderer.mark_dirty();
+                        gui.minimap_mut().mark_dirty();
+                        gui.measure_mut().mark_dirty();
+
                         translate_fence_id = None;
                     }
                 }
@@ -783,7 +931,7 @@ fn node_color(id) {
                     }
                 }
 
-                if let Some(fid) = select_fence_id {
+                if let Some((fid, select_op)) = select_fence_id {
 
                     if compute_manager.is_fence_ready(fid).unwrap() {
                         log::trace!("Node selection fence ready");
@@ -809,13 +957,13 @@ fn node_color(id) {
                         log::trace!("Updated CPU selection buffer");
                         trace!("fill_selection_set took {} ns", t.elapsed().as_nanos());
 
+                        let nodes = main_view
+                            .selection_buffer
+                            .selection_set()
+                            .clone();
+
                         app.channels().app_tx
-                            .send(AppMsg::Selection(Select::Many {
-                            nodes: main_view
-                                .selection_buffer
-                                .selection_set()
-                                .clone(),
-                            clear: true }))
+                            .send(AppMsg::Selection(select_op.apply(nodes)))
                             .unwrap();
 
 
@@ -869,6 +1017,8 @@ fn node_color(id) {
 
                 let _ = gui.console.eval_next(&mut app.reactor, true);
 
+                let tour_capture = gui.tour_mut().step(&app);
+
 
                 gui.begin_frame(
                     &app,
@@ -907,16 +1057,33 @@ fn node_color(id) {
 
 
                 context_mgr.begin_frame();
+                context_mgr.apply_queued_actions(&app);
                 context_mgr.show(&gui.ctx, &app);
 
                 let meshes = gui.end_frame(&mut app.reactor);
 
+                app.settings.store().update();
+
                 gui.upload_egui_texture(&gfaestus).unwrap();
 
+                if let Err(err) = gui.upload_minimap(&gfaestus) {
+                    log::error!("Error uploading minimap: {:?}", err);
+                }
+
                 if !meshes.is_empty() {
                     gui.upload_vertices(&gfaestus, &meshes).unwrap();
                 }
 
+                {
+                    let paths = app.shared_state().highlighted_paths();
+                    path_renderer
+                        .update_paths(&gfaestus,
+                                      app.reactor.graph_query.graph(),
+                                      universe.layout().nodes(),
+                                      &paths)
+                        .unwrap();
+                }
+
                 let node_pass = gfaestus.render_passes.nodes;
                 let edges_pass = gfaestus.render_passes.edges;
                 let edge_pass = gfaestus.render_passes.selection_edge_detect;
@@ -1005,6 +1172,7 @@ fn node_color(id) {
                             node_pass,
                             framebuffers,
                             size.into(),
+                            current_view,
                             Point::ZERO,
                             overlay,
                             gradient,
@@ -1049,6 +1217,26 @@ fn node_color(id) {
                             debug::end_cmd_buf_label(debug_utils, cmd_buf);
                         }
 
+                        if path_renderer.has_vertices() {
+                            log::trace!("Drawing path lines");
+                            debug::begin_cmd_buf_label(
+                                debug_utils,
+                                cmd_buf,
+                                "Path lines",
+                            );
+
+                            path_renderer.draw(
+                                cmd_buf,
+                                edges_pass,
+                                framebuffers,
+                                size.into(),
+                                current_view,
+                                Point::ZERO,
+                            ).unwrap();
+
+                            debug::end_cmd_buf_label(debug_utils, cmd_buf);
+                        }
+
 
                         log::trace!("Post-edge image transitions");
                         unsafe {
@@ -1170,6 +1358,89 @@ fn node_color(id) {
                 let size = window.inner_size();
                 dirty_swapchain = gfaestus.draw_frame_from([size.width, size.height], draw).unwrap();
 
+                if let Some(path) = tour_capture {
+                    let (width, height) = gui.tour_mut().export_extent();
+                    let extent = vk::Extent2D { width, height };
+
+                    let compatible = frame_capture
+                        .as_ref()
+                        .map(|c| c.is_compatible(&gfaestus, extent))
+                        .unwrap_or(false);
+
+                    if !compatible {
+                        if let Some(mut capture) = frame_capture.take() {
+                            capture.destroy(&gfaestus);
+                        }
+
+                        match FrameCapture::new(&gfaestus, extent) {
+                            Ok(capture) => frame_capture = Some(capture),
+                            Err(err) => gui.tour_mut().abort(err.to_string()),
+                        }
+                    }
+
+                    if let Some(capture) = frame_capture.as_mut() {
+                        // the exported frame covers the same width of
+                        // the layout as the window
+                        let view = View {
+                            scale: current_view.scale * swapchain_dims.width / width as f32,
+                            ..current_view
+                        };
+
+                        let size = [width as f32, height as f32];
+
+                        let gradient_name = app.shared_state().overlay_state().gradient();
+                        let gradient = gradients.gradient(gradient_name).unwrap();
+
+                        let result = capture.render(&gfaestus, path, |_device, cmd_buf, framebuffers| {
+                            main_view.draw_nodes(
+                                cmd_buf,
+                                node_pass,
+                                framebuffers,
+                                size,
+                                view,
+                                Point::ZERO,
+                                overlay,
+                                gradient,
+                            ).unwrap();
+
+                            if edges_enabled {
+                                for er in edge_renderer.iter_mut() {
+                                    er.draw(
+                                        cmd_buf,
+                                        edge_width,
+                                        &main_view.node_draw_system.vertices,
+                                        edges_pass,
+                                        framebuffers,
+                                        size,
+                                        2.0,
+                                        view,
+                                        Point::ZERO,
+                                    ).unwrap();
+                                }
+                            }
+
+                            if path_renderer.has_vertices() {
+                                path_renderer.draw(
+                                    cmd_buf,
+                                    edges_pass,
+                                    framebuffers,
+                                    size,
+                                    view,
+                                    Point::ZERO,
+                                ).unwrap();
+                            }
+                        });
+
+                        if let Err(err) = result {
+                            gui.tour_mut().abort(err.to_string());
+                        }
+                    }
+                } else if !gui.tour_mut().is_playing() {
+                    if let Some(mut capture) = frame_capture.take() {
+                        capture.destroy(&gfaestus);
+                    }
+                }
+
                 if !dirty_swapchain {
                     let screen_dims = app.dims();
 
@@ -1219,6 +1490,10 @@ fn node_color(id) {
             Event::LoopDestroyed => {
                 log::trace!("Event::LoopDestroyed");
 
+                if let Err(err) = app.settings.store().flush() {
+                    error!("Error writing settings: {}", err);
+                }
+
                 gfaestus.wait_gpu_idle().unwrap();
 
                 let device = gfaestus.vk_context().device();
@@ -1231,6 +1506,12 @@ fn node_color(id) {
 
                 selection_edge.destroy(device);
                 selection_blur.destroy(device);
+
+                path_renderer.destroy(&gfaestus);
+
+                if let Some(mut capture) = frame_capture.take() {
+                    capture.destroy(&gfaestus);
+                }
             }
             _ => (),
         }
@@ -19,6 +19,7 @@ use crate::{
 use rhai::plugin::*;
 
 pub mod plugins;
+pub mod query;
 
 pub fn create_engine() -> Engine {
     let mut engine = Engine::new();
@@ -0,0 +1,350 @@
+use std::sync::Arc;
+
+use rayon::prelude::*;
+use rhai::{Engine, EvalAltResult, Scope, AST};
+
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+
+use anyhow::{anyhow, Result};
+use bstr::ByteSlice;
+
+use crate::{app::selection::NodeSelection, graph_query::GraphQuery};
+
+/// A node predicate, written as a Rhai expression that's evaluated
+/// once for each node in the graph, e.g.
+///
+/// `len > 1000 and depth < 3 and on_path("HG002#1#chr6")`
+///
+/// `and`, `or`, and `not` can be used in place of `&&`, `||`, and
+/// `!`. Each evaluation has the following variables in scope:
+///
+/// * `node`: the `NodeId`
+/// * `id`: the node ID as an integer
+/// * `len`: the sequence length
+/// * `degree`, `in_degree`, `out_degree`: the number of edges
+/// * `depth`: the number of path steps on the node
+/// * `paths`: the number of distinct paths on the node
+/// * `graph`: the graph, for use with the regular console functions
+///
+/// The functions in `NodeQuery::NODE_FNS` implicitly take the current
+/// node as their first argument.
+pub struct NodeQuery {
+    engine: Engine,
+    ast: AST,
+
+    graph: Arc<PackedGraph>,
+}
+
+impl NodeQuery {
+    pub const NODE_FNS: [&'static str; 3] =
+        ["on_path", "on_path_prefix", "seq_contains"];
+
+    pub fn compile(graph: &GraphQuery, query: &str) -> Result<Self> {
+        let graph = graph.graph_arc().clone();
+
+        let mut engine = super::create_engine();
+        Self::register_node_fns(&mut engine, &graph);
+
+        let script = translate_query(query);
+
+        let ast = engine
+            .compile_expression(&script)
+            .map_err(|err| anyhow!("{}", err))?;
+
+        Ok(Self { engine, ast, graph })
+    }
+
+    /// Evaluates the query over every node in parallel, on the
+    /// current rayon thread pool
+    pub fn select(&self) -> Result<NodeSelection> {
+        let mut node_ids =
+            self.graph.handles().map(|h| h.id()).collect::<Vec<_>>();
+        node_ids.sort();
+
+        let mut scope = Scope::new();
+        scope.push("graph", self.graph.clone());
+
+        let matches = node_ids
+            .into_par_iter()
+            .map_with(scope, |scope, node| {
+                self.eval_node(scope, node).map(|hit| (hit, node))
+            })
+            .filter_map(|result| match result {
+                Ok((true, node)) => Some(Ok(node)),
+                Ok((false, _)) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(NodeSelection {
+            nodes: matches.into_iter().collect(),
+        })
+    }
+
+    fn eval_node(&self, scope: &mut Scope<'_>, node: NodeId) -> Result<bool> {
+        let graph = self.graph.as_ref();
+        let handle = Handle::pack(node, false);
+
+        let len = graph.node_len(handle) as i64;
+
+        let in_degree = graph.degree(handle, Direction::Left) as i64;
+        let out_degree = graph.degree(handle, Direction::Right) as i64;
+
+        let mut depth = 0i64;
+        let mut paths: Vec<PathId> = Vec::new();
+
+        if let Some(steps) = graph.steps_on_handle(handle) {
+            for (path, _) in steps {
+                depth += 1;
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+
+        scope.set_value("node", node);
+        scope.set_value("id", node.0 as i64);
+        scope.set_value("len", len);
+        scope.set_value("in_degree", in_degree);
+        scope.set_value("out_degree", out_degree);
+        scope.set_value("degree", in_degree + out_degree);
+        scope.set_value("depth", depth);
+        scope.set_value("paths", paths.len() as i64);
+
+        let result = self
+            .engine
+            .eval_ast_with_scope::<rhai::Dynamic>(scope, &self.ast)
+            .map_err(|err| anyhow!("Node {}: {}", node.0, err))?;
+
+        result.as_bool().map_err(|ty| {
+            anyhow!("The query must evaluate to true or false, got {}", ty)
+        })
+    }
+
+    fn register_node_fns(engine: &mut Engine, graph: &Arc<PackedGraph>) {
+        let graph_ = graph.clone();
+        engine.register_result_fn(
+            "on_path",
+            move |node: NodeId,
+                  name: &str|
+                  -> std::result::Result<bool, Box<EvalAltResult>> {
+                let path = graph_
+                    .get_path_id(name.as_bytes())
+                    .ok_or_else(|| format!("Path not found: {}", name))?;
+
+                let on_path = graph_
+                    .steps_on_handle(Handle::pack(node, false))
+                    .map(|mut steps| steps.any(|(p, _)| p == path))
+                    .unwrap_or(false);
+
+                Ok(on_path)
+            },
+        );
+
+        let graph_ = graph.clone();
+        engine.register_fn(
+            "on_path_prefix",
+            move |node: NodeId, prefix: &str| {
+                let prefix = prefix.as_bytes();
+
+                graph_
+                    .steps_on_handle(Handle::pack(node, false))
+                    .map(|mut steps| {
+                        steps.any(|(path, _)| {
+                            graph_
+                                .get_path_name_vec(path)
+                                .map(|name| name.starts_with(prefix))
+                                .unwrap_or(false)
+                        })
+                    })
+                    .unwrap_or(false)
+            },
+        );
+
+        let graph_ = graph.clone();
+        engine.register_fn("seq_contains", move |node: NodeId, seq: &str| {
+            let node_seq = graph_.sequence_vec(Handle::pack(node, false));
+            node_seq.contains_str(seq.as_bytes())
+        });
+    }
+}
+
+/// Rewrites a query into a plain Rhai expression, replacing the
+/// `and`, `or`, and `not` keywords, and passing the current node to
+/// the functions in `NodeQuery::NODE_FNS`; string literals are left
+/// untouched
+pub fn translate_query(query: &str) -> String {
+    let mut output = String::with_capacity(query.len() + 16);
+
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut ix = 0;
+
+    while ix < chars.len() {
+        let c = chars[ix];
+
+        if c == '"' || c == '\'' {
+            // copy the literal, including escaped quotes
+            output.push(c);
+            ix += 1;
+
+            while ix < chars.len() {
+                let d = chars[ix];
+                output.push(d);
+                ix += 1;
+
+                if d == '\\' {
+                    if let Some(&e) = chars.get(ix) {
+                        output.push(e);
+                        ix += 1;
+                    }
+                } else if d == c {
+                    break;
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = ix;
+            while ix < chars.len()
+                && (chars[ix].is_alphanumeric() || chars[ix] == '_')
+            {
+                ix += 1;
+            }
+
+            let word = chars[start..ix].iter().collect::<String>();
+
+            match word.as_str() {
+                "and" => output.push_str("&&"),
+                "or" => output.push_str("||"),
+                "not" => output.push('!'),
+                // skip method calls, e.g. `node.on_path(..)`
+                w if NodeQuery::NODE_FNS.contains(&w)
+                    && !output.trim_end().ends_with('.') =>
+                {
+                    output.push_str(w);
+
+                    let mut next = ix;
+                    while next < chars.len() && chars[next].is_whitespace() {
+                        next += 1;
+                    }
+
+                    if chars.get(next) == Some(&'(') {
+                        output.push_str("(node");
+
+                        let mut after = next + 1;
+                        while after < chars.len()
+                            && chars[after].is_whitespace()
+                        {
+                            after += 1;
+                        }
+
+                        if chars.get(after) != Some(&')') {
+                            output.push_str(", ");
+                        }
+
+                        ix = next + 1;
+                    }
+                }
+                _ => output.push_str(&word),
+            }
+        } else {
+            output.push(c);
+            ix += 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords() {
+        assert_eq!(
+            translate_query("degree > 2 and not (len < 10 or len > 100)"),
+            "degree > 2 && ! (len < 10 || len > 100)"
+        );
+    }
+
+    #[test]
+    fn keywords_in_identifiers() {
+        assert_eq!(
+            translate_query("android or order > 1 and nothing"),
+            "android || order > 1 && nothing"
+        );
+        assert_eq!(translate_query("_and and not_"), "_and && not_");
+    }
+
+    #[test]
+    fn keywords_in_string_literals() {
+        assert_eq!(
+            translate_query(r#"seq_contains("and or not")"#),
+            r#"seq_contains(node, "and or not")"#
+        );
+        assert_eq!(
+            translate_query("on_path('not a path') or len > 1"),
+            "on_path(node, 'not a path') || len > 1"
+        );
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        assert_eq!(
+            translate_query(r#"on_path("a\"b and c") and len > 1"#),
+            r#"on_path(node, "a\"b and c") && len > 1"#
+        );
+        assert_eq!(
+            translate_query(r"on_path('it\'s not') or not on_path('x')"),
+            r"on_path(node, 'it\'s not') || ! on_path(node, 'x')"
+        );
+        // an escaped backslash ends before the closing quote
+        assert_eq!(
+            translate_query(r#"on_path("a\\") and len > 1"#),
+            r#"on_path(node, "a\\") && len > 1"#
+        );
+    }
+
+    #[test]
+    fn negated_node_fn() {
+        assert_eq!(
+            translate_query(r#"not on_path("chr1")"#),
+            r#"! on_path(node, "chr1")"#
+        );
+        assert_eq!(
+            translate_query(r#"!seq_contains("ACGT")"#),
+            r#"!seq_contains(node, "ACGT")"#
+        );
+    }
+
+    #[test]
+    fn node_fn_without_arguments() {
+        assert_eq!(translate_query("on_path()"), "on_path(node)");
+        assert_eq!(translate_query("on_path ( )"), "on_path(node )");
+    }
+
+    #[test]
+    fn node_fn_method_calls() {
+        assert_eq!(
+            translate_query(r#"x.on_path("chr1")"#),
+            r#"x.on_path("chr1")"#
+        );
+        assert_eq!(
+            translate_query(r#"x . on_path_prefix("chr")"#),
+            r#"x . on_path_prefix("chr")"#
+        );
+    }
+
+    #[test]
+    fn other_identifiers() {
+        // only the exact function names are rewritten
+        assert_eq!(
+            translate_query(r#"on_path_x("a") or on_path"#),
+            r#"on_path_x("a") || on_path"#
+        );
+    }
+}
@@ -1,3 +1,4 @@
+pub mod capture;
 pub mod compute;
 pub mod context;
 pub mod debug;
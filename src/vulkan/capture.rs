@@ -0,0 +1,379 @@
+use ash::{version::DeviceV1_0, vk, Device};
+
+use anyhow::{bail, Result};
+
+use std::path::{Path, PathBuf};
+
+use super::{
+    render_pass::{Framebuffers, NodeAttachments, OffscreenAttachment},
+    texture::Texture,
+    GfaestusVk, SwapchainProperties,
+};
+
+/// An offscreen render target with its own attachments and a
+/// host-visible readback buffer, used to export rendered frames at a
+/// resolution independent of the window
+pub struct FrameCapture {
+    extent: vk::Extent2D,
+    format: vk::Format,
+    msaa_samples: vk::SampleCountFlags,
+
+    color: Texture,
+    node_attachments: NodeAttachments,
+    offscreen_attachment: OffscreenAttachment,
+    framebuffers: Framebuffers,
+
+    buffer: vk::Buffer,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+
+    fence: vk::Fence,
+    pending: Option<(vk::CommandBuffer, PathBuf)>,
+}
+
+impl FrameCapture {
+    pub fn new(app: &GfaestusVk, extent: vk::Extent2D) -> Result<Self> {
+        use vk::BufferUsageFlags as Usage;
+        use vk::MemoryPropertyFlags as MemPropFlags;
+
+        if extent.width == 0 || extent.height == 0 {
+            bail!("Can't capture frames with a zero resolution");
+        }
+
+        let format = app.swapchain_props.format.format;
+
+        if Self::rgba_order(format).is_none() {
+            bail!("Can't capture frames with the format {:?}", format);
+        }
+
+        let vk_context = app.vk_context();
+        let device = vk_context.device();
+
+        let pool = app.transient_command_pool;
+        let queue = app.graphics_queue;
+
+        let props = SwapchainProperties {
+            extent,
+            ..app.swapchain_props
+        };
+
+        let color = Texture::create_attachment_image(
+            vk_context,
+            pool,
+            queue,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            extent,
+            format,
+            None,
+        )?;
+
+        let node_attachments = NodeAttachments::new(
+            vk_context,
+            pool,
+            queue,
+            props,
+            app.msaa_samples,
+            app.render_passes.id_format,
+        )?;
+
+        let offscreen_attachment =
+            OffscreenAttachment::new(vk_context, pool, queue, props)?;
+
+        let framebuffers = app.render_passes.framebuffers(
+            device,
+            &node_attachments,
+            &offscreen_attachment,
+            color.view,
+            props,
+        )?;
+
+        let size = (extent.width * extent.height * 4) as vk::DeviceSize;
+
+        let (buffer, memory, _) = app.create_buffer(
+            size,
+            Usage::TRANSFER_DST,
+            MemPropFlags::HOST_VISIBLE
+                | MemPropFlags::HOST_COHERENT
+                | MemPropFlags::HOST_CACHED,
+        )?;
+
+        let fence = {
+            let fence_info = vk::FenceCreateInfo::builder().build();
+            unsafe { device.create_fence(&fence_info, None) }
+        }?;
+
+        Ok(Self {
+            extent,
+            format,
+            msaa_samples: app.msaa_samples,
+
+            color,
+            node_attachments,
+            offscreen_attachment,
+            framebuffers,
+
+            buffer,
+            memory,
+            size,
+
+            fence,
+            pending: None,
+        })
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.extent
+    }
+
+    /// Whether this target can be used to render a frame of the
+    /// given size with the current render passes
+    pub fn is_compatible(
+        &self,
+        app: &GfaestusVk,
+        extent: vk::Extent2D,
+    ) -> bool {
+        self.extent == extent
+            && self.format == app.swapchain_props.format.format
+            && self.msaa_samples == app.msaa_samples
+    }
+
+    /// The indices of the red, green, and blue channels in a pixel
+    fn rgba_order(format: vk::Format) -> Option<[usize; 3]> {
+        use vk::Format as F;
+
+        match format {
+            F::B8G8R8A8_UNORM | F::B8G8R8A8_SRGB => Some([2, 1, 0]),
+            F::R8G8B8A8_UNORM | F::R8G8B8A8_SRGB => Some([0, 1, 2]),
+            _ => None,
+        }
+    }
+
+    /// Submits `commands`, which draw into the capture framebuffers,
+    /// followed by copying the result into the readback buffer. The
+    /// frame is read back by `finish`, which must be called before
+    /// any buffers used by `commands` are written to again
+    pub fn render<F>(
+        &mut self,
+        app: &GfaestusVk,
+        path: PathBuf,
+        commands: F,
+    ) -> Result<()>
+    where
+        F: FnOnce(&Device, vk::CommandBuffer, &Framebuffers),
+    {
+        if self.pending.is_some() {
+            bail!("The previous captured frame hasn't been read back");
+        }
+
+        let device = app.vk_context().device();
+
+        let extent = self.extent;
+        let framebuffers = &self.framebuffers;
+        let color_image = self.color.image;
+        let id_image = self.node_attachments.id_resolve.image;
+        let buffer = self.buffer;
+
+        let cmd_buf = app.execute_one_time_commands_semaphores(
+            device,
+            app.command_pool,
+            app.graphics_queue,
+            &[],
+            &[],
+            &[],
+            self.fence,
+            |cmd_buf| unsafe {
+                let viewport = vk::Viewport {
+                    x: 0.0,
+                    y: 0.0,
+                    width: extent.width as f32,
+                    height: extent.height as f32,
+                    min_depth: 0.0,
+                    max_depth: 1.0,
+                };
+
+                let scissor = vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent,
+                };
+
+                device.cmd_set_viewport(cmd_buf, 0, &[viewport]);
+                device.cmd_set_scissor(cmd_buf, 0, &[scissor]);
+
+                commands(device, cmd_buf, framebuffers);
+
+                // the node pass expects the ID attachment to start
+                // out as a transfer source
+                let id_barrier = Self::color_barrier(
+                    id_image,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                );
+
+                let color_barrier = Self::color_barrier(
+                    color_image,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                );
+
+                device.cmd_pipeline_barrier(
+                    cmd_buf,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::DependencyFlags::empty(),
+                    &[],
+                    &[],
+                    &[id_barrier, color_barrier],
+                );
+
+                let region = vk::BufferImageCopy::builder()
+                    .buffer_offset(0)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(vk::ImageSubresourceLayers {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        mip_level: 0,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                    .image_extent(vk::Extent3D {
+                        width: extent.width,
+                        height: extent.height,
+                        depth: 1,
+                    })
+                    .build();
+
+                device.cmd_copy_image_to_buffer(
+                    cmd_buf,
+                    color_image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    buffer,
+                    &[region],
+                );
+            },
+        )?;
+
+        self.pending = Some((cmd_buf, path));
+
+        Ok(())
+    }
+
+    /// Waits for the frame submitted by `render`, if any, and returns
+    /// its path and opaque RGBA pixels
+    pub fn finish(
+        &mut self,
+        app: &GfaestusVk,
+    ) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        let (cmd_buf, path) = match self.pending.take() {
+            Some(pending) => pending,
+            None => return Ok(None),
+        };
+
+        let device = app.vk_context().device();
+
+        unsafe {
+            let fences = [self.fence];
+            device.wait_for_fences(&fences, true, std::u64::MAX)?;
+            device.reset_fences(&fences)?;
+            device.free_command_buffers(app.command_pool, &[cmd_buf]);
+        }
+
+        let [r, g, b] = Self::rgba_order(self.format).unwrap();
+
+        let mut pixels = vec![0u8; self.size as usize];
+
+        unsafe {
+            let data_ptr = device.map_memory(
+                self.memory,
+                0,
+                self.size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+
+            let src =
+                std::slice::from_raw_parts(data_ptr as *const u8, pixels.len());
+
+            for (dst, src) in
+                pixels.chunks_exact_mut(4).zip(src.chunks_exact(4))
+            {
+                dst[0] = src[r];
+                dst[1] = src[g];
+                dst[2] = src[b];
+                dst[3] = 255;
+            }
+
+            device.unmap_memory(self.memory);
+        }
+
+        Ok(Some((path, pixels)))
+    }
+
+    fn color_barrier(
+        image: vk::Image,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+    ) -> vk::ImageMemoryBarrier {
+        vk::ImageMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .build()
+    }
+
+    /// Writes pixels returned by `finish` as a PNG
+    pub fn write_png<P: AsRef<Path>>(
+        path: P,
+        extent: vk::Extent2D,
+        pixels: &[u8],
+    ) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let writer = std::io::BufWriter::new(file);
+
+        let mut encoder =
+            png::Encoder::new(writer, extent.width, extent.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(pixels)?;
+
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) {
+        let device = app.vk_context().device();
+
+        unsafe {
+            if let Some((cmd_buf, _)) = self.pending.take() {
+                let fences = [self.fence];
+                let _ = device.wait_for_fences(&fences, true, std::u64::MAX);
+                device.free_command_buffers(app.command_pool, &[cmd_buf]);
+            }
+
+            device.destroy_fence(self.fence, None);
+        }
+
+        self.framebuffers.destroy(device);
+        self.offscreen_attachment.destroy(device);
+        self.node_attachments.destroy(device);
+        self.color.destroy(device);
+
+        unsafe {
+            device.destroy_buffer(self.buffer, None);
+            device.free_memory(self.memory, None);
+        }
+    }
+}
@@ -31,6 +31,10 @@ use crate::vulkan::{draw_system::nodes::NodeVertices, GfaestusVk};
 
 use super::{ComputeManager, ComputePipeline};
 
+pub mod export;
+
+pub use export::*;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LoadState {
     Idle,
@@ -43,6 +47,54 @@ pub enum RenderState {
     Rendering,
 }
 
+/// How the path view colors each path's pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PathViewMode {
+    /// Color by the active overlay
+    Overlay,
+    /// Color forward and reverse traversals differently
+    Strand,
+    /// Color by how many times the path visits the node
+    Depth,
+}
+
+impl PathViewMode {
+    pub const ALL: [PathViewMode; 3] = [
+        PathViewMode::Overlay,
+        PathViewMode::Strand,
+        PathViewMode::Depth,
+    ];
+
+    fn as_push_constant(&self) -> u32 {
+        match self {
+            PathViewMode::Overlay => 0,
+            PathViewMode::Strand => 1,
+            PathViewMode::Depth => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for PathViewMode {
+    fn fmt(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+    ) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            PathViewMode::Overlay => write!(f, "Overlay"),
+            PathViewMode::Strand => write!(f, "Strand"),
+            PathViewMode::Depth => write!(f, "Depth"),
+        }
+    }
+}
+
+// The path buffer holds the node IDs for all rows, followed by the
+// same number of info words; each info word has the orientation flags
+// in the lowest two bits, and the path's visit count to the node in
+// the rest
+pub const PATH_INFO_FORWARD: u32 = 1;
+pub const PATH_INFO_REVERSE: u32 = 1 << 1;
+pub const PATH_INFO_DEPTH_SHIFT: u32 = 2;
+
 //////////////
 
 #[derive(Debug)]
@@ -166,6 +218,8 @@ pub struct PathViewRenderer {
     translation: Arc<AtomicCell<f32>>,
     scaling: Arc<AtomicCell<f32>>,
 
+    mode: AtomicCell<PathViewMode>,
+
     center: Arc<AtomicCell<f64>>,
     radius: Arc<AtomicCell<f64>>,
 
@@ -181,6 +235,12 @@ pub struct PathViewRenderer {
     pub path_name_order: Vec<PathId>,
     pub path_length_order: Vec<PathId>,
 
+    layout_1d: Mutex<Arc<Path1DLayout>>,
+
+    export_request: Mutex<Option<PathViewExport>>,
+    export_pending: Arc<Mutex<Option<(PathViewExport, PathViewSamples)>>>,
+    export_status: Arc<Mutex<Option<String>>>,
+
     path_buffer: vk::Buffer,
     path_allocation: vk_mem::Allocation,
     path_allocation_info: vk_mem::AllocationInfo,
@@ -228,7 +288,8 @@ impl PathViewRenderer {
             // | vk::BufferUsageFlags::TRANSFER_SRC;
             let memory_usage = vk_mem::MemoryUsage::CpuToGpu;
 
-            let data = vec![0u32; size];
+            // node IDs followed by the per-pixel info words
+            let data = vec![0u32; size * 2];
 
             let (buffer, allocation, allocation_info) =
                 app.create_buffer_with_data(usage, memory_usage, true, &data)?;
@@ -421,6 +482,8 @@ impl PathViewRenderer {
         path_lens.sort_by_key(|(_, n)| *n);
         let path_length_order = path_lens.into_iter().map(|(p, _)| p).collect();
 
+        let layout_1d = Arc::new(Path1DLayout::new(g));
+
         Ok(Self {
             rgb_pipeline,
             val_pipeline,
@@ -435,6 +498,8 @@ impl PathViewRenderer {
             translation: Arc::new(AtomicCell::new(0.0)),
             scaling: Arc::new(AtomicCell::new(0.0)),
 
+            mode: AtomicCell::new(PathViewMode::Overlay),
+
             center: Arc::new(AtomicCell::new(0.5)),
             radius: Arc::new(AtomicCell::new(0.5)),
             state: Arc::new(PathViewState::default()),
@@ -442,13 +507,19 @@ impl PathViewRenderer {
             row_states,
             next_row_ix: AtomicCell::new(0),
 
-            path_data: Arc::new(Mutex::new(vec![0u32; width * height])),
+            path_data: Arc::new(Mutex::new(vec![0u32; width * height * 2])),
             path_count: Arc::new(AtomicCell::new(0)),
 
             path_id_order,
             path_name_order,
             path_length_order,
 
+            layout_1d: Mutex::new(layout_1d),
+
+            export_request: Mutex::new(None),
+            export_pending: Arc::new(Mutex::new(None)),
+            export_status: Arc::new(Mutex::new(None)),
+
             path_buffer,
             path_allocation,
             path_allocation_info,
@@ -524,6 +595,15 @@ impl PathViewRenderer {
         self.should_rerender();
     }
 
+    pub fn mode(&self) -> PathViewMode {
+        self.mode.load()
+    }
+
+    pub fn set_mode(&self, mode: PathViewMode) {
+        self.mode.store(mode);
+        self.state.force_rerender();
+    }
+
     pub fn force_reload(&self) {
         self.state.should_reload.store(true);
 
@@ -650,6 +730,40 @@ impl PathViewRenderer {
         Ok(())
     }
 
+    /// Assigns rows to any of the `visible` paths that don't have
+    /// one, reusing the rows of paths that aren't visible. Paths past
+    /// the row count are left out
+    pub fn mark_visible_paths(&self, visible: &[PathId]) {
+        let visible_set: FxHashSet<PathId> = visible.iter().copied().collect();
+
+        let mut missing = visible
+            .iter()
+            .copied()
+            .filter(|&path| self.find_path_row(path).is_none())
+            .peekable();
+
+        if missing.peek().is_none() {
+            return;
+        }
+
+        for row in self.row_states.iter() {
+            let free = match row.load() {
+                RowState::Null => true,
+                RowState::NeedLoad(path) | RowState::Loaded(path) => {
+                    !visible_set.contains(&path)
+                }
+            };
+
+            if free {
+                if let Some(path) = missing.next() {
+                    row.store(RowState::NeedLoad(path));
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+
     pub fn find_path_row(&self, path: PathId) -> Option<usize> {
         for (ix, row) in self.row_states.iter().enumerate() {
             if row.load().same_path(path) {
@@ -663,7 +777,7 @@ impl PathViewRenderer {
     pub fn load_paths_impl(
         &self,
         reactor: &mut Reactor,
-        loader: impl Fn(PathId) -> Vec<u32> + Send + Sync + 'static,
+        loader: impl Fn(PathId) -> (Vec<u32>, Vec<u32>) + Send + Sync + 'static,
         // layout: &Arc<Path1DLayout>,
     ) -> Result<()> {
         let center = self.center.load();
@@ -695,7 +809,8 @@ impl PathViewRenderer {
             state.should_reload.store(false);
             state.loading.store(LoadState::Loading);
 
-            let mut loaded_paths: Vec<(usize, PathId, Vec<u32>)> = Vec::new();
+            let mut loaded_paths: Vec<(usize, PathId, (Vec<u32>, Vec<u32>))> =
+                Vec::new();
 
             let mut num_paths = 0;
 
@@ -721,12 +836,18 @@ impl PathViewRenderer {
 
                 let mut loaded = Vec::new();
 
-                for (y, path, path_data) in loaded_paths {
+                let info_offset = width * height;
+
+                for (y, path, (nodes, info)) in loaded_paths {
                     let offset = y * width;
                     let end = offset + width;
 
                     let slice = &mut lock[offset..end];
-                    slice.clone_from_slice(&path_data);
+                    slice.clone_from_slice(&nodes);
+
+                    let slice =
+                        &mut lock[info_offset + offset..info_offset + end];
+                    slice.clone_from_slice(&info);
 
                     loaded.push((y, path));
                 }
@@ -812,11 +933,98 @@ impl PathViewRenderer {
         Ok(())
     }
 
-    pub fn load_paths_1d(
+    pub fn layout_1d(&self) -> Arc<Path1DLayout> {
+        self.layout_1d.lock().clone()
+    }
+
+    /// Replaces the 1D layout, e.g. to switch to a reference-sorted
+    /// layout, and reloads all visible paths
+    pub fn set_layout_1d(&self, layout: Arc<Path1DLayout>) {
+        *self.layout_1d.lock() = layout;
+        self.force_reload();
+    }
+
+    /// Queues an export; it's started from the main loop, which has
+    /// access to the overlay descriptor sets used to render the PNG
+    pub fn request_export(&self, request: PathViewExport) {
+        *self.export_request.lock() = Some(request);
+    }
+
+    pub fn take_export_request(&self) -> Option<PathViewExport> {
+        self.export_request.lock().take()
+    }
+
+    pub fn export_status(&self) -> Option<String> {
+        self.export_status.lock().clone()
+    }
+
+    /// Samples the paths in the current view at the requested width
+    /// and writes the TSV in the background; if a PNG was requested,
+    /// the samples are then left for `render_export`
+    pub fn export(
         &self,
-        reactor: &mut Reactor,
-        layout: &Arc<Path1DLayout>,
+        reactor: &Reactor,
+        request: PathViewExport,
     ) -> Result<()> {
+        let layout = self.layout_1d();
+
+        let (left, right) = self.view();
+        let view = (
+            (left * layout.total_len as f64) as usize,
+            (right * layout.total_len as f64) as usize,
+        );
+
+        let graph = reactor.graph_query.clone();
+        let rayon_pool = reactor.rayon_pool.clone();
+        let pending = self.export_pending.clone();
+        let status = self.export_status.clone();
+
+        *status.lock() = Some("Exporting path view".to_string());
+
+        let result = move || -> Result<Option<String>> {
+            let graph = graph.graph();
+
+            let samples = rayon_pool.install(|| {
+                PathViewSamples::sample(
+                    &layout,
+                    graph,
+                    view,
+                    &request.paths,
+                    request.width.max(1),
+                )
+            });
+
+            if let Some(path) = &request.tsv {
+                let file = std::fs::File::create(path)?;
+                let writer = std::io::BufWriter::new(file);
+                samples.write_tsv(&layout, graph, writer)?;
+            }
+
+            if request.png.is_some() {
+                *pending.lock() = Some((request, samples));
+                Ok(None)
+            } else {
+                Ok(Some("Path view exported".to_string()))
+            }
+        };
+
+        reactor.spawn_forget(async move {
+            let msg = match result() {
+                Ok(msg) => msg,
+                Err(err) => {
+                    Some(format!("Error exporting path view: {:?}", err))
+                }
+            };
+
+            if let Some(msg) = msg {
+                *status.lock() = Some(msg);
+            }
+        })
+    }
+
+    pub fn load_paths_1d(&self, reactor: &mut Reactor) -> Result<()> {
+        let layout = self.layout_1d();
+
         let center = self.center.load();
         let radius = self.radius.load();
 
@@ -831,9 +1039,10 @@ impl PathViewRenderer {
         );
         let view = (view.0 as usize, view.1 as usize);
 
-        let layout = layout.clone();
+        let graph = reactor.graph_query.clone();
+
         self.load_paths_impl(reactor, move |path| {
-            layout.load_path(view, path, width)
+            layout.load_path(graph.graph(), view, path, width)
         })
     }
 
@@ -850,6 +1059,9 @@ impl PathViewRenderer {
 
         self.load_paths_impl(reactor, move |path| {
             let mut path_row = Vec::with_capacity(width);
+            let mut info_row = Vec::with_capacity(width);
+
+            let mut last_info: Option<(NodeId, u32)> = None;
 
             let steps = graph.path_pos_steps(path).unwrap();
             let (_, _, path_len) = steps.last().unwrap();
@@ -878,9 +1090,23 @@ impl PathViewRenderer {
 
                 let v = handle.id().0;
                 path_row.push(v as u32);
+
+                let info = match last_info {
+                    Some((node, info)) if node == handle.id() => info,
+                    _ => {
+                        let info = Path1DLayout::path_node_info(
+                            graph.graph(),
+                            path,
+                            handle.id(),
+                        );
+                        last_info = Some((handle.id(), info));
+                        info
+                    }
+                };
+                info_row.push(info);
             }
 
-            path_row
+            (path_row, info_row)
         })
     }
 
@@ -1044,7 +1270,7 @@ impl PathViewRenderer {
                 path_count as u32,
                 self.width as u32,
                 self.height as u32,
-                0u32,
+                self.mode.load().as_push_constant(),
             ];
 
             let float_consts = [self.translation.load(), self.scaling.load()];
@@ -1112,7 +1338,7 @@ impl PathViewRenderer {
                 path_count as u32,
                 self.width as u32,
                 self.height as u32,
-                0u32,
+                self.mode.load().as_push_constant(),
             ];
 
             // let (left, right) = self.offsets.load();
@@ -1209,6 +1435,17 @@ pub struct Path1DLayout {
     pub path_ranges: FxHashMap<PathId, Vec<std::ops::Range<usize>>>,
 
     node_offsets: Vec<usize>,
+    // the node at each index in `node_offsets`
+    node_order: Vec<NodeId>,
+    // the index in `node_order` of each node, by node ID - 1
+    node_indices: Vec<usize>,
+
+    /// The path whose coordinates the layout follows, if any
+    pub reference: Option<PathId>,
+    // the reference coordinate at the start of each node, and whether
+    // the node is on the reference; other nodes take the coordinate
+    // of the end of the preceding reference node
+    ref_coords: Vec<(usize, bool)>,
 }
 
 impl Path1DLayout {
@@ -1217,14 +1454,19 @@ impl Path1DLayout {
     // fn load_path(&self, view: (usize, usize), path: PathId, width: usize) -> Vec<(PathId, Vec<u32>)>
     fn load_path(
         &self,
+        graph: &PackedGraph,
         view: (usize, usize),
         path: PathId,
         width: usize,
-    ) -> Vec<u32> {
-        let mut res = Vec::new();
+    ) -> (Vec<u32>, Vec<u32>) {
+        let mut res = Vec::with_capacity(width);
+        let mut info_res = Vec::with_capacity(width);
 
         let path_ranges = self.path_ranges.get(&path).unwrap();
 
+        // neighboring pixels are often on the same node
+        let mut last_info: Option<(NodeId, u32)> = None;
+
         let w = width as f64;
         for i in 0..width {
             let x = i as f64;
@@ -1234,12 +1476,55 @@ impl Path1DLayout {
             if Self::sample_path(path_ranges, pos) {
                 let node = self.node_at_pos(pos);
                 res.push(node.0 as u32);
+
+                let info = match last_info {
+                    Some((n, info)) if n == node => info,
+                    _ => {
+                        let info = Self::path_node_info(graph, path, node);
+                        last_info = Some((node, info));
+                        info
+                    }
+                };
+                info_res.push(info);
             } else {
                 res.push(0u32);
+                info_res.push(0u32);
+            }
+        }
+
+        (res, info_res)
+    }
+
+    /// The orientations and number of visits of `path` on `node`,
+    /// packed as a path buffer info word
+    pub fn path_node_info(
+        graph: &PackedGraph,
+        path: PathId,
+        node: NodeId,
+    ) -> u32 {
+        let mut flags = 0u32;
+        let mut depth = 0u32;
+
+        let steps = graph
+            .steps_on_handle(Handle::pack(node, false))
+            .into_iter()
+            .flatten()
+            .filter(|(p, _)| *p == path);
+
+        for (_, step) in steps {
+            if let Some(handle) = graph.path_handle_at_step(path, step) {
+                if handle.is_reverse() {
+                    flags |= PATH_INFO_REVERSE;
+                } else {
+                    flags |= PATH_INFO_FORWARD;
+                }
             }
+            depth += 1;
         }
 
-        res
+        let max_depth = u32::MAX >> PATH_INFO_DEPTH_SHIFT;
+
+        flags | (depth.min(max_depth) << PATH_INFO_DEPTH_SHIFT)
     }
 
     fn pos_for_pixel(view: (usize, usize), width: f64, px_x: f64) -> usize {
@@ -1265,18 +1550,90 @@ impl Path1DLayout {
         self.node_at_pos(pos)
     }
 
-    // fn node_at_pos(&self, pos: usize) -> Option<NodeId> {
+    fn node_index_at_pos(&self, pos: usize) -> usize {
+        match self.node_offsets.binary_search(&pos) {
+            Ok(ix) => ix,
+            Err(ix) => ix.saturating_sub(1),
+        }
+    }
 
     fn node_at_pos(&self, pos: usize) -> NodeId {
-        let bin_ix = self.node_offsets.binary_search(&pos);
+        let ix = self.node_index_at_pos(pos);
 
-        // TODO the Err branch here might be off by one
-        let ix = match bin_ix {
-            Ok(ix) => ix,
-            Err(ix) => ix,
-        };
+        self.node_order
+            .get(ix)
+            .copied()
+            .unwrap_or_else(|| NodeId::from((ix + 1) as u64))
+    }
 
-        NodeId::from((ix + 1) as u64)
+    /// The range each node covers in the layout, indexed by node ID - 1
+    pub fn node_ranges(&self) -> Vec<std::ops::Range<usize>> {
+        let max_id = self.node_order.iter().map(|n| n.0).max().unwrap_or(0);
+
+        let mut ranges = vec![0..0; max_id as usize];
+
+        for (ix, node) in self.node_order.iter().enumerate() {
+            let start = self.node_offsets[ix];
+            let end = self
+                .node_offsets
+                .get(ix + 1)
+                .copied()
+                .unwrap_or(self.total_len);
+
+            ranges[(node.0 - 1) as usize] = start..end;
+        }
+
+        ranges
+    }
+
+    /// The range the node covers in the layout
+    pub fn node_range(&self, node: NodeId) -> Option<std::ops::Range<usize>> {
+        let ix = *self.node_indices.get((node.0 as usize).checked_sub(1)?)?;
+
+        let start = *self.node_offsets.get(ix)?;
+        let end = self
+            .node_offsets
+            .get(ix + 1)
+            .copied()
+            .unwrap_or(self.total_len);
+
+        Some(start..end)
+    }
+
+    /// The nodes that overlap the range `start..end` of the layout, in
+    /// layout order
+    pub fn nodes_in_range(&self, start: usize, end: usize) -> &[NodeId] {
+        if self.node_order.is_empty() || end <= start {
+            return &[];
+        }
+
+        let first = self.node_index_at_pos(start);
+        let last = self.node_index_at_pos(end - 1);
+
+        &self.node_order[first..=last.min(self.node_order.len() - 1)]
+    }
+
+    /// Whether the path visits the node at the given layout position
+    pub fn path_at_pos(&self, path: PathId, pos: usize) -> bool {
+        self.path_ranges
+            .get(&path)
+            .map(|ranges| Self::sample_path(ranges, pos))
+            .unwrap_or(false)
+    }
+
+    /// Maps a position on the 1D layout to the reference path's
+    /// coordinates, if the layout follows a reference
+    pub fn reference_pos(&self, pos: usize) -> Option<usize> {
+        self.reference?;
+
+        let ix = self.node_index_at_pos(pos);
+        let (coord, on_ref) = *self.ref_coords.get(ix)?;
+
+        if on_ref {
+            Some(coord + (pos - self.node_offsets[ix]))
+        } else {
+            Some(coord)
+        }
     }
 
     pub fn sample_path_dbg(
@@ -1336,6 +1693,7 @@ impl Path1DLayout {
         }
     }
 
+    /// Lays out the nodes in ascending ID order
     pub fn new(graph: &PackedGraph) -> Self {
         let nodes = {
             let mut ns = graph.handles().map(|h| h.id()).collect::<Vec<_>>();
@@ -1343,6 +1701,107 @@ impl Path1DLayout {
             ns
         };
 
+        Self::from_node_order(graph, nodes)
+    }
+
+    /// Lays out the nodes following the reference path, with nodes
+    /// that aren't on the reference inserted after the node they
+    /// branch off from, in the order other paths visit them. Nodes
+    /// that aren't on any path are placed at the end
+    pub fn new_reference_sorted(
+        graph_query: &GraphQuery,
+        ref_path: PathId,
+    ) -> Option<Self> {
+        let graph = graph_query.graph();
+
+        let ref_steps = graph_query.path_pos_steps(ref_path)?;
+
+        let name_offset = graph
+            .get_path_name_vec(ref_path)
+            .and_then(|name| crate::annotations::path_name_offset(&name))
+            .unwrap_or(0);
+
+        let mut placed: FxHashSet<NodeId> = FxHashSet::default();
+
+        let mut ref_nodes: Vec<NodeId> = Vec::new();
+        let mut ref_pos: FxHashMap<NodeId, usize> = FxHashMap::default();
+
+        for (handle, _, pos) in ref_steps {
+            let id = handle.id();
+            if placed.insert(id) {
+                ref_nodes.push(id);
+                ref_pos.insert(id, pos + name_offset);
+            }
+        }
+
+        // nodes inserted directly after each anchor node
+        let mut inserts: FxHashMap<NodeId, Vec<NodeId>> = FxHashMap::default();
+        // nodes at the start of paths, before any placed node
+        let mut leading: Vec<NodeId> = Vec::new();
+
+        for path in graph.path_ids() {
+            if path == ref_path {
+                continue;
+            }
+
+            let mut anchor: Option<NodeId> = None;
+
+            for step in graph.path_steps(path).into_iter().flatten() {
+                let id = step.handle().id();
+
+                if placed.insert(id) {
+                    match anchor {
+                        Some(a) => inserts.entry(a).or_default().push(id),
+                        None => leading.push(id),
+                    }
+                }
+
+                anchor = Some(id);
+            }
+        }
+
+        let mut unplaced = graph
+            .handles()
+            .map(|h| h.id())
+            .filter(|id| !placed.contains(id))
+            .collect::<Vec<_>>();
+        unplaced.sort();
+
+        let mut nodes: Vec<NodeId> =
+            Vec::with_capacity(graph_query.node_count());
+        let mut ref_coords: Vec<(usize, bool)> =
+            Vec::with_capacity(graph_query.node_count());
+
+        let mut ref_end = name_offset;
+        let mut stack: Vec<NodeId> = Vec::new();
+
+        for root in leading.into_iter().chain(ref_nodes).chain(unplaced) {
+            stack.push(root);
+
+            while let Some(id) = stack.pop() {
+                nodes.push(id);
+
+                if let Some(&pos) = ref_pos.get(&id) {
+                    ref_coords.push((pos, true));
+                    ref_end = pos + graph.node_len(Handle::pack(id, false));
+                } else {
+                    ref_coords.push((ref_end, false));
+                }
+
+                if let Some(inserted) = inserts.get(&id) {
+                    stack.extend(inserted.iter().rev());
+                }
+            }
+        }
+
+        let mut layout = Self::from_node_order(graph, nodes);
+        layout.reference = Some(ref_path);
+        layout.ref_coords = ref_coords;
+
+        Some(layout)
+    }
+
+    fn from_node_order(graph: &PackedGraph, nodes: Vec<NodeId>) -> Self {
         let path_count = graph.path_count();
 
         let mut open_ranges: Vec<Option<usize>> = vec![None; path_count];
@@ -1356,7 +1815,7 @@ impl Path1DLayout {
 
         let mut node_offsets = Vec::with_capacity(nodes.len());
 
-        for node in nodes {
+        for &node in nodes.iter() {
             let handle = Handle::pack(node, false);
 
             let len = graph.node_len(handle);
@@ -1427,10 +1886,22 @@ impl Path1DLayout {
                 .map(|(ix, ranges)| (PathId(ix as u64), ranges))
                 .collect();
 
+        let max_id = nodes.iter().map(|n| n.0).max().unwrap_or(0);
+        let mut node_indices = vec![0; max_id as usize];
+
+        for (ix, node) in nodes.iter().enumerate() {
+            node_indices[(node.0 - 1) as usize] = ix;
+        }
+
         Self {
             total_len,
             path_ranges,
             node_offsets,
+            node_order: nodes,
+            node_indices,
+
+            reference: None,
+            ref_coords: Vec::new(),
         }
     }
 }
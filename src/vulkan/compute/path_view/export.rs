@@ -0,0 +1,541 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use bstr::ByteSlice;
+use handlegraph::packedgraph::PackedGraph;
+use handlegraph::pathhandlegraph::{GraphPathNames, PathId};
+
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+
+use crate::overlays::OverlayKind;
+use crate::reactor::Reactor;
+use crate::vulkan::texture::Texture;
+use crate::vulkan::GfaestusVk;
+
+use super::{Path1DLayout, PathViewMode, PathViewRenderer};
+
+/// A request to export the path view at an arbitrary width, as a PNG
+/// image and/or a TSV matrix of the sampled node IDs
+#[derive(Debug, Clone)]
+pub struct PathViewExport {
+    pub paths: Vec<PathId>,
+    pub width: usize,
+    pub row_height: usize,
+    pub mode: PathViewMode,
+
+    pub png: Option<PathBuf>,
+    pub tsv: Option<PathBuf>,
+}
+
+/// The sampled node IDs and info words for each exported path, one
+/// row per path
+pub struct PathViewSamples {
+    pub view: (usize, usize),
+    pub width: usize,
+    pub paths: Vec<PathId>,
+    pub nodes: Vec<Vec<u32>>,
+    pub infos: Vec<Vec<u32>>,
+}
+
+impl PathViewSamples {
+    pub fn sample(
+        layout: &Path1DLayout,
+        graph: &PackedGraph,
+        view: (usize, usize),
+        paths: &[PathId],
+        width: usize,
+    ) -> Self {
+        use rayon::prelude::*;
+
+        let paths = paths
+            .iter()
+            .copied()
+            .filter(|path| layout.path_ranges.contains_key(path))
+            .collect::<Vec<_>>();
+
+        let (nodes, infos): (Vec<_>, Vec<_>) = paths
+            .par_iter()
+            .map(|&path| layout.load_path(graph, view, path, width))
+            .unzip();
+
+        Self {
+            view,
+            width,
+            paths,
+            nodes,
+            infos,
+        }
+    }
+
+    /// Writes one row per path, with the path name followed by the
+    /// node ID at each bin, or 0 if the path isn't present there; the
+    /// header holds the layout position of each bin, or the reference
+    /// path position if the layout follows a reference
+    pub fn write_tsv<W: Write>(
+        &self,
+        layout: &Path1DLayout,
+        graph: &PackedGraph,
+        mut out: W,
+    ) -> Result<()> {
+        let w = self.width as f64;
+
+        write!(out, "path")?;
+        for x in 0..self.width {
+            let pos = Path1DLayout::pos_for_pixel(self.view, w, x as f64);
+            let pos = layout.reference_pos(pos).unwrap_or(pos);
+            write!(out, "\t{}", pos)?;
+        }
+        writeln!(out)?;
+
+        for (path, row) in self.paths.iter().zip(self.nodes.iter()) {
+            let name = graph.get_path_name_vec(*path).unwrap_or_default();
+            write!(out, "{}", name.as_bstr())?;
+
+            for node in row {
+                write!(out, "\t{}", node)?;
+            }
+            writeln!(out)?;
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// Writes pixels rendered by `PathViewRenderer::render_export`,
+    /// one row per path, as an RGBA image with each row repeated
+    /// `row_height` times
+    pub fn write_png<W: Write>(
+        &self,
+        pixels: &[u8],
+        row_height: usize,
+        out: W,
+    ) -> Result<()> {
+        let row_height = row_height.max(1);
+        let height = self.paths.len() * row_height;
+
+        let mut image: Vec<u8> = Vec::with_capacity(self.width * height * 4);
+
+        for row in pixels.chunks_exact(self.width * 4) {
+            for _ in 0..row_height {
+                image.extend_from_slice(row);
+            }
+        }
+
+        let mut encoder =
+            png::Encoder::new(out, self.width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image)?;
+
+        Ok(())
+    }
+}
+
+impl PathViewRenderer {
+    /// Renders the samples of a pending export with the path view
+    /// shaders, and writes the PNG in the background; this waits on
+    /// the GPU, so it's called from the main loop
+    pub fn render_export(
+        &self,
+        app: &GfaestusVk,
+        reactor: &Reactor,
+        rgb_overlay_desc: vk::DescriptorSet,
+        val_overlay_desc: vk::DescriptorSet,
+        overlay_kind: OverlayKind,
+    ) -> Result<()> {
+        let (request, samples) = match self.export_pending.lock().take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        let status = self.export_status.clone();
+
+        let pixels = self.render_samples(
+            app,
+            &samples,
+            request.mode,
+            rgb_overlay_desc,
+            val_overlay_desc,
+            overlay_kind,
+        );
+
+        let result = move || -> Result<()> {
+            let pixels = pixels?;
+
+            if let Some(path) = &request.png {
+                let file = std::fs::File::create(path)?;
+                let writer = std::io::BufWriter::new(file);
+                samples.write_png(&pixels, request.row_height, writer)?;
+            }
+
+            Ok(())
+        };
+
+        reactor.spawn_forget(async move {
+            let msg = match result() {
+                Ok(_) => "Path view exported".to_string(),
+                Err(err) => format!("Error exporting path view: {:?}", err),
+            };
+            *status.lock() = Some(msg);
+        })
+    }
+
+    /// Runs the path view shader over the sampled rows, `self.height`
+    /// rows at a time, and reads back the resulting RGBA pixels
+    fn render_samples(
+        &self,
+        app: &GfaestusVk,
+        samples: &PathViewSamples,
+        mode: PathViewMode,
+        rgb_overlay_desc: vk::DescriptorSet,
+        val_overlay_desc: vk::DescriptorSet,
+        overlay_kind: OverlayKind,
+    ) -> Result<Vec<u8>> {
+        use vk::BufferUsageFlags as Usage;
+        use vk::MemoryPropertyFlags as MemPropFlags;
+
+        let vk_context = app.vk_context();
+        let device = vk_context.device();
+
+        let width = samples.width;
+        let rows = self.height;
+
+        let max_width = unsafe {
+            vk_context
+                .instance()
+                .get_physical_device_properties(vk_context.physical_device())
+        }
+        .limits
+        .max_image_dimension2_d as usize;
+
+        if width > max_width {
+            bail!("The export width can be at most {} on this GPU", max_width);
+        }
+
+        let mut pixels: Vec<u8> =
+            Vec::with_capacity(width * samples.paths.len() * 4);
+
+        if samples.paths.is_empty() {
+            return Ok(pixels);
+        }
+
+        let (pipeline, overlay_desc) = match overlay_kind {
+            OverlayKind::RGB => (&self.rgb_pipeline, rgb_overlay_desc),
+            OverlayKind::Value => (&self.val_pipeline, val_overlay_desc),
+        };
+
+        // node IDs followed by the per-pixel info words, like the
+        // path buffer used on screen
+        let path_len = width * rows * 2;
+        let path_size = (path_len * 4) as vk::DeviceSize;
+        let image_size = (width * rows * 4) as vk::DeviceSize;
+
+        let (path_buffer, path_memory, _) = app.create_buffer(
+            path_size,
+            Usage::STORAGE_BUFFER,
+            MemPropFlags::HOST_VISIBLE | MemPropFlags::HOST_COHERENT,
+        )?;
+
+        let (readback_buffer, readback_memory, _) = app.create_buffer(
+            image_size,
+            Usage::TRANSFER_DST,
+            MemPropFlags::HOST_VISIBLE
+                | MemPropFlags::HOST_COHERENT
+                | MemPropFlags::HOST_CACHED,
+        )?;
+
+        let mut image = Texture::allocate(
+            app,
+            app.transient_command_pool,
+            app.graphics_queue,
+            width,
+            rows,
+            vk::Format::R8G8B8A8_UNORM,
+            vk::ImageUsageFlags::STORAGE | vk::ImageUsageFlags::TRANSFER_SRC,
+        )?;
+
+        let descriptor_pool = {
+            let pool_sizes = [
+                vk::DescriptorPoolSize {
+                    ty: vk::DescriptorType::STORAGE_BUFFER,
+                    descriptor_count: 1,
+                },
+                vk::DescriptorPoolSize {
+                    ty: vk::DescriptorType::STORAGE_IMAGE,
+                    descriptor_count: 1,
+                },
+            ];
+
+            let pool_info = vk::DescriptorPoolCreateInfo::builder()
+                .pool_sizes(&pool_sizes)
+                .max_sets(1)
+                .build();
+
+            unsafe { device.create_descriptor_pool(&pool_info, None) }
+        }?;
+
+        let mut render = || -> Result<()> {
+            let desc_set = {
+                let layouts = [self.descriptor_set_layout];
+
+                let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&layouts)
+                    .build();
+
+                unsafe { device.allocate_descriptor_sets(&alloc_info) }?[0]
+            };
+
+            {
+                let buf_infos = [vk::DescriptorBufferInfo::builder()
+                    .buffer(path_buffer)
+                    .offset(0)
+                    .range(vk::WHOLE_SIZE)
+                    .build()];
+
+                let img_infos = [vk::DescriptorImageInfo::builder()
+                    .image_layout(vk::ImageLayout::GENERAL)
+                    .image_view(image.view)
+                    .build()];
+
+                let writes = [
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(desc_set)
+                        .dst_binding(0)
+                        .dst_array_element(0)
+                        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                        .buffer_info(&buf_infos)
+                        .build(),
+                    vk::WriteDescriptorSet::builder()
+                        .dst_set(desc_set)
+                        .dst_binding(1)
+                        .dst_array_element(0)
+                        .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                        .image_info(&img_infos)
+                        .build(),
+                ];
+
+                unsafe { device.update_descriptor_sets(&writes, &[]) };
+            }
+
+            let info_offset = width * rows;
+
+            for (nodes, infos) in
+                samples.nodes.chunks(rows).zip(samples.infos.chunks(rows))
+            {
+                let count = nodes.len();
+
+                unsafe {
+                    let ptr = device.map_memory(
+                        path_memory,
+                        0,
+                        path_size,
+                        vk::MemoryMapFlags::empty(),
+                    )?;
+
+                    let data = std::slice::from_raw_parts_mut(
+                        ptr as *mut u32,
+                        path_len,
+                    );
+
+                    for (y, (n, i)) in nodes.iter().zip(infos).enumerate() {
+                        let offset = y * width;
+                        let end = offset + width;
+
+                        data[offset..end].copy_from_slice(n);
+                        data[info_offset + offset..info_offset + end]
+                            .copy_from_slice(i);
+                    }
+
+                    device.unmap_memory(path_memory);
+                }
+
+                GfaestusVk::execute_one_time_commands(
+                    device,
+                    app.transient_command_pool,
+                    app.graphics_queue,
+                    |cmd_buf| unsafe {
+                        let barrier = Self::export_barrier(
+                            image.image,
+                            vk::ImageLayout::UNDEFINED,
+                            vk::ImageLayout::GENERAL,
+                            vk::AccessFlags::empty(),
+                            vk::AccessFlags::SHADER_WRITE,
+                        );
+
+                        device.cmd_pipeline_barrier(
+                            cmd_buf,
+                            vk::PipelineStageFlags::TOP_OF_PIPE,
+                            vk::PipelineStageFlags::COMPUTE_SHADER,
+                            vk::DependencyFlags::empty(),
+                            &[],
+                            &[],
+                            &[barrier],
+                        );
+
+                        device.cmd_bind_pipeline(
+                            cmd_buf,
+                            vk::PipelineBindPoint::COMPUTE,
+                            pipeline.pipeline,
+                        );
+
+                        let desc_sets = [desc_set, overlay_desc];
+
+                        device.cmd_bind_descriptor_sets(
+                            cmd_buf,
+                            vk::PipelineBindPoint::COMPUTE,
+                            pipeline.pipeline_layout,
+                            0,
+                            &desc_sets,
+                            &[],
+                        );
+
+                        let push_constants = [
+                            count as u32,
+                            width as u32,
+                            rows as u32,
+                            mode.as_push_constant(),
+                        ];
+
+                        // no translation or scaling, so each pixel
+                        // is its own sample
+                        let float_consts = [0.0f32, 1.0];
+
+                        let mut bytes: Vec<u8> = Vec::with_capacity(24);
+                        bytes.extend_from_slice(bytemuck::cast_slice(
+                            &push_constants,
+                        ));
+                        bytes.extend_from_slice(bytemuck::cast_slice(
+                            &float_consts,
+                        ));
+
+                        device.cmd_push_constants(
+                            cmd_buf,
+                            pipeline.pipeline_layout,
+                            vk::ShaderStageFlags::COMPUTE,
+                            0,
+                            &bytes,
+                        );
+
+                        let x_group_count = (width + 255) / 256;
+
+                        device.cmd_dispatch(
+                            cmd_buf,
+                            x_group_count as u32,
+                            count as u32,
+                            1,
+                        );
+
+                        let barrier = Self::export_barrier(
+                            image.image,
+                            vk::ImageLayout::GENERAL,
+                            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                            vk::AccessFlags::SHADER_WRITE,
+                            vk::AccessFlags::TRANSFER_READ,
+                        );
+
+                        device.cmd_pipeline_barrier(
+                            cmd_buf,
+                            vk::PipelineStageFlags::COMPUTE_SHADER,
+                            vk::PipelineStageFlags::TRANSFER,
+                            vk::DependencyFlags::empty(),
+                            &[],
+                            &[],
+                            &[barrier],
+                        );
+
+                        let region = vk::BufferImageCopy::builder()
+                            .buffer_offset(0)
+                            .buffer_row_length(0)
+                            .buffer_image_height(0)
+                            .image_subresource(vk::ImageSubresourceLayers {
+                                aspect_mask: vk::ImageAspectFlags::COLOR,
+                                mip_level: 0,
+                                base_array_layer: 0,
+                                layer_count: 1,
+                            })
+                            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                            .image_extent(vk::Extent3D {
+                                width: width as u32,
+                                height: count as u32,
+                                depth: 1,
+                            })
+                            .build();
+
+                        device.cmd_copy_image_to_buffer(
+                            cmd_buf,
+                            image.image,
+                            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                            readback_buffer,
+                            &[region],
+                        );
+                    },
+                )?;
+
+                unsafe {
+                    let len = width * count * 4;
+
+                    let ptr = device.map_memory(
+                        readback_memory,
+                        0,
+                        len as vk::DeviceSize,
+                        vk::MemoryMapFlags::empty(),
+                    )?;
+
+                    let src = std::slice::from_raw_parts(ptr as *const u8, len);
+                    pixels.extend_from_slice(src);
+
+                    device.unmap_memory(readback_memory);
+                }
+            }
+
+            Ok(())
+        };
+
+        let result = render();
+
+        unsafe {
+            device.destroy_descriptor_pool(descriptor_pool, None);
+
+            device.destroy_buffer(path_buffer, None);
+            device.free_memory(path_memory, None);
+
+            device.destroy_buffer(readback_buffer, None);
+            device.free_memory(readback_memory, None);
+        }
+
+        image.destroy(device);
+
+        result.map(|_| pixels)
+    }
+
+    fn export_barrier(
+        image: vk::Image,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+        src_access_mask: vk::AccessFlags,
+        dst_access_mask: vk::AccessFlags,
+    ) -> vk::ImageMemoryBarrier {
+        vk::ImageMemoryBarrier::builder()
+            .src_access_mask(src_access_mask)
+            .dst_access_mask(dst_access_mask)
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: 1,
+            })
+            .build()
+    }
+}
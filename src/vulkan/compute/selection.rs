@@ -19,12 +19,23 @@ pub struct GpuSelection {
 
     descriptor_set: vk::DescriptorSet,
 
+    polygon_pipeline: ComputePipeline,
+
+    polygon_descriptor_set: vk::DescriptorSet,
+
+    polygon_buffer: vk::Buffer,
+    polygon_memory: vk::DeviceMemory,
+
     pub selection_buffer: SelectionBuffer,
 
     node_count: usize,
 }
 
 impl GpuSelection {
+    /// Lasso and polygon selections with more points than this are
+    /// simplified before being uploaded
+    pub const MAX_POLYGON_POINTS: usize = 4096;
+
     pub fn new(app: &GfaestusVk, node_count: usize) -> Result<Self> {
         let device = app.vk_context().device();
 
@@ -69,6 +80,34 @@ impl GpuSelection {
             unsafe { device.allocate_descriptor_sets(&alloc_info) }
         }?;
 
+        let polygon_pipeline = Self::create_polygon_pipeline(device)?;
+
+        let polygon_descriptor_sets = {
+            let layouts = vec![polygon_pipeline.descriptor_set_layout];
+
+            let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(polygon_pipeline.descriptor_pool)
+                .set_layouts(&layouts)
+                .build();
+
+            unsafe { device.allocate_descriptor_sets(&alloc_info) }
+        }?;
+
+        let (polygon_buffer, polygon_memory, _size) = {
+            let size =
+                (Self::MAX_POLYGON_POINTS * 2 * std::mem::size_of::<f32>())
+                    as vk::DeviceSize;
+
+            let usage = vk::BufferUsageFlags::STORAGE_BUFFER;
+
+            let mem_props = vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT;
+
+            app.create_buffer(size, usage, mem_props)
+        }?;
+
+        app.set_debug_object_name(polygon_buffer, "Selection Polygon Buffer")?;
+
         let selection_buffer = SelectionBuffer::new(app, node_count)?;
 
         Ok(Self {
@@ -76,6 +115,13 @@ impl GpuSelection {
 
             descriptor_set: descriptor_sets[0],
 
+            polygon_pipeline,
+
+            polygon_descriptor_set: polygon_descriptor_sets[0],
+
+            polygon_buffer,
+            polygon_memory,
+
             selection_buffer,
 
             node_count,
@@ -98,6 +144,148 @@ impl GpuSelection {
         Ok(fence_id)
     }
 
+    /// Selects the nodes with an endpoint inside the polygon, which
+    /// is implicitly closed; `polygon` is in world coordinates
+    pub fn polygon_select(
+        &self,
+        comp_manager: &mut ComputeManager,
+        vertices: &NodeVertices,
+        polygon: &[Point],
+        // returns fence ID
+    ) -> Result<usize> {
+        let polygon = Self::simplify_polygon(polygon);
+
+        self.write_polygon(&polygon)?;
+        self.write_polygon_descriptor_set(vertices);
+
+        let mut bbox_min = Point::new(std::f32::MAX, std::f32::MAX);
+        let mut bbox_max = Point::new(std::f32::MIN, std::f32::MIN);
+
+        for p in polygon.iter() {
+            bbox_min.x = bbox_min.x.min(p.x);
+            bbox_min.y = bbox_min.y.min(p.y);
+            bbox_max.x = bbox_max.x.max(p.x);
+            bbox_max.y = bbox_max.y.max(p.y);
+        }
+
+        let push_constants = PolygonPushConstants {
+            node_count: self.node_count as u32,
+            point_count: polygon.len() as u32,
+            bbox_min,
+            bbox_max,
+        };
+
+        let fence_id = comp_manager.dispatch_with(|_device, cmd_buf| {
+            self.polygon_select_cmd(cmd_buf, push_constants).unwrap();
+        })?;
+
+        Ok(fence_id)
+    }
+
+    fn simplify_polygon(polygon: &[Point]) -> Vec<Point> {
+        if polygon.len() <= Self::MAX_POLYGON_POINTS {
+            return polygon.to_vec();
+        }
+
+        let step = (polygon.len() as f32) / (Self::MAX_POLYGON_POINTS as f32);
+
+        (0..Self::MAX_POLYGON_POINTS)
+            .map(|i| polygon[((i as f32) * step) as usize])
+            .collect()
+    }
+
+    fn write_polygon(&self, polygon: &[Point]) -> Result<()> {
+        let device = &self.compute_pipeline.device;
+
+        let size =
+            (polygon.len() * 2 * std::mem::size_of::<f32>()) as vk::DeviceSize;
+
+        unsafe {
+            let data_ptr = device.map_memory(
+                self.polygon_memory,
+                0,
+                size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+
+            let val_ptr = data_ptr as *mut f32;
+            let points =
+                std::slice::from_raw_parts_mut(val_ptr, polygon.len() * 2);
+
+            for (ix, p) in polygon.iter().enumerate() {
+                points[ix * 2] = p.x;
+                points[ix * 2 + 1] = p.y;
+            }
+
+            device.unmap_memory(self.polygon_memory);
+        }
+
+        Ok(())
+    }
+
+    fn polygon_select_cmd(
+        &self,
+        cmd_buf: vk::CommandBuffer,
+        push_constants: PolygonPushConstants,
+    ) -> Result<()> {
+        let device = &self.polygon_pipeline.device;
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.polygon_pipeline.pipeline,
+            )
+        };
+
+        unsafe {
+            let desc_sets = [self.polygon_descriptor_set];
+
+            let null = [];
+            device.cmd_bind_descriptor_sets(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.polygon_pipeline.pipeline_layout,
+                0,
+                &desc_sets[0..=0],
+                &null,
+            );
+        };
+
+        let pc_bytes = push_constants.bytes();
+
+        unsafe {
+            use vk::ShaderStageFlags as Flags;
+            device.cmd_push_constants(
+                cmd_buf,
+                self.polygon_pipeline.pipeline_layout,
+                Flags::COMPUTE,
+                0,
+                &pc_bytes,
+            )
+        };
+
+        let x_group_count = {
+            let div = self.node_count / 256;
+            let rem = self.node_count % 256;
+
+            let mut count = div;
+            if rem > 0 {
+                count += 1;
+            }
+            count as u32
+        };
+
+        trace!(
+            "Polygon selection dispatch with x_group_count {}",
+            x_group_count
+        );
+
+        unsafe { device.cmd_dispatch(cmd_buf, x_group_count, 1, 1) };
+
+        Ok(())
+    }
+
     fn rectangle_select_cmd(
         &self,
         // comp_manager: &mut ComputeManager,
@@ -210,6 +398,99 @@ impl GpuSelection {
         };
     }
 
+    pub fn write_polygon_descriptor_set(&self, vertices: &NodeVertices) {
+        let buffers = [
+            self.selection_buffer.buffer,
+            vertices.buffer(),
+            self.polygon_buffer,
+        ];
+
+        let buf_infos = buffers
+            .iter()
+            .map(|&buffer| {
+                [vk::DescriptorBufferInfo::builder()
+                    .buffer(buffer)
+                    .offset(0)
+                    .range(vk::WHOLE_SIZE)
+                    .build()]
+            })
+            .collect::<Vec<_>>();
+
+        let desc_writes = buf_infos
+            .iter()
+            .enumerate()
+            .map(|(binding, buf_info)| {
+                vk::WriteDescriptorSet::builder()
+                    .dst_set(self.polygon_descriptor_set)
+                    .dst_binding(binding as u32)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(buf_info)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            self.polygon_pipeline
+                .device
+                .update_descriptor_sets(&desc_writes, &[])
+        };
+    }
+
+    fn create_polygon_pipeline(device: &Device) -> Result<ComputePipeline> {
+        use vk::ShaderStageFlags as Stages;
+
+        let bindings = (0..3)
+            .map(|binding| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(binding)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .descriptor_count(1)
+                    .stage_flags(Stages::COMPUTE)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&bindings)
+            .build();
+
+        let desc_set_layout =
+            unsafe { device.create_descriptor_set_layout(&layout_info, None) }?;
+
+        let pipeline_layout = {
+            let pc_range = vk::PushConstantRange::builder()
+                .stage_flags(Stages::COMPUTE)
+                .offset(0)
+                .size(24)
+                .build();
+
+            let pc_ranges = [pc_range];
+
+            let layouts = [desc_set_layout];
+
+            let layout_info = vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(&layouts)
+                .push_constant_ranges(&pc_ranges)
+                .build();
+
+            unsafe { device.create_pipeline_layout(&layout_info, None) }
+        }?;
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 3,
+        }];
+
+        ComputePipeline::new_with_pool_size(
+            device,
+            desc_set_layout,
+            &pool_sizes,
+            pipeline_layout,
+            crate::include_shader!("compute/polygon_select.comp.spv"),
+        )
+    }
+
     fn layout_binding() -> [vk::DescriptorSetLayoutBinding; 2] {
         use vk::ShaderStageFlags as Stages;
 
@@ -293,3 +574,33 @@ impl RectPushConstants {
         bytes
     }
 }
+
+#[derive(Debug, Clone, Copy)]
+pub struct PolygonPushConstants {
+    bbox_min: Point,
+    bbox_max: Point,
+    node_count: u32,
+    point_count: u32,
+}
+
+impl PolygonPushConstants {
+    #[inline]
+    pub fn bytes(&self) -> [u8; 24] {
+        let mut bytes = [0u8; 24];
+
+        let words = [
+            self.bbox_min.x.to_ne_bytes(),
+            self.bbox_min.y.to_ne_bytes(),
+            self.bbox_max.x.to_ne_bytes(),
+            self.bbox_max.y.to_ne_bytes(),
+            self.node_count.to_ne_bytes(),
+            self.point_count.to_ne_bytes(),
+        ];
+
+        for (ix, word) in words.iter().enumerate() {
+            bytes[ix * 4..(ix + 1) * 4].copy_from_slice(word);
+        }
+
+        bytes
+    }
+}
@@ -6,6 +6,7 @@ use bytemuck::{Pod, Zeroable};
 pub mod edges;
 pub mod gui;
 pub mod nodes;
+pub mod paths;
 pub mod post;
 pub mod selection;
 
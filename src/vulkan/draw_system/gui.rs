@@ -23,6 +23,7 @@ pub struct GuiPipeline {
     egui_texture_version: u64,
 
     texture_set_map: FxHashMap<u64, vk::DescriptorSet>,
+    next_texture_id: u64,
 
     pub vertices: GuiVertices,
 
@@ -130,6 +131,7 @@ impl GuiPipeline {
             egui_texture_version: 0,
 
             texture_set_map,
+            next_texture_id: 0,
 
             vertices,
 
@@ -401,10 +403,24 @@ impl GuiPipeline {
         &mut self,
         app: &GfaestusVk,
         texture: Texture,
+    ) -> Result<egui::TextureId> {
+        let id = self.next_texture_id;
+        self.next_texture_id += 1;
+
+        self.set_texture(app, id, texture)
+    }
+
+    /// Makes `texture` available to the GUI as `TextureId::User(id)`,
+    /// for textures that need an ID that doesn't depend on the order
+    /// textures are added in
+    pub fn set_texture(
+        &mut self,
+        app: &GfaestusVk,
+        id: u64,
+        texture: Texture,
     ) -> Result<egui::TextureId> {
         let device = app.vk_context().device();
 
-        let id = self.texture_sets.len() as u64;
         let tex_id = egui::TextureId::User(id);
 
         let texture_sets = {
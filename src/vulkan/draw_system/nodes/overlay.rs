@@ -65,6 +65,18 @@ impl OverlayPipelines {
         Some(o.kind)
     }
 
+    /// Reads back the colors of the overlay for each node, mapping
+    /// value overlays using the gradient
+    pub fn overlay_node_colors(
+        &self,
+        id: usize,
+        gradient: colorous::Gradient,
+        node_count: usize,
+    ) -> Option<Vec<rgb::RGBA<u8>>> {
+        let o = self.overlays.get(&id)?;
+        o.node_colors(gradient, node_count)
+    }
+
     pub(super) fn bind_pipeline(
         &self,
         device: &Device,
@@ -616,6 +628,47 @@ impl Overlay {
         })
     }
 
+    /// Reads the color of each node from a host-visible overlay,
+    /// mapping values to colors with the gradient
+    pub fn node_colors(
+        &self,
+        gradient: colorous::Gradient,
+        node_count: usize,
+    ) -> Option<Vec<rgb::RGBA<u8>>> {
+        if !self.host_visible {
+            return None;
+        }
+
+        let mut colors = Vec::with_capacity(node_count);
+
+        unsafe {
+            let ptr = self.alloc_info.get_mapped_data();
+
+            match self.kind {
+                OverlayKind::RGB => {
+                    let ptr = ptr as *const [u8; 4];
+
+                    for ix in 0..node_count {
+                        let [r, g, b, a] = ptr.add(ix).read();
+                        colors.push(rgb::RGBA::new(r, g, b, a));
+                    }
+                }
+                OverlayKind::Value => {
+                    let ptr = ptr as *const f32;
+
+                    for ix in 0..node_count {
+                        let v = ptr.add(ix).read();
+                        let t = v.clamp(0.0, 1.0) as f64;
+                        let c = gradient.eval_continuous(t);
+                        colors.push(rgb::RGBA::new(c.r, c.g, c.b, 255));
+                    }
+                }
+            }
+        }
+
+        Some(colors)
+    }
+
     /// Update the colors for a host-visible overlay by providing a
     /// set of node IDs and new values
     pub fn update_value_overlay<I>(
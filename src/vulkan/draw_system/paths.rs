@@ -0,0 +1,565 @@
+use ash::version::DeviceV1_0;
+use ash::{vk, Device};
+
+use bytemuck::{Pod, Zeroable};
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+
+use anyhow::Result;
+
+use std::ffi::CString;
+
+use crate::geometry::Point;
+use crate::universe::Node;
+use crate::view::View;
+use crate::vulkan::render_pass::Framebuffers;
+use crate::vulkan::GfaestusVk;
+
+use super::create_shader_module;
+use super::nodes::NodePushConstants;
+
+#[derive(Clone, Copy, Zeroable, Pod)]
+#[repr(C)]
+pub struct PathVertex {
+    pub position: [f32; 2],
+    /// Offset from `position` in units of the line width, applied in
+    /// screen space
+    pub offset: [f32; 2],
+    pub color: [f32; 4],
+}
+
+impl PathVertex {
+    fn get_binding_desc() -> vk::VertexInputBindingDescription {
+        vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(std::mem::size_of::<PathVertex>() as u32)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build()
+    }
+
+    fn get_attribute_descs() -> [vk::VertexInputAttributeDescription; 3] {
+        let pos_desc = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(0)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(0)
+            .build();
+
+        let offset_desc = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(1)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(8)
+            .build();
+
+        let color_desc = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(2)
+            .format(vk::Format::R32G32B32A32_SFLOAT)
+            .offset(16)
+            .build();
+
+        [pos_desc, offset_desc, color_desc]
+    }
+}
+
+/// The color used for the path's line
+pub fn path_line_color(path: PathId) -> rgb::RGB<u8> {
+    let colors = colorous::CATEGORY10;
+    let c = colors[(path.0 as usize) % colors.len()];
+    rgb::RGB::new(c.r, c.g, c.b)
+}
+
+/// Draws paths as thick, semi-transparent polylines through the node
+/// endpoints in step order, with arrows showing the direction
+pub struct PathRenderer {
+    pipeline_layout: vk::PipelineLayout,
+    pipeline: vk::Pipeline,
+
+    vertex_buffer: vk::Buffer,
+    allocation: vk_mem::Allocation,
+    allocation_info: Option<vk_mem::AllocationInfo>,
+    vertex_count: usize,
+
+    // the paths the vertex buffer was built for
+    paths: Vec<PathId>,
+    dirty: bool,
+
+    device: Device,
+}
+
+impl PathRenderer {
+    pub const LINE_WIDTH: f32 = 6.0;
+    pub const LINE_ALPHA: f32 = 0.55;
+
+    /// An arrow is drawn on every `ARROW_INTERVAL`th step
+    const ARROW_INTERVAL: usize = 4;
+
+    pub fn new(app: &GfaestusVk) -> Result<Self> {
+        let device = app.vk_context().device();
+
+        let (pipeline, pipeline_layout) = Self::create_pipeline(
+            device,
+            app.msaa_samples,
+            app.render_passes.edges,
+        );
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline,
+
+            vertex_buffer: vk::Buffer::null(),
+            allocation: vk_mem::Allocation::null(),
+            allocation_info: None,
+            vertex_count: 0,
+
+            paths: Vec::new(),
+            dirty: false,
+
+            device: device.clone(),
+        })
+    }
+
+    pub fn paths(&self) -> &[PathId] {
+        &self.paths
+    }
+
+    pub fn has_vertices(&self) -> bool {
+        self.allocation_info.is_some()
+    }
+
+    /// Rebuild the lines on the next update, e.g. after nodes have
+    /// been moved
+    pub fn mark_dirty(&mut self) {
+        self.dirty = !self.paths.is_empty();
+    }
+
+    /// Rebuilds the vertices if the set of paths has changed, or the
+    /// lines have been marked dirty
+    pub fn update_paths(
+        &mut self,
+        app: &GfaestusVk,
+        graph: &PackedGraph,
+        nodes: &[Node],
+        paths: &[PathId],
+    ) -> Result<()> {
+        if !self.dirty && self.paths == paths {
+            return Ok(());
+        }
+
+        self.dirty = false;
+        self.paths = paths.to_vec();
+
+        let mut vertices: Vec<PathVertex> = Vec::new();
+
+        for &path in paths {
+            let rgb = path_line_color(path);
+            let color = [
+                rgb.r as f32 / 255.0,
+                rgb.g as f32 / 255.0,
+                rgb.b as f32 / 255.0,
+                Self::LINE_ALPHA,
+            ];
+
+            Self::path_vertices(graph, nodes, path, color, &mut vertices);
+        }
+
+        // the previous buffer may still be in use by the last frame
+        app.wait_gpu_idle()?;
+        self.destroy_buffer(app)?;
+
+        if vertices.is_empty() {
+            return Ok(());
+        }
+
+        let usage = vk::BufferUsageFlags::VERTEX_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_DST;
+        let memory_usage = vk_mem::MemoryUsage::GpuOnly;
+
+        let (buffer, allocation, allocation_info) =
+            app.create_buffer_with_data(usage, memory_usage, false, &vertices)?;
+
+        app.set_debug_object_name(buffer, "Path Line Vertex Buffer")?;
+
+        self.vertex_buffer = buffer;
+        self.allocation = allocation;
+        self.allocation_info = Some(allocation_info);
+        self.vertex_count = vertices.len();
+
+        Ok(())
+    }
+
+    fn path_vertices(
+        graph: &PackedGraph,
+        nodes: &[Node],
+        path: PathId,
+        color: [f32; 4],
+        vertices: &mut Vec<PathVertex>,
+    ) {
+        let steps = if let Some(steps) = graph.path_steps(path) {
+            steps
+        } else {
+            return;
+        };
+
+        let mut points: Vec<Point> = Vec::new();
+        let mut arrows: Vec<(Point, Point)> = Vec::new();
+
+        for (ix, step) in steps.enumerate() {
+            let handle = step.handle();
+
+            let node = match nodes.get((handle.id().0 - 1) as usize) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            let (start, end) = if handle.is_reverse() {
+                (node.p1, node.p0)
+            } else {
+                (node.p0, node.p1)
+            };
+
+            points.push(start);
+            points.push(end);
+
+            if ix % Self::ARROW_INTERVAL == 0 {
+                arrows.push((start, end));
+            }
+        }
+
+        for pair in points.windows(2) {
+            if let &[a, b] = pair {
+                Self::segment_vertices(a, b, color, vertices);
+            }
+        }
+
+        // arrows are drawn on top of the lines
+        let arrow_color = [color[0] * 0.6, color[1] * 0.6, color[2] * 0.6, 0.9];
+
+        for (start, end) in arrows {
+            Self::arrow_vertices(start, end, arrow_color, vertices);
+        }
+    }
+
+    fn direction(a: Point, b: Point) -> Option<(f32, f32)> {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len = (dx * dx + dy * dy).sqrt();
+
+        if len > 0.0 {
+            Some((dx / len, dy / len))
+        } else {
+            None
+        }
+    }
+
+    fn segment_vertices(
+        a: Point,
+        b: Point,
+        color: [f32; 4],
+        vertices: &mut Vec<PathVertex>,
+    ) {
+        let (dx, dy) = if let Some(dir) = Self::direction(a, b) {
+            dir
+        } else {
+            return;
+        };
+
+        let n = [-dy * 0.5, dx * 0.5];
+        let n_neg = [-n[0], -n[1]];
+
+        let vx = |p: Point, offset: [f32; 2]| PathVertex {
+            position: [p.x, p.y],
+            offset,
+            color,
+        };
+
+        vertices.push(vx(a, n));
+        vertices.push(vx(a, n_neg));
+        vertices.push(vx(b, n));
+
+        vertices.push(vx(b, n));
+        vertices.push(vx(a, n_neg));
+        vertices.push(vx(b, n_neg));
+    }
+
+    fn arrow_vertices(
+        start: Point,
+        end: Point,
+        color: [f32; 4],
+        vertices: &mut Vec<PathVertex>,
+    ) {
+        let (dx, dy) = if let Some(dir) = Self::direction(start, end) {
+            dir
+        } else {
+            return;
+        };
+
+        let mid = [(start.x + end.x) / 2.0, (start.y + end.y) / 2.0];
+        let (nx, ny) = (-dy, dx);
+
+        let vx = |offset: [f32; 2]| PathVertex {
+            position: mid,
+            offset,
+            color,
+        };
+
+        vertices.push(vx([dx * 1.5, dy * 1.5]));
+        vertices.push(vx([-dx + nx * 1.25, -dy + ny * 1.25]));
+        vertices.push(vx([-dx - nx * 1.25, -dy - ny * 1.25]));
+    }
+
+    pub fn draw(
+        &self,
+        cmd_buf: vk::CommandBuffer,
+        render_pass: vk::RenderPass,
+        framebuffers: &Framebuffers,
+        viewport_dims: [f32; 2],
+        view: View,
+        offset: Point,
+    ) -> Result<()> {
+        if !self.has_vertices() {
+            return Ok(());
+        }
+
+        let device = &self.device;
+
+        let extent = vk::Extent2D {
+            width: viewport_dims[0] as u32,
+            height: viewport_dims[1] as u32,
+        };
+
+        let clear_values = [];
+
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(render_pass)
+            .framebuffer(framebuffers.edges)
+            .render_area(vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent,
+            })
+            .clear_values(&clear_values)
+            .build();
+
+        unsafe {
+            device.cmd_begin_render_pass(
+                cmd_buf,
+                &render_pass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+
+            device.cmd_bind_pipeline(
+                cmd_buf,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline,
+            );
+
+            let vx_bufs = [self.vertex_buffer];
+            let offsets = [0];
+            device.cmd_bind_vertex_buffers(cmd_buf, 0, &vx_bufs, &offsets);
+        }
+
+        let push_constants = NodePushConstants::new(
+            [offset.x, offset.y],
+            viewport_dims,
+            view,
+            Self::LINE_WIDTH,
+            7,
+        );
+
+        let pc_bytes = push_constants.bytes();
+
+        unsafe {
+            device.cmd_push_constants(
+                cmd_buf,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::VERTEX,
+                0,
+                &pc_bytes,
+            );
+
+            device.cmd_draw(cmd_buf, self.vertex_count as u32, 1, 0, 0);
+
+            device.cmd_end_render_pass(cmd_buf);
+        }
+
+        Ok(())
+    }
+
+    fn destroy_buffer(&mut self, app: &GfaestusVk) -> Result<()> {
+        if self.has_vertices() {
+            app.allocator
+                .destroy_buffer(self.vertex_buffer, &self.allocation)?;
+
+            self.vertex_buffer = vk::Buffer::null();
+            self.allocation = vk_mem::Allocation::null();
+            self.allocation_info = None;
+            self.vertex_count = 0;
+        }
+
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) {
+        self.destroy_buffer(app).unwrap();
+
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_pipeline(self.pipeline, None);
+        }
+    }
+
+    fn create_pipeline(
+        device: &Device,
+        msaa_samples: vk::SampleCountFlags,
+        render_pass: vk::RenderPass,
+    ) -> (vk::Pipeline, vk::PipelineLayout) {
+        let vert_src = crate::load_shader!("paths/path_line.vert.spv");
+        let frag_src = crate::load_shader!("paths/path_line.frag.spv");
+
+        let vert_module = create_shader_module(device, &vert_src);
+        let frag_module = create_shader_module(device, &frag_src);
+
+        let entry_point = CString::new("main").unwrap();
+
+        let vert_state_info = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::VERTEX)
+            .module(vert_module)
+            .name(&entry_point)
+            .build();
+
+        let frag_state_info = vk::PipelineShaderStageCreateInfo::builder()
+            .stage(vk::ShaderStageFlags::FRAGMENT)
+            .module(frag_module)
+            .name(&entry_point)
+            .build();
+
+        let shader_state_infos = [vert_state_info, frag_state_info];
+
+        let vert_binding_descs = [PathVertex::get_binding_desc()];
+        let vert_attr_descs = PathVertex::get_attribute_descs();
+        let vert_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(&vert_binding_descs)
+            .vertex_attribute_descriptions(&vert_attr_descs)
+            .build();
+
+        let input_assembly_info =
+            vk::PipelineInputAssemblyStateCreateInfo::builder()
+                .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+                .primitive_restart_enable(false)
+                .build();
+
+        let viewport_info = vk::PipelineViewportStateCreateInfo::builder()
+            .viewport_count(1)
+            .scissor_count(1)
+            .build();
+
+        let dynamic_states = {
+            use vk::DynamicState as DS;
+            [DS::VIEWPORT, DS::SCISSOR]
+        };
+
+        let dynamic_state_info = vk::PipelineDynamicStateCreateInfo::builder()
+            .dynamic_states(&dynamic_states)
+            .build();
+
+        let rasterizer_info =
+            vk::PipelineRasterizationStateCreateInfo::builder()
+                .depth_clamp_enable(false)
+                .rasterizer_discard_enable(false)
+                .polygon_mode(vk::PolygonMode::FILL)
+                .line_width(1.0)
+                .cull_mode(vk::CullModeFlags::NONE)
+                .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+                .depth_bias_enable(false)
+                .build();
+
+        let multisampling_info =
+            vk::PipelineMultisampleStateCreateInfo::builder()
+                .sample_shading_enable(false)
+                .rasterization_samples(msaa_samples)
+                .min_sample_shading(1.0)
+                .alpha_to_coverage_enable(false)
+                .alpha_to_one_enable(false)
+                .build();
+
+        let color_blend_attachment =
+            vk::PipelineColorBlendAttachmentState::builder()
+                .color_write_mask(vk::ColorComponentFlags::all())
+                .blend_enable(true)
+                .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .build();
+
+        let color_blend_attachments = [color_blend_attachment];
+
+        let color_blending_info =
+            vk::PipelineColorBlendStateCreateInfo::builder()
+                .logic_op_enable(false)
+                .logic_op(vk::LogicOp::COPY)
+                .attachments(&color_blend_attachments)
+                .blend_constants([0.0, 0.0, 0.0, 0.0])
+                .build();
+
+        let layout = {
+            let pc_range = vk::PushConstantRange::builder()
+                .stage_flags(vk::ShaderStageFlags::VERTEX)
+                .offset(0)
+                .size(84)
+                .build();
+
+            let pc_ranges = [pc_range];
+
+            let layout_info = vk::PipelineLayoutCreateInfo::builder()
+                .push_constant_ranges(&pc_ranges)
+                .build();
+
+            unsafe {
+                device.create_pipeline_layout(&layout_info, None).unwrap()
+            }
+        };
+
+        let pipeline_info = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&shader_state_infos)
+            .vertex_input_state(&vert_input_info)
+            .input_assembly_state(&input_assembly_info)
+            .viewport_state(&viewport_info)
+            .dynamic_state(&dynamic_state_info)
+            .rasterization_state(&rasterizer_info)
+            .multisample_state(&multisampling_info)
+            .color_blend_state(&color_blending_info)
+            .layout(layout)
+            .render_pass(render_pass)
+            .subpass(0)
+            .build();
+
+        let pipeline_infos = [pipeline_info];
+
+        let pipeline = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &pipeline_infos,
+                    None,
+                )
+                .unwrap()[0]
+        };
+
+        unsafe {
+            device.destroy_shader_module(vert_module, None);
+            device.destroy_shader_module(frag_module, None);
+        }
+
+        (pipeline, layout)
+    }
+}
@@ -8,7 +8,10 @@ use std::{
     sync::Arc,
 };
 
-use crate::{app::App, universe::Node};
+use crate::{
+    app::{App, SettingsStore},
+    universe::Node,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum WindowRoot {
@@ -275,12 +278,28 @@ impl GuiWindows {
         let mut open = cell.load();
         let w = self.windows.get(&id)?;
 
+        let store = app.settings.store();
+
         {
             let mut lock = w.lock();
 
-            window.open(&mut open).show(ctx, |ui| {
+            let key = Self::settings_key(&lock.title);
+            let pos_key = format!("{}.pos", key);
+
+            let window = match store.get::<egui::Pos2>(&pos_key) {
+                Some(pos) => window.default_pos(pos),
+                None => window,
+            };
+
+            let response = window.open(&mut open).show(ctx, |ui| {
                 (lock.show)(app, ui, nodes);
             });
+
+            if let Some(response) = response {
+                store.set(&pos_key, &response.response.rect.min);
+            }
+
+            store.set(&format!("{}.open", key), &open);
         }
 
         cell.store(open);
@@ -288,6 +307,30 @@ impl GuiWindows {
         Some(())
     }
 
+    /// Restores the open windows from the settings store
+    pub fn restore_open(&self, store: &SettingsStore) {
+        for (id, w) in self.windows.iter() {
+            let key = Self::settings_key(&w.lock().title);
+
+            if let Some(open) = store.get::<bool>(&format!("{}.open", key)) {
+                self.set_open(*id, open);
+            }
+        }
+    }
+
+    /// The settings store key prefix for a window, e.g.
+    /// `window.go_to_location`
+    pub fn settings_key(title: &str) -> String {
+        let name = title
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("_");
+
+        format!("window.{}", name)
+    }
+
     pub fn add_window<F>(&mut self, id: GuiId, title: &str, f: F)
     where
         F: FnMut(&App, &mut egui::Ui, &[Node]) + Send + Sync + 'static,
@@ -354,3 +397,25 @@ pub struct AppViewState {
 
 
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_settings_key() {
+        assert_eq!(
+            GuiWindows::settings_key("Go to location"),
+            "window.go_to_location"
+        );
+        assert_eq!(
+            GuiWindows::settings_key("Selection Statistics"),
+            "window.selection_statistics"
+        );
+        assert_eq!(
+            GuiWindows::settings_key("  Path -- View (1D) "),
+            "window.path_view_1d"
+        );
+        assert_eq!(GuiWindows::settings_key("Tour"), "window.tour");
+    }
+}